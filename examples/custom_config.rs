@@ -17,6 +17,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             ".git".to_string(),   // Ignore git directory
         ],
         max_file_size: Some(1024 * 1024), // Ignore files larger than 1MB
+        ..Default::default()
     };
 
     let searcher = FileSearcher::with_config(config);
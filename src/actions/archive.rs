@@ -0,0 +1,208 @@
+//! Packing files into an archive
+//!
+//! [`archive`] writes every file of a result set into one new tar, gzipped
+//! tar or zip archive. Each entry is named after the file's path relative to
+//! a root, usually the directory that was searched, with `/` as separator on
+//! every platform. Files that cannot be read are left out and reported, and
+//! the archive is still written with the rest.
+
+use super::{Operation, Report};
+use crate::{FileSearchError, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Container format written by [`archive`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// Uncompressed tar
+    Tar,
+    /// Gzip-compressed tar
+    TarGz,
+    /// Zip with deflate compression
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Format named by the extension of `path`: `.tar`, `.tar.gz`, `.tgz` or
+    /// `.zip`, in any case
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = PathBuf::from(path.file_name()?.to_str()?.to_ascii_lowercase());
+        match name.extension()?.to_str()? {
+            "tar" => Some(Self::Tar),
+            "tgz" => Some(Self::TarGz),
+            "gz" if Path::new(name.file_stem()?).extension() == Some("tar".as_ref()) => {
+                Some(Self::TarGz)
+            }
+            "zip" => Some(Self::Zip),
+            _ => None,
+        }
+    }
+}
+
+/// Pack `files` into a new archive at `dest`
+///
+/// Entries are named after each file's path relative to `root`; files outside
+/// `root` and directories are reported as errors. A dry run lists the entries
+/// without creating `dest`.
+///
+/// # Errors
+///
+/// Returns an error if `dest` already exists or the archive cannot be
+/// written; problems with single files are collected in the report instead
+pub fn archive<P: AsRef<Path>>(
+    files: &[P],
+    dest: &Path,
+    root: &Path,
+    format: ArchiveFormat,
+    dry_run: bool,
+) -> Result<Report> {
+    if fs::symlink_metadata(dest).is_ok() {
+        return Err(FileSearchError::invalid_path(
+            dest,
+            "archive already exists",
+        ));
+    }
+
+    let mut report = Report::default();
+    let mut planned = Vec::new();
+    let mut claimed = HashSet::new();
+    for file in files {
+        let file = file.as_ref();
+        match entry_name(file, root) {
+            Ok(entry) if claimed.insert(entry.clone()) => planned.push((file, entry)),
+            Ok(_) => {}
+            Err(e) => report.errors.push((
+                file.to_path_buf(),
+                FileSearchError::io_error_with_path(e, "archiving", file),
+            )),
+        }
+    }
+    if dry_run {
+        report.operations = planned
+            .into_iter()
+            .map(|(file, entry)| Operation::Archive {
+                from: file.to_path_buf(),
+                entry: PathBuf::from(entry),
+            })
+            .collect();
+        return Ok(report);
+    }
+
+    let output = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)
+        .map_err(|e| FileSearchError::io_error_with_path(e, "creating archive", dest))?;
+    let mut writer = Writer::new(format, BufWriter::new(output));
+    for (file, entry) in planned {
+        match writer.add(file, &entry) {
+            Ok(()) => report.operations.push(Operation::Archive {
+                from: file.to_path_buf(),
+                entry: PathBuf::from(entry),
+            }),
+            Err(e) => report.errors.push((
+                file.to_path_buf(),
+                FileSearchError::io_error_with_path(e, "archiving", file),
+            )),
+        }
+    }
+    if let Err(e) = writer.finish() {
+        let _ = fs::remove_file(dest);
+        return Err(FileSearchError::io_error_with_path(
+            e,
+            "writing archive",
+            dest,
+        ));
+    }
+    Ok(report)
+}
+
+/// Name of `file` inside the archive, relative to `root` with `/` separators
+fn entry_name(file: &Path, root: &Path) -> io::Result<String> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let relative = file
+        .strip_prefix(root)
+        .map_err(|_| invalid(format!("path is not inside '{}'", root.display())))?;
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => parts.push(
+                part.to_str()
+                    .ok_or_else(|| invalid("path is not valid Unicode".to_string()))?,
+            ),
+            Component::CurDir => {}
+            _ => return Err(invalid("path leaves the archive root".to_string())),
+        }
+    }
+    if parts.is_empty() {
+        return Err(invalid("path is the archive root itself".to_string()));
+    }
+    Ok(parts.join("/"))
+}
+
+/// Archive being written in one of the formats
+enum Writer {
+    Tar(tar::Builder<BufWriter<File>>),
+    TarGz(tar::Builder<GzEncoder<BufWriter<File>>>),
+    Zip(ZipWriter<BufWriter<File>>),
+}
+
+impl Writer {
+    fn new(format: ArchiveFormat, output: BufWriter<File>) -> Self {
+        match format {
+            ArchiveFormat::Tar => Self::Tar(tar::Builder::new(output)),
+            ArchiveFormat::TarGz => Self::TarGz(tar::Builder::new(GzEncoder::new(
+                output,
+                Compression::default(),
+            ))),
+            ArchiveFormat::Zip => Self::Zip(ZipWriter::new(output)),
+        }
+    }
+
+    /// Append the contents of `file` as `entry`
+    ///
+    /// The file is opened before anything is written, so a file that cannot
+    /// be read leaves the archive intact.
+    fn add(&mut self, file: &Path, entry: &str) -> io::Result<()> {
+        let mut source = File::open(file)?;
+        let metadata = source.metadata()?;
+        if metadata.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "directories are not archived",
+            ));
+        }
+        match self {
+            Self::Tar(builder) => builder.append_file(entry, &mut source),
+            Self::TarGz(builder) => builder.append_file(entry, &mut source),
+            Self::Zip(zip) => {
+                let options = FileOptions::default()
+                    .compression_method(CompressionMethod::Deflated)
+                    .large_file(metadata.len() >= u64::from(u32::MAX));
+                #[cfg(unix)]
+                let options = {
+                    use std::os::unix::fs::PermissionsExt;
+                    options.unix_permissions(metadata.permissions().mode())
+                };
+                zip.start_file(entry, options)?;
+                io::copy(&mut source, zip).map(drop)
+            }
+        }
+    }
+
+    /// Write the end of the archive and flush it to disk
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Tar(builder) => builder.into_inner()?.flush(),
+            Self::TarGz(builder) => builder.into_inner()?.finish()?.flush(),
+            Self::Zip(mut zip) => zip.finish()?.flush(),
+        }
+    }
+}
@@ -0,0 +1,484 @@
+//! Bulk file operations on search results
+//!
+//! [`delete`], [`move_to`], [`copy_to`], [`link_to`] and [`rename()`] apply
+//! one operation to every path of a result set, and `archive()` packs them
+//! into one archive with the `archive` feature. They never overwrite anything: a target that already
+//! exists, or that another file of the same batch would also land on, is
+//! reported as an error for that file instead. One failing file does not stop
+//! the others, so each call returns a [`Report`] listing what was done and
+//! what failed. With `dry_run` set nothing is touched, and the report lists
+//! the operations that would be performed along with the errors that can be
+//! foreseen.
+//!
+//! ```no_run
+//! use whatever_find::actions::{self, DeleteMode};
+//! use std::path::PathBuf;
+//!
+//! let results = vec![PathBuf::from("old.log"), PathBuf::from("older.log")];
+//! let planned = actions::delete(&results, DeleteMode::Trash, true);
+//! for operation in &planned.operations {
+//!     println!("{operation}");
+//! }
+//! let report = actions::delete(&results, DeleteMode::Trash, false);
+//! for (path, error) in &report.errors {
+//!     eprintln!("{}: {error}", path.display());
+//! }
+//! ```
+
+/// Packing files into tar and zip archives
+#[cfg(feature = "archive")]
+pub mod archive;
+/// Renaming files from a template
+pub mod rename;
+/// Moving files to the desktop trash
+pub mod trash;
+
+#[cfg(feature = "archive")]
+pub use archive::{archive, ArchiveFormat};
+pub use rename::{rename, Rename};
+
+use crate::{FileSearchError, Result};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How [`delete`] disposes of files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeleteMode {
+    /// Move to the desktop trash, from where files can be restored
+    #[default]
+    Trash,
+    /// Remove for good
+    Permanent,
+}
+
+/// Kind of link made by [`link_to`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkKind {
+    /// Symbolic link to the absolute path of the file
+    #[default]
+    Symbolic,
+    /// Hard link, which only works within one file system
+    Hard,
+}
+
+/// Where files land inside the target directory of [`link_to`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// Directly inside the target directory, under their own names
+    #[default]
+    Flatten,
+    /// At their path relative to this root, recreating the directories in
+    /// between
+    Preserve(PathBuf),
+}
+
+impl Layout {
+    /// Path of `file` inside `dir`
+    fn target(&self, file: &Path, dir: &Path) -> io::Result<PathBuf> {
+        match self {
+            Self::Flatten => file.file_name().map(|name| dir.join(name)).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "path has no file name")
+            }),
+            Self::Preserve(root) => match file.strip_prefix(root) {
+                Ok(relative) if relative.file_name().is_some() => Ok(dir.join(relative)),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("path is not inside '{}'", root.display()),
+                )),
+            },
+        }
+    }
+}
+
+/// One operation on one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// Move `path` to the desktop trash
+    Trash(PathBuf),
+    /// Remove `path` permanently
+    Delete(PathBuf),
+    /// Move `from` to `to`
+    Move {
+        /// Current path
+        from: PathBuf,
+        /// New path
+        to: PathBuf,
+    },
+    /// Copy `from` to `to`
+    Copy {
+        /// Source path
+        from: PathBuf,
+        /// Path of the copy
+        to: PathBuf,
+    },
+    /// Link `to` to `from`
+    Link {
+        /// Linked file
+        from: PathBuf,
+        /// Path of the link
+        to: PathBuf,
+        /// Symbolic or hard link
+        kind: LinkKind,
+    },
+    /// Add `from` to an archive as `entry`
+    ///
+    /// Entries are only written by `archive()`, as part of a whole archive;
+    /// [`Operation::apply`] refuses them.
+    Archive {
+        /// Archived file
+        from: PathBuf,
+        /// Path of the entry inside the archive
+        entry: PathBuf,
+    },
+    /// Give `from` the new path `to` in the same directory
+    Rename {
+        /// Current path
+        from: PathBuf,
+        /// New path
+        to: PathBuf,
+    },
+}
+
+impl Operation {
+    /// The file operated on
+    #[must_use]
+    pub fn source(&self) -> &Path {
+        match self {
+            Self::Trash(path) | Self::Delete(path) => path,
+            Self::Move { from, .. }
+            | Self::Copy { from, .. }
+            | Self::Link { from, .. }
+            | Self::Archive { from, .. }
+            | Self::Rename { from, .. } => from,
+        }
+    }
+
+    /// Where the file ends up, or its entry inside an archive
+    #[must_use]
+    pub fn target(&self) -> Option<&Path> {
+        match self {
+            Self::Trash(_) | Self::Delete(_) => None,
+            Self::Move { to, .. }
+            | Self::Copy { to, .. }
+            | Self::Link { to, .. }
+            | Self::Archive { entry: to, .. }
+            | Self::Rename { to, .. } => Some(to),
+        }
+    }
+
+    /// Perform the operation
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file system refuses the operation or the
+    /// target appeared in the meantime
+    pub fn apply(&self) -> Result<()> {
+        let result = match self {
+            Self::Trash(path) => trash::move_to_trash(path),
+            Self::Delete(path) if path.is_dir() => fs::remove_dir_all(path),
+            Self::Delete(path) => fs::remove_file(path),
+            Self::Move { from, to } => ensure_vacant(to).and_then(|()| move_file(from, to)),
+            Self::Copy { from, to } => {
+                ensure_vacant(to).and_then(|()| fs::copy(from, to).map(drop))
+            }
+            Self::Link { from, to, kind } => ensure_vacant(to).and_then(|()| link(from, to, *kind)),
+            Self::Archive { .. } => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "archive entries are only written with their whole archive",
+            )),
+            // A change of case only names the same file on case-insensitive
+            // file systems
+            Self::Rename { from, to } if is_case_change(from, to) => fs::rename(from, to),
+            Self::Rename { from, to } => ensure_vacant(to).and_then(|()| fs::rename(from, to)),
+        };
+        result.map_err(|e| FileSearchError::io_error_with_path(e, self.verb(), self.source()))
+    }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            Self::Trash(_) => "moving to trash",
+            Self::Delete(_) => "deleting",
+            Self::Move { .. } => "moving",
+            Self::Copy { .. } => "copying",
+            Self::Link { .. } => "linking",
+            Self::Archive { .. } => "archiving",
+            Self::Rename { .. } => "renaming",
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Trash(path) => write!(f, "trash {}", path.display()),
+            Self::Delete(path) => write!(f, "delete {}", path.display()),
+            Self::Move { from, to } => write!(f, "move {} -> {}", from.display(), to.display()),
+            Self::Copy { from, to } => write!(f, "copy {} -> {}", from.display(), to.display()),
+            Self::Archive { from, entry } => {
+                write!(f, "archive {} as {}", from.display(), entry.display())
+            }
+            Self::Link { from, to, kind } => {
+                let kind = match kind {
+                    LinkKind::Symbolic => "symlink",
+                    LinkKind::Hard => "hardlink",
+                };
+                write!(f, "{kind} {} -> {}", to.display(), from.display())
+            }
+            Self::Rename { from, to } => {
+                write!(f, "rename {} -> {}", from.display(), to.display())
+            }
+        }
+    }
+}
+
+/// Outcome of a bulk operation
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Operations performed, or planned in a dry run
+    pub operations: Vec<Operation>,
+    /// Files that failed or would fail, with the reason
+    pub errors: Vec<(PathBuf, FileSearchError)>,
+}
+
+impl Report {
+    /// Whether every file was, or would be, handled
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Perform `planned` unless this is a dry run, recording the outcome
+    fn run(&mut self, planned: Operation, dry_run: bool) {
+        if dry_run {
+            self.operations.push(planned);
+            return;
+        }
+        match planned.apply() {
+            Ok(()) => self.operations.push(planned),
+            Err(e) => self.errors.push((planned.source().to_path_buf(), e)),
+        }
+    }
+}
+
+/// Move `files` to the trash or remove them permanently
+///
+/// Directories are removed with their contents.
+#[must_use]
+pub fn delete<P: AsRef<Path>>(files: &[P], mode: DeleteMode, dry_run: bool) -> Report {
+    let mut report = Report::default();
+    for file in files {
+        let file = file.as_ref();
+        if let Err(e) = fs::symlink_metadata(file) {
+            report.errors.push((
+                file.to_path_buf(),
+                FileSearchError::io_error_with_path(e, "deleting", file),
+            ));
+            continue;
+        }
+        let planned = match mode {
+            DeleteMode::Trash => Operation::Trash(file.to_path_buf()),
+            DeleteMode::Permanent => Operation::Delete(file.to_path_buf()),
+        };
+        report.run(planned, dry_run);
+    }
+    report
+}
+
+/// Move `files` into the directory `dir`, keeping their names
+///
+/// # Errors
+///
+/// Returns an error if `dir` is not a directory; problems with single files
+/// are collected in the report instead
+pub fn move_to<P: AsRef<Path>>(files: &[P], dir: &Path, dry_run: bool) -> Result<Report> {
+    let layout = Layout::Flatten;
+    transfer(files, dir, &layout, dry_run, "moving", |from, to| {
+        Operation::Move { from, to }
+    })
+}
+
+/// Copy `files` into the directory `dir`, keeping their names
+///
+/// Only regular files can be copied.
+///
+/// # Errors
+///
+/// Returns an error if `dir` is not a directory; problems with single files
+/// are collected in the report instead
+pub fn copy_to<P: AsRef<Path>>(files: &[P], dir: &Path, dry_run: bool) -> Result<Report> {
+    let layout = Layout::Flatten;
+    transfer(files, dir, &layout, dry_run, "copying", |from, to| {
+        Operation::Copy { from, to }
+    })
+}
+
+/// Link `files` into the directory `dir`, placed as `layout` says
+///
+/// Symbolic links point to the absolute path of each file, so the farm keeps
+/// working wherever it is viewed from. Directories needed by
+/// [`Layout::Preserve`] are created when the links are made.
+///
+/// # Errors
+///
+/// Returns an error if `dir` is not a directory; problems with single files
+/// are collected in the report instead
+pub fn link_to<P: AsRef<Path>>(
+    files: &[P],
+    dir: &Path,
+    kind: LinkKind,
+    layout: &Layout,
+    dry_run: bool,
+) -> Result<Report> {
+    transfer(files, dir, layout, dry_run, "linking", |from, to| {
+        Operation::Link { from, to, kind }
+    })
+}
+
+fn transfer<P: AsRef<Path>>(
+    files: &[P],
+    dir: &Path,
+    layout: &Layout,
+    dry_run: bool,
+    verb: &str,
+    operation: impl Fn(PathBuf, PathBuf) -> Operation,
+) -> Result<Report> {
+    if !dir.is_dir() {
+        return Err(FileSearchError::invalid_path(
+            dir,
+            "target is not a directory",
+        ));
+    }
+
+    let mut report = Report::default();
+    let mut claimed = HashSet::new();
+    for file in files {
+        let file = file.as_ref();
+        let planned = layout.target(file, dir).and_then(|to| {
+            ensure_vacant(&to)?;
+            if !claimed.insert(to.clone()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("another file is also going to '{}'", to.display()),
+                ));
+            }
+            Ok(operation(file.to_path_buf(), to))
+        });
+        match planned {
+            Ok(planned) => report.run(planned, dry_run),
+            Err(e) => {
+                report.errors.push((
+                    file.to_path_buf(),
+                    FileSearchError::io_error_with_path(e, verb, file),
+                ));
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Fail if something already exists at `target`, even a dangling symlink
+fn ensure_vacant(target: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(target).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("'{}' already exists", target.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `a` and `b` name the same file in one directory, differing only
+/// in case, as on case-insensitive file systems
+///
+/// Symlinks never count, so renaming a link onto its target is still
+/// refused as a collision.
+fn is_case_change(a: &Path, b: &Path) -> bool {
+    use crate::indexer::file_id::{device_id, file_id};
+
+    let (Some(name_a), Some(name_b)) = (
+        a.file_name().and_then(|name| name.to_str()),
+        b.file_name().and_then(|name| name.to_str()),
+    ) else {
+        return false;
+    };
+    let is_plain =
+        |path: &Path| fs::symlink_metadata(path).is_ok_and(|m| !m.file_type().is_symlink());
+    a.parent() == b.parent()
+        && name_a != name_b
+        && name_a.to_lowercase() == name_b.to_lowercase()
+        && is_plain(a)
+        && is_plain(b)
+        && file_id(a).is_some_and(|id| file_id(b) == Some(id))
+        && device_id(a) == device_id(b)
+}
+
+/// Make `to` a link of `kind` to `from`, creating its directory if needed
+fn link(from: &Path, to: &Path, kind: LinkKind) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match kind {
+        LinkKind::Hard => fs::hard_link(from, to),
+        LinkKind::Symbolic => symlink(&from.canonicalize()?, to),
+    }
+}
+
+#[cfg(unix)]
+fn symlink(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink(original: &Path, link: &Path) -> io::Result<()> {
+    if original.is_dir() {
+        std::os::windows::fs::symlink_dir(original, link)
+    } else {
+        std::os::windows::fs::symlink_file(original, link)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_original: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symbolic links are not supported on this platform",
+    ))
+}
+
+/// Rename `from` to `to`, copying and removing the original when the two are
+/// on different file systems
+///
+/// If the original cannot be removed after copying, the copy is removed again
+/// so the file is not left in both places.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if crosses_devices(&e) => {}
+        result => return result,
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from).map_err(|e| {
+        let _ = fs::remove_file(to);
+        e
+    })
+}
+
+/// Whether `error` is a rename failing because its paths are on different
+/// file systems, which `io::ErrorKind` only names from Rust 1.85
+#[cfg(unix)]
+fn crosses_devices(error: &io::Error) -> bool {
+    error.raw_os_error() == Some(libc::EXDEV)
+}
+
+#[cfg(windows)]
+fn crosses_devices(error: &io::Error) -> bool {
+    // ERROR_NOT_SAME_DEVICE
+    error.raw_os_error() == Some(17)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn crosses_devices(_error: &io::Error) -> bool {
+    false
+}
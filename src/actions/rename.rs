@@ -0,0 +1,184 @@
+//! Renaming files from a template
+//!
+//! A [`Rename`] turns each file name into a new one by filling in a template.
+//! `{stem}` stands for the name without its extension, `{ext}` for the
+//! extension without the dot and `{n}` for the file's position in the batch,
+//! counting from 1. A rename built with a regex only applies to names the
+//! regex matches, and its template may also refer to capture groups as `$1`,
+//! `$name` or `${name}`, following [`regex::Captures::expand`]; `$$` stands
+//! for a literal `$`. Files keep their directory, so the new name must not
+//! contain a path separator.
+
+use super::{ensure_vacant, is_case_change, Operation, Report};
+use crate::FileSearchError;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::io;
+use std::path::{self, Path, PathBuf};
+
+#[cfg(feature = "regex")]
+use regex::{Regex, RegexBuilder};
+
+/// Template for new file names
+#[derive(Debug, Clone)]
+pub struct Rename {
+    template: String,
+    #[cfg(feature = "regex")]
+    pattern: Option<Regex>,
+}
+
+impl Rename {
+    /// Rename every file by filling in `template`
+    #[must_use]
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+            #[cfg(feature = "regex")]
+            pattern: None,
+        }
+    }
+
+    /// Rename files whose names match `pattern`, filling its capture groups
+    /// into `template`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pattern` is not a valid regex
+    #[cfg(feature = "regex")]
+    pub fn with_regex(
+        pattern: &str,
+        case_sensitive: bool,
+        template: impl Into<String>,
+    ) -> crate::Result<Self> {
+        let pattern = RegexBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| FileSearchError::regex_error(e, pattern))?;
+        Ok(Self {
+            template: template.into(),
+            pattern: Some(pattern),
+        })
+    }
+
+    /// New name for `file`, the `n`th file of the batch
+    ///
+    /// Returns `None` if `file` has no name that is valid Unicode, or if the
+    /// regex does not match its name.
+    #[must_use]
+    pub fn new_name(&self, file: &Path, n: usize) -> Option<String> {
+        let name = file.file_name()?.to_str()?;
+        let stem = Path::new(name)
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .unwrap_or(name);
+        let ext = Path::new(name)
+            .extension()
+            .and_then(OsStr::to_str)
+            .unwrap_or("");
+        let number = n.to_string();
+        #[cfg(feature = "regex")]
+        let captures = if let Some(pattern) = &self.pattern {
+            Some(pattern.captures(name)?)
+        } else {
+            None
+        };
+
+        let mut expanded = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find(['{', '$']) {
+            let (before, from) = rest.split_at(start);
+            expanded.push_str(before);
+            #[cfg(feature = "regex")]
+            if let (Some(captures), Some(len)) = (&captures, capture_reference(from)) {
+                captures.expand(&from[..len], &mut expanded);
+                rest = &from[len..];
+                continue;
+            }
+            let placeholder = [("{stem}", stem), ("{ext}", ext), ("{n}", &number)]
+                .into_iter()
+                .find(|(placeholder, _)| from.starts_with(placeholder));
+            let len = if let Some((placeholder, value)) = placeholder {
+                expanded.push_str(value);
+                placeholder.len()
+            } else {
+                // '{' and '$' are one byte each
+                expanded.push_str(&from[..1]);
+                1
+            };
+            rest = &from[len..];
+        }
+        expanded.push_str(rest);
+        Some(expanded)
+    }
+}
+
+/// Length of the `$…` capture group reference at the start of `text`
+#[cfg(feature = "regex")]
+fn capture_reference(text: &str) -> Option<usize> {
+    let after = text.strip_prefix('$')?;
+    if after.starts_with('$') {
+        return Some(2);
+    }
+    if let Some(braced) = after.strip_prefix('{') {
+        return braced.find('}').map(|close| close + 3);
+    }
+    let len = after
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(after.len());
+    (len > 0).then_some(len + 1)
+}
+
+/// Rename `files` in place as `rename` describes
+///
+/// Files whose name would not change are left out of the report. A new name
+/// that already exists, or that another file of the batch is also given, is
+/// reported as an error for that file.
+#[must_use]
+pub fn rename<P: AsRef<Path>>(files: &[P], rename: &Rename, dry_run: bool) -> Report {
+    let mut report = Report::default();
+    let mut claimed = HashSet::new();
+    for (i, file) in files.iter().enumerate() {
+        let file = file.as_ref();
+        match plan(file, rename, i + 1, &mut claimed) {
+            Ok(Some(planned)) => report.run(planned, dry_run),
+            Ok(None) => {}
+            Err(e) => report.errors.push((
+                file.to_path_buf(),
+                FileSearchError::io_error_with_path(e, "renaming", file),
+            )),
+        }
+    }
+    report
+}
+
+fn plan(
+    file: &Path,
+    rename: &Rename,
+    n: usize,
+    claimed: &mut HashSet<PathBuf>,
+) -> io::Result<Option<Operation>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let name = rename
+        .new_name(file, n)
+        .ok_or_else(|| invalid("name does not match the rename pattern".to_string()))?;
+    if name.is_empty() || name == "." || name == ".." || name.contains(path::is_separator) {
+        return Err(invalid(format!("'{name}' is not a valid file name")));
+    }
+    let to = file.with_file_name(&name);
+    if to == file {
+        return Ok(None);
+    }
+    if !is_case_change(file, &to) {
+        ensure_vacant(&to)?;
+    }
+    if !claimed.insert(to.clone()) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("another file is also renamed to '{name}'"),
+        ));
+    }
+    Ok(Some(Operation::Rename {
+        from: file.to_path_buf(),
+        to,
+    }))
+}
@@ -0,0 +1,203 @@
+//! Moving files to the desktop trash
+//!
+//! Trashed files can be restored with the platform's own tools. Windows sends
+//! them to the Recycle Bin through PowerShell, macOS asks Finder to trash them,
+//! and other Unix systems follow the freedesktop.org trash specification,
+//! moving the file into the home trash next to a `.trashinfo` file that
+//! records where it came from.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Move `file` to the desktop trash, where it can be restored from
+///
+/// A symlink is trashed itself, leaving its target in place.
+///
+/// # Errors
+///
+/// Returns an error if `file` does not exist or the trash refuses it. With the
+/// freedesktop.org trash, the home trash must be on the same file system as
+/// `file`.
+pub fn move_to_trash(file: &Path) -> io::Result<()> {
+    imp::move_to_trash(&absolute(file)?)
+}
+
+/// `file` below its canonical directory, so that a symlink at `file` itself
+/// is not resolved
+fn absolute(file: &Path) -> io::Result<PathBuf> {
+    std::fs::symlink_metadata(file)?;
+    let name = file
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let parent = match file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Ok(parent.canonicalize()?.join(name))
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use std::io;
+    use std::path::Path;
+    use std::process::Command;
+
+    /// Send `file` to the Recycle Bin through the .NET file system API
+    pub fn move_to_trash(file: &Path) -> io::Result<()> {
+        let status = Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                "Add-Type -AssemblyName Microsoft.VisualBasic; \
+                 [Microsoft.VisualBasic.FileIO.FileSystem]::DeleteFile(\
+                 $env:WHATEVER_FIND_TRASH, 'OnlyErrorDialogs', 'SendToRecycleBin')",
+            ])
+            .env("WHATEVER_FIND_TRASH", file)
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "the Recycle Bin refused the file",
+            ))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::io;
+    use std::path::Path;
+    use std::process::Command;
+
+    /// Ask Finder to move `file` to the Trash, so it can be put back
+    pub fn move_to_trash(file: &Path) -> io::Result<()> {
+        let status = Command::new("osascript")
+            .args([
+                "-e",
+                "on run argv",
+                "-e",
+                "tell application \"Finder\" to delete POSIX file (item 1 of argv)",
+                "-e",
+                "end run",
+            ])
+            .arg(file)
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Finder refused to trash the file",
+            ))
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use crate::indexer::trash::home_trash;
+    use std::fmt::Write as _;
+    use std::fs::{self, OpenOptions};
+    use std::io::{self, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// Move `file` to the home trash of the freedesktop.org trash spec
+    ///
+    /// The trash must be on the same file system as `file`.
+    pub fn move_to_trash(file: &Path) -> io::Result<()> {
+        let trash = home_trash()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        let files = trash.join("files");
+        let info = trash.join("info");
+        fs::create_dir_all(&files)?;
+        fs::create_dir_all(&info)?;
+
+        let name = file
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?
+            .to_string_lossy()
+            .into_owned();
+        // Claim a name by creating its info file, so concurrent trashing
+        // never overwrites another entry
+        let mut candidate = name.clone();
+        let mut attempt = 1;
+        let (info_path, mut info_file) = loop {
+            let info_path = info.join(format!("{candidate}.trashinfo"));
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(info_file) => break (info_path, info_file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    attempt += 1;
+                    candidate = format!("{name}.{attempt}");
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        let written = writeln!(
+            info_file,
+            "[Trash Info]\nPath={}\nDeletionDate={}",
+            percent_encode(file.as_os_str().as_bytes()),
+            local_timestamp()
+        );
+        let moved = written.and_then(|()| fs::rename(file, files.join(&candidate)));
+        if moved.is_err() {
+            let _ = fs::remove_file(&info_path);
+        }
+        moved
+    }
+
+    /// Percent-encode a path as the trash spec requires, keeping `/`
+    fn percent_encode(bytes: &[u8]) -> String {
+        let mut encoded = String::with_capacity(bytes.len());
+        for &byte in bytes {
+            if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+                encoded.push(char::from(byte));
+            } else {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+        encoded
+    }
+
+    /// The current local time as `YYYY-MM-DDThh:mm:ss`
+    fn local_timestamp() -> String {
+        // SAFETY: `time` accepts a null pointer, and `localtime_r` only
+        // writes into `tm`, which lives for the duration of the call
+        let tm = unsafe {
+            let now = libc::time(std::ptr::null_mut());
+            let mut tm: libc::tm = std::mem::zeroed();
+            libc::localtime_r(&now, &mut tm);
+            tm
+        };
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec
+        )
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use std::io;
+    use std::path::Path;
+
+    pub fn move_to_trash(_file: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "no trash on this platform",
+        ))
+    }
+}
@@ -1,9 +1,34 @@
-use clap::{Arg, Command};
+use clap::{Arg, ArgGroup, ArgMatches, Command};
+use std::borrow::Cow;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use whatever_find::{FileSearcher, SearchMode};
+use whatever_find::actions::{self, ArchiveFormat, DeleteMode, Layout, LinkKind, Rename};
+use whatever_find::config::ignore_file::IgnoreFile;
+use whatever_find::config::settings::ConfigManager;
+use whatever_find::config::tilde::expand_tilde;
+use whatever_find::config::{
+    AuditCheck, CloudPlaceholders, FileType, IoThrottle, LengthFilter, LinkCount, PathStyle,
+};
+use whatever_find::events::{self, EventWriter};
+use whatever_find::indexer::attributes::FileAttribute;
+use whatever_find::indexer::database::Database;
+use whatever_find::indexer::diff::DatabaseDiff;
+use whatever_find::indexer::drives::{self, Drive, DriveKind};
+use whatever_find::indexer::git::{GitStatus, GitStatuses};
+use whatever_find::indexer::priority;
+use whatever_find::indexer::scheduler::{IndexRoot, IndexScheduler};
+use whatever_find::indexer::FileIndexer;
+use whatever_find::rpc::RpcServer;
+use whatever_find::search::aggregate::directories_with_at_least;
+use whatever_find::search::history::DirHistory;
+use whatever_find::search::report::SizeReport;
+use whatever_find::search::{validate_query, SearchEngine};
+use whatever_find::{
+    Config, FileIndex, FileMatch, FileSearcher, SearchMode, SearchResults, SortOrder,
+};
 
 fn main() {
     let matches = Command::new("whatever-find")
@@ -24,21 +49,198 @@ Examples:
   whatever-find --regex '^test'      # Force regex mode
   whatever-find --glob 'test_*'      # Force glob mode
   whatever-find test -p /home/user   # Search in specific directory
-  whatever-find --interactive '*.rs' # Interactive mode to select and open files",
+  whatever-find --interactive '*.rs' # Interactive mode to select and open files
+  cd \"$(whatever-find --dirs proj)\"  # Jump to the best matching directory, z-style
+  whatever-find --regex 'IMG_(\\d+)\\.jpg' --rename 'vacation_$1.jpg' --dry-run
+                                     # Preview renaming matches with their capture groups
+  whatever-find --glob '*.log' --archive logs.tar.gz  # Pack every match into one archive
+  whatever-find --events '*.rs'      # Stream progress and matches as JSON lines for a GUI
+  whatever-find index --system       # Build the shared file database for faster searches
+  whatever-find run recent-downloads # Run a search preset saved in the config file
+  whatever-find report ~/data        # Count files and bytes by extension and directory
+  whatever-find diff old.db new.db   # List files added, removed or renamed between snapshots",
+        )
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("index")
+                .about("Build a file database that later searches use instead of walking")
+                .arg(
+                    Arg::new("system")
+                        .long("system")
+                        .help("Write the shared database to its well-known location")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("database")
+                        .long("database")
+                        .help("Write the database to FILE instead")
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("root")
+                        .long("root")
+                        .help("Directory to index (repeatable, default: home directory)")
+                        .value_name("PATH")
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("watch")
+                        .long("watch")
+                        .help("Keep running and refresh each root when its interval elapses")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .help("Refresh interval in seconds for --watch (default: 3600)")
+                        .value_name("SECS")
+                        .value_parser(clap::value_parser!(u64))
+                        .requires("watch"),
+                )
+                .arg(
+                    Arg::new("background")
+                        .long("background")
+                        .help("Run at low CPU and IO priority")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("max-rate")
+                        .long("max-rate")
+                        .help("Visit at most N entries per second to go easy on slow disks")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u32).range(1..)),
+                )
+                .group(
+                    ArgGroup::new("target")
+                        .args(["system", "database"])
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Compare two file database snapshots")
+                .arg(
+                    Arg::new("old")
+                        .help("Older database snapshot")
+                        .value_name("SNAPSHOT_A")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("new")
+                        .help("Newer database snapshot")
+                        .value_name("SNAPSHOT_B")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("pattern")
+                        .help("Only show changes to files matching PATTERN")
+                        .value_name("PATTERN"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the changes as JSON")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("report")
+                .about("Count files and bytes by extension and top-level directory, hidden and ignored files included")
+                .arg(
+                    Arg::new("root")
+                        .help("Directory to report on (default: current directory)")
+                        .value_name("PATH"),
+                )
+                .arg(
+                    Arg::new("pattern")
+                        .long("pattern")
+                        .help("Only count files matching PATTERN")
+                        .value_name("PATTERN"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format")
+                        .value_name("FORMAT")
+                        .value_parser(["table", "json", "csv"])
+                        .default_value("table"),
+                ),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Run a search preset saved in the config file")
+                .arg(
+                    Arg::new("preset")
+                        .help("Name of the preset")
+                        .value_name("NAME")
+                        .required_unless_present("list"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .short('p')
+                        .long("path")
+                        .help("Search PATH instead of the preset's own directory")
+                        .value_name("PATH"),
+                )
+                .arg(
+                    Arg::new("config")
+                        .long("config")
+                        .help("Read presets from FILE instead of the default config file")
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("list")
+                        .long("list")
+                        .help("List the saved presets instead of running one")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["preset", "path"]),
+                ),
         )
         .arg(
             Arg::new("query")
                 .help("Search query")
-                .required(true)
+                .required_unless_present_any([
+                    "why-ignored",
+                    "audit",
+                    "audit-check",
+                    "xattr",
+                    "tag",
+                    "attr",
+                    "links",
+                    "name-len",
+                    "path-len",
+                    "file-id",
+                    "rpc",
+                ])
                 .index(1),
         )
         .arg(
             Arg::new("path")
                 .short('p')
                 .long("path")
-                .help("Search path (default: current directory)")
+                .help("Search path (default: current directory, '*' for all drives)")
                 .value_name("PATH"),
         )
+        .arg(
+            Arg::new("all-drives")
+                .long("all-drives")
+                .help("Search every fixed drive in parallel (same as -p '*')")
+                .conflicts_with("path")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("removable-drives")
+                .long("removable-drives")
+                .help("Also search removable and optical drives when searching all drives")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("network-drives")
+                .long("network-drives")
+                .help("Also search network drives when searching all drives")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("regex")
                 .short('r')
@@ -67,6 +269,66 @@ Examples:
                 .help("Force substring matching (overrides auto-detection)")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("fixed-strings")
+                .short('F')
+                .long("fixed-strings")
+                .help("Match the query exactly as typed, without reading it as a glob or regex")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("word")
+                .short('w')
+                .long("word")
+                .help("Only match whole words of file names in substring searches")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("full-path")
+                .long("full-path")
+                .help("Match glob patterns against whole paths, with ** spanning directories")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("relative-path")
+                .long("relative-path")
+                .help("Match substring, regex and fuzzy queries against paths relative to the search root")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("type")
+                .short('t')
+                .long("type")
+                .help("Kind of entries to find: f for files, d for directories, l for symlinks or a for all")
+                .value_name("TYPE")
+                .value_parser(clap::builder::PossibleValuesParser::new(
+                    FileType::ALL
+                        .into_iter()
+                        .flat_map(|kind| [kind.letter(), kind.name()]),
+                ))
+                .default_value("f"),
+        )
+        .arg(
+            Arg::new("recency")
+                .long("recency")
+                .value_name("WEIGHT")
+                .help("Rank recently modified files higher in fuzzy searches, giving recency this share of the ranking (0.0 to 1.0)")
+                .value_parser(parse_share),
+        )
+        .arg(
+            Arg::new("shallow-first")
+                .long("shallow-first")
+                .help("List shallower paths first for substring, regex and glob searches")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .value_name("ORDER")
+                .help("Order results by relevance, name, depth, size or modified time")
+                .value_parser(SortOrder::ALL.map(SortOrder::name))
+                .default_value("relevance"),
+        )
         .arg(
             Arg::new("interactive")
                 .short('i')
@@ -74,20 +336,585 @@ Examples:
                 .help("Interactive mode - select files to open in explorer")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Write results with their size and modification time to FILE instead of listing them")
+                .value_name("FILE")
+                .conflicts_with("interactive"),
+        )
+        .arg(
+            Arg::new("no-pager")
+                .long("no-pager")
+                .help("Print long result lists directly instead of through $PAGER or less")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("copy-results")
+                .long("copy-results")
+                .help("Also copy the full result list, one path per line, to the clipboard")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("Format of the --output file (default: from its extension, else json)")
+                .value_name("FORMAT")
+                .value_parser(["json", "csv"])
+                .requires("output"),
+        )
+        .arg(
+            Arg::new("rename")
+                .long("rename")
+                .help("Rename results from TEMPLATE, filling in {stem}, {ext}, a running number and groups of a regex query as $1 or ${name}")
+                .value_name("TEMPLATE")
+                .conflicts_with_all(["interactive", "output"]),
+        )
+        .arg(
+            Arg::new("link-to")
+                .long("link-to")
+                .help("Link every result into DIR instead of listing them")
+                .value_name("DIR")
+                .conflicts_with_all(["interactive", "output", "rename"]),
+        )
+        .arg(
+            Arg::new("hardlink")
+                .long("hardlink")
+                .help("Make hard links instead of symbolic links with --link-to")
+                .action(clap::ArgAction::SetTrue)
+                .requires("link-to"),
+        )
+        .arg(
+            Arg::new("preserve-structure")
+                .long("preserve-structure")
+                .help("Recreate the directories below the search path inside the --link-to directory")
+                .action(clap::ArgAction::SetTrue)
+                .requires("link-to"),
+        )
+        .arg(
+            Arg::new("archive")
+                .long("archive")
+                .help("Pack every result into a new .tar, .tar.gz, .tgz or .zip FILE, named relative to the search path")
+                .value_name("FILE")
+                .conflicts_with_all(["interactive", "output", "rename", "link-to"]),
+        )
+        .group(ArgGroup::new("bulk-action").args(["rename", "link-to", "archive"]))
+        .arg(
+            Arg::new("summarize")
+                .long("summarize")
+                .help("Print the number and total size of the results, by extension and directory, instead of listing them")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["interactive", "output", "bulk-action"]),
+        )
+        .arg(
+            Arg::new("min-per-dir")
+                .long("min-per-dir")
+                .help("List the directories directly containing at least N results, with their counts, instead of the results")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .conflicts_with_all(["interactive", "output", "bulk-action", "summarize"]),
+        )
+        .arg(
+            Arg::new("events")
+                .long("events")
+                .help("Index without the file database and print progress, warnings, matches and a final summary as JSON lines, for GUI frontends")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["interactive", "output", "copy-results", "bulk-action", "summarize", "min-per-dir"]),
+        )
+        .arg(
+            Arg::new("count-dirs")
+                .long("count-dirs")
+                .help("Count directories in a quick first pass so --events progress includes a percentage")
+                .action(clap::ArgAction::SetTrue)
+                .requires("events"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Only show what --rename, --link-to or --archive would do")
+                .action(clap::ArgAction::SetTrue)
+                .requires("bulk-action"),
+        )
+        .arg(
+            Arg::new("database")
+                .long("database")
+                .help(
+                    "Search using the file database at FILE (default: shared database if present)",
+                )
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("no-database")
+                .long("no-database")
+                .help("Always walk the file system, even if a file database is available")
+                .conflicts_with("database")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("io-timeout")
+                .long("io-timeout")
+                .help("Skip directories that do not respond within MS milliseconds")
+                .value_name("MS")
+                .value_parser(clap::value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("no-default-ignores")
+                .long("no-default-ignores")
+                .help("Also search files skipped by the built-in ignore patterns (*.log, target, ...)")
+                .conflicts_with("database")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pseudo-fs")
+                .long("pseudo-fs")
+                .help("Also walk into virtual file systems such as /proc, /sys and /dev")
+                .conflicts_with("database")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("one-file-system")
+                .long("one-file-system")
+                .visible_alias("xdev")
+                .help("Stay on the file system of the search path, skipping network shares and other mounts")
+                .conflicts_with("database")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ignore-file")
+                .long("ignore-file")
+                .help("Also skip paths matching the gitignore-style patterns in FILE (repeatable)")
+                .value_name("FILE")
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("no-expand-tilde")
+                .long("no-expand-tilde")
+                .help("Treat a leading ~ in paths and ignore patterns literally")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("gitignore")
+                .long("gitignore")
+                .help("Skip paths ignored by git (.gitignore, .git/info/exclude, core.excludesFile)")
+                .conflicts_with("database")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("submodules")
+                .long("submodules")
+                .help("With --gitignore, also search inside git submodules")
+                .requires("gitignore")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("worktrees")
+                .long("worktrees")
+                .help("With --gitignore, also search inside linked git worktrees")
+                .requires("gitignore")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("git-tracked")
+                .long("git-tracked")
+                .help("Only search files tracked by git")
+                .conflicts_with("database")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("changed-since")
+                .long("changed-since")
+                .help("Only search files added or modified since the git revision REF")
+                .value_name("REF")
+                .conflicts_with("database"),
+        )
+        .arg(
+            Arg::new("audit")
+                .long("audit")
+                .help("Security sweep: list world-writable, setuid/setgid and misowned root files, including hidden ones")
+                .conflicts_with("database")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("audit-check")
+                .long("audit-check")
+                .help("Only list files failing CHECK (repeatable)")
+                .value_name("CHECK")
+                .value_parser(["world-writable", "setid", "root-owned"])
+                .conflicts_with_all(["database", "audit"])
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("xattr")
+                .long("xattr")
+                .help("Only list files carrying the extended attribute NAME (repeatable)")
+                .value_name("NAME")
+                .conflicts_with("database")
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("tag")
+                .long("tag")
+                .help("Only list files with the macOS Finder tag TAG (repeatable)")
+                .value_name("TAG")
+                .conflicts_with("database")
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("attr")
+                .long("attr")
+                .help("Only list files with the storage attribute ATTR (repeatable)")
+                .value_name("ATTR")
+                .value_parser(FileAttribute::ALL.map(FileAttribute::name))
+                .conflicts_with("database")
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("links")
+                .long("links")
+                .help("Only list files with N hard links (+N for more, -N for fewer)")
+                .value_name("N")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(LinkCount))
+                .conflicts_with("database"),
+        )
+        .arg(
+            Arg::new("name-len")
+                .long("name-len")
+                .help("Only list files whose name is N characters long (+N for longer, -N for shorter)")
+                .value_name("N")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(LengthFilter))
+                .conflicts_with("database"),
+        )
+        .arg(
+            Arg::new("path-len")
+                .long("path-len")
+                .help("Only list files whose full path is N characters long (+N for longer, -N for shorter)")
+                .value_name("N")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(LengthFilter))
+                .conflicts_with("database"),
+        )
+        .arg(
+            Arg::new("cloud-placeholders")
+                .long("cloud-placeholders")
+                .help("How to handle online-only OneDrive, iCloud and Dropbox files: skip them, index them by name without downloading them, or include them fully")
+                .value_name("POLICY")
+                .value_parser(CloudPlaceholders::ALL.map(CloudPlaceholders::name))
+                .default_value("name-only"),
+        )
+        .arg(
+            Arg::new("include-trash")
+                .long("include-trash")
+                .help("Also search the trash or Recycle Bin, matching files by the name they had before deletion")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ads")
+                .long("ads")
+                .help("List the NTFS alternate data streams of each result (Windows)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("git-status")
+                .long("git-status")
+                .help("Prefix results with their git status: M modified, ? untracked, ! ignored")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("path-style")
+                .long("path-style")
+                .help("Print result paths with native separators or forward slashes")
+                .value_name("STYLE")
+                .value_parser(PathStyle::ALL.map(PathStyle::name))
+                .default_value("native"),
+        )
+        .arg(
+            Arg::new("file-id")
+                .long("file-id")
+                .help("List the files with inode number or Windows file ID ID")
+                .value_name("ID")
+                .value_parser(clap::value_parser!(u64))
+                .conflicts_with_all(["query", "why-ignored"]),
+        )
+        .arg(
+            Arg::new("dirs")
+                .long("dirs")
+                .help("Print the single best directory matching QUERY, ranked by visit frecency, for shell cd functions")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "regex",
+                    "glob",
+                    "substring",
+                    "interactive",
+                    "database",
+                    "why-ignored",
+                    "file-id",
+                ]),
+        )
+        .arg(
+            Arg::new("rpc")
+                .long("rpc")
+                .help("Serve JSON-RPC requests on stdin/stdout for editor plugins")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["query", "why-ignored", "file-id", "dirs"]),
+        )
+        .arg(
+            Arg::new("why-ignored")
+                .long("why-ignored")
+                .help("Explain which rule, if any, keeps PATH out of the results")
+                .value_name("PATH")
+                .conflicts_with("query"),
+        )
+        .arg(
+            Arg::new("explain-mode")
+                .long("explain-mode")
+                .help("Explain which search mode auto-detection picks for the query, and why")
+                .action(clap::ArgAction::SetTrue),
+        )
         .get_matches();
 
-    let query = matches.get_one::<String>("query").unwrap();
-    let search_path = matches
-        .get_one::<String>("path")
-        .map(|s| s.as_str())
-        .unwrap_or(".");
+    match matches.subcommand() {
+        Some(("index", index_matches)) => {
+            if let Err(e) = run_index(index_matches) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Some(("diff", diff_matches)) => {
+            if let Err(e) = run_diff(diff_matches) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Some(("report", report_matches)) => {
+            if let Err(e) = run_report(report_matches) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Some(("run", run_matches)) => {
+            if let Err(e) = run_preset(run_matches) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    if matches.get_flag("rpc") {
+        let stdin = io::stdin();
+        if let Err(e) = RpcServer::new().serve(stdin.lock(), io::stdout().lock()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let expand = !matches.get_flag("no-expand-tilde");
+    let search_path = expand_path(
+        matches
+            .get_one::<String>("path")
+            .map(|s| s.as_str())
+            .unwrap_or("."),
+        expand,
+    );
+    let search_path = search_path.as_ref();
     let use_regex = matches.get_flag("regex");
     let use_fuzzy = matches.get_flag("fuzzy");
     let use_glob = matches.get_flag("glob");
     let use_substring = matches.get_flag("substring");
+    let use_literal = matches.get_flag("fixed-strings");
     let interactive = matches.get_flag("interactive");
+    let database = matches
+        .get_one::<String>("database")
+        .map(|path| PathBuf::from(expand_path(path, expand).as_ref()));
+    let no_default_ignores = matches.get_flag("no-default-ignores");
+    let respect_gitignore = matches.get_flag("gitignore");
+    let git_tracked = matches.get_flag("git-tracked");
+    let changed_since = matches.get_one::<String>("changed-since").cloned();
+    let audit = matches.get_flag("audit");
+    let audit_checks: Vec<AuditCheck> = if audit {
+        AuditCheck::ALL.to_vec()
+    } else {
+        matches
+            .get_many::<String>("audit-check")
+            .into_iter()
+            .flatten()
+            .map(|check| match check.as_str() {
+                "world-writable" => AuditCheck::WorldWritable,
+                "setid" => AuditCheck::SetId,
+                _ => AuditCheck::RootOwnedInUserDir,
+            })
+            .collect()
+    };
+    let required_xattrs: Vec<String> = matches
+        .get_many::<String>("xattr")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    let required_tags: Vec<String> = matches
+        .get_many::<String>("tag")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    let required_attributes: Vec<FileAttribute> = matches
+        .get_many::<String>("attr")
+        .into_iter()
+        .flatten()
+        .filter_map(|name| {
+            FileAttribute::ALL
+                .into_iter()
+                .find(|attribute| attribute.name() == name)
+        })
+        .collect();
+    // The file database is built with the default ignores, so it cannot help here
+    let no_database = matches.get_flag("no-database")
+        || no_default_ignores
+        || matches.get_flag("pseudo-fs")
+        || respect_gitignore
+        || git_tracked
+        || changed_since.is_some()
+        || !audit_checks.is_empty()
+        || !required_xattrs.is_empty()
+        || !required_tags.is_empty()
+        || !required_attributes.is_empty()
+        || matches.contains_id("links")
+        || matches.contains_id("name-len")
+        || matches.contains_id("path-len")
+        || matches.get_flag("include-trash");
+
+    let mut config = Config {
+        io_timeout: matches
+            .get_one::<u64>("io-timeout")
+            .map(|ms| Duration::from_millis(*ms)),
+        ignore_patterns: if no_default_ignores {
+            Vec::new()
+        } else {
+            Config::default().ignore_patterns
+        },
+        pseudo_filesystems: if matches.get_flag("pseudo-fs") {
+            Vec::new()
+        } else {
+            Config::default().pseudo_filesystems
+        },
+        same_file_system: matches.get_flag("one-file-system"),
+        respect_gitignore,
+        git_submodules: matches.get_flag("submodules"),
+        git_worktrees: matches.get_flag("worktrees"),
+        git_tracked,
+        changed_since,
+        audit_checks,
+        required_xattrs,
+        required_tags,
+        link_count: matches.get_one::<LinkCount>("links").copied(),
+        name_length: matches.get_one::<LengthFilter>("name-len").copied(),
+        path_length: matches.get_one::<LengthFilter>("path-len").copied(),
+        alternate_streams: matches.get_flag("ads"),
+        include_trash: matches.get_flag("include-trash"),
+        shallow_first: matches.get_flag("shallow-first"),
+        sort: matches
+            .get_one::<String>("sort")
+            .and_then(|name| {
+                SortOrder::ALL
+                    .into_iter()
+                    .find(|order| order.name() == name)
+            })
+            .unwrap_or_default(),
+        word_boundaries: matches.get_flag("word"),
+        match_full_path: matches.get_flag("full-path"),
+        match_relative_path: matches.get_flag("relative-path"),
+        file_type: matches
+            .get_one::<String>("type")
+            .and_then(|name| {
+                FileType::ALL
+                    .into_iter()
+                    .find(|kind| kind.letter() == name || kind.name() == name)
+            })
+            .unwrap_or_default(),
+        recency_weight: matches.get_one::<f64>("recency").copied().unwrap_or(0.0),
+        cloud_placeholders: matches
+            .get_one::<String>("cloud-placeholders")
+            .and_then(|name| {
+                CloudPlaceholders::ALL
+                    .into_iter()
+                    .find(|policy| policy.name() == name)
+            })
+            .unwrap_or_default(),
+        git_status: matches.get_flag("git-status"),
+        path_style: match matches.get_one::<String>("path-style").map(String::as_str) {
+            Some("forward") => PathStyle::Forward,
+            _ => PathStyle::Native,
+        },
+        required_attributes,
+        ignore_hidden: !audit,
+        expand_tilde: expand,
+        ..Config::default()
+    };
+    for path in matches
+        .get_many::<String>("ignore-file")
+        .into_iter()
+        .flatten()
+    {
+        let path = expand_path(path, expand);
+        match IgnoreFile::load(Path::new(path.as_ref())) {
+            Ok(ignore_file) => {
+                for line in &ignore_file.unsupported {
+                    eprintln!("Warning: unsupported pattern '{}' in '{}'", line, path);
+                }
+                config.ignore_patterns.extend(ignore_file.patterns);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
 
-    let search_modes = [use_regex, use_fuzzy, use_glob, use_substring];
+    if let Some(path) = matches.get_one::<String>("why-ignored") {
+        let path = expand_path(path, expand);
+        explain_ignore(Path::new(search_path), Path::new(path.as_ref()), config);
+        return;
+    }
+    if matches.get_flag("dirs") {
+        let query = matches
+            .get_one::<String>("query")
+            .map_or("", String::as_str);
+        if let Err(e) = run_jump(query, search_path, config) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    if let Some(id) = matches.get_one::<u64>("file-id") {
+        let style = config.path_style;
+        match FileSearcher::with_config(config).find_by_file_id(Path::new(search_path), *id) {
+            Ok(files) if files.is_empty() => println!("No files found with ID {}", id),
+            Ok(files) => {
+                for file in files {
+                    println!("{}", style.display(&file));
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    let query = matches
+        .get_one::<String>("query")
+        .map(String::as_str)
+        .unwrap_or("");
+
+    let search_modes = [use_regex, use_fuzzy, use_glob, use_substring, use_literal];
     let active_modes = search_modes.iter().filter(|&&x| x).count();
 
     if active_modes > 1 {
@@ -103,27 +930,1006 @@ Examples:
         Some(SearchMode::Glob)
     } else if use_substring {
         Some(SearchMode::Substring)
+    } else if use_literal {
+        Some(SearchMode::Literal)
     } else {
         None // Use auto-detection
     };
 
-    if let Err(e) = run_search(query, search_path, force_mode, interactive) {
+    if matches.get_flag("explain-mode") {
+        println!("{}", SearchEngine::new(config).explain_detection(query));
+        return;
+    }
+
+    if matches.get_flag("events") {
+        let mode = force_mode
+            .unwrap_or_else(|| SearchEngine::new(config.clone()).detect_search_mode(query));
+        let mut events = EventWriter::new(io::stdout().lock());
+        if let Err(e) = events::search(
+            Path::new(search_path),
+            query,
+            mode,
+            &config,
+            matches.get_flag("count-dirs"),
+            &mut events,
+        ) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let database = if no_database {
+        None
+    } else {
+        database.or_else(|| Database::default_path().ok())
+    };
+    let drives: Option<Vec<Drive>> =
+        (matches.get_flag("all-drives") || search_path == "*").then(|| {
+            drives::available()
+                .into_iter()
+                .filter(|drive| match drive.kind {
+                    DriveKind::Removable | DriveKind::Optical => {
+                        matches.get_flag("removable-drives")
+                    }
+                    DriveKind::Network => matches.get_flag("network-drives"),
+                    kind => kind.searched_by_default(),
+                })
+                .collect()
+        });
+    let search_path = if drives.is_some() { "*" } else { search_path };
+    let candidates = match &drives {
+        Some(drives) => Candidates::Drives(drives),
+        None => Candidates::Path(database.as_deref()),
+    };
+
+    let output = matches.get_one::<String>("output").map(|path| {
+        let path = PathBuf::from(expand_path(path, expand).as_ref());
+        let format = match matches.get_one::<String>("format").map(String::as_str) {
+            Some("csv") => OutputFormat::Csv,
+            Some(_) => OutputFormat::Json,
+            None if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("csv")) =>
+            {
+                OutputFormat::Csv
+            }
+            None => OutputFormat::Json,
+        };
+        OutputFile { path, format }
+    });
+
+    let bulk_action = if let Some(template) = matches.get_one::<String>("rename") {
+        Some(BulkAction::Rename(template.clone()))
+    } else if let Some(file) = matches.get_one::<String>("archive") {
+        let path = PathBuf::from(expand_path(file, expand).as_ref());
+        let Some(format) = ArchiveFormat::from_path(&path) else {
+            eprintln!("Error: --archive needs a .tar, .tar.gz, .tgz or .zip file name");
+            process::exit(1);
+        };
+        Some(BulkAction::Archive { path, format })
+    } else {
+        matches
+            .get_one::<String>("link-to")
+            .map(|dir| BulkAction::Link {
+                dir: PathBuf::from(expand_path(dir, expand).as_ref()),
+                kind: if matches.get_flag("hardlink") {
+                    LinkKind::Hard
+                } else {
+                    LinkKind::Symbolic
+                },
+                preserve: matches.get_flag("preserve-structure"),
+            })
+    };
+
+    if let Err(e) = run_search(
+        query,
+        search_path,
+        force_mode,
+        interactive,
+        Outputs {
+            file: output.as_ref(),
+            clipboard: matches.get_flag("copy-results"),
+            action: bulk_action.as_ref(),
+            dry_run: matches.get_flag("dry-run"),
+            summarize: matches.get_flag("summarize"),
+            min_per_dir: matches.get_one::<usize>("min-per-dir").copied(),
+            pager: !matches.get_flag("no-pager"),
+        },
+        candidates,
+        config,
+    ) {
         eprintln!("Error: {}", e);
         process::exit(1);
     }
 }
 
+/// Print the best directory for `query` and remember the visit
+///
+/// Only the path is printed, so a shell function can `cd` into it.
+fn run_jump(query: &str, path: &str, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let history_path = DirHistory::default_path()?;
+    let mut history = DirHistory::load(&history_path)?;
+    let dirs = FileIndexer::new(config.clone()).build_dir_index(path)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let engine = SearchEngine::new(config);
+    let dir = history
+        .jump(&engine, &dirs, query, now)
+        .ok_or_else(|| format!("No directory found matching '{}'", query))?;
+    history.record(&dir, now);
+    history.save(&history_path)?;
+    println!("{}", engine.config().path_style.display(&dir));
+    Ok(())
+}
+
+/// Parse a share between 0.0 and 1.0
+fn parse_share(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(share) if (0.0..=1.0).contains(&share) => Ok(share),
+        _ => Err("expected a number between 0.0 and 1.0".to_string()),
+    }
+}
+
+/// `path` with a leading `~` or `~user` expanded, unless disabled
+fn expand_path(path: &str, enabled: bool) -> Cow<'_, str> {
+    if enabled {
+        expand_tilde(path)
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+fn explain_ignore(search_path: &Path, path: &Path, config: Config) {
+    let searcher = FileSearcher::with_config(config);
+    // Compare absolute paths so relative arguments line up with the search root
+    let root = search_path
+        .canonicalize()
+        .unwrap_or_else(|_| search_path.to_path_buf());
+    let target = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    if !target.exists() {
+        println!("'{}' does not exist", path.display());
+    } else if !target.starts_with(&root) {
+        println!(
+            "'{}' is outside the search path '{}'",
+            path.display(),
+            search_path.display()
+        );
+    } else {
+        match searcher.explain_ignore(&root, &target) {
+            Some(reason) => println!("'{}' is ignored: {}", path.display(), reason),
+            None => println!("'{}' is not ignored", path.display()),
+        }
+    }
+}
+
+fn run_index(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let database_path = match matches.get_one::<String>("database") {
+        Some(path) => PathBuf::from(expand_tilde(path).as_ref()),
+        None => Database::default_path()?,
+    };
+    let roots: Vec<PathBuf> = match matches.get_many::<String>("root") {
+        Some(roots) => roots
+            .map(|root| PathBuf::from(expand_tilde(root).as_ref()))
+            .collect(),
+        None => vec![dirs::home_dir().ok_or("Could not determine home directory")?],
+    };
+
+    let config = Config {
+        io_throttle: matches
+            .get_one::<u32>("max-rate")
+            .map(|rate| IoThrottle::EntriesPerSecond(*rate)),
+        background_priority: matches.get_flag("background"),
+        ..Config::default()
+    };
+    let background = config.background_priority.then(priority::enter_background);
+    if background
+        .as_ref()
+        .is_some_and(|background| !background.is_lowered())
+    {
+        eprintln!("Warning: could not lower indexing priority on this platform");
+    }
+
+    if matches.get_flag("watch") {
+        let interval = Duration::from_secs(*matches.get_one::<u64>("interval").unwrap_or(&3600));
+        let mut scheduler = IndexScheduler::new(config);
+        for root in roots {
+            scheduler.add_root(IndexRoot::new(root).refresh_interval(interval));
+        }
+        let mut database = if database_path.exists() {
+            Database::load(&database_path)?
+        } else {
+            Database::new()
+        };
+        println!(
+            "Watching {} root(s), refreshing '{}' every {}s (Ctrl+C to stop)",
+            scheduler.roots().len(),
+            database_path.display(),
+            interval.as_secs()
+        );
+        scheduler.run(&mut database, &database_path, || false)?;
+        return Ok(());
+    }
+
+    for root in &roots {
+        println!("Indexing '{}'...", root.display());
+    }
+    let database = Database::build(&roots, &config)?;
+    database.save(&database_path)?;
+    println!(
+        "Indexed {} file(s) into '{}'",
+        database.len(),
+        database_path.display()
+    );
+
+    Ok(())
+}
+
+fn run_diff(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let old = Database::load(Path::new(
+        expand_tilde(matches.get_one::<String>("old").unwrap()).as_ref(),
+    ))?;
+    let new = Database::load(Path::new(
+        expand_tilde(matches.get_one::<String>("new").unwrap()).as_ref(),
+    ))?;
+
+    let mut diff = DatabaseDiff::between(&old, &new);
+    if let Some(pattern) = matches.get_one::<String>("pattern") {
+        diff = diff.matching(&SearchEngine::new(Config::default()), pattern)?;
+    }
+
+    if matches.get_flag("json") {
+        let renamed: Vec<_> = diff
+            .renamed
+            .iter()
+            .map(|(from, to)| serde_json::json!({ "from": from, "to": to }))
+            .collect();
+        let output = serde_json::json!({
+            "added": diff.added,
+            "removed": diff.removed,
+            "renamed": renamed,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    for path in &diff.added {
+        println!("+ {}", path.display());
+    }
+    for path in &diff.removed {
+        println!("- {}", path.display());
+    }
+    for (from, to) in &diff.renamed {
+        println!("~ {} -> {}", from.display(), to.display());
+    }
+    println!(
+        "{} added, {} removed, {} renamed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.renamed.len()
+    );
+
+    Ok(())
+}
+
+fn run_report(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let root = PathBuf::from(
+        expand_tilde(
+            matches
+                .get_one::<String>("root")
+                .map_or(".", String::as_str),
+        )
+        .as_ref(),
+    );
+    // An inventory counts everything that takes up space
+    let searcher = FileSearcher::with_config(Config {
+        ignore_hidden: false,
+        ignore_patterns: Vec::new(),
+        ..Config::default()
+    });
+    let report = match matches.get_one::<String>("pattern") {
+        Some(pattern) => {
+            let mode = SearchEngine::new(searcher.config().clone()).detect_search_mode(pattern);
+            SizeReport::new(&root, &searcher.search_results(&root, pattern, mode)?)
+        }
+        None => searcher.size_report(&root)?,
+    };
+
+    let mut stdout = io::stdout().lock();
+    match matches.get_one::<String>("format").map(String::as_str) {
+        Some("json") => {
+            serde_json::to_writer_pretty(&mut stdout, &report)?;
+            writeln!(stdout)?;
+        }
+        Some("csv") => report.write_csv(&mut stdout)?,
+        _ => report.write_table(&mut stdout)?,
+    }
+    Ok(())
+}
+
+fn run_preset(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let config = match matches.get_one::<String>("config") {
+        Some(file) => {
+            let mut config = Config::load_from_file(&PathBuf::from(expand_tilde(file).as_ref()))?;
+            config.expand_env_vars();
+            config
+        }
+        None => ConfigManager::new()?.config().clone(),
+    };
+
+    if matches.get_flag("list") {
+        if config.presets.is_empty() {
+            println!("No presets saved");
+        }
+        for (name, preset) in &config.presets {
+            println!("{}: {}", name, preset.query);
+        }
+        return Ok(());
+    }
+
+    let name = matches
+        .get_one::<String>("preset")
+        .map_or("", String::as_str);
+    let style = config.path_style;
+    let searcher = FileSearcher::with_config(config);
+    let results = match matches.get_one::<String>("path") {
+        Some(path) => {
+            let preset = searcher
+                .config()
+                .presets
+                .get(name)
+                .ok_or_else(|| format!("No preset named '{}'", name))?;
+            searcher.search_preset(Path::new(expand_tilde(path).as_ref()), preset)?
+        }
+        None => searcher.run_preset(name)?,
+    };
+    for file in results {
+        println!("{}", style.display(&file));
+    }
+    Ok(())
+}
+
+/// What to search
+enum Candidates<'a> {
+    /// The search path, through the file database at this path if it covers it
+    Path(Option<&'a Path>),
+    /// These drives, walked in parallel
+    Drives(&'a [Drive]),
+}
+
+/// Where search candidates come from: a prebuilt file database or a fresh walk
+enum FileSource {
+    Database(Database),
+    Index(FileIndex),
+}
+
+impl FileSource {
+    /// Open the file database when it covers `path`, otherwise index `path`
+    /// or the drives directly
+    fn open(
+        path: &Path,
+        config: &Config,
+        candidates: Candidates<'_>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let database = match candidates {
+            Candidates::Path(database) => database,
+            Candidates::Drives(drives) => {
+                let (index, failed) = drives::build_index(drives, config);
+                for (root, e) in failed {
+                    eprintln!("Warning: skipped drive '{}': {}", root.display(), e);
+                }
+                return Ok(Self::Index(index));
+            }
+        };
+        if let Some(database_path) = database.filter(|p| p.exists()) {
+            let database = Database::load(database_path)?;
+            if database.covers(path) {
+                println!("Using file database '{}'", database_path.display());
+                return Ok(Self::Database(database));
+            }
+        }
+
+        let mut indexer = FileIndexer::new(config.clone());
+        let root = path.to_str().ok_or("Search path contains invalid UTF-8")?;
+        let index = indexer.build_index(root)?;
+        for skipped in &indexer.stats().timed_out {
+            eprintln!(
+                "Warning: skipped '{}' (no response within IO timeout)",
+                skipped.display()
+            );
+        }
+        Ok(Self::Index(index))
+    }
+
+    fn search(
+        &self,
+        engine: &SearchEngine,
+        path: &Path,
+        query: &str,
+        mode: SearchMode,
+    ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        Ok(match self {
+            Self::Database(database) => database.search(path, query, mode, engine.config())?,
+            Self::Index(index) => engine.search(index, query, mode)?,
+        })
+    }
+
+    fn search_fuzzy(
+        &self,
+        engine: &SearchEngine,
+        path: &Path,
+        query: &str,
+    ) -> Result<Vec<(PathBuf, f64)>, Box<dyn std::error::Error>> {
+        Ok(match self {
+            Self::Database(database) => database.search_fuzzy(path, query, engine.config())?,
+            Self::Index(index) => engine.search_fuzzy(index, query),
+        })
+    }
+}
+
+/// Point out the default ignore patterns when they may have hidden the results
+fn print_ignore_hint(config: &Config) {
+    let defaults: Vec<String> = Config::default()
+        .ignore_patterns
+        .into_iter()
+        .filter(|pattern| config.ignore_patterns.contains(pattern))
+        .collect();
+    if !defaults.is_empty() {
+        eprintln!(
+            "Note: files matching the default ignore patterns ({}) were skipped; \
+             use --no-default-ignores to include them",
+            defaults.join(", ")
+        );
+    }
+}
+
+/// List the alternate data streams of `file` and where it was trashed from
+/// below it, if requested
+fn print_details(out: &mut impl Write, file: &Path, config: &Config) -> io::Result<()> {
+    if !config.alternate_streams && !config.include_trash {
+        return Ok(());
+    }
+    let file_match = FileMatch::new(file.to_path_buf(), config);
+    for stream in &file_match.alternate_streams {
+        writeln!(out, "      :{} ({} bytes)", stream.name, stream.size)?;
+    }
+    if let Some(original) = &file_match.trashed_from {
+        writeln!(
+            out,
+            "      in the trash, deleted from {}",
+            config.path_style.display(original)
+        )?;
+    }
+    Ok(())
+}
+
+/// Where a result listing goes: stdout, or a pager when the listing would
+/// scroll off the screen
+enum Listing {
+    Stdout(io::Stdout),
+    Pager(process::Child),
+}
+
+impl Listing {
+    /// Page a listing of about `lines` lines through `$PAGER`, or `less -R`
+    /// if unset, when `paging` is enabled, stdout is a terminal and the
+    /// listing does not fit on it
+    fn open(lines: usize, paging: bool) -> Self {
+        use std::io::IsTerminal;
+
+        let fits = crossterm::terminal::size().map_or(true, |(_, rows)| lines < usize::from(rows));
+        if !paging || fits || !io::stdout().is_terminal() {
+            return Self::Stdout(io::stdout());
+        }
+        let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+        let mut words = pager.split_whitespace();
+        let Some(program) = words.next().filter(|&program| program != "cat") else {
+            return Self::Stdout(io::stdout());
+        };
+        let mut command = process::Command::new(program);
+        command.args(words).stdin(process::Stdio::piped());
+        if std::env::var_os("LESS").is_none() {
+            // Show the git status colors and leave the listing on screen
+            command.env("LESS", "FRX");
+        }
+        command
+            .spawn()
+            .map_or_else(|_| Self::Stdout(io::stdout()), Self::Pager)
+    }
+
+    /// Wait for the user to leave the pager
+    fn finish(self) -> io::Result<()> {
+        if let Self::Pager(mut child) = self {
+            drop(child.stdin.take());
+            child.wait()?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for Listing {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Stdout(stdout) => stdout.write(buf),
+            Self::Pager(child) => match child.stdin.as_mut().map(|stdin| stdin.write(buf)) {
+                // The user quit the pager before reaching the end
+                Some(Err(e)) if e.kind() == io::ErrorKind::BrokenPipe => Ok(buf.len()),
+                Some(result) => result,
+                None => Ok(buf.len()),
+            },
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::Pager(child) => match child.stdin.as_mut().map(Write::flush) {
+                Some(Err(e)) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                Some(result) => result,
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// Colored git status code and a space, or nothing unless requested
+fn status_column(statuses: &mut GitStatuses, file: &Path, config: &Config) -> String {
+    use crossterm::style::Stylize;
+    use std::io::IsTerminal;
+
+    if !config.git_status {
+        return String::new();
+    }
+    let status = statuses.status(file);
+    let code = status.map_or(' ', GitStatus::code).to_string();
+    if !io::stdout().is_terminal() {
+        return format!("{code} ");
+    }
+    let code = match status {
+        Some(GitStatus::Modified) => code.yellow(),
+        Some(GitStatus::Untracked) => code.red(),
+        Some(GitStatus::Ignored) => code.dark_grey(),
+        Some(GitStatus::Clean) | None => code.reset(),
+    };
+    format!("{code} ")
+}
+
+fn mode_name(mode: SearchMode) -> &'static str {
+    match mode {
+        SearchMode::Regex => "regex",
+        SearchMode::Glob => "glob",
+        SearchMode::Substring => "substring",
+        SearchMode::Literal => "literal",
+        SearchMode::Fuzzy => "fuzzy",
+        SearchMode::Custom => "custom",
+    }
+}
+
+/// File format written by `--output`
+#[derive(Clone, Copy)]
+enum OutputFormat {
+    Json,
+    Csv,
+}
+
+/// Destination of `--output`
+struct OutputFile {
+    path: PathBuf,
+    format: OutputFormat,
+}
+
+/// Where results go besides the listing on stdout
+struct Outputs<'a> {
+    /// Write them to a file instead of listing them
+    file: Option<&'a OutputFile>,
+    /// Also copy them to the clipboard
+    clipboard: bool,
+    /// Apply a file operation to them instead of listing them
+    action: Option<&'a BulkAction>,
+    /// Only show what `action` would do
+    dry_run: bool,
+    /// Print size totals instead of listing them
+    summarize: bool,
+    /// List the directories holding at least this many of them instead
+    min_per_dir: Option<usize>,
+    /// Page long listings
+    pager: bool,
+}
+
+/// Number of groups listed by `--summarize`
+const SUMMARY_GROUPS: usize = 10;
+
+/// Print how many `files` there are and how large they are, in total and
+/// for the largest extensions and directories
+fn print_summary(files: Vec<PathBuf>, config: &Config) {
+    let results = SearchResults::new(files);
+    println!(
+        "{} files, {}",
+        format_count(results.len()),
+        format_size(results.total_size())
+    );
+    if results.is_empty() {
+        return;
+    }
+
+    let mut extensions: Vec<_> = results.size_by_extension().into_iter().collect();
+    extensions.sort_by_key(|(_, total)| std::cmp::Reverse(total.bytes));
+    println!("By extension:");
+    for (extension, total) in extensions.iter().take(SUMMARY_GROUPS) {
+        let extension = if extension.is_empty() {
+            "(none)".to_string()
+        } else {
+            format!(".{}", extension)
+        };
+        println!(
+            "  {:>10}  {:>8} files  {}",
+            format_size(total.bytes),
+            format_count(total.files),
+            extension
+        );
+    }
+
+    let mut dirs: Vec<_> = results.size_by_directory().into_iter().collect();
+    dirs.sort_by_key(|(_, total)| std::cmp::Reverse(total.bytes));
+    println!("By directory:");
+    for (dir, total) in dirs.iter().take(SUMMARY_GROUPS) {
+        println!(
+            "  {:>10}  {:>8} files  {}",
+            format_size(total.bytes),
+            format_count(total.files),
+            config.path_style.display(dir)
+        );
+    }
+}
+
+/// List the directories directly containing at least `min_files` of
+/// `files`, those with the most first
+fn print_directories(files: &[PathBuf], min_files: usize, config: &Config) {
+    let dirs = directories_with_at_least(files, min_files);
+    if dirs.is_empty() {
+        println!("No directory holds {} or more results", min_files);
+        return;
+    }
+    for dir in dirs {
+        println!(
+            "  {:>8} files  {}",
+            format_count(dir.files),
+            config.path_style.display(&dir.path)
+        );
+    }
+}
+
+/// `n` with thousands separators, like `1,234`
+fn format_count(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// `bytes` in decimal units with one decimal, like `5.6 GB`
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    // Precision loss is far below the one decimal shown
+    #[allow(clippy::cast_precision_loss)]
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 999.95 && unit + 1 < UNITS.len() {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// File operation applied to every result from the command line
+enum BulkAction {
+    /// `--rename TEMPLATE`
+    Rename(String),
+    /// `--link-to DIR`
+    Link {
+        dir: PathBuf,
+        kind: LinkKind,
+        preserve: bool,
+    },
+    /// `--archive FILE`
+    Archive {
+        path: PathBuf,
+        format: ArchiveFormat,
+    },
+}
+
+/// Apply `action` to `files` found for `query` below `search_path`, listing
+/// each operation
+///
+/// A rename fills in capture groups of `query` when it is a regex.
+fn run_bulk_action(
+    action: &BulkAction,
+    dry_run: bool,
+    query: &str,
+    mode: SearchMode,
+    search_path: &Path,
+    files: &[PathBuf],
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (report, verb, done) = match action {
+        BulkAction::Rename(template) => {
+            let renamer = if mode == SearchMode::Regex {
+                Rename::with_regex(query, config.case_sensitive, template.as_str())?
+            } else {
+                Rename::new(template.as_str())
+            };
+            (
+                actions::rename(files, &renamer, dry_run),
+                "rename",
+                "renamed",
+            )
+        }
+        BulkAction::Link {
+            dir,
+            kind,
+            preserve,
+        } => {
+            let layout = if *preserve {
+                Layout::Preserve(results_root(search_path, files)?)
+            } else {
+                Layout::Flatten
+            };
+            (
+                actions::link_to(files, dir, *kind, &layout, dry_run)?,
+                "link",
+                "linked",
+            )
+        }
+        BulkAction::Archive { path, format } => {
+            let root = results_root(search_path, files)?;
+            (
+                actions::archive(files, path, &root, *format, dry_run)?,
+                "archive",
+                "archived",
+            )
+        }
+    };
+    for operation in &report.operations {
+        if let Some(to) = operation.target() {
+            println!(
+                "  {} -> {}",
+                config.path_style.display(operation.source()),
+                config.path_style.display(to)
+            );
+        }
+    }
+    for (_, e) in &report.errors {
+        eprintln!("Error: {}", e);
+    }
+    if dry_run {
+        println!("Would {} {} file(s).", verb, report.operations.len());
+    } else {
+        println!("Done: {} {} file(s).", done, report.operations.len());
+    }
+    if !report.is_success() {
+        return Err(format!("{} file(s) could not be {}", report.errors.len(), done).into());
+    }
+    Ok(())
+}
+
+/// Directory the results are named relative to, which is the search path
+///
+/// Database results are absolute even for a relative search path, so the
+/// search path is made absolute to match them.
+fn results_root(search_path: &Path, files: &[PathBuf]) -> io::Result<PathBuf> {
+    match files.first() {
+        Some(first) if first.is_absolute() && search_path.is_relative() => {
+            search_path.canonicalize()
+        }
+        _ => Ok(search_path.to_path_buf()),
+    }
+}
+
+/// Copy every result path, one per line, to the system clipboard
+///
+/// Failing to copy only warns, since the results are still listed.
+fn copy_results<'a>(files: impl IntoIterator<Item = &'a PathBuf>, config: &Config) {
+    let mut text = String::new();
+    for file in files {
+        text.push_str(&config.path_style.display(file));
+        text.push('\n');
+    }
+    match copy_to_clipboard(&text) {
+        Ok(()) => eprintln!("Copied {} path(s) to the clipboard", text.lines().count()),
+        Err(e) => eprintln!("Warning: could not copy results to the clipboard: {}", e),
+    }
+}
+
+/// Pipe `text` into the first clipboard tool available on this platform
+fn copy_to_clipboard(text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use std::process::{Command, Stdio};
+
+    let tools: &[(&str, &[&str])] = if cfg!(target_os = "windows") {
+        &[("clip", &[])]
+    } else if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    };
+
+    for (tool, args) in tools {
+        let Ok(mut child) = Command::new(tool)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    let names: Vec<&str> = tools.iter().map(|(tool, _)| *tool).collect();
+    Err(format!("No clipboard tool available (tried {})", names.join(", ")).into())
+}
+
+/// Write `results`, with fuzzy scores if any, to `output` and report on stderr
+fn write_output(
+    output: &OutputFile,
+    query: &str,
+    mode: SearchMode,
+    results: &[(PathBuf, Option<f64>)],
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut statuses = GitStatuses::default();
+    let rows: Vec<serde_json::Value> = results
+        .iter()
+        .map(|(file, score)| {
+            let metadata = std::fs::metadata(file).ok();
+            let modified = metadata
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|age| age.as_secs());
+            let mut row = serde_json::json!({
+                "path": config.path_style.display(file),
+                "size": metadata.map(|m| m.len()),
+                "modified": modified,
+            });
+            if let Some(score) = score {
+                row["score"] = serde_json::json!(score);
+            }
+            if config.git_status {
+                let status = statuses.status(file).map(|s| s.code().to_string());
+                row["git_status"] = serde_json::json!(status);
+            }
+            row
+        })
+        .collect();
+
+    let file = std::fs::File::create(&output.path)?;
+    let mut writer = io::BufWriter::new(file);
+    match output.format {
+        OutputFormat::Json => {
+            let document = serde_json::json!({
+                "query": query,
+                "mode": mode_name(mode),
+                "count": rows.len(),
+                "results": rows,
+            });
+            serde_json::to_writer_pretty(&mut writer, &document)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Csv => {
+            let mut columns = vec!["path", "size", "modified"];
+            if results.iter().any(|(_, score)| score.is_some()) {
+                columns.push("score");
+            }
+            if config.git_status {
+                columns.push("git_status");
+            }
+            writeln!(writer, "{}", columns.join(","))?;
+            for row in &rows {
+                let fields: Vec<String> = columns
+                    .iter()
+                    .map(|column| match &row[*column] {
+                        serde_json::Value::Null => String::new(),
+                        serde_json::Value::String(text) => csv_field(text),
+                        value => value.to_string(),
+                    })
+                    .collect();
+                writeln!(writer, "{}", fields.join(","))?;
+            }
+        }
+    }
+    writer.flush()?;
+
+    eprintln!(
+        "Wrote {} result(s) for '{}' to '{}'",
+        rows.len(),
+        query,
+        output.path.display()
+    );
+    Ok(())
+}
+
+/// `text` quoted for a CSV file if it contains a delimiter, quote or newline
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
 fn run_search(
     query: &str,
     path: &str,
     force_mode: Option<SearchMode>,
     interactive: bool,
+    outputs: Outputs<'_>,
+    candidates: Candidates<'_>,
+    config: Config,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let searcher = FileSearcher::new();
     let search_path = Path::new(path);
+    let engine = SearchEngine::new(config);
+    let actual_mode = force_mode.unwrap_or_else(|| engine.detect_search_mode(query));
+    // Reject bad queries before spending time on indexing
+    validate_query(query, actual_mode)?;
+    let source = FileSource::open(search_path, engine.config(), candidates)?;
+    let mut statuses = GitStatuses::default();
 
     if let Some(SearchMode::Fuzzy) = force_mode {
-        let scored_results = searcher.search_fuzzy(search_path, query)?;
+        let scored_results = source.search_fuzzy(&engine, search_path, query)?;
+        if outputs.clipboard {
+            copy_results(scored_results.iter().map(|(file, _)| file), engine.config());
+        }
+        if let Some(output) = outputs.file {
+            let results: Vec<(PathBuf, Option<f64>)> = scored_results
+                .into_iter()
+                .map(|(file, score)| (file, Some(score)))
+                .collect();
+            return write_output(output, query, actual_mode, &results, engine.config());
+        }
+        if outputs.summarize {
+            print_summary(
+                scored_results.into_iter().map(|(file, _)| file).collect(),
+                engine.config(),
+            );
+            return Ok(());
+        }
+        if let Some(min_files) = outputs.min_per_dir {
+            let files: Vec<PathBuf> = scored_results.into_iter().map(|(file, _)| file).collect();
+            print_directories(&files, min_files, engine.config());
+            return Ok(());
+        }
+        if let Some(action) = outputs.action {
+            let files: Vec<PathBuf> = scored_results.into_iter().map(|(file, _)| file).collect();
+            return run_bulk_action(
+                action,
+                outputs.dry_run,
+                query,
+                actual_mode,
+                search_path,
+                &files,
+                engine.config(),
+            );
+        }
         println!(
             "Searching for '{}' in '{}' using forced fuzzy matching...",
             query, path
@@ -131,6 +1937,7 @@ fn run_search(
 
         if scored_results.is_empty() {
             println!("No files found matching '{}'", query);
+            print_ignore_hint(engine.config());
         } else {
             let files: Vec<PathBuf> = scored_results
                 .iter()
@@ -138,39 +1945,74 @@ fn run_search(
                 .collect();
             if interactive {
                 println!(
-                    "Found {} file(s) (sorted by relevance):",
-                    scored_results.len()
+                    "Found {} file(s) (sorted by {}):",
+                    scored_results.len(),
+                    engine.config().sort.name()
                 );
                 for (i, (file, score)) in scored_results.iter().take(20).enumerate() {
-                    println!("  [{}] {} (score: {:.2})", i + 1, file.display(), score);
+                    println!(
+                        "  [{}] {}{} (score: {:.2})",
+                        i + 1,
+                        status_column(&mut statuses, file, engine.config()),
+                        engine.config().path_style.display(file),
+                        score
+                    );
                 }
                 handle_interactive_selection(&files)?;
             } else {
-                println!(
-                    "Found {} file(s) (sorted by relevance):",
-                    scored_results.len()
-                );
+                let mut out = Listing::open(scored_results.len().min(20) + 2, outputs.pager);
+                writeln!(
+                    out,
+                    "Found {} file(s) (sorted by {}):",
+                    scored_results.len(),
+                    engine.config().sort.name()
+                )?;
                 for (file, score) in scored_results.iter().take(20) {
-                    println!("  {} (score: {:.2})", file.display(), score);
+                    writeln!(
+                        out,
+                        "  {}{} (score: {:.2})",
+                        status_column(&mut statuses, file, engine.config()),
+                        engine.config().path_style.display(file),
+                        score
+                    )?;
+                    print_details(&mut out, file, engine.config())?;
                 }
+                out.finish()?;
             }
         }
         return Ok(());
     }
 
-    let (results, actual_mode) = if let Some(mode) = force_mode {
-        let results = searcher.search(search_path, query, mode)?;
-        (results, mode)
-    } else {
-        searcher.search_auto_with_mode(search_path, query)?
-    };
+    let results = source.search(&engine, search_path, query, actual_mode)?;
+    if outputs.clipboard {
+        copy_results(&results, engine.config());
+    }
+    if let Some(output) = outputs.file {
+        let results: Vec<(PathBuf, Option<f64>)> =
+            results.into_iter().map(|file| (file, None)).collect();
+        return write_output(output, query, actual_mode, &results, engine.config());
+    }
+    if let Some(action) = outputs.action {
+        return run_bulk_action(
+            action,
+            outputs.dry_run,
+            query,
+            actual_mode,
+            search_path,
+            &results,
+            engine.config(),
+        );
+    }
+    if outputs.summarize {
+        print_summary(results, engine.config());
+        return Ok(());
+    }
+    if let Some(min_files) = outputs.min_per_dir {
+        print_directories(&results, min_files, engine.config());
+        return Ok(());
+    }
 
-    let mode_name = match actual_mode {
-        SearchMode::Regex => "regex",
-        SearchMode::Glob => "glob",
-        SearchMode::Substring => "substring",
-        SearchMode::Fuzzy => "fuzzy",
-    };
+    let mode_name = mode_name(actual_mode);
 
     let detection_text = if force_mode.is_some() {
         format!("forced {}", mode_name)
@@ -185,18 +2027,32 @@ fn run_search(
 
     if results.is_empty() {
         println!("No files found matching '{}'", query);
+        print_ignore_hint(engine.config());
     } else {
         if interactive {
             println!("Found {} file(s):", results.len());
             for (i, file) in results.iter().enumerate() {
-                println!("  [{}] {}", i + 1, file.display());
+                println!(
+                    "  [{}] {}{}",
+                    i + 1,
+                    status_column(&mut statuses, file, engine.config()),
+                    engine.config().path_style.display(file)
+                );
             }
             handle_interactive_selection(&results)?;
         } else {
-            println!("Found {} file(s):", results.len());
+            let mut out = Listing::open(results.len() + 2, outputs.pager);
+            writeln!(out, "Found {} file(s):", results.len())?;
             for file in results {
-                println!("  {}", file.display());
+                writeln!(
+                    out,
+                    "  {}{}",
+                    status_column(&mut statuses, &file, engine.config()),
+                    engine.config().path_style.display(&file)
+                )?;
+                print_details(&mut out, &file, engine.config())?;
             }
+            out.finish()?;
         }
     }
 
@@ -210,9 +2066,13 @@ fn handle_interactive_selection(files: &[PathBuf]) -> Result<(), Box<dyn std::er
 
     println!();
     println!(
-        "Enter number to open in explorer (1-{}), 'a' for all, or 'q' to quit:",
+        "Enter number to open in explorer (1-{}), 'a' for all, or 'q' to quit.",
         files.len()
     );
+    println!(
+        "To act on a selection such as '2', '1,3-5' or 'a': 'd SEL' moves it to the trash, \
+         'D SEL' deletes it permanently, 'm SEL DIR' moves it and 'c SEL DIR' copies it into DIR."
+    );
     print!("> ");
     io::stdout().flush()?;
 
@@ -220,6 +2080,17 @@ fn handle_interactive_selection(files: &[PathBuf]) -> Result<(), Box<dyn std::er
     io::stdin().read_line(&mut input)?;
     let input = input.trim();
 
+    if let Some(action) = parse_action(input, files.len()) {
+        match action {
+            Ok((action, selected)) => {
+                let selected: Vec<&PathBuf> = selected.iter().map(|&i| &files[i]).collect();
+                run_action(&action, &selected)?;
+            }
+            Err(e) => println!("Invalid action: {}", e),
+        }
+        return Ok(());
+    }
+
     match input {
         "q" | "quit" => {
             println!("Goodbye!");
@@ -251,6 +2122,121 @@ fn handle_interactive_selection(files: &[PathBuf]) -> Result<(), Box<dyn std::er
     Ok(())
 }
 
+/// File operation offered in interactive mode
+enum Action {
+    Trash,
+    Delete,
+    Move(PathBuf),
+    Copy(PathBuf),
+}
+
+/// Parse `d SEL`, `D SEL`, `m SEL DIR` or `c SEL DIR` into an action and the
+/// zero-based indices it applies to
+///
+/// Returns `None` if `input` is not an action command at all.
+fn parse_action(input: &str, count: usize) -> Option<Result<(Action, Vec<usize>), String>> {
+    let (command, rest) = input.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let (selection, target) = match rest.split_once(char::is_whitespace) {
+        Some((selection, target)) => (selection, Some(target.trim())),
+        None => (rest, None),
+    };
+    let target_dir = |dir: &str| PathBuf::from(expand_tilde(dir).as_ref());
+    let action = match (command, target) {
+        ("d", None) => Action::Trash,
+        ("D", None) => Action::Delete,
+        ("m", Some(dir)) => Action::Move(target_dir(dir)),
+        ("c", Some(dir)) => Action::Copy(target_dir(dir)),
+        ("d" | "D", Some(_)) => {
+            return Some(Err(format!("'{}' takes no target directory", command)))
+        }
+        ("m" | "c", None) => return Some(Err(format!("'{}' needs a target directory", command))),
+        _ => return None,
+    };
+    Some(parse_selection(selection, count).map(|selected| (action, selected)))
+}
+
+/// Zero-based indices named by `a`, or by numbers and ranges like `1,3-5`
+fn parse_selection(selection: &str, count: usize) -> Result<Vec<usize>, String> {
+    if selection == "a" || selection == "all" {
+        return Ok((0..count).collect());
+    }
+    let number = |text: &str| match text.parse::<usize>() {
+        Ok(n) if (1..=count).contains(&n) => Ok(n - 1),
+        _ => Err(format!(
+            "'{}' is not a number between 1 and {}",
+            text, count
+        )),
+    };
+    let mut selected = Vec::new();
+    for part in selection.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (number(first)?, number(last)?);
+                if first > last {
+                    return Err(format!("'{}' is a range that runs backwards", part));
+                }
+                selected.extend(first..=last);
+            }
+            None => selected.push(number(part)?),
+        }
+    }
+    selected.sort_unstable();
+    selected.dedup();
+    Ok(selected)
+}
+
+/// Show the operations `action` plans for `files`, then perform them once
+/// the user confirms
+fn run_action(action: &Action, files: &[&PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    if files.is_empty() {
+        println!("Nothing selected.");
+        return Ok(());
+    }
+    let plan = match action {
+        Action::Trash => actions::delete(files, DeleteMode::Trash, true),
+        Action::Delete => actions::delete(files, DeleteMode::Permanent, true),
+        Action::Move(dir) => actions::move_to(files, dir, true)?,
+        Action::Copy(dir) => actions::copy_to(files, dir, true)?,
+    };
+    for (file, e) in &plan.errors {
+        println!("  skipping '{}': {}", file.display(), e);
+    }
+    if plan.operations.is_empty() {
+        println!("Nothing to do.");
+        return Ok(());
+    }
+
+    for operation in &plan.operations {
+        println!("  {}", operation.source().display());
+    }
+    let count = plan.operations.len();
+    let summary = match action {
+        Action::Trash => format!("Move {} file(s) to the trash", count),
+        Action::Delete => format!("Permanently delete {} file(s)", count),
+        Action::Move(dir) => format!("Move {} file(s) into '{}'", count, dir.display()),
+        Action::Copy(dir) => format!("Copy {} file(s) into '{}'", count, dir.display()),
+    };
+    print!("{}? [y/N] ", summary);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for operation in &plan.operations {
+        if let Err(e) = operation.apply() {
+            eprintln!("Error: {}", e);
+            failed += 1;
+        }
+    }
+    println!("Done: {} succeeded, {} failed.", count - failed, failed);
+    Ok(())
+}
+
 fn open_in_explorer(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
     println!("Opening {} in explorer...", file_path.display());
 
@@ -0,0 +1,64 @@
+//! Expanding environment variables in config file values
+//!
+//! `$NAME`, `${NAME}` and `%NAME%` are replaced by the value of the variable
+//! `NAME`, so one config file can name per-user paths on every platform.
+//! Names start with a letter or `_` and continue with letters, digits and
+//! `_`. References to unset variables, or to variables whose value is not
+//! valid Unicode, are kept as written.
+
+use std::borrow::Cow;
+use std::env;
+
+/// `text` with every reference to a set environment variable replaced by its
+/// value
+#[must_use]
+pub fn expand_env_vars(text: &str) -> Cow<'_, str> {
+    if !text.contains(['$', '%']) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['$', '%']) {
+        let (before, from) = rest.split_at(start);
+        expanded.push_str(before);
+        if let Some((value, len)) =
+            reference(from).and_then(|(name, len)| Some((env::var(name).ok()?, len)))
+        {
+            expanded.push_str(&value);
+            rest = &from[len..];
+        } else {
+            // '$' and '%' are one byte each
+            expanded.push_str(&from[..1]);
+            rest = &from[1..];
+        }
+    }
+    expanded.push_str(rest);
+    Cow::Owned(expanded)
+}
+
+/// Name of the variable referenced at the start of `text`, and the length of
+/// the reference
+fn reference(text: &str) -> Option<(&str, usize)> {
+    let (name, len) = if let Some(braced) = text.strip_prefix("${") {
+        let close = braced.find('}')?;
+        (&braced[..close], close + 3)
+    } else if let Some(bare) = text.strip_prefix('$') {
+        let end = bare.find(|c: char| !is_name_char(c)).unwrap_or(bare.len());
+        (&bare[..end], end + 1)
+    } else {
+        let percent = text.strip_prefix('%')?;
+        let close = percent.find('%')?;
+        (&percent[..close], close + 2)
+    };
+    is_name(name).then_some((name, len))
+}
+
+fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(is_name_char)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
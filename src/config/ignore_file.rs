@@ -0,0 +1,72 @@
+//! Loading ignore patterns from gitignore-style files
+//!
+//! Ignore patterns match a single path component, so only the subset of
+//! gitignore syntax that maps onto that is supported: blank lines and `#`
+//! comments are skipped, anchoring and directory-only slashes are dropped, and
+//! leading or trailing `**` segments are removed. Negated patterns, character
+//! classes and wildcards spanning several directories are reported as
+//! unsupported.
+
+use crate::error::FileSearchError;
+use crate::Result;
+use std::path::Path;
+
+/// Patterns read from an ignore file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreFile {
+    /// Patterns usable as `Config::ignore_patterns`
+    pub patterns: Vec<String>,
+    /// Lines that could not be expressed as ignore patterns
+    pub unsupported: Vec<String>,
+}
+
+impl IgnoreFile {
+    /// Read and parse the ignore file at `path`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| FileSearchError::io_error_with_path(e, "reading ignore file", path))?;
+        Ok(Self::parse(&contents))
+    }
+
+    /// Parse ignore file contents in gitignore syntax
+    #[must_use]
+    pub fn parse(contents: &str) -> Self {
+        let mut ignore_file = Self::default();
+        for line in contents.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match pattern_from_line(line) {
+                Some(pattern) => ignore_file.patterns.push(pattern),
+                None => ignore_file.unsupported.push(line.to_string()),
+            }
+        }
+        ignore_file
+    }
+}
+
+fn pattern_from_line(line: &str) -> Option<String> {
+    if line.starts_with('!') {
+        return None;
+    }
+
+    let line = line.strip_prefix('\\').unwrap_or(line);
+    let mut pattern = line.trim_start_matches('/').trim_end_matches('/');
+    while let Some(rest) = pattern.strip_prefix("**/") {
+        pattern = rest;
+    }
+    while let Some(rest) = pattern.strip_suffix("/**") {
+        pattern = rest;
+    }
+
+    let has_wildcard = pattern.contains(['*', '?']);
+    if pattern.is_empty() || pattern.contains('[') || (has_wildcard && pattern.contains('/')) {
+        return None;
+    }
+    Some(pattern.to_string())
+}
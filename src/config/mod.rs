@@ -1,32 +1,532 @@
+/// Environment variable expansion
+pub mod env;
+/// Gitignore-style ignore files
+pub mod ignore_file;
 /// Configuration settings management
 #[cfg(feature = "config")]
 pub mod settings;
+/// Home directory expansion
+pub mod tilde;
 
+use crate::indexer::attributes::FileAttribute;
+use crate::indexer::scheduler::IndexRoot;
+use crate::search::detect::DetectionPolicy;
+use crate::search::preset::Preset;
+use crate::search::SortOrder;
 #[cfg(feature = "config")]
 use serde::{Deserialize, Serialize};
-#[cfg(feature = "config")]
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::path::{self, Path};
+use std::time::Duration;
 
 /// Configuration options for file search operations
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config", serde(default))]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     /// Maximum depth to traverse in directory tree (None for unlimited)
     pub max_depth: Option<usize>,
+    /// Minimum depth below the search root of reported entries, 1 being the
+    /// root's own entries (None for no minimum)
+    ///
+    /// Shallower directories are still walked, only their entries are left
+    /// out of indexes.
+    pub min_depth: Option<usize>,
     /// Whether to ignore hidden files and directories
     pub ignore_hidden: bool,
     /// Glob patterns to ignore during search
     pub ignore_patterns: Vec<String>,
     /// Whether search should be case-sensitive
     pub case_sensitive: bool,
+    /// Whether file names and queries are compared in Unicode NFC form, with
+    /// full case folding when case-insensitive (requires the `unicode`
+    /// feature)
+    pub unicode_normalization: bool,
     /// Maximum file size to consider (None for no limit)
     pub max_file_size: Option<u64>,
+    /// Kinds of entries indexed and reported
+    pub file_type: FileType,
+    /// Roots kept fresh in the file database by the index scheduler
+    pub index_roots: Vec<IndexRoot>,
+    /// Time allowed for a single directory or metadata read before the entry is
+    /// skipped (None waits forever)
+    pub io_timeout: Option<Duration>,
+    /// How many times a transiently failing read is retried
+    ///
+    /// A read that timed out is not retried while it still blocks; it is
+    /// given the retry backoff to answer late instead.
+    pub io_retries: u32,
+    /// Delay before the first retry, doubled after each further attempt
+    pub io_retry_backoff: Duration,
+    /// Limit on how fast the file system is walked (None walks at full speed)
+    pub io_throttle: Option<IoThrottle>,
+    /// Whether long-running background indexing lowers its CPU and IO priority
+    pub background_priority: bool,
+    /// Lowest fuzzy score (0.0 to 1.0) a file needs to be reported
+    pub fuzzy_min_score: f64,
+    /// Weights of the measures combined into fuzzy scores
+    pub fuzzy_scoring: FuzzyScoringConfig,
+    /// Whether substring queries only match whole words of file names,
+    /// delimited by punctuation or the ends of the name
+    pub word_boundaries: bool,
+    /// Whether glob patterns match whole paths, with `**` spanning
+    /// directories, even without a path separator in the pattern
+    pub match_full_path: bool,
+    /// Whether substring, regex and fuzzy queries match the path relative to
+    /// the search root, with `/` separators, instead of the file name
+    pub match_relative_path: bool,
+    /// Which search modes automatic detection may choose
+    pub detection: DetectionPolicy,
+    /// Longest chain of symlinks followed during a walk (None or 0 leaves
+    /// symlinks unfollowed)
+    pub max_symlink_depth: Option<usize>,
+    /// Most directories a walk visits before stopping early (None for no
+    /// limit)
+    pub max_dirs: Option<usize>,
+    /// Most files and directories together a walk visits before stopping
+    /// early (None for no limit)
+    pub max_entries: Option<usize>,
+    /// Longest a search runs, from its start, before stopping early (None
+    /// for no limit)
+    ///
+    /// Directory listings still pending at the deadline are abandoned, and
+    /// matching the files found stops there too.
+    pub search_timeout: Option<Duration>,
+    /// Types of virtual file systems, such as `proc` and `sysfs`, skipped
+    /// where they are mounted below the search root (empty skips none)
+    pub pseudo_filesystems: Vec<String>,
+    /// Whether walks stay on the file system of the search root, skipping
+    /// mount points of others such as network shares and external drives
+    pub same_file_system: bool,
+    /// Fuzzy score deducted per path component when ranking, so shallower
+    /// paths come first among equal or close matches (0.0 ranks by score
+    /// alone)
+    pub depth_penalty: f64,
+    /// Multipliers applied to the fuzzy scores of files by extension when
+    /// ranking, keyed without the leading dot and compared without regard to
+    /// case. Compound extensions such as `min.js` take precedence over the
+    /// plain `js`.
+    pub extension_weights: BTreeMap<String, f64>,
+    /// Share of the fuzzy ranking (0.0 to 1.0) given to how recently a file
+    /// was modified rather than how well its name matches
+    pub recency_weight: f64,
+    /// Age at which a file's recency counts half as much as that of one
+    /// modified just now
+    pub recency_half_life: Duration,
+    /// Whether substring, regex and glob results are ordered shallowest
+    /// first instead of purely by path
+    pub shallow_first: bool,
+    /// Order results are returned in, applied after ranking and
+    /// [`shallow_first`](Config::shallow_first)
+    pub sort: SortOrder,
+    /// Whether indexing also records filename bigrams so fuzzy search only
+    /// scores filenames sharing a bigram with the query
+    pub fuzzy_candidate_index: bool,
+    /// Threads matching the filenames of large in-memory indexes against a
+    /// query (None for rayon's global pool of one per CPU, 1 to match on the
+    /// calling thread; requires the `parallel` feature)
+    pub match_threads: Option<usize>,
+    /// Longest regex pattern accepted, in bytes (None for no limit beyond
+    /// the general query length limit)
+    pub max_regex_len: Option<usize>,
+    /// Largest compiled size of a regex, in bytes (None for the regex crate's
+    /// default)
+    pub regex_size_limit: Option<usize>,
+    /// Largest lazy DFA cache of a regex, in bytes (None for the regex crate's
+    /// default)
+    pub regex_dfa_size_limit: Option<usize>,
+    /// Whether entries deleted or renamed during a walk are skipped instead of
+    /// failing the search
+    pub tolerate_races: bool,
+    /// Whether paths excluded by git (`.gitignore` files, the repository's
+    /// `.git/info/exclude` and the global excludes file) are skipped
+    pub respect_gitignore: bool,
+    /// Whether gitignore mode walks into git submodules
+    pub git_submodules: bool,
+    /// Whether gitignore mode walks into linked git worktrees
+    pub git_worktrees: bool,
+    /// Whether only files tracked by git are searched (requires the `git` feature)
+    pub git_tracked: bool,
+    /// Only search files added or modified since this git revision (requires
+    /// the `git` feature)
+    pub changed_since: Option<String>,
+    /// Only report files flagged by at least one of these security checks
+    ///
+    /// Empty to report files regardless of their permissions.
+    pub audit_checks: Vec<AuditCheck>,
+    /// Only report files carrying every one of these extended attributes
+    pub required_xattrs: Vec<String>,
+    /// Only report files carrying every one of these macOS Finder tags
+    pub required_tags: Vec<String>,
+    /// Whether [`FileMatch`](crate::search::file_match::FileMatch) results list
+    /// NTFS alternate data streams
+    pub alternate_streams: bool,
+    /// Only report files having every one of these storage attributes
+    pub required_attributes: Vec<FileAttribute>,
+    /// Whether [`FileMatch`](crate::search::file_match::FileMatch) results
+    /// carry the file's storage attributes
+    pub file_attributes: bool,
+    /// Only report files whose hard link count matches
+    pub link_count: Option<LinkCount>,
+    /// Only report files whose name length in characters matches
+    pub name_length: Option<LengthFilter>,
+    /// Only report files whose path length in characters, including the
+    /// search root, matches
+    pub path_length: Option<LengthFilter>,
+    /// Whether [`FileMatch`](crate::search::file_match::FileMatch) results
+    /// carry the file's git status (requires the `git` feature)
+    pub git_status: bool,
+    /// Separators used when printing result paths
+    pub path_style: PathStyle,
+    /// Whether a leading `~` or `~user` in search roots and ignore patterns
+    /// stands for a home directory
+    pub expand_tilde: bool,
+    /// How online-only files of cloud storage clients are handled
+    pub cloud_placeholders: CloudPlaceholders,
+    /// Whether searches also cover the current user's trash, matching
+    /// trashed files by the name they had before deletion
+    pub include_trash: bool,
+    /// Saved searches, run by name with
+    /// [`FileSearcher::run_preset`](crate::FileSearcher::run_preset)
+    pub presets: BTreeMap<String, Preset>,
+}
+
+/// Rate limit applied while walking the file system
+///
+/// Keeps background indexing from saturating slow disks or network storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub enum IoThrottle {
+    /// Visit at most this many entries per second
+    EntriesPerSecond(u32),
+    /// Pause for `sleep` after every `dirs` directories entered
+    SleepEveryDirs {
+        /// Number of directories between pauses
+        dirs: u32,
+        /// Length of each pause
+        sleep: Duration,
+    },
+}
+
+/// Weights of the measures combined into a fuzzy score
+///
+/// A name equal to the query scores 1.0, and one containing it at least 0.9,
+/// whatever the weights. Other names are scored from 0.0 to 1.0 by edit
+/// distance, by how many of the query's characters appear in order, and by
+/// the bigrams they share with the query, and their score is the mean of the
+/// three weighted as given here. Only the proportions of the weights matter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config", serde(default))]
+pub struct FuzzyScoringConfig {
+    /// Weight of the edit distance, which tolerates typos
+    pub levenshtein: f64,
+    /// Weight of the query's characters appearing in order, with a bonus
+    /// for runs of consecutive characters
+    pub subsequence: f64,
+    /// Weight of the share of bigrams in common
+    pub ngram: f64,
+}
+
+impl FuzzyScoringConfig {
+    /// Sum of the weights
+    #[must_use]
+    pub fn total(&self) -> f64 {
+        self.levenshtein + self.subsequence + self.ngram
+    }
+
+    /// Whether every weight is a finite number of at least 0.0 and not all
+    /// of them are 0.0
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        [self.levenshtein, self.subsequence, self.ngram]
+            .iter()
+            .all(|weight| *weight >= 0.0 && weight.is_finite())
+            && self.total() > 0.0
+    }
+}
+
+impl Default for FuzzyScoringConfig {
+    fn default() -> Self {
+        Self {
+            levenshtein: 0.4,
+            subsequence: 0.4,
+            ngram: 0.2,
+        }
+    }
+}
+
+/// How files that cloud storage clients keep online only are indexed
+///
+/// Opening such a placeholder downloads the file, so a careless walk can
+/// start a mass download. See [`placeholder`](crate::indexer::placeholder)
+/// for how they are detected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub enum CloudPlaceholders {
+    /// Leave placeholders out of the walk
+    Skip,
+    /// Index placeholders by name without opening them
+    ///
+    /// They are exempt from the size limit, since their size on disk says
+    /// nothing about the file, and left out when a filter that inspects
+    /// files is configured.
+    #[default]
+    NameOnly,
+    /// Treat placeholders like any other file, which may download them
+    Include,
+}
+
+impl CloudPlaceholders {
+    /// Every policy
+    pub const ALL: [Self; 3] = [Self::Skip, Self::NameOnly, Self::Include];
+
+    /// Lowercase name, as used on the command line
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::NameOnly => "name-only",
+            Self::Include => "include",
+        }
+    }
+}
+
+/// Kinds of entries a walk indexes
+///
+/// Directories are indexed by their own names, so `node_modules` can be
+/// found as a directory. The root of a walk is never indexed itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub enum FileType {
+    /// Regular files, including symlinks to them that are followed
+    #[default]
+    Files,
+    /// Directories, including symlinks to them that are followed
+    Dirs,
+    /// Symbolic links, whether followed or not
+    Symlinks,
+    /// Every entry, including sockets, devices and other special files
+    All,
+}
+
+impl FileType {
+    /// Every kind
+    pub const ALL: [Self; 4] = [Self::Files, Self::Dirs, Self::Symlinks, Self::All];
+
+    /// Lowercase name, as used on the command line
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Files => "files",
+            Self::Dirs => "dirs",
+            Self::Symlinks => "symlinks",
+            Self::All => "all",
+        }
+    }
+
+    /// Letter abbreviating the name, as with `find -type`
+    #[must_use]
+    pub fn letter(self) -> &'static str {
+        match self {
+            Self::Files => "f",
+            Self::Dirs => "d",
+            Self::Symlinks => "l",
+            Self::All => "a",
+        }
+    }
+
+    /// Whether an entry of `file_type` is of this kind, `is_symlink` telling
+    /// whether it was reached through a symlink
+    #[must_use]
+    pub fn includes(self, file_type: std::fs::FileType, is_symlink: bool) -> bool {
+        match self {
+            Self::Files => file_type.is_file(),
+            Self::Dirs => file_type.is_dir(),
+            Self::Symlinks => is_symlink,
+            Self::All => true,
+        }
+    }
+}
+
+/// Permission problem a security audit looks for
+///
+/// Checks inspect Unix permission bits and ownership, so they never flag a
+/// file on other platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub enum AuditCheck {
+    /// Anyone may write to the file
+    WorldWritable,
+    /// The file has its setuid or setgid bit set
+    SetId,
+    /// The file is owned by root but sits in a directory owned by another user
+    RootOwnedInUserDir,
+}
+
+impl AuditCheck {
+    /// Every available check
+    pub const ALL: [Self; 3] = [Self::WorldWritable, Self::SetId, Self::RootOwnedInUserDir];
+}
+
+/// Hard link count a file must have to be reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub enum LinkCount {
+    /// Exactly this many links
+    Exactly(u64),
+    /// More than this many links
+    MoreThan(u64),
+    /// Fewer than this many links
+    FewerThan(u64),
+}
+
+impl LinkCount {
+    /// Whether a file with `links` hard links matches
+    #[must_use]
+    pub fn matches(self, links: u64) -> bool {
+        match self {
+            Self::Exactly(n) => links == n,
+            Self::MoreThan(n) => links > n,
+            Self::FewerThan(n) => links < n,
+        }
+    }
+}
+
+/// Length in characters a file name or path must have to be reported
+///
+/// Lengths count Unicode scalar values, which matches the UTF-16 units
+/// Windows limits paths by except for characters outside the Basic
+/// Multilingual Plane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub enum LengthFilter {
+    /// Exactly this many characters
+    Exactly(usize),
+    /// More than this many characters
+    LongerThan(usize),
+    /// Fewer than this many characters
+    ShorterThan(usize),
+}
+
+impl LengthFilter {
+    /// Whether a length of `length` characters matches
+    #[must_use]
+    pub fn matches(self, length: usize) -> bool {
+        match self {
+            Self::Exactly(n) => length == n,
+            Self::LongerThan(n) => length > n,
+            Self::ShorterThan(n) => length < n,
+        }
+    }
+}
+
+impl std::str::FromStr for LengthFilter {
+    type Err = std::num::ParseIntError;
+
+    /// Parse `N`, `+N` or `-N` like [`LinkCount`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(n) = s.strip_prefix('+') {
+            n.parse().map(Self::LongerThan)
+        } else if let Some(n) = s.strip_prefix('-') {
+            n.parse().map(Self::ShorterThan)
+        } else {
+            s.parse().map(Self::Exactly)
+        }
+    }
+}
+
+impl std::fmt::Display for LengthFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exactly(n) => write!(f, "exactly {n}"),
+            Self::LongerThan(n) => write!(f, "longer than {n}"),
+            Self::ShorterThan(n) => write!(f, "shorter than {n}"),
+        }
+    }
+}
+
+/// Separators written between the components of result paths
+///
+/// Scripts that pass results between Windows and WSL can ask for forward
+/// slashes on every platform. Only characters the platform treats as
+/// separators are rewritten, so a `\` in a Unix filename is kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub enum PathStyle {
+    /// The platform's own separator, `\` on Windows and `/` elsewhere
+    #[default]
+    Native,
+    /// `/` on every platform
+    Forward,
+}
+
+impl PathStyle {
+    /// Every style
+    pub const ALL: [Self; 2] = [Self::Native, Self::Forward];
+
+    /// Lowercase name, as used on the command line
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::Forward => "forward",
+        }
+    }
+
+    /// `path` as text, with its separators written in this style
+    #[must_use]
+    pub fn display(self, path: &Path) -> Cow<'_, str> {
+        let separator = match self {
+            Self::Native => path::MAIN_SEPARATOR,
+            Self::Forward => '/',
+        };
+        let text = path.to_string_lossy();
+        if text.contains(|c: char| c != separator && path::is_separator(c)) {
+            Cow::Owned(
+                text.chars()
+                    .map(|c| if path::is_separator(c) { separator } else { c })
+                    .collect(),
+            )
+        } else {
+            text
+        }
+    }
+}
+
+impl std::str::FromStr for LinkCount {
+    type Err = std::num::ParseIntError;
+
+    /// Parse `N`, `+N` or `-N` as in `find -links`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(n) = s.strip_prefix('+') {
+            n.parse().map(Self::MoreThan)
+        } else if let Some(n) = s.strip_prefix('-') {
+            n.parse().map(Self::FewerThan)
+        } else {
+            s.parse().map(Self::Exactly)
+        }
+    }
+}
+
+impl std::fmt::Display for LinkCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exactly(n) => write!(f, "exactly {n}"),
+            Self::MoreThan(n) => write!(f, "more than {n}"),
+            Self::FewerThan(n) => write!(f, "fewer than {n}"),
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             max_depth: None,
+            min_depth: None,
             ignore_hidden: true,
             ignore_patterns: vec![
                 "*.tmp".to_string(),
@@ -36,12 +536,108 @@ impl Default for Config {
                 "target".to_string(),
             ],
             case_sensitive: false,
+            unicode_normalization: false,
             max_file_size: None,
+            file_type: FileType::Files,
+            index_roots: Vec::new(),
+            io_timeout: None,
+            io_retries: 2,
+            io_retry_backoff: Duration::from_millis(100),
+            io_throttle: None,
+            background_priority: false,
+            fuzzy_min_score: 0.3,
+            fuzzy_scoring: FuzzyScoringConfig::default(),
+            word_boundaries: false,
+            match_full_path: false,
+            match_relative_path: false,
+            detection: DetectionPolicy::default(),
+            max_symlink_depth: None,
+            max_dirs: None,
+            max_entries: None,
+            search_timeout: None,
+            pseudo_filesystems: crate::indexer::mounts::PSEUDO_FILESYSTEMS
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            same_file_system: false,
+            depth_penalty: 0.01,
+            extension_weights: BTreeMap::new(),
+            recency_weight: 0.0,
+            recency_half_life: Duration::from_secs(7 * 24 * 60 * 60),
+            shallow_first: false,
+            sort: SortOrder::Relevance,
+            fuzzy_candidate_index: false,
+            match_threads: None,
+            max_regex_len: None,
+            regex_size_limit: None,
+            regex_dfa_size_limit: None,
+            tolerate_races: true,
+            respect_gitignore: false,
+            git_submodules: false,
+            git_worktrees: false,
+            git_tracked: false,
+            changed_since: None,
+            audit_checks: Vec::new(),
+            required_xattrs: Vec::new(),
+            required_tags: Vec::new(),
+            alternate_streams: false,
+            required_attributes: Vec::new(),
+            file_attributes: false,
+            link_count: None,
+            name_length: None,
+            path_length: None,
+            git_status: false,
+            path_style: PathStyle::Native,
+            expand_tilde: true,
+            cloud_placeholders: CloudPlaceholders::NameOnly,
+            include_trash: false,
+            presets: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
+    /// Expand environment variables in the ignore patterns, index roots and
+    /// preset roots
+    ///
+    /// See [`env::expand_env_vars`] for the supported syntax.
+    pub fn expand_env_vars(&mut self) {
+        let expand = |text: &mut String| {
+            if let Cow::Owned(expanded) = env::expand_env_vars(text) {
+                *text = expanded;
+            }
+        };
+        self.ignore_patterns.iter_mut().for_each(expand);
+        for root in &mut self.index_roots {
+            if let Some(Cow::Owned(expanded)) = root.path.to_str().map(env::expand_env_vars) {
+                root.path = PathBuf::from(expanded);
+            }
+            root.ignore_patterns.iter_mut().for_each(expand);
+        }
+        for root in self
+            .presets
+            .values_mut()
+            .filter_map(|preset| preset.root.as_mut())
+        {
+            if let Some(Cow::Owned(expanded)) = root.to_str().map(env::expand_env_vars) {
+                *root = PathBuf::from(expanded);
+            }
+        }
+    }
+
+    /// Expand `~` in the ignore patterns, if enabled, so walks need not
+    /// look up home directories for every entry
+    pub(crate) fn expand_ignore_patterns(&mut self) {
+        if !self.expand_tilde {
+            return;
+        }
+        for pattern in &mut self.ignore_patterns {
+            if let Cow::Owned(expanded) = tilde::expand_tilde(pattern) {
+                *pattern = expanded;
+            }
+        }
+    }
+
     /// Load configuration from a JSON file
     ///
     /// # Errors
@@ -2,8 +2,15 @@ use super::Config;
 use std::path::PathBuf;
 
 /// Configuration manager for handling persistent settings
+///
+/// Environment variables in the ignore patterns and index roots of the loaded
+/// file are expanded, while [`ConfigManager::save`] writes them back
+/// unexpanded so the file stays portable.
 pub struct ConfigManager {
     config_path: PathBuf,
+    /// Settings as written in the file
+    stored: Config,
+    /// Settings with environment variables expanded
     config: Config,
 }
 
@@ -16,14 +23,17 @@ impl ConfigManager {
     #[cfg(feature = "config")]
     pub fn new() -> crate::Result<Self> {
         let config_path = Self::default_config_path()?;
-        let config = if config_path.exists() {
+        let stored = if config_path.exists() {
             Config::load_from_file(&config_path)?
         } else {
             Config::default()
         };
+        let mut config = stored.clone();
+        config.expand_env_vars();
 
         Ok(Self {
             config_path,
+            stored,
             config,
         })
     }
@@ -44,7 +54,7 @@ impl ConfigManager {
         if let Some(parent) = self.config_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        self.config.save_to_file(&self.config_path)
+        self.stored.save_to_file(&self.config_path)
     }
 
     #[cfg(feature = "config")]
@@ -60,6 +70,7 @@ impl Default for ConfigManager {
     fn default() -> Self {
         Self::new().unwrap_or_else(|_| Self {
             config_path: PathBuf::from("config.json"),
+            stored: Config::default(),
             config: Config::default(),
         })
     }
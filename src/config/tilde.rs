@@ -0,0 +1,108 @@
+//! Expanding `~` and `~user` the way a shell would
+//!
+//! Paths handed over by scripts, config files or quoted arguments reach the
+//! library without the shell having expanded them. A leading `~` stands for
+//! the current user's home directory (`HOME`, or `USERPROFILE` on Windows) and
+//! `~user` for that user's, read from the password database on Unix and
+//! assumed to sit next to the current user's on Windows. Anything else, or a
+//! home directory that cannot be found, leaves the text unchanged.
+
+use std::borrow::Cow;
+use std::path::{self, PathBuf};
+
+/// `text` with a leading `~` or `~user` replaced by the home directory
+#[must_use]
+pub fn expand_tilde(text: &str) -> Cow<'_, str> {
+    let Some(rest) = text.strip_prefix('~') else {
+        return Cow::Borrowed(text);
+    };
+    let (user, tail) = match rest.find(path::is_separator) {
+        Some(end) => rest.split_at(end),
+        None => (rest, ""),
+    };
+    let home = if user.is_empty() {
+        home_dir()
+    } else {
+        user_home_dir(user)
+    };
+    match home.as_deref().and_then(|home| home.to_str()) {
+        Some(home) => Cow::Owned(format!("{home}{tail}")),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Home directory of the current user
+#[must_use]
+pub fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var_os(var)
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Home directory of `user`
+#[must_use]
+pub fn user_home_dir(user: &str) -> Option<PathBuf> {
+    imp::user_home_dir(user)
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::ffi::{CStr, CString, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+
+    /// Largest buffer offered to `getpwnam_r` before giving up
+    const MAX_BUFFER: usize = 1 << 20;
+
+    pub fn user_home_dir(user: &str) -> Option<PathBuf> {
+        let name = CString::new(user).ok()?;
+        let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+        loop {
+            // SAFETY: an all-zero passwd is a valid value for every field
+            let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+            let mut found = std::ptr::null_mut();
+            // SAFETY: every pointer is valid for the duration of the call and
+            // `buffer.len()` is the size of the buffer behind `buffer`
+            let result = unsafe {
+                libc::getpwnam_r(
+                    name.as_ptr(),
+                    &mut entry,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut found,
+                )
+            };
+            if result == libc::ERANGE && buffer.len() < MAX_BUFFER {
+                buffer.resize(buffer.len() * 2, 0);
+                continue;
+            }
+            if result != 0 || found.is_null() || entry.pw_dir.is_null() {
+                return None;
+            }
+            // SAFETY: on success `pw_dir` points to a NUL-terminated string
+            // inside `buffer`, which is still alive
+            let dir = unsafe { CStr::from_ptr(entry.pw_dir) };
+            return Some(PathBuf::from(OsStr::from_bytes(dir.to_bytes())));
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::path::PathBuf;
+
+    pub fn user_home_dir(user: &str) -> Option<PathBuf> {
+        let home = super::home_dir()?.parent()?.join(user);
+        home.is_dir().then_some(home)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use std::path::PathBuf;
+
+    pub fn user_home_dir(_user: &str) -> Option<PathBuf> {
+        None
+    }
+}
@@ -1,5 +1,6 @@
 //! Error types for the file search library
 
+use crate::indexer::file_walker::TraversalLimit;
 use std::fmt;
 use std::path::PathBuf;
 
@@ -16,6 +17,7 @@ pub enum FileSearchError {
         path: Option<PathBuf>,
     },
     /// Invalid regular expression pattern
+    #[cfg(feature = "regex")]
     InvalidRegex {
         /// The regex error
         source: regex::Error,
@@ -23,9 +25,10 @@ pub enum FileSearchError {
         pattern: String,
     },
     /// Invalid glob pattern
+    #[cfg(feature = "glob")]
     InvalidGlob {
         /// The glob pattern error
-        source: glob::PatternError,
+        source: globset::Error,
         /// The pattern that failed to compile
         pattern: String,
     },
@@ -55,6 +58,39 @@ pub enum FileSearchError {
         /// Reason for the failure
         reason: String,
     },
+    /// File database is corrupt or was written by an incompatible version
+    InvalidDatabase {
+        /// Path of the database file
+        path: PathBuf,
+        /// Reason the database could not be used
+        reason: String,
+    },
+    /// Search mode whose cargo feature is not enabled in this build
+    FeatureDisabled {
+        /// Name of the cargo feature that provides the mode
+        feature: &'static str,
+    },
+    /// Background task running an async search failed to complete
+    #[cfg(feature = "async")]
+    AsyncTask {
+        /// The error returned when joining the task
+        source: tokio::task::JoinError,
+    },
+    /// Git repository could not be read
+    #[cfg(feature = "git")]
+    Git {
+        /// The underlying git error
+        source: git2::Error,
+        /// Path the repository was looked up from
+        path: PathBuf,
+    },
+    /// A walk reached a configured traversal limit and was stopped early
+    LimitExceeded {
+        /// The limit that was reached
+        limit: TraversalLimit,
+        /// Matches among the files found before the walk stopped
+        partial: Vec<PathBuf>,
+    },
     /// Configuration error
     InvalidConfig {
         /// Description of the configuration issue
@@ -82,11 +118,13 @@ impl fmt::Display for FileSearchError {
                     write!(f, "IO error in {context}: {source}")
                 }
             }
+            #[cfg(feature = "regex")]
             Self::InvalidRegex { source, pattern } => {
                 write!(f, "Invalid regex pattern '{pattern}': {source}")
             }
+            #[cfg(feature = "glob")]
             Self::InvalidGlob { source, pattern } => {
-                write!(f, "Invalid glob pattern '{pattern}': {source}")
+                write!(f, "Invalid glob pattern '{pattern}': {}", source.kind())
             }
             Self::WalkDir { source, root_path } => {
                 write!(
@@ -105,6 +143,34 @@ impl fmt::Display for FileSearchError {
             Self::InvalidPath { path, reason } => {
                 write!(f, "Invalid path '{}': {}", path.display(), reason)
             }
+            Self::InvalidDatabase { path, reason } => {
+                write!(f, "Invalid file database '{}': {}", path.display(), reason)
+            }
+            Self::FeatureDisabled { feature } => {
+                write!(
+                    f,
+                    "{feature} search is not available: built without the '{feature}' feature"
+                )
+            }
+            #[cfg(feature = "async")]
+            Self::AsyncTask { source } => {
+                if source.is_panic() {
+                    write!(f, "Async search task panicked")
+                } else {
+                    write!(f, "Async search task was cancelled: {source}")
+                }
+            }
+            #[cfg(feature = "git")]
+            Self::Git { source, path } => {
+                write!(f, "Git error in '{}': {}", path.display(), source.message())
+            }
+            Self::LimitExceeded { limit, partial } => {
+                write!(
+                    f,
+                    "Search stopped at {limit} with {} partial result(s)",
+                    partial.len()
+                )
+            }
             Self::InvalidConfig { reason } => {
                 write!(f, "Invalid configuration: {reason}")
             }
@@ -116,12 +182,21 @@ impl std::error::Error for FileSearchError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::Io { source, .. } => Some(source),
+            #[cfg(feature = "regex")]
             Self::InvalidRegex { source, .. } => Some(source),
+            #[cfg(feature = "glob")]
             Self::InvalidGlob { source, .. } => Some(source),
             Self::WalkDir { source, .. } => Some(source),
+            #[cfg(feature = "async")]
+            Self::AsyncTask { source } => Some(source),
+            #[cfg(feature = "git")]
+            Self::Git { source, .. } => Some(source),
             Self::EmptyIndex { .. }
             | Self::InvalidQuery { .. }
             | Self::InvalidPath { .. }
+            | Self::InvalidDatabase { .. }
+            | Self::FeatureDisabled { .. }
+            | Self::LimitExceeded { .. }
             | Self::InvalidConfig { .. } => None,
         }
     }
@@ -152,6 +227,7 @@ impl FileSearchError {
     }
 
     /// Create a regex error with pattern
+    #[cfg(feature = "regex")]
     pub fn regex_error<S: Into<String>>(source: regex::Error, pattern: S) -> Self {
         Self::InvalidRegex {
             source,
@@ -160,7 +236,8 @@ impl FileSearchError {
     }
 
     /// Create a glob error with pattern
-    pub fn glob_error<S: Into<String>>(source: glob::PatternError, pattern: S) -> Self {
+    #[cfg(feature = "glob")]
+    pub fn glob_error<S: Into<String>>(source: globset::Error, pattern: S) -> Self {
         Self::InvalidGlob {
             source,
             pattern: pattern.into(),
@@ -196,6 +273,43 @@ impl FileSearchError {
         }
     }
 
+    /// Create an invalid database error
+    pub fn invalid_database<P: Into<PathBuf>, R: Into<String>>(path: P, reason: R) -> Self {
+        Self::InvalidDatabase {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Create an error for a search mode whose feature is disabled
+    #[must_use]
+    pub fn feature_disabled(feature: &'static str) -> Self {
+        Self::FeatureDisabled { feature }
+    }
+
+    /// Create an async task error from a failed join
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub fn async_task(source: tokio::task::JoinError) -> Self {
+        Self::AsyncTask { source }
+    }
+
+    /// Create a git error for the repository looked up from `path`
+    #[cfg(feature = "git")]
+    pub fn git_error<P: Into<PathBuf>>(source: git2::Error, path: P) -> Self {
+        Self::Git {
+            source,
+            path: path.into(),
+        }
+    }
+
+    /// Create an error for a walk stopped at `limit`, keeping the `partial`
+    /// results found before it
+    #[must_use]
+    pub fn limit_exceeded(limit: TraversalLimit, partial: Vec<PathBuf>) -> Self {
+        Self::LimitExceeded { limit, partial }
+    }
+
     /// Create an invalid config error
     pub fn invalid_config<R: Into<String>>(reason: R) -> Self {
         Self::InvalidConfig {
@@ -211,15 +325,25 @@ impl From<std::io::Error> for FileSearchError {
     }
 }
 
+#[cfg(feature = "regex")]
 impl From<regex::Error> for FileSearchError {
     fn from(err: regex::Error) -> Self {
         Self::regex_error(err, "<unknown pattern>")
     }
 }
 
-impl From<glob::PatternError> for FileSearchError {
-    fn from(err: glob::PatternError) -> Self {
-        Self::glob_error(err, "<unknown pattern>")
+#[cfg(feature = "glob")]
+impl From<globset::Error> for FileSearchError {
+    fn from(err: globset::Error) -> Self {
+        let pattern = err.glob().unwrap_or("<unknown pattern>").to_string();
+        Self::glob_error(err, pattern)
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<tokio::task::JoinError> for FileSearchError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        Self::async_task(err)
     }
 }
 
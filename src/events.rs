@@ -0,0 +1,219 @@
+//! Newline-delimited JSON events for graphical frontends
+//!
+//! [`search`] indexes a directory and searches it while writing one JSON
+//! object per line through an [`EventWriter`], flushing after each, so a
+//! frontend running the search as a child process can drive a progress bar
+//! and fill its result list as events arrive. Every object names its kind in
+//! the `event` field:
+//!
+//! | Event            | Fields                                                   |
+//! |------------------|----------------------------------------------------------|
+//! | `index-progress` | `dirs`, `files`, optional `total_dirs` and `percent`     |
+//! | `warning`        | `message`, optional `path`                               |
+//! | `match`          | `path`, plus `score` for fuzzy queries                   |
+//! | `done`           | `files_indexed`, `matches`, `elapsed_ms`, `retries`, `vanished`, `timed_out` |
+//!
+//! Exactly one `done` event ends a successful search.
+//!
+//! Progress carries `total_dirs` and a `percent` between 0 and 100 only when
+//! the directories are counted in a quick first pass before indexing, which
+//! lets a frontend show a determinate progress bar at the cost of walking the
+//! tree twice.
+
+use crate::config::Config;
+use crate::indexer::FileIndexer;
+use crate::search::handle::{Progress, SearchHandle};
+use crate::search::{validate_query, SearchEngine, SearchMode};
+use crate::{FileSearchError, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often progress is reported while indexing
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// How often the indexing thread is checked for having finished
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Something that happened during a search
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    /// Indexing has walked this far
+    IndexProgress {
+        /// Counts so far
+        #[serde(flatten)]
+        progress: Progress,
+        /// Estimated share of the walk done, when directories were counted
+        #[serde(skip_serializing_if = "Option::is_none")]
+        percent: Option<f64>,
+    },
+    /// A problem that did not stop the search
+    Warning {
+        /// Description of the problem
+        message: String,
+        /// Path the problem concerns
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+    /// A file matching the query
+    Match {
+        /// Path of the file, styled as the configuration asks
+        path: String,
+        /// Relevance of the match, for fuzzy queries only
+        #[serde(skip_serializing_if = "Option::is_none")]
+        score: Option<f64>,
+    },
+    /// The search has finished
+    Done(DoneStats),
+}
+
+impl Event {
+    /// Progress event for `progress`, with its percentage when known
+    #[must_use]
+    pub fn progress(progress: Progress) -> Self {
+        Self::IndexProgress {
+            progress,
+            percent: progress.percent(),
+        }
+    }
+}
+
+/// Summary carried by the final [`Event::Done`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DoneStats {
+    /// Files in the index that was searched
+    pub files_indexed: usize,
+    /// Matches reported
+    pub matches: usize,
+    /// Time from the start of indexing to the end of the search
+    pub elapsed_ms: u64,
+    /// IO operations that were retried while indexing
+    pub retries: usize,
+    /// Entries that disappeared while indexing
+    pub vanished: usize,
+    /// Entries skipped because they did not respond within the IO timeout
+    pub timed_out: usize,
+}
+
+/// Writes events as JSON lines
+#[derive(Debug)]
+pub struct EventWriter<W: Write> {
+    output: W,
+}
+
+impl<W: Write> EventWriter<W> {
+    /// Write events to `output`
+    pub fn new(output: W) -> Self {
+        Self { output }
+    }
+
+    /// Write `event` as one line and flush it so the reader sees it at once
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the output fails
+    pub fn emit(&mut self, event: &Event) -> Result<()> {
+        let write = |output: &mut W| {
+            serde_json::to_writer(&mut *output, event)?;
+            output.write_all(b"\n")?;
+            output.flush()
+        };
+        write(&mut self.output).map_err(|e| FileSearchError::io_error(e, "writing event"))
+    }
+
+    /// The underlying output
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+/// Index `root`, search it for `query` and report every step to `events`
+///
+/// Progress is reported about every 100 milliseconds while indexing runs on
+/// a separate thread, and once more when it ends. With `count_dirs`, the
+/// directories are counted first so progress includes a percentage. Returns
+/// the number of matches.
+///
+/// # Errors
+///
+/// Returns an error if the query is invalid, `root` cannot be indexed or
+/// writing an event fails. No `done` event is written in that case.
+pub fn search<W: Write>(
+    root: &Path,
+    query: &str,
+    mode: SearchMode,
+    config: &Config,
+    count_dirs: bool,
+    events: &mut EventWriter<W>,
+) -> Result<usize> {
+    validate_query(query, mode)?;
+    let root_str = root
+        .to_str()
+        .ok_or_else(|| FileSearchError::invalid_path(root, "Contains invalid UTF-8"))?;
+    let started = Instant::now();
+    let handle = SearchHandle::new();
+    let mut indexer = FileIndexer::new(config.clone());
+    if count_dirs {
+        handle.set_total_dirs(indexer.count_dirs(root_str)?);
+    }
+
+    let index = thread::scope(|scope| {
+        let worker = scope.spawn(|| indexer.build_index_with_handle(root_str, &handle));
+        let mut reported = None;
+        let mut last_report = None::<Instant>;
+        let mut failed = None;
+        while !worker.is_finished() {
+            if failed.is_none() && last_report.map_or(true, |at| at.elapsed() >= PROGRESS_INTERVAL)
+            {
+                last_report = Some(Instant::now());
+                let progress = handle.progress();
+                if reported != Some(progress) {
+                    reported = Some(progress);
+                    if let Err(e) = events.emit(&Event::progress(progress)) {
+                        // Nobody is reading any more, so there is no point walking on
+                        handle.stop();
+                        failed = Some(e);
+                    }
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        let index = worker
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        if let Some(e) = failed {
+            return Err(e);
+        }
+        events.emit(&Event::progress(handle.progress()))?;
+        index
+    })?;
+
+    let stats = indexer.stats();
+    for path in &stats.timed_out {
+        events.emit(&Event::Warning {
+            message: "skipped, no response within IO timeout".to_string(),
+            path: Some(config.path_style.display(path).into_owned()),
+        })?;
+    }
+
+    let engine = SearchEngine::new(config.clone());
+    let results = engine.search_scored(&index, query, mode)?;
+    for (path, score) in &results {
+        events.emit(&Event::Match {
+            path: config.path_style.display(path).into_owned(),
+            score: *score,
+        })?;
+    }
+
+    events.emit(&Event::Done(DoneStats {
+        files_indexed: index.len(),
+        matches: results.len(),
+        elapsed_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        retries: stats.retries,
+        vanished: stats.vanished,
+        timed_out: stats.timed_out.len(),
+    }))?;
+    Ok(results.len())
+}
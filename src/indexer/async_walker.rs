@@ -0,0 +1,292 @@
+//! Directory walks on the Tokio runtime
+//!
+//! [`FileWalker`] blocks on every directory listing, so async callers have to
+//! move a whole walk onto Tokio's blocking thread pool, where a long traversal
+//! holds a thread from start to finish. [`AsyncWalker`] lists directories with
+//! `tokio::fs` instead, which only hands each single listing or `stat` to the
+//! pool, so concurrent walks and other blocking work take turns on it.
+//!
+//! The walker applies the hidden-file, ignore-pattern, depth, size, length,
+//! cloud placeholder, virtual file system, IO timeout and traversal limit
+//! rules of [`FileWalker`], and indexes the entry types
+//! [`Config::file_type`] asks for. Rules that read git state or per-file
+//! attributes, following symlinks, staying on one file system and IO
+//! throttling are only available from the blocking walker, and
+//! configurations using them are rejected rather than walked differently.
+
+use crate::config::tilde::expand_tilde;
+use crate::config::{CloudPlaceholders, Config};
+use crate::error::FileSearchError;
+use crate::indexer::file_walker::{FileWalker, LimitCounter, TraversalLimit, WalkStats};
+use crate::indexer::mounts::PseudoMounts;
+use crate::indexer::{is_deep_enough, placeholder};
+use crate::Result;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File system walker listing directories with `tokio::fs`
+pub struct AsyncWalker {
+    walker: FileWalker,
+    config: Config,
+}
+
+impl AsyncWalker {
+    /// Create a walker with the given configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration uses a rule that only
+    /// [`FileWalker`] supports
+    pub fn new(config: &Config) -> Result<Self> {
+        if let Some(setting) = unsupported(config) {
+            return Err(FileSearchError::invalid_config(format!(
+                "{setting} is not supported by the async walker"
+            )));
+        }
+        Ok(Self {
+            walker: FileWalker::new(config),
+            config: config.clone(),
+        })
+    }
+
+    /// Every entry below `root_path` that the configuration keeps and
+    /// indexes, with what the walk skipped
+    ///
+    /// A traversal limit ends the walk early, keeping the entries found
+    /// before it and recording the limit in [`WalkStats::limit_exceeded`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root_path` cannot be accessed or a directory below
+    /// it cannot be listed
+    pub async fn walk_files(&self, root_path: &str) -> Result<(Vec<PathBuf>, WalkStats)> {
+        let config = &self.config;
+        let mut walk = Walk::new(config);
+        let root = if config.expand_tilde {
+            PathBuf::from(expand_tilde(root_path).as_ref())
+        } else {
+            PathBuf::from(root_path)
+        };
+        let metadata = tokio::fs::metadata(&root).await.map_err(|e| {
+            let reason = if e.kind() == io::ErrorKind::NotFound {
+                "does not exist".to_string()
+            } else {
+                format!("cannot be accessed: {e}")
+            };
+            FileSearchError::invalid_path(&root, reason)
+        })?;
+
+        if self.walker.excludes_name(&root) {
+            return Ok((walk.files, walk.stats));
+        }
+        if !metadata.is_dir() {
+            if config.file_type.includes(metadata.file_type(), false)
+                && is_deep_enough(0, config)
+                && self.keeps_file(&root, &mut walk.stats).await?
+                && walk.count(false, config)
+            {
+                walk.files.push(root);
+            }
+            walk.stats.limit_exceeded = walk.limit;
+            return Ok((walk.files, walk.stats));
+        }
+        walk.count(true, config);
+
+        let pseudo = PseudoMounts::for_root(&root, config);
+        let mut pending = vec![(root.clone(), 0)];
+        while let Some((dir, depth)) = pending.pop() {
+            if walk.limit.is_some() {
+                break;
+            }
+            if config.max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+            let Some(mut entries) = self.read_dir(&dir, depth, &mut walk).await? else {
+                continue;
+            };
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| FileSearchError::io_error_with_path(e, "reading directory", &dir))?
+            {
+                let path = entry.path();
+                if self.walker.excludes_name(&path) {
+                    continue;
+                }
+                let file_type = match entry.file_type().await {
+                    Ok(file_type) => file_type,
+                    Err(e) if config.tolerate_races && e.kind() == io::ErrorKind::NotFound => {
+                        walk.stats.vanished += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        return Err(FileSearchError::io_error_with_path(
+                            e,
+                            "reading file type",
+                            &path,
+                        ))
+                    }
+                };
+
+                let indexed = config.file_type.includes(file_type, file_type.is_symlink())
+                    && is_deep_enough(depth + 1, config);
+                if file_type.is_dir() {
+                    let relative = path.strip_prefix(&root).unwrap_or(&path);
+                    if pseudo.fs_type(relative).is_none() && walk.count(true, config) {
+                        if indexed {
+                            walk.files.push(path.clone());
+                        }
+                        pending.push((path, depth + 1));
+                    }
+                } else if !file_type.is_file() {
+                    if walk.count(false, config) && indexed {
+                        walk.files.push(path);
+                    }
+                } else if self.keeps_file(&path, &mut walk.stats).await?
+                    && walk.count(false, config)
+                    && indexed
+                {
+                    walk.files.push(path);
+                }
+                if walk.limit.is_some() {
+                    break;
+                }
+            }
+        }
+
+        walk.stats.limit_exceeded = walk.limit;
+        Ok((walk.files, walk.stats))
+    }
+
+    /// Start listing `dir`, or `None` if it vanished, timed out or was still
+    /// pending at the deadline
+    async fn read_dir(
+        &self,
+        dir: &Path,
+        depth: usize,
+        walk: &mut Walk,
+    ) -> Result<Option<tokio::fs::ReadDir>> {
+        let listing = tokio::fs::read_dir(dir);
+        let remaining = walk.counter.remaining();
+        let timeout = match (self.config.io_timeout, remaining) {
+            (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+            (timeout, remaining) => timeout.or(remaining),
+        };
+        let result = if let Some(timeout) = timeout {
+            let Ok(result) = tokio::time::timeout(timeout, listing).await else {
+                if remaining == Some(timeout) {
+                    walk.limit = self.config.search_timeout.map(TraversalLimit::Time);
+                } else {
+                    walk.stats.timed_out.push(dir.to_path_buf());
+                }
+                return Ok(None);
+            };
+            result
+        } else {
+            listing.await
+        };
+        match result {
+            Ok(entries) => Ok(Some(entries)),
+            Err(e)
+                if self.config.tolerate_races
+                    && depth > 0
+                    && e.kind() == io::ErrorKind::NotFound =>
+            {
+                walk.stats.vanished += 1;
+                Ok(None)
+            }
+            Err(e) => Err(FileSearchError::io_error_with_path(
+                e,
+                "reading directory",
+                dir,
+            )),
+        }
+    }
+
+    /// Whether the length, placeholder and size rules keep the file at `path`
+    async fn keeps_file(&self, path: &Path, stats: &mut WalkStats) -> Result<bool> {
+        let config = &self.config;
+        if self.walker.excludes_length(path) {
+            return Ok(false);
+        }
+        let placeholders =
+            placeholder::DETECTABLE && config.cloud_placeholders != CloudPlaceholders::Include;
+        if config.max_file_size.is_none() && !placeholders {
+            return Ok(true);
+        }
+
+        let metadata = match tokio::fs::symlink_metadata(path).await {
+            Ok(metadata) => metadata,
+            Err(e) if config.tolerate_races && e.kind() == io::ErrorKind::NotFound => {
+                stats.vanished += 1;
+                return Ok(false);
+            }
+            Err(e) => {
+                return Err(FileSearchError::io_error_with_path(
+                    e,
+                    "reading metadata",
+                    path,
+                ))
+            }
+        };
+        if placeholders && placeholder::metadata_is_placeholder(&metadata) {
+            // Placeholders kept by name are exempt from the size limit
+            return Ok(config.cloud_placeholders == CloudPlaceholders::NameOnly);
+        }
+        Ok(config
+            .max_file_size
+            .map_or(true, |max_size| metadata.len() <= max_size))
+    }
+}
+
+/// What a walk has found so far
+struct Walk {
+    files: Vec<PathBuf>,
+    stats: WalkStats,
+    counter: LimitCounter,
+    limit: Option<TraversalLimit>,
+}
+
+impl Walk {
+    /// Walk starting now
+    fn new(config: &Config) -> Self {
+        Self {
+            files: Vec::new(),
+            stats: WalkStats::default(),
+            counter: LimitCounter::new(config),
+            limit: None,
+        }
+    }
+
+    /// Count an entry the rules keep, returning whether it is within the
+    /// traversal limits
+    fn count(&mut self, is_dir: bool, config: &Config) -> bool {
+        self.limit = self.counter.count(is_dir, config);
+        self.limit.is_none()
+    }
+}
+
+/// The first setting in `config` that only [`FileWalker`] supports
+fn unsupported(config: &Config) -> Option<&'static str> {
+    [
+        (config.respect_gitignore, "respect_gitignore"),
+        (config.git_tracked, "git_tracked"),
+        (config.changed_since.is_some(), "changed_since"),
+        (!config.audit_checks.is_empty(), "audit_checks"),
+        (!config.required_xattrs.is_empty(), "required_xattrs"),
+        (!config.required_tags.is_empty(), "required_tags"),
+        (
+            !config.required_attributes.is_empty(),
+            "required_attributes",
+        ),
+        (config.link_count.is_some(), "link_count"),
+        (
+            config.max_symlink_depth.is_some_and(|depth| depth > 0),
+            "max_symlink_depth",
+        ),
+        (config.io_throttle.is_some(), "io_throttle"),
+        (config.same_file_system, "same_file_system"),
+    ]
+    .into_iter()
+    .find_map(|(used, setting)| used.then_some(setting))
+}
@@ -0,0 +1,198 @@
+//! Sparse, compressed, encrypted and immutable file attributes
+//!
+//! Windows reports all but immutability as file attribute bits. Linux reports
+//! compression, encryption and immutability as inode flags, and macOS as BSD
+//! file flags; sparseness is inferred there from files occupying fewer blocks
+//! than their length. Attributes a platform cannot report are never set.
+
+use std::fs::Metadata;
+use std::path::Path;
+
+/// A storage attribute a file may have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileAttribute {
+    /// Unwritten regions take no disk space
+    Sparse,
+    /// Contents are compressed transparently by the file system
+    Compressed,
+    /// Contents are encrypted transparently by the file system
+    Encrypted,
+    /// The file cannot be modified, renamed or deleted
+    Immutable,
+}
+
+impl FileAttribute {
+    /// Every attribute
+    pub const ALL: [Self; 4] = [
+        Self::Sparse,
+        Self::Compressed,
+        Self::Encrypted,
+        Self::Immutable,
+    ];
+
+    /// Lowercase name, as used on the command line
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sparse => "sparse",
+            Self::Compressed => "compressed",
+            Self::Encrypted => "encrypted",
+            Self::Immutable => "immutable",
+        }
+    }
+}
+
+/// Storage attributes of one file
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileAttributes {
+    /// Unwritten regions take no disk space
+    pub sparse: bool,
+    /// Contents are compressed transparently by the file system
+    pub compressed: bool,
+    /// Contents are encrypted transparently by the file system
+    pub encrypted: bool,
+    /// The file cannot be modified, renamed or deleted
+    pub immutable: bool,
+}
+
+impl FileAttributes {
+    /// Read the attributes of the file at `path`, without following symlinks
+    ///
+    /// Returns `None` if the file cannot be inspected.
+    #[must_use]
+    pub fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::symlink_metadata(path).ok()?;
+        Some(imp::read(path, &metadata))
+    }
+
+    /// Whether `attribute` is set
+    #[must_use]
+    pub fn has(self, attribute: FileAttribute) -> bool {
+        match attribute {
+            FileAttribute::Sparse => self.sparse,
+            FileAttribute::Compressed => self.compressed,
+            FileAttribute::Encrypted => self.encrypted,
+            FileAttribute::Immutable => self.immutable,
+        }
+    }
+}
+
+/// Whether a file occupies fewer bytes on disk than its length
+///
+/// Files smaller than one block are never counted, since some file systems
+/// store them inline without allocating any blocks.
+#[cfg(unix)]
+fn has_holes(metadata: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.is_file()
+        && metadata.len() > metadata.blksize()
+        && metadata.blocks().saturating_mul(512) < metadata.len()
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::{has_holes, FileAttributes};
+    use std::fs::Metadata;
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    const FS_COMPR_FL: libc::c_int = 0x0000_0004;
+    const FS_IMMUTABLE_FL: libc::c_int = 0x0000_0010;
+    const FS_ENCRYPT_FL: libc::c_int = 0x0000_0800;
+
+    /// Inode flags of a regular file, read with `FS_IOC_GETFLAGS`
+    fn inode_flags(path: &Path) -> Option<libc::c_int> {
+        let file = std::fs::File::open(path).ok()?;
+        let mut flags: libc::c_int = 0;
+        // SAFETY: the descriptor is open for the duration of the call and the
+        // kernel writes a single int into `flags`
+        let result = unsafe { libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) };
+        (result == 0).then_some(flags)
+    }
+
+    pub fn read(path: &Path, metadata: &Metadata) -> FileAttributes {
+        // Opening special files could block or have side effects
+        let flags = if metadata.is_file() || metadata.is_dir() {
+            inode_flags(path).unwrap_or(0)
+        } else {
+            0
+        };
+        FileAttributes {
+            sparse: has_holes(metadata),
+            compressed: flags & FS_COMPR_FL != 0,
+            encrypted: flags & FS_ENCRYPT_FL != 0,
+            immutable: flags & FS_IMMUTABLE_FL != 0,
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod imp {
+    use super::{has_holes, FileAttributes};
+    use std::fs::Metadata;
+    use std::os::darwin::fs::MetadataExt;
+    use std::path::Path;
+
+    pub fn read(_path: &Path, metadata: &Metadata) -> FileAttributes {
+        let flags = metadata.st_flags();
+        FileAttributes {
+            sparse: has_holes(metadata),
+            compressed: flags & libc::UF_COMPRESSED != 0,
+            encrypted: false,
+            immutable: flags & (libc::UF_IMMUTABLE | libc::SF_IMMUTABLE) != 0,
+        }
+    }
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "linux", target_os = "macos", target_os = "ios"))
+))]
+mod imp {
+    use super::{has_holes, FileAttributes};
+    use std::fs::Metadata;
+    use std::path::Path;
+
+    pub fn read(_path: &Path, metadata: &Metadata) -> FileAttributes {
+        FileAttributes {
+            sparse: has_holes(metadata),
+            ..FileAttributes::default()
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::FileAttributes;
+    use std::fs::Metadata;
+    use std::os::windows::fs::MetadataExt;
+    use std::path::Path;
+
+    const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x0000_0200;
+    const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x0000_0800;
+    const FILE_ATTRIBUTE_ENCRYPTED: u32 = 0x0000_4000;
+
+    pub fn read(_path: &Path, metadata: &Metadata) -> FileAttributes {
+        let attributes = metadata.file_attributes();
+        FileAttributes {
+            sparse: attributes & FILE_ATTRIBUTE_SPARSE_FILE != 0,
+            compressed: attributes & FILE_ATTRIBUTE_COMPRESSED != 0,
+            encrypted: attributes & FILE_ATTRIBUTE_ENCRYPTED != 0,
+            immutable: false,
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use super::FileAttributes;
+    use std::fs::Metadata;
+    use std::path::Path;
+
+    pub fn read(_path: &Path, _metadata: &Metadata) -> FileAttributes {
+        FileAttributes::default()
+    }
+}
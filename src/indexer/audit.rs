@@ -0,0 +1,63 @@
+//! Permission and ownership checks for security audits
+//!
+//! With [`Config::audit_checks`] set, a walk still descends into every
+//! directory but only reports files flagged by at least one check, so the
+//! fast walker doubles as a permission sweep. The checks read Unix mode bits
+//! and owners; on other platforms no file is ever flagged.
+
+use crate::config::{AuditCheck, Config};
+use std::path::Path;
+
+/// The first of the configured checks that flags `path`, if any
+///
+/// Symbolic links are inspected themselves rather than followed.
+#[must_use]
+pub fn flagged_by(path: &Path, config: &Config) -> Option<AuditCheck> {
+    config
+        .audit_checks
+        .iter()
+        .copied()
+        .find(|check| check.flags(path))
+}
+
+impl AuditCheck {
+    /// Whether this check flags the file at `path`
+    #[cfg(unix)]
+    #[must_use]
+    pub fn flags(self, path: &Path) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        let Ok(metadata) = std::fs::symlink_metadata(path) else {
+            return false;
+        };
+        if metadata.file_type().is_symlink() {
+            return false;
+        }
+        let mode = metadata.mode();
+        match self {
+            Self::WorldWritable => mode & 0o002 != 0,
+            Self::SetId => mode & 0o6000 != 0,
+            Self::RootOwnedInUserDir => {
+                metadata.uid() == 0
+                    && path
+                        .parent()
+                        .map(|parent| {
+                            if parent.as_os_str().is_empty() {
+                                Path::new(".")
+                            } else {
+                                parent
+                            }
+                        })
+                        .and_then(|parent| std::fs::metadata(parent).ok())
+                        .is_some_and(|parent| parent.uid() != 0)
+            }
+        }
+    }
+
+    /// Whether this check flags the file at `path`
+    #[cfg(not(unix))]
+    #[must_use]
+    pub fn flags(self, _path: &Path) -> bool {
+        false
+    }
+}
@@ -0,0 +1,104 @@
+//! Storage abstraction for filename indexes
+//!
+//! [`SearchEngine`](crate::search::SearchEngine) only talks to an index through
+//! [`IndexBackend`], so persistent, remote or database-backed stores can be
+//! searched without changes to the engine. The in-memory [`FileIndex`] is the
+//! default backend.
+
+use crate::indexer::FileIndex;
+use crate::search::fold::Folding;
+use std::path::{Path, PathBuf};
+
+/// Storage engine holding filename keys and the paths recorded under them
+///
+/// Keys are filenames, lowercased unless the index is case-sensitive. Names
+/// that are not valid UTF-8 are keyed by their lossy form, with invalid bytes
+/// replaced by U+FFFD, while their paths are kept as they are on disk.
+pub trait IndexBackend {
+    /// Record `path` under the filename key `name`
+    fn insert(&mut self, name: String, path: PathBuf);
+
+    /// Call `f` with every filename key and its paths, in no particular order
+    fn for_each_name(&self, f: &mut dyn FnMut(&str, &[PathBuf]));
+
+    /// Paths recorded under exactly the key `name`
+    fn lookup_exact(&self, name: &str) -> Vec<PathBuf>;
+
+    /// Number of distinct filename keys
+    fn name_count(&self) -> usize;
+
+    /// Total number of recorded paths
+    fn path_count(&self) -> usize {
+        let mut count = 0;
+        self.for_each_name(&mut |_, paths| count += paths.len());
+        count
+    }
+
+    /// Whether no paths are recorded
+    fn is_empty(&self) -> bool {
+        self.name_count() == 0
+    }
+
+    /// The in-memory index holding the keys, if this is one, which searches
+    /// can split across threads
+    fn as_file_index(&self) -> Option<&FileIndex> {
+        None
+    }
+}
+
+impl IndexBackend for FileIndex {
+    /// Costs time linear in the size of the index; build in bulk with
+    /// `collect` or `extend` instead where possible
+    fn insert(&mut self, name: String, path: PathBuf) {
+        self.extend(std::iter::once((name, path)));
+    }
+
+    fn for_each_name(&self, f: &mut dyn FnMut(&str, &[PathBuf])) {
+        for (name, paths) in self {
+            f(name, paths);
+        }
+    }
+
+    fn lookup_exact(&self, name: &str) -> Vec<PathBuf> {
+        self.get(name).map(<[PathBuf]>::to_vec).unwrap_or_default()
+    }
+
+    fn name_count(&self) -> usize {
+        FileIndex::len(self)
+    }
+
+    fn path_count(&self) -> usize {
+        FileIndex::path_count(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        FileIndex::is_empty(self)
+    }
+
+    fn as_file_index(&self) -> Option<&FileIndex> {
+        Some(self)
+    }
+}
+
+/// Index key for `filename` under the given case sensitivity and
+/// normalization
+pub(crate) fn name_key(filename: &str, folding: Folding) -> String {
+    folding.fold(filename).into_owned()
+}
+
+/// Record `path` in `backend` under its filename, if it has one
+pub(crate) fn insert_path<B: IndexBackend + ?Sized>(
+    backend: &mut B,
+    path: &Path,
+    folding: Folding,
+) {
+    if let Some((name, path)) = path_entry(path.to_path_buf(), folding) {
+        backend.insert(name, path);
+    }
+}
+
+/// Index key and path recording `path` under its filename, if it has one
+pub(crate) fn path_entry(path: PathBuf, folding: Folding) -> Option<(String, PathBuf)> {
+    let name = name_key(&path.file_name()?.to_string_lossy(), folding);
+    Some((name, path))
+}
@@ -0,0 +1,165 @@
+//! Filename n-gram bloom filters used to skip shards that cannot match a query
+//!
+//! Filenames and queries are folded to lowercase character by character before
+//! their trigrams are taken, so a filter answers for both case-sensitive and
+//! case-insensitive searches. A filter can report false positives but never
+//! false negatives.
+
+use crate::search::SearchMode;
+use std::collections::HashSet;
+
+/// Length of the character n-grams recorded in the filter
+const NGRAM_LEN: usize = 3;
+/// Bits reserved per distinct n-gram
+const BITS_PER_NGRAM: usize = 10;
+/// Number of hash probes per n-gram, optimal for `BITS_PER_NGRAM`
+const HASH_COUNT: u32 = 7;
+
+/// Bloom filter over the character trigrams of a set of filenames
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NgramBloom {
+    bits: Vec<u64>,
+    hash_count: u32,
+}
+
+impl Default for NgramBloom {
+    fn default() -> Self {
+        Self {
+            bits: vec![0; 1],
+            hash_count: HASH_COUNT,
+        }
+    }
+}
+
+impl NgramBloom {
+    /// Build a filter sized for the trigrams of the given filenames
+    pub fn from_names<'a, I: IntoIterator<Item = &'a str>>(names: I) -> Self {
+        let hashes: HashSet<u64> = names
+            .into_iter()
+            .flat_map(|name| ngrams(&fold(name)))
+            .map(|ngram| fnv1a(ngram.as_bytes()))
+            .collect();
+
+        let words = ((hashes.len() * BITS_PER_NGRAM).next_power_of_two() / 64).max(1);
+        let mut bloom = Self {
+            bits: vec![0; words],
+            hash_count: HASH_COUNT,
+        };
+        for hash in hashes {
+            for bit in bloom.probes(hash) {
+                bloom.bits[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        bloom
+    }
+
+    /// Restore a filter from its raw parts, as written by [`NgramBloom::to_parts`]
+    ///
+    /// Returns `None` if the parts do not describe a valid filter.
+    #[must_use]
+    pub fn from_parts(bits: Vec<u64>, hash_count: u32) -> Option<Self> {
+        (bits.len().is_power_of_two() && hash_count > 0).then_some(Self { bits, hash_count })
+    }
+
+    /// Raw bit words and hash count, for persisting the filter
+    #[must_use]
+    pub fn to_parts(&self) -> (&[u64], u32) {
+        (&self.bits, self.hash_count)
+    }
+
+    /// Whether every one of `ngrams` may be present in the filter
+    #[must_use]
+    pub fn may_contain_all(&self, ngrams: &[String]) -> bool {
+        ngrams.iter().all(|ngram| {
+            self.probes(fnv1a(ngram.as_bytes()))
+                .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+        })
+    }
+
+    /// Trigrams any filename matching `query` in `mode` must contain
+    ///
+    /// Returns an empty list when nothing can be required, e.g. for regex,
+    /// fuzzy and custom queries or queries shorter than a trigram.
+    #[must_use]
+    pub fn query_ngrams(query: &str, mode: SearchMode) -> Vec<String> {
+        let literals = match mode {
+            SearchMode::Substring | SearchMode::Literal => vec![query.to_string()],
+            // Directory parts of a full-path glob need not be in the name
+            SearchMode::Glob => glob_literals(
+                query
+                    .rsplit(std::path::is_separator)
+                    .next()
+                    .unwrap_or(query),
+            ),
+            SearchMode::Regex | SearchMode::Fuzzy | SearchMode::Custom => Vec::new(),
+        };
+        literals
+            .iter()
+            .flat_map(|literal| ngrams(&fold(literal)))
+            .collect()
+    }
+
+    // Probes are masked to the bit count, so they always fit in usize
+    #[allow(clippy::cast_possible_truncation)]
+    fn probes(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let mask = self.bits.len() as u64 * 64 - 1;
+        let step = (hash >> 32) | 1;
+        (0..u64::from(self.hash_count))
+            .map(move |i| (hash.wrapping_add(i.wrapping_mul(step)) & mask) as usize)
+    }
+}
+
+/// Lowercase `text` one character at a time so substrings stay substrings
+fn fold(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| if c == 'ς' { 'σ' } else { c })
+        .collect()
+}
+
+fn ngrams(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .windows(NGRAM_LEN)
+        .map(|window| window.iter().collect())
+        .collect()
+}
+
+/// Literal runs of a glob pattern outside wildcards, character classes and
+/// `{a,b}` alternatives
+fn glob_literals(pattern: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut current = String::new();
+    let mut in_class = false;
+    let mut alternatives = 0usize;
+
+    for c in pattern.chars() {
+        match c {
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            _ if in_class => {}
+            '{' => alternatives += 1,
+            '}' if alternatives > 0 => alternatives -= 1,
+            _ if alternatives > 0 => {}
+            '*' | '?' => {}
+            _ => {
+                current.push(c);
+                continue;
+            }
+        }
+        if !current.is_empty() {
+            literals.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() && !in_class {
+        literals.push(current);
+    }
+    literals
+}
+
+/// 64-bit FNV-1a, stable across platforms and releases unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,345 @@
+//! Index of one root kept on disk between runs
+//!
+//! Walking hundreds of thousands of files dominates the time of a cold
+//! search. An [`IndexCache`] records the files and subdirectories found in
+//! every directory below a root together with the directory's modification
+//! time, which changes whenever an entry is created, deleted or renamed in
+//! it. [`IndexCache::refresh`] then only lists the directories whose time
+//! changed and reuses the recorded entries of all others, so a reloaded
+//! cache costs one `stat` per directory instead of a full walk.
+//!
+//! As for incremental updates, a directory modified while it was listed may
+//! keep the same time afterwards on file systems with coarse timestamps, so
+//! times at or after the start of the listing are never trusted and such
+//! directories are listed again by the next refresh.
+//!
+//! Entries are filtered with the configuration of the listing that found
+//! them, so a cache should be refreshed with the configuration it was built
+//! with. Files in the trash are not cached. Names that are not valid UTF-8
+//! are kept as they are on Unix and saved in their lossy form elsewhere.
+
+use crate::config::Config;
+use crate::error::FileSearchError;
+use crate::indexer::backend;
+use crate::indexer::database::{
+    read_len, read_os_str, read_u32, read_u64, write_len, write_os_str,
+};
+use crate::indexer::file_walker::FileWalker;
+use crate::indexer::FileIndex;
+use crate::search::fold::Folding;
+use crate::Result;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current version of the on-disk cache format
+pub const CACHE_VERSION: u32 = 3;
+
+const MAGIC: &[u8; 4] = b"WFIC";
+
+/// What a directory held when it was last listed
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CachedDir {
+    /// Modification time as seconds and nanoseconds since the Unix epoch,
+    /// if the platform reports one
+    modified: Option<(u64, u32)>,
+    /// Names of the entries directly inside that are indexed, which need
+    /// not be UTF-8
+    files: Vec<OsString>,
+    /// Names of the subdirectories to descend into, which need not be UTF-8
+    subdirs: Vec<OsString>,
+}
+
+/// Files below one root, by directory, with the times needed to tell which
+/// directories changed since
+///
+/// Caches compare equal when they hold the same listings, whenever those
+/// were made.
+#[derive(Debug, Clone)]
+pub struct IndexCache {
+    root: PathBuf,
+    /// When the last refresh started
+    started: Option<(u64, u32)>,
+    dirs: BTreeMap<PathBuf, CachedDir>,
+}
+
+impl PartialEq for IndexCache {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root && self.dirs == other.dirs
+    }
+}
+
+impl Eq for IndexCache {}
+
+impl IndexCache {
+    /// Walk `root` with `config`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` cannot be walked
+    pub fn build(root: &Path, config: &Config) -> Result<Self> {
+        let mut cache = Self {
+            root: root.to_path_buf(),
+            started: None,
+            dirs: BTreeMap::new(),
+        };
+        cache.refresh(config)?;
+        Ok(cache)
+    }
+
+    /// Load the cache at `cache_path` and refresh it if it covers `root`,
+    /// otherwise walk `root` from scratch, then save the result
+    ///
+    /// A missing, unreadable or outdated cache file is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` cannot be walked or the cache cannot be
+    /// saved
+    pub fn open(cache_path: &Path, root: &Path, config: &Config) -> Result<Self> {
+        let cache = match Self::load(cache_path) {
+            Ok(mut cache) if cache.root == root => {
+                cache.refresh(config)?;
+                cache
+            }
+            _ => Self::build(root, config)?,
+        };
+        cache.save(cache_path)?;
+        Ok(cache)
+    }
+
+    /// Root the cache covers
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Number of cached files
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.dirs.values().map(|dir| dir.files.len()).sum()
+    }
+
+    /// Whether no files are cached
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every cached file
+    pub fn files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.dirs
+            .iter()
+            .flat_map(|(dir, cached)| cached.files.iter().map(move |name| dir.join(name)))
+    }
+
+    /// Search index of the cached files
+    #[must_use]
+    pub fn to_index(&self, config: &Config) -> FileIndex {
+        self.files()
+            .filter_map(|path| backend::path_entry(path, Folding::new(config)))
+            .collect()
+    }
+
+    /// List again every directory whose modification time changed since it
+    /// was last listed, or was too recent to trust then, and forget
+    /// directories that are gone
+    ///
+    /// Returns how many directories were listed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a changed directory cannot be listed
+    pub fn refresh(&mut self, config: &Config) -> Result<usize> {
+        let walker = FileWalker::new(&Config {
+            max_depth: Some(1),
+            ..config.clone()
+        });
+        let previous = std::mem::replace(&mut self.started, timestamp(SystemTime::now()));
+        let mut stale = std::mem::take(&mut self.dirs);
+        let mut listed = 0;
+        let mut pending = vec![(self.root.clone(), 0)];
+
+        while let Some((dir, depth)) = pending.pop() {
+            if config.max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+            let modified = modified(&dir);
+            let trusted = modified.is_some_and(|modified| Some(modified) < previous);
+            let cached = match stale.remove(&dir) {
+                Some(cached) if trusted && cached.modified == modified => cached,
+                _ => {
+                    listed += 1;
+                    list(&walker, config, &dir, depth, modified)?
+                }
+            };
+            pending.extend(
+                cached
+                    .subdirs
+                    .iter()
+                    .map(|name| (dir.join(name), depth + 1)),
+            );
+            self.dirs.insert(dir, cached);
+        }
+        Ok(listed)
+    }
+
+    /// Write the cache to `path`, replacing it atomically
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                FileSearchError::io_error_with_path(e, "creating index cache directory", parent)
+            })?;
+        }
+
+        let temp_path = path.with_extension("tmp");
+        let write = || -> std::io::Result<()> {
+            let mut writer = BufWriter::new(File::create(&temp_path)?);
+            writer.write_all(MAGIC)?;
+            writer.write_all(&CACHE_VERSION.to_le_bytes())?;
+            write_os_str(&mut writer, self.root.as_os_str())?;
+            write_time(&mut writer, self.started)?;
+            write_len(&mut writer, self.dirs.len())?;
+            for (dir, cached) in &self.dirs {
+                write_os_str(&mut writer, dir.as_os_str())?;
+                write_time(&mut writer, cached.modified)?;
+                write_len(&mut writer, cached.files.len())?;
+                for name in &cached.files {
+                    write_os_str(&mut writer, name)?;
+                }
+                write_len(&mut writer, cached.subdirs.len())?;
+                for name in &cached.subdirs {
+                    write_os_str(&mut writer, name)?;
+                }
+            }
+            writer.flush()?;
+            std::fs::rename(&temp_path, path)
+        };
+
+        write().map_err(|e| FileSearchError::io_error_with_path(e, "writing index cache", path))
+    }
+
+    /// Read a cache previously written with [`IndexCache::save`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not an index cache, or
+    /// was written by an incompatible version
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| FileSearchError::io_error_with_path(e, "opening index cache", path))?;
+        let mut reader = BufReader::new(file);
+        let io_err = |e| FileSearchError::io_error_with_path(e, "reading index cache", path);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).map_err(io_err)?;
+        if &magic != MAGIC {
+            return Err(FileSearchError::invalid_database(
+                path,
+                "Not a whatever-find index cache",
+            ));
+        }
+        let version = read_u32(&mut reader).map_err(io_err)?;
+        if version != CACHE_VERSION {
+            return Err(FileSearchError::invalid_database(
+                path,
+                format!("Unsupported index cache version {version} (expected {CACHE_VERSION})"),
+            ));
+        }
+
+        let read_names = |reader: &mut BufReader<File>| -> std::io::Result<Vec<OsString>> {
+            (0..read_len(reader)?)
+                .map(|_| read_os_str(reader))
+                .collect()
+        };
+        let root = PathBuf::from(read_os_str(&mut reader).map_err(io_err)?);
+        let started = read_time(&mut reader).map_err(io_err)?;
+        let dir_count = read_len(&mut reader).map_err(io_err)?;
+        let mut dirs = BTreeMap::new();
+        for _ in 0..dir_count {
+            let dir = PathBuf::from(read_os_str(&mut reader).map_err(io_err)?);
+            let modified = read_time(&mut reader).map_err(io_err)?;
+            let files = read_names(&mut reader).map_err(io_err)?;
+            let subdirs = read_names(&mut reader).map_err(io_err)?;
+            dirs.insert(
+                dir,
+                CachedDir {
+                    modified,
+                    files,
+                    subdirs,
+                },
+            );
+        }
+
+        Ok(Self {
+            root,
+            started,
+            dirs,
+        })
+    }
+}
+
+/// Write a time from [`timestamp`], with all bits set for none
+fn write_time(writer: &mut impl Write, time: Option<(u64, u32)>) -> std::io::Result<()> {
+    let (secs, nanos) = time.unwrap_or((u64::MAX, u32::MAX));
+    writer.write_all(&secs.to_le_bytes())?;
+    writer.write_all(&nanos.to_le_bytes())
+}
+
+/// Read a time written by [`write_time`]
+fn read_time(reader: &mut impl Read) -> std::io::Result<Option<(u64, u32)>> {
+    let secs = read_u64(reader)?;
+    let nanos = read_u32(reader)?;
+    Ok(((secs, nanos) != (u64::MAX, u32::MAX)).then_some((secs, nanos)))
+}
+
+/// Modification time of `dir`, if it can be read
+fn modified(dir: &Path) -> Option<(u64, u32)> {
+    timestamp(
+        std::fs::metadata(dir)
+            .and_then(|metadata| metadata.modified())
+            .ok()?,
+    )
+}
+
+/// `time` as seconds and nanoseconds since the Unix epoch
+pub(crate) fn timestamp(time: SystemTime) -> Option<(u64, u32)> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).ok()?;
+    Some((since_epoch.as_secs(), since_epoch.subsec_nanos()))
+}
+
+/// The entries indexed under `config` and subdirectories directly inside
+/// `dir`, `depth` levels below the root, that `walker` keeps
+fn list(
+    walker: &FileWalker,
+    config: &Config,
+    dir: &Path,
+    depth: usize,
+    modified: Option<(u64, u32)>,
+) -> Result<CachedDir> {
+    let mut cached = CachedDir {
+        modified,
+        files: Vec::new(),
+        subdirs: Vec::new(),
+    };
+    for entry in walker.walk_dir(dir)? {
+        let entry = entry?;
+        if entry.depth() == 0 {
+            continue;
+        }
+        if crate::indexer::is_indexed(&entry, depth, config) {
+            cached.files.push(entry.file_name().to_os_string());
+        }
+        if entry.file_type().is_dir() {
+            cached.subdirs.push(entry.file_name().to_os_string());
+        }
+    }
+    Ok(cached)
+}
@@ -0,0 +1,534 @@
+//! Persistent, versioned file database in the spirit of `updatedb`
+//!
+//! A database stores the file paths found under one or more indexed roots so
+//! that later searches can skip walking the file system entirely. Each root is
+//! split into [`Shard`]s that are built, searched and refreshed independently.
+
+use crate::config::Config;
+use crate::error::FileSearchError;
+use crate::indexer::bloom::NgramBloom;
+use crate::indexer::shard::{parallel_map, Shard};
+use crate::indexer::FileIndex;
+use crate::search::{SearchEngine, SearchMode};
+use crate::Result;
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current version of the on-disk database format
+pub const DATABASE_VERSION: u32 = 3;
+
+const MAGIC: &[u8; 4] = b"WFDB";
+
+/// A single indexed root, split into shards by top-level directory
+#[derive(Debug, Clone)]
+pub struct DatabaseRoot {
+    /// Canonical path of the indexed root
+    pub path: PathBuf,
+    /// Shards making up this root, ordered by prefix
+    pub shards: Vec<Shard>,
+}
+
+impl DatabaseRoot {
+    /// Walk `root` with the given configuration, building its shards in parallel
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the root cannot be resolved or traversed
+    pub fn build(root: &Path, config: &Config) -> Result<Self> {
+        let path = root
+            .canonicalize()
+            .map_err(|e| FileSearchError::io_error_with_path(e, "resolving index root", root))?;
+        let prefixes = Shard::prefixes(&path, config)?;
+        let mut shards = parallel_map(&prefixes, |prefix| Shard::build(&path, prefix, config))
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        shards.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+
+        Ok(Self { path, shards })
+    }
+
+    /// Re-index a single shard, leaving the rest of the root untouched
+    ///
+    /// Shards whose directory no longer exists are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shard directory cannot be traversed
+    pub fn refresh_shard(&mut self, prefix: &Path, config: &Config) -> Result<()> {
+        let position = self
+            .shards
+            .binary_search_by(|s| s.prefix.as_path().cmp(prefix));
+        if !prefix.as_os_str().is_empty() && !self.path.join(prefix).is_dir() {
+            if let Ok(position) = position {
+                self.shards.remove(position);
+            }
+            return Ok(());
+        }
+
+        let shard = Shard::build(&self.path, prefix, config)?;
+        match position {
+            Ok(position) => self.shards[position] = shard,
+            Err(position) => self.shards.insert(position, shard),
+        }
+        Ok(())
+    }
+
+    /// Time at which the least recently indexed shard was built
+    #[must_use]
+    pub fn indexed_at(&self) -> u64 {
+        self.shards.iter().map(|s| s.indexed_at).min().unwrap_or(0)
+    }
+
+    /// Number of files recorded beneath this root
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(Shard::len).sum()
+    }
+
+    /// Full paths of all files recorded beneath this root
+    pub fn files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.shards.iter().flat_map(move |shard| {
+            let dir = self.path.join(&shard.prefix);
+            shard.entries.iter().map(move |entry| dir.join(entry))
+        })
+    }
+
+    /// Whether no files are recorded beneath this root
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Versioned collection of indexed roots that can be saved to and loaded from disk
+#[derive(Debug, Clone, Default)]
+pub struct Database {
+    roots: Vec<DatabaseRoot>,
+}
+
+impl Database {
+    /// Create an empty database
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a database by walking each of the given roots
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any root cannot be resolved or traversed
+    pub fn build<P: AsRef<Path>>(roots: &[P], config: &Config) -> Result<Self> {
+        let mut database = Self::new();
+        for root in roots {
+            database.index_root(root.as_ref(), config)?;
+        }
+        Ok(database)
+    }
+
+    /// Index `root`, replacing any previous entries recorded for it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the root cannot be resolved or traversed
+    pub fn index_root(&mut self, root: &Path, config: &Config) -> Result<()> {
+        let built = DatabaseRoot::build(root, config)?;
+        if let Some(existing) = self.roots.iter_mut().find(|r| r.path == built.path) {
+            *existing = built;
+        } else {
+            self.roots.push(built);
+        }
+        Ok(())
+    }
+
+    /// Re-index only the shard `prefix` of the already indexed `root`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` is not part of the database or the shard
+    /// cannot be traversed
+    pub fn refresh_shard(&mut self, root: &Path, prefix: &Path, config: &Config) -> Result<()> {
+        let canonical = root
+            .canonicalize()
+            .map_err(|e| FileSearchError::io_error_with_path(e, "resolving index root", root))?;
+        let db_root = self
+            .roots
+            .iter_mut()
+            .find(|r| r.path == canonical)
+            .ok_or_else(|| {
+                FileSearchError::invalid_path(root, "Not indexed in the file database")
+            })?;
+        db_root.refresh_shard(prefix, config)
+    }
+
+    /// Roots recorded in this database
+    #[must_use]
+    pub fn roots(&self) -> &[DatabaseRoot] {
+        &self.roots
+    }
+
+    /// Full paths of all files recorded across all roots
+    pub fn files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.roots.iter().flat_map(DatabaseRoot::files)
+    }
+
+    /// Total number of files recorded across all roots
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.roots.iter().map(DatabaseRoot::len).sum()
+    }
+
+    /// Whether the database records no files at all
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Find the indexed root containing `path`, if any
+    #[must_use]
+    pub fn covering_root(&self, path: &Path) -> Option<&DatabaseRoot> {
+        let canonical = path.canonicalize().ok()?;
+        self.roots.iter().find(|r| canonical.starts_with(&r.path))
+    }
+
+    /// Whether searches under `path` can be answered from this database
+    #[must_use]
+    pub fn covers(&self, path: &Path) -> bool {
+        self.covering_root(path).is_some()
+    }
+
+    /// Build a searchable index of the recorded files beneath `root`
+    ///
+    /// Paths in the returned index are expressed relative to `root` exactly as a
+    /// file system walk would report them, and the depth and ignore settings of
+    /// `config` are applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` is not covered by this database
+    pub fn to_index(&self, root: &Path, config: &Config) -> Result<FileIndex> {
+        let (db_root, subpath) = self.resolve(root)?;
+
+        // Collected first so the sorted layout is built once, not per shard
+        let mut entries = Vec::new();
+        for shard in db_root.shards.iter().filter(|s| s.may_contain(&subpath)) {
+            entries.extend(shard.index(root, &subpath, config));
+        }
+        Ok(entries.into_iter().collect())
+    }
+
+    /// Search the recorded files beneath `root`, querying shards in parallel
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` is not covered by this database or the query
+    /// is not a valid pattern for `mode`
+    pub fn search(
+        &self,
+        root: &Path,
+        query: &str,
+        mode: SearchMode,
+        config: &Config,
+    ) -> Result<Vec<PathBuf>> {
+        #[cfg(feature = "fuzzy")]
+        if mode == SearchMode::Fuzzy {
+            return Ok(self
+                .search_fuzzy(root, query, config)?
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect());
+        }
+
+        let mut engine = SearchEngine::new(config.clone());
+        engine.set_root(root);
+        let mut results = Vec::new();
+        for shard_results in self.map_shards(root, config, query, mode, |index| {
+            engine.search(index, query, mode)
+        })? {
+            results.extend(shard_results?);
+        }
+        engine.sort_matches(&mut results);
+        Ok(results)
+    }
+
+    /// Fuzzy search the recorded files beneath `root`, querying shards in parallel
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` is not covered by this database
+    #[cfg(feature = "fuzzy")]
+    pub fn search_fuzzy(
+        &self,
+        root: &Path,
+        query: &str,
+        config: &Config,
+    ) -> Result<Vec<(PathBuf, f64)>> {
+        let mut engine = SearchEngine::new(config.clone());
+        engine.set_root(root);
+        let mut results: Vec<(PathBuf, f64)> = self
+            .map_shards(root, config, query, SearchMode::Fuzzy, |index| {
+                engine.search_fuzzy(index, query)
+            })?
+            .into_iter()
+            .flatten()
+            .collect();
+        engine.rank_fuzzy(&mut results);
+        Ok(results)
+    }
+
+    /// Run `f` over the index of every shard that may hold matches for `query`
+    ///
+    /// Shards whose trigram summary rules out a match are skipped without
+    /// building their index.
+    fn map_shards<R, F>(
+        &self,
+        root: &Path,
+        config: &Config,
+        query: &str,
+        mode: SearchMode,
+        f: F,
+    ) -> Result<Vec<R>>
+    where
+        R: Send,
+        F: Fn(&FileIndex) -> R + Sync,
+    {
+        let (db_root, subpath) = self.resolve(root)?;
+        // Summaries hold trigrams of the names as stored, which a normalized
+        // query or one matching relative paths need not share
+        let required = if config.unicode_normalization || config.match_relative_path {
+            Vec::new()
+        } else {
+            NgramBloom::query_ngrams(query, mode)
+        };
+        let shards: Vec<&Shard> = db_root
+            .shards
+            .iter()
+            .filter(|s| s.may_contain(&subpath) && s.bloom.may_contain_all(&required))
+            .collect();
+        Ok(parallel_map(&shards, |shard| {
+            f(&shard.index(root, &subpath, config))
+        }))
+    }
+
+    /// Locate the root covering `path` and the path of `path` relative to it
+    fn resolve(&self, path: &Path) -> Result<(&DatabaseRoot, PathBuf)> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| FileSearchError::io_error_with_path(e, "resolving search root", path))?;
+        let db_root = self
+            .roots
+            .iter()
+            .find(|r| canonical.starts_with(&r.path))
+            .ok_or_else(|| {
+                FileSearchError::invalid_path(path, "Not covered by the file database")
+            })?;
+        let subpath = canonical
+            .strip_prefix(&db_root.path)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Ok((db_root, subpath))
+    }
+
+    /// Write the database to `path`
+    ///
+    /// The file is written next to its destination and renamed into place so
+    /// that concurrent readers never observe a partially written database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                FileSearchError::io_error_with_path(e, "creating database directory", parent)
+            })?;
+        }
+
+        let temp_path = path.with_extension("tmp");
+        let write = || -> std::io::Result<()> {
+            let mut writer = BufWriter::new(File::create(&temp_path)?);
+            writer.write_all(MAGIC)?;
+            writer.write_all(&DATABASE_VERSION.to_le_bytes())?;
+            write_len(&mut writer, self.roots.len())?;
+            for root in &self.roots {
+                write_str(&mut writer, &root.path.to_string_lossy())?;
+                write_len(&mut writer, root.shards.len())?;
+                for shard in &root.shards {
+                    write_str(&mut writer, &shard.prefix.to_string_lossy())?;
+                    writer.write_all(&shard.indexed_at.to_le_bytes())?;
+                    write_len(&mut writer, shard.entries.len())?;
+                    for entry in &shard.entries {
+                        write_str(&mut writer, &entry.to_string_lossy())?;
+                    }
+                    let (bits, hash_count) = shard.bloom.to_parts();
+                    writer.write_all(&hash_count.to_le_bytes())?;
+                    write_len(&mut writer, bits.len())?;
+                    for word in bits {
+                        writer.write_all(&word.to_le_bytes())?;
+                    }
+                }
+            }
+            writer.flush()?;
+            std::fs::rename(&temp_path, path)
+        };
+
+        write().map_err(|e| FileSearchError::io_error_with_path(e, "writing file database", path))
+    }
+
+    /// Read a database previously written with [`Database::save`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not a database, or was
+    /// written by an incompatible version
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| FileSearchError::io_error_with_path(e, "opening file database", path))?;
+        let mut reader = BufReader::new(file);
+        let io_err = |e| FileSearchError::io_error_with_path(e, "reading file database", path);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).map_err(io_err)?;
+        if &magic != MAGIC {
+            return Err(FileSearchError::invalid_database(
+                path,
+                "Not a whatever-find database",
+            ));
+        }
+        let version = read_u32(&mut reader).map_err(io_err)?;
+        if version != DATABASE_VERSION {
+            return Err(FileSearchError::invalid_database(
+                path,
+                format!("Unsupported database version {version} (expected {DATABASE_VERSION})"),
+            ));
+        }
+
+        let root_count = read_len(&mut reader).map_err(io_err)?;
+        let mut roots = Vec::new();
+        for _ in 0..root_count {
+            let root_path = PathBuf::from(read_str(&mut reader).map_err(io_err)?);
+            let shard_count = read_len(&mut reader).map_err(io_err)?;
+            let mut shards = Vec::new();
+            for _ in 0..shard_count {
+                let prefix = PathBuf::from(read_str(&mut reader).map_err(io_err)?);
+                let indexed_at = read_u64(&mut reader).map_err(io_err)?;
+                let entry_count = read_len(&mut reader).map_err(io_err)?;
+                let mut entries = Vec::new();
+                for _ in 0..entry_count {
+                    entries.push(PathBuf::from(read_str(&mut reader).map_err(io_err)?));
+                }
+                let hash_count = read_u32(&mut reader).map_err(io_err)?;
+                let word_count = read_len(&mut reader).map_err(io_err)?;
+                let mut bits = Vec::new();
+                for _ in 0..word_count {
+                    bits.push(read_u64(&mut reader).map_err(io_err)?);
+                }
+                let bloom = NgramBloom::from_parts(bits, hash_count).ok_or_else(|| {
+                    FileSearchError::invalid_database(path, "Corrupt shard summary")
+                })?;
+                shards.push(Shard {
+                    prefix,
+                    indexed_at,
+                    entries,
+                    bloom,
+                });
+            }
+            roots.push(DatabaseRoot {
+                path: root_path,
+                shards,
+            });
+        }
+
+        Ok(Self { roots })
+    }
+
+    /// Well-known location of the system database shared by all searches
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the local data directory cannot be determined
+    #[cfg(feature = "dirs")]
+    pub fn default_path() -> Result<PathBuf> {
+        let data_dir = dirs::data_local_dir().ok_or_else(|| {
+            FileSearchError::invalid_config("Could not determine local data directory")
+        })?;
+        Ok(data_dir.join("whatever-find").join("whatever-find.db"))
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+pub(crate) fn write_len<W: Write>(writer: &mut W, len: usize) -> std::io::Result<()> {
+    writer.write_all(&(len as u64).to_le_bytes())
+}
+
+pub(crate) fn write_str<W: Write>(writer: &mut W, value: &str) -> std::io::Result<()> {
+    write_len(writer, value.len())?;
+    writer.write_all(value.as_bytes())
+}
+
+/// Write `value` as its raw bytes where the platform has them, so names
+/// that are not valid UTF-8 survive a round trip, and lossily elsewhere
+pub(crate) fn write_os_str<W: Write>(writer: &mut W, value: &OsStr) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        write_len(writer, value.len())?;
+        writer.write_all(value.as_bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        write_str(writer, &value.to_string_lossy())
+    }
+}
+
+pub(crate) fn read_u32<R: Read>(reader: &mut R) -> std::io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(crate) fn read_u64<R: Read>(reader: &mut R) -> std::io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn read_len<R: Read>(reader: &mut R) -> std::io::Result<usize> {
+    usize::try_from(read_u64(reader)?)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+pub(crate) fn read_str<R: Read>(reader: &mut R) -> std::io::Result<String> {
+    String::from_utf8(read_bytes(reader)?)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Read a value written with [`write_os_str`]
+pub(crate) fn read_os_str<R: Read>(reader: &mut R) -> std::io::Result<OsString> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Ok(OsString::from_vec(read_bytes(reader)?))
+    }
+    #[cfg(not(unix))]
+    {
+        read_str(reader).map(OsString::from)
+    }
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> std::io::Result<Vec<u8>> {
+    let len = read_u64(reader)?;
+    let mut buf = Vec::new();
+    if reader.take(len).read_to_end(&mut buf)? as u64 != len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
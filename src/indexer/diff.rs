@@ -0,0 +1,121 @@
+//! Differences between two file database snapshots
+//!
+//! A saved [`Database`] records every file beneath its roots at indexing time,
+//! so two databases of the same roots taken at different times describe what
+//! was added and removed in between. A removed and an added file sharing a
+//! filename, with no other candidate for either, are reported as a rename.
+
+use crate::indexer::backend;
+use crate::indexer::database::Database;
+use crate::indexer::FileIndex;
+use crate::search::fold::Folding;
+use crate::search::SearchEngine;
+use crate::Result;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Files added, removed and renamed between two snapshots
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatabaseDiff {
+    /// Files only in the newer snapshot
+    pub added: Vec<PathBuf>,
+    /// Files only in the older snapshot
+    pub removed: Vec<PathBuf>,
+    /// Files moved between directories, as `(old, new)` pairs
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+}
+
+impl DatabaseDiff {
+    /// Compare the snapshot `old` with the later snapshot `new`
+    #[must_use]
+    pub fn between(old: &Database, new: &Database) -> Self {
+        let old_files: BTreeSet<PathBuf> = old.files().collect();
+        let new_files: BTreeSet<PathBuf> = new.files().collect();
+        let mut removed: Vec<PathBuf> = old_files.difference(&new_files).cloned().collect();
+        let mut added: Vec<PathBuf> = new_files.difference(&old_files).cloned().collect();
+
+        let removed_names = unique_names(&removed);
+        let added_names = unique_names(&added);
+        let mut renamed = Vec::new();
+        for (name, old_path) in &removed_names {
+            if let Some(new_path) = added_names.get(name) {
+                renamed.push((old_path.clone(), new_path.clone()));
+            }
+        }
+        renamed.sort();
+        let renamed_old: HashSet<&PathBuf> = renamed.iter().map(|(old, _)| old).collect();
+        let renamed_new: HashSet<&PathBuf> = renamed.iter().map(|(_, new)| new).collect();
+        removed.retain(|path| !renamed_old.contains(path));
+        added.retain(|path| !renamed_new.contains(path));
+
+        Self {
+            added,
+            removed,
+            renamed,
+        }
+    }
+
+    /// Whether the snapshots record the same files
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty()
+    }
+
+    /// Keep only the changes involving a file that matches `query`
+    ///
+    /// The search mode is detected from the query as for any other search. A
+    /// rename is kept if either its old or its new path matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is not a valid pattern for its mode
+    pub fn matching(&self, engine: &SearchEngine, query: &str) -> Result<Self> {
+        let folding = Folding::new(engine.config());
+        let renamed_paths = self.renamed.iter().flat_map(|(old, new)| [old, new]);
+        let index: FileIndex = self
+            .added
+            .iter()
+            .chain(&self.removed)
+            .chain(renamed_paths)
+            .filter_map(|path| backend::path_entry(path.clone(), folding))
+            .collect();
+        let matches: HashSet<PathBuf> = engine.search_auto(&index, query)?.into_iter().collect();
+
+        Ok(Self {
+            added: keep_matching(&self.added, &matches),
+            removed: keep_matching(&self.removed, &matches),
+            renamed: self
+                .renamed
+                .iter()
+                .filter(|(old, new)| matches.contains(old) || matches.contains(new))
+                .cloned()
+                .collect(),
+        })
+    }
+}
+
+/// Paths keyed by filename, for filenames occurring exactly once in `paths`
+fn unique_names(paths: &[PathBuf]) -> HashMap<OsString, PathBuf> {
+    let mut by_name: HashMap<OsString, Option<PathBuf>> = HashMap::new();
+    for path in paths {
+        if let Some(name) = path.file_name() {
+            by_name
+                .entry(name.to_os_string())
+                .and_modify(|slot| *slot = None)
+                .or_insert_with(|| Some(path.clone()));
+        }
+    }
+    by_name
+        .into_iter()
+        .filter_map(|(name, path)| Some((name, path?)))
+        .collect()
+}
+
+fn keep_matching(paths: &[PathBuf], matches: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .filter(|path| matches.contains(*path))
+        .cloned()
+        .collect()
+}
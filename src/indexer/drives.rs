@@ -0,0 +1,147 @@
+//! Whole-machine searches across all drives
+//!
+//! Windows has no single root, so searching the whole machine means walking
+//! every drive letter. [`available`] lists the drives that are mounted and
+//! ready together with their kind, so callers can leave out removable media
+//! and network shares, which are slow or may prompt for media. Elsewhere the
+//! whole machine is below `/`, which is reported as the only fixed drive.
+//!
+//! [`build_index`] walks the chosen drives in parallel, one thread each, and
+//! merges their files into one index.
+
+use super::FileIndex;
+use crate::config::Config;
+use crate::error::FileSearchError;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What kind of storage a drive is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DriveKind {
+    /// A hard disk or SSD built into or attached to the machine
+    Fixed,
+    /// A USB stick, memory card or other removable disk
+    Removable,
+    /// A mapped network share
+    Network,
+    /// A CD, DVD or Blu-ray drive
+    Optical,
+    /// A RAM disk
+    RamDisk,
+    /// A drive the system cannot classify
+    Unknown,
+}
+
+impl DriveKind {
+    /// Whether drives of this kind are searched unless asked otherwise, which
+    /// only excludes removable, optical and network drives
+    #[must_use]
+    pub fn searched_by_default(self) -> bool {
+        matches!(self, Self::Fixed | Self::RamDisk | Self::Unknown)
+    }
+}
+
+impl fmt::Display for DriveKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Fixed => "fixed",
+            Self::Removable => "removable",
+            Self::Network => "network",
+            Self::Optical => "optical",
+            Self::RamDisk => "RAM disk",
+            Self::Unknown => "unknown",
+        })
+    }
+}
+
+/// A mounted drive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drive {
+    /// Root directory of the drive, such as `C:\`
+    pub root: PathBuf,
+    /// Kind of storage
+    pub kind: DriveKind,
+}
+
+/// The drives that are mounted and ready, by drive letter
+///
+/// Drives without media, such as an empty card reader, are left out.
+#[must_use]
+pub fn available() -> Vec<Drive> {
+    imp::drives()
+        .into_iter()
+        .filter(|drive| drive.root.is_dir())
+        .collect()
+}
+
+/// Index the files on `drives`, walking each on its own thread
+///
+/// Drives that cannot be walked are left out of the index and returned with
+/// their errors, so one unreadable drive does not fail the whole search. The
+/// trash is indexed only once, along with the first drive, when
+/// `include_trash` is enabled.
+#[must_use]
+pub fn build_index(
+    drives: &[Drive],
+    config: &Config,
+) -> (FileIndex, Vec<(PathBuf, FileSearchError)>) {
+    let roots: Vec<&Path> = drives.iter().map(|drive| drive.root.as_path()).collect();
+    super::roots::build_index(&roots, config, &super::filter::Filters::default())
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::{Drive, DriveKind};
+    use std::path::PathBuf;
+
+    const DRIVE_REMOVABLE: u32 = 2;
+    const DRIVE_FIXED: u32 = 3;
+    const DRIVE_REMOTE: u32 = 4;
+    const DRIVE_CDROM: u32 = 5;
+    const DRIVE_RAMDISK: u32 = 6;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetLogicalDrives() -> u32;
+        fn GetDriveTypeW(root_path_name: *const u16) -> u32;
+    }
+
+    pub fn drives() -> Vec<Drive> {
+        // SAFETY: takes no arguments and only returns a bit mask
+        let mask = unsafe { GetLogicalDrives() };
+        (b'A'..=b'Z')
+            .filter(|letter| mask & (1 << (letter - b'A')) != 0)
+            .map(|letter| {
+                let root = format!("{}:\\", char::from(letter));
+                let wide: Vec<u16> = root.encode_utf16().chain(Some(0)).collect();
+                // SAFETY: `wide` is a NUL-terminated UTF-16 string that
+                // outlives the call
+                let kind = match unsafe { GetDriveTypeW(wide.as_ptr()) } {
+                    DRIVE_FIXED => DriveKind::Fixed,
+                    DRIVE_REMOVABLE => DriveKind::Removable,
+                    DRIVE_REMOTE => DriveKind::Network,
+                    DRIVE_CDROM => DriveKind::Optical,
+                    DRIVE_RAMDISK => DriveKind::RamDisk,
+                    _ => DriveKind::Unknown,
+                };
+                Drive {
+                    root: PathBuf::from(root),
+                    kind,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use super::{Drive, DriveKind};
+    use std::path::PathBuf;
+
+    pub fn drives() -> Vec<Drive> {
+        vec![Drive {
+            root: PathBuf::from("/"),
+            kind: DriveKind::Fixed,
+        }]
+    }
+}
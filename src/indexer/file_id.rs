@@ -0,0 +1,137 @@
+//! Inode numbers, Windows file IDs, hard link counts and device IDs
+//!
+//! Unix file systems identify a file by its inode number and NTFS by its file
+//! index, both reported as a `u64`. An ID is only unique within one volume,
+//! and all hard links to a file share it. Symbolic links are inspected
+//! themselves rather than followed on Unix, except by [`device_id`], which
+//! tells which volume a walk would enter through them.
+
+use std::path::Path;
+
+/// Inode number or Windows file index of the file at `path`
+///
+/// Returns `None` if the file cannot be inspected or the platform has no
+/// stable file IDs.
+#[must_use]
+pub fn file_id(path: &Path) -> Option<u64> {
+    imp::file_id(path)
+}
+
+/// Number of hard links to the file at `path`
+///
+/// Returns `None` if the file cannot be inspected or the platform does not
+/// report link counts.
+#[must_use]
+pub fn link_count(path: &Path) -> Option<u64> {
+    imp::link_count(path)
+}
+
+/// Device number or Windows volume serial number of the file system holding
+/// the file at `path`, following symlinks
+///
+/// Returns `None` if the file cannot be inspected or the platform does not
+/// identify file systems.
+#[must_use]
+pub fn device_id(path: &Path) -> Option<u64> {
+    imp::device_id(path)
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+
+    pub fn file_id(path: &Path) -> Option<u64> {
+        std::fs::symlink_metadata(path).ok().map(|m| m.ino())
+    }
+
+    pub fn link_count(path: &Path) -> Option<u64> {
+        std::fs::symlink_metadata(path).ok().map(|m| m.nlink())
+    }
+
+    pub fn device_id(path: &Path) -> Option<u64> {
+        std::fs::metadata(path).ok().map(|m| m.dev())
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::ffi::c_void;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use std::path::Path;
+
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+
+    /// Layout of `BY_HANDLE_FILE_INFORMATION`
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(dead_code)]
+    struct HandleInfo {
+        attributes: u32,
+        creation_time: [u32; 2],
+        last_access_time: [u32; 2],
+        last_write_time: [u32; 2],
+        volume_serial_number: u32,
+        size_high: u32,
+        size_low: u32,
+        number_of_links: u32,
+        index_high: u32,
+        index_low: u32,
+    }
+
+    impl HandleInfo {
+        fn file_index(&self) -> u64 {
+            (u64::from(self.index_high) << 32) | u64::from(self.index_low)
+        }
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetFileInformationByHandle(file: *mut c_void, information: *mut HandleInfo) -> i32;
+    }
+
+    /// Handle information of `path`, opened without read access so that
+    /// directories and locked files can be inspected too
+    fn handle_info(path: &Path) -> Option<HandleInfo> {
+        let file = std::fs::OpenOptions::new()
+            .access_mode(0)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)
+            .ok()?;
+        let mut info = HandleInfo::default();
+        // SAFETY: the handle is open for the duration of the call and `info`
+        // matches the layout of BY_HANDLE_FILE_INFORMATION
+        let ok = unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut info) };
+        (ok != 0).then_some(info)
+    }
+
+    pub fn file_id(path: &Path) -> Option<u64> {
+        handle_info(path).map(|info| info.file_index())
+    }
+
+    pub fn link_count(path: &Path) -> Option<u64> {
+        handle_info(path).map(|info| u64::from(info.number_of_links))
+    }
+
+    pub fn device_id(path: &Path) -> Option<u64> {
+        handle_info(path).map(|info| u64::from(info.volume_serial_number))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use std::path::Path;
+
+    pub fn file_id(_path: &Path) -> Option<u64> {
+        None
+    }
+
+    pub fn link_count(_path: &Path) -> Option<u64> {
+        None
+    }
+
+    pub fn device_id(_path: &Path) -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,253 @@
+//! Sorted, contiguous in-memory filename index
+//!
+//! Every search scans all filename keys, so [`FileIndex`] keeps them in a
+//! layout that is read front to back: the keys are concatenated in sorted
+//! order into one string, a table of end offsets delimits each key and its
+//! paths, and the paths of all keys share one vector, grouped by key. Looking
+//! up an exact name is a binary search over the offset table.
+//!
+//! The layout is rebuilt whenever entries are added, so an index is best built
+//! in bulk with `collect` or `extend`. Inserting paths one at a time through
+//! [`IndexBackend::insert`](crate::indexer::backend::IndexBackend::insert)
+//! costs time linear in the size of the index for each path.
+
+use std::path::{Path, PathBuf};
+
+/// File index mapping filenames to their full paths
+///
+/// Keys are iterated in sorted order, and the paths of a key in the order
+/// they were added.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileIndex {
+    /// Every key, concatenated in sorted order
+    names: String,
+    /// Where each key ends in `names` and its paths end in `paths`
+    ends: Vec<Ends>,
+    /// Paths of every key, grouped by key in key order
+    paths: Vec<PathBuf>,
+}
+
+/// End offsets of one key, each group starting where the previous one ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Ends {
+    name: u32,
+    paths: u32,
+}
+
+impl FileIndex {
+    /// Create an empty index
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of distinct filename keys
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Whether no paths are recorded
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Total number of recorded paths
+    #[must_use]
+    pub fn path_count(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Paths recorded under exactly the key `name`
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&[PathBuf]> {
+        let position = self.binary_search(name).ok()?;
+        Some(self.entry(position).1)
+    }
+
+    /// Every key and its paths, in key order
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            index: self,
+            range: 0..self.ends.len(),
+        }
+    }
+
+    /// Key and paths at `position` in the offset table
+    pub(crate) fn entry(&self, position: usize) -> (&str, &[PathBuf]) {
+        let (name_start, paths_start) = match position.checked_sub(1) {
+            Some(previous) => {
+                let ends = self.ends[previous];
+                (ends.name as usize, ends.paths as usize)
+            }
+            None => (0, 0),
+        };
+        let ends = self.ends[position];
+        (
+            &self.names[name_start..ends.name as usize],
+            &self.paths[paths_start..ends.paths as usize],
+        )
+    }
+
+    fn binary_search(&self, name: &str) -> Result<usize, usize> {
+        let (mut low, mut high) = (0, self.ends.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.entry(middle).0.cmp(name) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Ok(middle),
+            }
+        }
+        Err(low)
+    }
+
+    /// Keep only the paths for which `keep` returns true, dropping keys left
+    /// without paths
+    pub fn retain<F: FnMut(&Path) -> bool>(&mut self, mut keep: F) {
+        let old = std::mem::take(self);
+        let mut old_paths = old.paths.into_iter();
+        let mut name_start = 0;
+        let mut paths_start = 0;
+        for ends in &old.ends {
+            let name = &old.names[name_start..ends.name as usize];
+            for path in old_paths.by_ref().take(ends.paths as usize - paths_start) {
+                if keep(&path) {
+                    self.push_sorted(name, path);
+                }
+            }
+            name_start = ends.name as usize;
+            paths_start = ends.paths as usize;
+        }
+    }
+
+    /// Append `path` under `name`, which must not sort before the last key
+    fn push_sorted(&mut self, name: &str, path: PathBuf) {
+        self.paths.push(path);
+        let paths_end = offset(self.paths.len());
+        let same_key = self
+            .ends
+            .len()
+            .checked_sub(1)
+            .is_some_and(|last| self.entry(last).0 == name);
+        match self.ends.last_mut() {
+            Some(last) if same_key => last.paths = paths_end,
+            _ => {
+                self.names.push_str(name);
+                self.ends.push(Ends {
+                    name: offset(self.names.len()),
+                    paths: paths_end,
+                });
+            }
+        }
+    }
+}
+
+/// Offset into one of the arenas
+///
+/// # Panics
+///
+/// Panics if an arena outgrows `u32` offsets, which would take billions of
+/// paths or gigabytes of filenames
+fn offset(len: usize) -> u32 {
+    u32::try_from(len).expect("file index exceeds 4 GiB of filenames or 4 billion paths")
+}
+
+impl FromIterator<(String, PathBuf)> for FileIndex {
+    fn from_iter<I: IntoIterator<Item = (String, PathBuf)>>(entries: I) -> Self {
+        let mut index = Self::new();
+        index.extend(entries);
+        index
+    }
+}
+
+impl Extend<(String, PathBuf)> for FileIndex {
+    fn extend<I: IntoIterator<Item = (String, PathBuf)>>(&mut self, entries: I) {
+        let mut added: Vec<(String, PathBuf)> = entries.into_iter().collect();
+        if added.is_empty() {
+            return;
+        }
+        // Stable, so paths of one key keep the order they were added in
+        added.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let old = std::mem::take(self);
+        self.names.reserve(old.names.len());
+        self.ends.reserve(old.ends.len());
+        self.paths.reserve(old.paths.len() + added.len());
+
+        let mut added = added.into_iter().peekable();
+        let mut name_start = 0;
+        let mut old_paths = old.paths.into_iter();
+        let mut paths_start = 0;
+        for ends in &old.ends {
+            let name = &old.names[name_start..ends.name as usize];
+            while let Some((new_name, path)) =
+                added.next_if(|(new_name, _)| new_name.as_str() < name)
+            {
+                self.push_sorted(&new_name, path);
+            }
+            for path in old_paths.by_ref().take(ends.paths as usize - paths_start) {
+                self.push_sorted(name, path);
+            }
+            while let Some((_, path)) = added.next_if(|(new_name, _)| new_name == name) {
+                self.push_sorted(name, path);
+            }
+            name_start = ends.name as usize;
+            paths_start = ends.paths as usize;
+        }
+        for (name, path) in added {
+            self.push_sorted(&name, path);
+        }
+    }
+}
+
+impl IntoIterator for FileIndex {
+    type Item = (String, PathBuf);
+    type IntoIter = std::vec::IntoIter<(String, PathBuf)>;
+
+    /// Every path with its key, in key order
+    fn into_iter(self) -> Self::IntoIter {
+        let names: Vec<(String, usize)> = self
+            .iter()
+            .map(|(name, paths)| (name.to_string(), paths.len()))
+            .collect();
+        let mut paths = self.paths.into_iter();
+        let mut entries = Vec::with_capacity(paths.len());
+        for (name, count) in names {
+            entries.extend(paths.by_ref().take(count).map(|path| (name.clone(), path)));
+        }
+        entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FileIndex {
+    type Item = (&'a str, &'a [PathBuf]);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the keys of a [`FileIndex`] and their paths
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    index: &'a FileIndex,
+    range: std::ops::Range<usize>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a [PathBuf]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().map(|position| self.index.entry(position))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
@@ -1,60 +1,602 @@
-use crate::config::Config;
+use crate::config::tilde::expand_tilde;
+use crate::config::{CloudPlaceholders, Config, IoThrottle, LengthFilter, LinkCount};
+use crate::indexer::attributes::{FileAttribute, FileAttributes};
+use crate::indexer::filter::Filters;
+use crate::indexer::git::{self, GitIgnore, NestedCheckout};
+use crate::indexer::mounts::PseudoMounts;
+use crate::indexer::{audit, file_id, placeholder, xattr};
+use crate::search::handle::SearchHandle;
 use crate::Result;
-use std::path::Path;
+use std::fmt;
+use std::io;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
 use walkdir::{DirEntry, WalkDir};
 
+/// Statistics gathered while walking the file system
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalkStats {
+    /// Entries skipped because they did not respond within the IO timeout
+    pub timed_out: Vec<PathBuf>,
+    /// Number of IO operations that were retried
+    pub retries: usize,
+    /// Entries that disappeared between being listed and being inspected
+    pub vanished: usize,
+    /// Limit that stopped the walk early, leaving the entries found before it
+    pub limit_exceeded: Option<TraversalLimit>,
+}
+
+/// Hard limit on the size of a walk, for bounding the work done on
+/// adversarial trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraversalLimit {
+    /// More directories than `max_dirs` were reached
+    Dirs(usize),
+    /// More entries than `max_entries` were reached
+    Entries(usize),
+    /// The walk ran for longer than `search_timeout`
+    Time(Duration),
+}
+
+impl fmt::Display for TraversalLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dirs(max) => write!(f, "the limit of {max} directories"),
+            Self::Entries(max) => write!(f, "the limit of {max} entries"),
+            Self::Time(max) => write!(f, "the time limit of {max:?}"),
+        }
+    }
+}
+
+/// Counts of what a walk has visited, checked against the configured limits
+pub(crate) struct LimitCounter {
+    dirs: usize,
+    entries: usize,
+    /// When the walk must stop under `search_timeout`
+    deadline: Option<Instant>,
+}
+
+impl LimitCounter {
+    /// Counter for a walk starting now
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            dirs: 0,
+            entries: 0,
+            deadline: deadline(config),
+        }
+    }
+
+    /// Time left until the deadline, if there is one
+    #[cfg(feature = "async")]
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Count `entry`, returning the limit it exceeds if any
+    fn visit(&mut self, entry: &DirEntry, config: &Config) -> Option<TraversalLimit> {
+        self.count(entry.file_type().is_dir(), config)
+    }
+
+    /// Count an entry that is a directory or not, returning the limit it
+    /// exceeds if any
+    pub(crate) fn count(&mut self, is_dir: bool, config: &Config) -> Option<TraversalLimit> {
+        self.entries += 1;
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() > deadline)
+        {
+            return config.search_timeout.map(TraversalLimit::Time);
+        }
+        if let Some(max) = config.max_entries.filter(|max| self.entries > *max) {
+            return Some(TraversalLimit::Entries(max));
+        }
+        if is_dir {
+            self.dirs += 1;
+            if let Some(max) = config.max_dirs.filter(|max| self.dirs > *max) {
+                return Some(TraversalLimit::Dirs(max));
+            }
+        }
+        None
+    }
+}
+
+/// Rule that excludes a path from a walk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IgnoreReason {
+    /// The path or one of its parent directories is hidden
+    Hidden {
+        /// The hidden path
+        path: PathBuf,
+    },
+    /// The path or one of its parent directories matches an ignore pattern
+    Pattern {
+        /// The matching ignore pattern
+        pattern: String,
+        /// The path the pattern matched
+        path: PathBuf,
+    },
+    /// The path or one of its parent directories is ignored by git
+    GitIgnore {
+        /// The matching git ignore pattern
+        pattern: String,
+        /// The ignore file the pattern came from
+        source: PathBuf,
+        /// The path the pattern matched
+        path: PathBuf,
+    },
+    /// The path is or lies within a nested git checkout that is not descended
+    NestedCheckout {
+        /// Kind of checkout
+        kind: NestedCheckout,
+        /// Root directory of the checkout
+        path: PathBuf,
+    },
+    /// The path is not tracked by git
+    Untracked {
+        /// The untracked path
+        path: PathBuf,
+    },
+    /// The path has not changed since a git revision
+    Unchanged {
+        /// The unchanged path
+        path: PathBuf,
+        /// The revision changes are counted from
+        since: String,
+    },
+    /// The file is not flagged by any of the configured audit checks
+    NotFlagged {
+        /// The unflagged file
+        path: PathBuf,
+    },
+    /// The file lacks a required extended attribute
+    MissingXattr {
+        /// Name of the missing attribute
+        name: String,
+        /// The file lacking it
+        path: PathBuf,
+    },
+    /// The file lacks a required Finder tag
+    MissingTag {
+        /// Name of the missing tag
+        tag: String,
+        /// The file lacking it
+        path: PathBuf,
+    },
+    /// The file lacks a required storage attribute
+    MissingAttribute {
+        /// The missing attribute
+        attribute: FileAttribute,
+        /// The file lacking it
+        path: PathBuf,
+    },
+    /// The file's hard link count does not match the required count
+    LinkCount {
+        /// Number of hard links to the file
+        links: u64,
+        /// Required link count
+        required: LinkCount,
+        /// The file
+        path: PathBuf,
+    },
+    /// The path is on a virtual file system such as `/proc`
+    PseudoFilesystem {
+        /// Type of the file system
+        fs_type: String,
+        /// Where it is mounted
+        path: PathBuf,
+    },
+    /// The path is on another file system than the search root
+    OtherFileSystem {
+        /// Where the other file system is mounted
+        path: PathBuf,
+    },
+    /// The file's name or path is not of the required length
+    Length {
+        /// Whether the path's file name rather than the whole path was
+        /// measured
+        name_only: bool,
+        /// Length in characters
+        length: usize,
+        /// Required length
+        required: LengthFilter,
+        /// The file
+        path: PathBuf,
+    },
+    /// The path is reached through more symlinks than are followed
+    SymlinkDepth {
+        /// The first symlink beyond the limit
+        path: PathBuf,
+        /// Configured maximum symlink depth
+        max_symlink_depth: usize,
+    },
+    /// The path lies deeper than the maximum depth
+    MaxDepth {
+        /// Depth of the path below the search root
+        depth: usize,
+        /// Configured maximum depth
+        max_depth: usize,
+    },
+    /// The path lies shallower than the minimum depth
+    MinDepth {
+        /// Depth of the path below the search root
+        depth: usize,
+        /// Configured minimum depth
+        min_depth: usize,
+    },
+    /// The file is an online-only cloud placeholder, which are skipped
+    CloudPlaceholder {
+        /// The placeholder
+        path: PathBuf,
+    },
+    /// The file is larger than the maximum file size
+    MaxFileSize {
+        /// Size of the file in bytes
+        size: u64,
+        /// Configured maximum file size in bytes
+        max_file_size: u64,
+    },
+}
+
+impl fmt::Display for IgnoreReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hidden { path } => write!(f, "'{}' is hidden", path.display()),
+            Self::Pattern { pattern, path } => {
+                write!(f, "'{}' matches ignore pattern '{pattern}'", path.display())
+            }
+            Self::GitIgnore {
+                pattern,
+                source,
+                path,
+            } => write!(
+                f,
+                "'{}' matches git ignore pattern '{pattern}' from '{}'",
+                path.display(),
+                source.display()
+            ),
+            Self::NestedCheckout { kind, path } => {
+                let kind = match kind {
+                    NestedCheckout::Submodule => "git submodule",
+                    NestedCheckout::Worktree => "linked git worktree",
+                };
+                write!(f, "'{}' is a {kind}", path.display())
+            }
+            Self::Untracked { path } => write!(f, "'{}' is not tracked by git", path.display()),
+            Self::Unchanged { path, since } => {
+                write!(f, "'{}' has not changed since '{since}'", path.display())
+            }
+            Self::NotFlagged { path } => {
+                write!(f, "'{}' is not flagged by any audit check", path.display())
+            }
+            Self::MissingXattr { name, path } => {
+                write!(f, "'{}' has no extended attribute '{name}'", path.display())
+            }
+            Self::MissingTag { tag, path } => {
+                write!(f, "'{}' has no Finder tag '{tag}'", path.display())
+            }
+            Self::MissingAttribute { attribute, path } => {
+                write!(f, "'{}' is not {}", path.display(), attribute.name())
+            }
+            Self::LinkCount {
+                links,
+                required,
+                path,
+            } => write!(
+                f,
+                "'{}' has {links} hard link(s), not {required}",
+                path.display()
+            ),
+            Self::PseudoFilesystem { fs_type, path } => write!(
+                f,
+                "'{}' is a virtual '{fs_type}' file system",
+                path.display()
+            ),
+            Self::OtherFileSystem { path } => write!(
+                f,
+                "'{}' is on another file system than the search root",
+                path.display()
+            ),
+            Self::Length {
+                name_only,
+                length,
+                required,
+                path,
+            } => write!(
+                f,
+                "'{}' has a {length}-character {}, not {required}",
+                path.display(),
+                if *name_only { "name" } else { "path" }
+            ),
+            Self::CloudPlaceholder { path } => {
+                write!(f, "'{}' is an online-only cloud file", path.display())
+            }
+            Self::SymlinkDepth {
+                path,
+                max_symlink_depth,
+            } => write!(
+                f,
+                "'{}' is reached through more than {max_symlink_depth} symlink(s)",
+                path.display()
+            ),
+            Self::MaxDepth { depth, max_depth } => {
+                write!(f, "depth {depth} exceeds the maximum depth of {max_depth}")
+            }
+            Self::MinDepth { depth, min_depth } => {
+                write!(f, "depth {depth} is below the minimum depth of {min_depth}")
+            }
+            Self::MaxFileSize {
+                size,
+                max_file_size,
+            } => write!(
+                f,
+                "size of {size} bytes exceeds the maximum file size of {max_file_size} bytes"
+            ),
+        }
+    }
+}
+
 /// File system walker that respects configuration settings
 pub struct FileWalker {
     config: Config,
+    filters: Filters,
 }
 
 impl FileWalker {
     /// Create a new file walker with the given configuration
     #[must_use]
     pub fn new(config: &Config) -> Self {
+        let mut config = config.clone();
+        config.expand_ignore_patterns();
         Self {
-            config: config.clone(),
+            config,
+            filters: Filters::default(),
         }
     }
 
+    /// Only keep the files that every one of `filters` accepts
+    #[must_use]
+    pub fn with_filters(mut self, filters: Filters) -> Self {
+        self.filters = filters;
+        self
+    }
+
     /// Walk the file system starting from `root_path`, respecting configuration
     pub fn walk(&self, root_path: &str) -> Result<Vec<walkdir::Result<DirEntry>>> {
+        self.walk_with_stats(root_path).map(|(entries, _)| entries)
+    }
+
+    /// Walk like [`FileWalker::walk`], also reporting what was skipped or retried
+    ///
+    /// When an IO timeout is configured, every directory is probed on a helper
+    /// thread before it is entered, so an unresponsive mount only costs its own
+    /// subtree. Probes that never return leave their helper thread blocked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the walk cannot be started
+    pub fn walk_with_stats(
+        &self,
+        root_path: &str,
+    ) -> Result<(Vec<walkdir::Result<DirEntry>>, WalkStats)> {
+        let mut entries = Vec::new();
+        let stats = self.walk_inner(&self.expand(root_path), None, &mut |entry| {
+            entries.push(entry);
+            ControlFlow::Continue(())
+        })?;
+        Ok((entries, stats))
+    }
+
+    /// Walk like [`FileWalker::walk`] from `dir`, a directory found by an
+    /// earlier walk
+    ///
+    /// The path is taken as it is, so it need not be valid UTF-8 and a
+    /// directory named `~` is not expanded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the walk cannot be started
+    pub fn walk_dir(&self, dir: &Path) -> Result<Vec<walkdir::Result<DirEntry>>> {
+        let mut entries = Vec::new();
+        self.walk_inner(dir, None, &mut |entry| {
+            entries.push(entry);
+            ControlFlow::Continue(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Walk like [`FileWalker::walk_with_stats`], passing each entry to
+    /// `visit` as soon as it is found instead of collecting them
+    ///
+    /// The walk ends early once `visit` returns [`ControlFlow::Break`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the walk cannot be started
+    pub fn walk_each(
+        &self,
+        root_path: &str,
+        mut visit: impl FnMut(walkdir::Result<DirEntry>) -> ControlFlow<()>,
+    ) -> Result<WalkStats> {
+        self.walk_inner(&self.expand(root_path), None, &mut visit)
+    }
+
+    /// Walk like [`FileWalker::walk_with_stats`] under the control of `handle`
+    ///
+    /// Directories completed by an earlier walk recorded in the handle are
+    /// skipped, as are files it has already found. The walk blocks while the
+    /// handle is paused and ends early once it is stopped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the walk cannot be started
+    pub fn walk_with_handle(
+        &self,
+        root_path: &str,
+        handle: &SearchHandle,
+    ) -> Result<(Vec<walkdir::Result<DirEntry>>, WalkStats)> {
+        let mut entries = Vec::new();
+        let stats = self.walk_inner(&self.expand(root_path), Some(handle), &mut |entry| {
+            entries.push(entry);
+            ControlFlow::Continue(())
+        })?;
+        handle.finish();
+        Ok((entries, stats))
+    }
+
+    /// `root_path` as a path, with a leading `~` expanded if enabled
+    fn expand(&self, root_path: &str) -> PathBuf {
+        if self.config.expand_tilde {
+            PathBuf::from(expand_tilde(root_path).as_ref())
+        } else {
+            PathBuf::from(root_path)
+        }
+    }
+
+    fn walk_inner(
+        &self,
+        root_path: &Path,
+        handle: Option<&SearchHandle>,
+        visit: &mut dyn FnMut(walkdir::Result<DirEntry>) -> ControlFlow<()>,
+    ) -> Result<WalkStats> {
+        let config = &self.config;
+        let mut stats = WalkStats::default();
+        let mut counter = LimitCounter::new(config);
+        let mut probe = IoProbe::new(config);
+        validate_root(root_path, config, &mut probe, &mut stats)?;
+        if probe.expired {
+            stats.limit_exceeded = config.search_timeout.map(TraversalLimit::Time);
+            return Ok(stats);
+        }
+
         let mut walker = WalkDir::new(root_path);
 
         if let Some(max_depth) = self.config.max_depth {
             walker = walker.max_depth(max_depth);
         }
+        if config.max_symlink_depth.is_some_and(|depth| depth > 0) {
+            walker = walker.follow_links(true);
+        }
 
-        let config = self.config.clone();
-        let entries: Vec<_> = walker
+        let mut vanished = 0;
+        let mut limit_exceeded = None;
+        let mut throttle = config.io_throttle.map(Throttle::new);
+        let mut git = GitWalk::new(root_path, config)?;
+        let pseudo = PseudoMounts::for_root(root_path, config);
+        let device = config
+            .same_file_system
+            .then(|| file_id::device_id(root_path))
+            .flatten();
+        // A walk ended by `visit` needs no further handling
+        let _ = walker
             .into_iter()
-            .filter_entry(move |e| !Self::should_skip_entry_with_config(e, &config))
-            .collect();
+            .filter_entry(|e| {
+                !handle.is_some_and(|handle| handle.is_completed(e.path()))
+                    && !Self::should_skip_entry_with_config(e, config, &mut probe, &mut stats)
+                    && (e.file_type().is_dir() || self.filters.matches(e))
+                    && !git.as_mut().is_some_and(|git| git.is_ignored(e, config))
+                    && !pseudo.is_mounted_at(e, root_path)
+                    && !device.is_some_and(|device| is_other_device(e, device))
+            })
+            .take_while(|_| handle.map_or(true, SearchHandle::wait))
+            .take_while(|entry| {
+                if let Ok(entry) = entry {
+                    limit_exceeded = counter.visit(entry, config);
+                }
+                limit_exceeded.is_none()
+            })
+            .filter(|entry| match entry {
+                Err(e) if config.tolerate_races && e.depth() > 0 && is_vanished(e.io_error()) => {
+                    vanished += 1;
+                    false
+                }
+                // Followed symlinks that lead back to a directory being walked
+                Err(e) if e.loop_ancestor().is_some() => false,
+                Ok(e) => handle.map_or(true, |handle| {
+                    handle.visit(e, crate::indexer::is_indexed(e, 0, config))
+                }),
+                _ => true,
+            })
+            .inspect(|entry| {
+                if let Some(throttle) = throttle.as_mut() {
+                    throttle.visit(entry.as_ref().is_ok_and(|e| e.file_type().is_dir()));
+                }
+            })
+            .try_for_each(visit);
+        stats.vanished += vanished;
+        // A listing abandoned at the deadline may have been the last entry
+        stats.limit_exceeded = limit_exceeded.or(if probe.expired {
+            config.search_timeout.map(TraversalLimit::Time)
+        } else {
+            None
+        });
 
-        Ok(entries)
+        Ok(stats)
     }
 
-    fn should_skip_entry_with_config(entry: &DirEntry, config: &Config) -> bool {
+    fn should_skip_entry_with_config(
+        entry: &DirEntry,
+        config: &Config,
+        probe: &mut IoProbe,
+        stats: &mut WalkStats,
+    ) -> bool {
         let path = entry.path();
 
-        if config.ignore_hidden {
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.starts_with('.') {
-                    return true;
-                }
-            }
+        if Self::name_rule(path, config).is_some() {
+            return true;
+        }
+
+        if entry.path_is_symlink()
+            && config
+                .max_symlink_depth
+                .is_some_and(|max| symlink_depth(entry) > max)
+        {
+            return true;
         }
 
-        for pattern in &config.ignore_patterns {
-            if Self::matches_pattern(path, pattern) {
-                return true;
+        if !entry.file_type().is_dir() && Self::length_rule(path, config).is_some() {
+            return true;
+        }
+
+        if !entry.file_type().is_dir()
+            && config.cloud_placeholders != CloudPlaceholders::Include
+            && placeholder::entry_is_placeholder(entry)
+        {
+            return Self::placeholder_rule(path, config).is_some();
+        }
+
+        if !entry.file_type().is_dir() && Self::file_rule(path, config).is_some() {
+            return true;
+        }
+
+        if (config.io_timeout.is_some() || config.search_timeout.is_some())
+            && entry.file_type().is_dir()
+            && probe.list(path, config, stats).is_none()
+        {
+            if !probe.expired {
+                stats.timed_out.push(path.to_path_buf());
             }
+            return true;
         }
 
         if let Some(max_size) = config.max_file_size {
             if entry.file_type().is_file() {
-                if let Ok(metadata) = entry.metadata() {
-                    if metadata.len() > max_size {
+                let file = entry.clone();
+                match probe.run(move || Ok(file.metadata()?), config, stats) {
+                    Some(Ok(metadata)) if metadata.len() > max_size => return true,
+                    Some(Err(e)) if config.tolerate_races && is_vanished(Some(&e)) => {
+                        stats.vanished += 1;
+                        return true;
+                    }
+                    Some(_) => {}
+                    None => {
+                        if !probe.expired {
+                            stats.timed_out.push(path.to_path_buf());
+                        }
                         return true;
                     }
                 }
@@ -64,17 +606,699 @@ impl FileWalker {
         false
     }
 
+    /// The depth or symlink depth limit exceeded by a path `relative` to the
+    /// root of a walk, reached through the directories of `chain`
+    fn depth_rule(
+        relative: Option<&Path>,
+        chain: &[PathBuf],
+        config: &Config,
+    ) -> Option<IgnoreReason> {
+        if let Some(relative) = relative {
+            let depth = relative.components().count();
+            if let Some(max_depth) = config.max_depth.filter(|max| depth > *max) {
+                return Some(IgnoreReason::MaxDepth { depth, max_depth });
+            }
+            if let Some(min_depth) = config.min_depth.filter(|min| depth < *min) {
+                return Some(IgnoreReason::MinDepth { depth, min_depth });
+            }
+        }
+
+        let max_symlink_depth = config.max_symlink_depth?;
+        let path = chain
+            .iter()
+            .filter(|p| p.is_symlink())
+            .nth(max_symlink_depth)?;
+        Some(IgnoreReason::SymlinkDepth {
+            path: path.clone(),
+            max_symlink_depth,
+        })
+    }
+
+    /// Whether the hidden file or ignore pattern rules exclude `path`
+    pub(crate) fn excludes_name(&self, path: &Path) -> bool {
+        Self::name_rule(path, &self.config).is_some()
+    }
+
+    /// Whether the name or path length rules exclude the file at `path`
+    #[cfg(feature = "async")]
+    pub(crate) fn excludes_length(&self, path: &Path) -> bool {
+        Self::length_rule(path, &self.config).is_some()
+    }
+
+    /// Explain why `path` would be left out of a walk starting at `root`
+    ///
+    /// Checks every directory between `root` and `path`, since an excluded
+    /// directory hides everything beneath it. Returns `None` if the path would
+    /// be visited.
+    #[must_use]
+    pub fn explain(&self, root: &Path, path: &Path) -> Option<IgnoreReason> {
+        let config = &self.config;
+        let relative = path.strip_prefix(root).ok();
+
+        let chain: Vec<PathBuf> = match relative {
+            Some(relative) => relative
+                .iter()
+                .scan(root.to_path_buf(), |current, component| {
+                    current.push(component);
+                    Some(current.clone())
+                })
+                .collect(),
+            None => vec![path.to_path_buf()],
+        };
+        let path_rules = chain.iter().find_map(|p| Self::name_rule(p, config));
+        if let Some(reason) = path_rules.or_else(|| Self::mount_rule(root, &chain, config)) {
+            return Some(reason);
+        }
+
+        if config.respect_gitignore {
+            let nested = chain.iter().find_map(|p| {
+                git::nested_checkout(p)
+                    .filter(|kind| !kind.is_descended(config))
+                    .map(|kind| (kind, p))
+            });
+            if let Some((kind, path)) = nested {
+                return Some(IgnoreReason::NestedCheckout {
+                    kind,
+                    path: path.clone(),
+                });
+            }
+
+            let mut git = GitIgnore::for_root(root);
+            let found = chain.iter().find_map(|p| {
+                let canonical = p.canonicalize().ok()?;
+                let is_dir = canonical.is_dir();
+                git.find_match(&canonical, is_dir).map(|m| (m, p))
+            });
+            if let Some(((pattern, source), path)) = found {
+                return Some(IgnoreReason::GitIgnore {
+                    pattern,
+                    source,
+                    path: path.clone(),
+                });
+            }
+        }
+
+        #[cfg(feature = "git")]
+        {
+            let first_missing = |files: &git::GitFiles| {
+                chain
+                    .iter()
+                    .find(|p| {
+                        p.canonicalize()
+                            .is_ok_and(|canonical| !files.contains(&canonical, canonical.is_dir()))
+                    })
+                    .cloned()
+            };
+            if config.git_tracked {
+                if let Some(path) = git::GitFiles::tracked(root)
+                    .ok()
+                    .and_then(|files| first_missing(&files))
+                {
+                    return Some(IgnoreReason::Untracked { path });
+                }
+            }
+            if let Some(since) = &config.changed_since {
+                if let Some(path) = git::GitFiles::changed_since(root, since)
+                    .ok()
+                    .and_then(|files| first_missing(&files))
+                {
+                    return Some(IgnoreReason::Unchanged {
+                        path,
+                        since: since.clone(),
+                    });
+                }
+            }
+        }
+
+        if let Some(reason) = Self::depth_rule(relative, &chain, config) {
+            return Some(reason);
+        }
+
+        if !path.is_dir() {
+            if let Some(reason) = Self::length_rule(path, config) {
+                return Some(reason);
+            }
+        }
+
+        if config.cloud_placeholders != CloudPlaceholders::Include
+            && placeholder::is_placeholder(path)
+        {
+            return Self::placeholder_rule(path, config);
+        }
+
+        if let Some(max_file_size) = config.max_file_size {
+            if let Ok(metadata) = std::fs::symlink_metadata(path) {
+                if metadata.is_file() && metadata.len() > max_file_size {
+                    return Some(IgnoreReason::MaxFileSize {
+                        size: metadata.len(),
+                        max_file_size,
+                    });
+                }
+            }
+        }
+
+        if path.is_dir() {
+            return None;
+        }
+        Self::file_rule(path, config)
+    }
+
+    /// Rule excluding the cloud placeholder at `path`, if any
+    ///
+    /// Placeholders kept by name are exempt from every later rule, since
+    /// looking any further could download the file.
+    fn placeholder_rule(path: &Path, config: &Config) -> Option<IgnoreReason> {
+        (config.cloud_placeholders == CloudPlaceholders::Skip || Self::has_file_rules(config)).then(
+            || IgnoreReason::CloudPlaceholder {
+                path: path.to_path_buf(),
+            },
+        )
+    }
+
+    /// Whether any of the rules of [`FileWalker::file_rule`] is configured
+    fn has_file_rules(config: &Config) -> bool {
+        !config.audit_checks.is_empty()
+            || !config.required_xattrs.is_empty()
+            || config.link_count.is_some()
+            || !config.required_attributes.is_empty()
+            || !config.required_tags.is_empty()
+    }
+
+    /// Audit, extended attribute, link count, storage attribute and Finder tag
+    /// rules, which only apply to files
+    fn file_rule(path: &Path, config: &Config) -> Option<IgnoreReason> {
+        if !config.audit_checks.is_empty() && audit::flagged_by(path, config).is_none() {
+            return Some(IgnoreReason::NotFlagged {
+                path: path.to_path_buf(),
+            });
+        }
+
+        if let Some(name) = config
+            .required_xattrs
+            .iter()
+            .find(|name| !xattr::has_xattr(path, name))
+        {
+            return Some(IgnoreReason::MissingXattr {
+                name: name.clone(),
+                path: path.to_path_buf(),
+            });
+        }
+
+        if let Some(required) = config.link_count {
+            let links = file_id::link_count(path).unwrap_or(0);
+            if !required.matches(links) {
+                return Some(IgnoreReason::LinkCount {
+                    links,
+                    required,
+                    path: path.to_path_buf(),
+                });
+            }
+        }
+
+        if !config.required_attributes.is_empty() {
+            let attributes = FileAttributes::read(path).unwrap_or_default();
+            if let Some(attribute) = config
+                .required_attributes
+                .iter()
+                .find(|attribute| !attributes.has(**attribute))
+            {
+                return Some(IgnoreReason::MissingAttribute {
+                    attribute: *attribute,
+                    path: path.to_path_buf(),
+                });
+            }
+        }
+
+        if config.required_tags.is_empty() {
+            return None;
+        }
+        let tags = xattr::finder_tags(path);
+        config
+            .required_tags
+            .iter()
+            .find(|tag| !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            .map(|tag| IgnoreReason::MissingTag {
+                tag: tag.clone(),
+                path: path.to_path_buf(),
+            })
+    }
+
+    /// The first directory of `chain`, below `root`, on a skipped virtual file
+    /// system, or with [`Config::same_file_system`] on another file system
+    /// than `root`
+    fn mount_rule(root: &Path, chain: &[PathBuf], config: &Config) -> Option<IgnoreReason> {
+        let pseudo = PseudoMounts::for_root(root, config);
+        let device = config
+            .same_file_system
+            .then(|| file_id::device_id(root))
+            .flatten();
+        chain.iter().find_map(|path| {
+            if path.is_dir()
+                && device
+                    .is_some_and(|device| file_id::device_id(path).is_some_and(|id| id != device))
+            {
+                return Some(IgnoreReason::OtherFileSystem { path: path.clone() });
+            }
+            let fs_type = pseudo.fs_type(path.strip_prefix(root).ok()?)?;
+            Some(IgnoreReason::PseudoFilesystem {
+                fs_type: fs_type.to_string(),
+                path: path.clone(),
+            })
+        })
+    }
+
+    /// Name and path length rules, which only apply to files but need nothing
+    /// from the disk
+    fn length_rule(path: &Path, config: &Config) -> Option<IgnoreReason> {
+        let measure = |name_only: bool, text: &str, required: LengthFilter| {
+            let length = text.chars().count();
+            (!required.matches(length)).then(|| IgnoreReason::Length {
+                name_only,
+                length,
+                required,
+                path: path.to_path_buf(),
+            })
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        config
+            .name_length
+            .and_then(|required| measure(true, &name, required))
+            .or_else(|| {
+                let required = config.path_length?;
+                measure(false, &path.to_string_lossy(), required)
+            })
+    }
+
+    /// Hidden-file and ignore-pattern rules, which only look at the path itself
+    fn name_rule(path: &Path, config: &Config) -> Option<IgnoreReason> {
+        if config.ignore_hidden {
+            if let Some(name) = path.file_name() {
+                if name.to_string_lossy().starts_with('.') {
+                    return Some(IgnoreReason::Hidden {
+                        path: path.to_path_buf(),
+                    });
+                }
+            }
+        }
+
+        config
+            .ignore_patterns
+            .iter()
+            .find(|pattern| Self::matches_pattern(path, pattern))
+            .map(|pattern| IgnoreReason::Pattern {
+                pattern: pattern.clone(),
+                path: path.to_path_buf(),
+            })
+    }
+
     fn matches_pattern(path: &Path, pattern: &str) -> bool {
-        if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-            if pattern.contains('*') {
-                let regex_pattern = pattern.replace("*", ".*");
-                if let Ok(regex) = regex::Regex::new(&regex_pattern) {
-                    return regex.is_match(filename);
+        if let Some(filename) = path.file_name().map(|n| n.to_string_lossy()) {
+            if pattern.contains(['*', '?']) {
+                return crate::search::matcher::wildcard_match(pattern, &filename);
+            }
+            return filename == pattern || crate::search::matcher::path_contains(path, pattern);
+        }
+        false
+    }
+}
+
+/// Pacing state for a configured [`IoThrottle`]
+struct Throttle {
+    limit: IoThrottle,
+    started: Instant,
+    entries: u64,
+    dirs: u32,
+}
+
+impl Throttle {
+    fn new(limit: IoThrottle) -> Self {
+        Self {
+            limit,
+            started: Instant::now(),
+            entries: 0,
+            dirs: 0,
+        }
+    }
+
+    /// Record a visited entry and sleep if the walk is ahead of the limit
+    fn visit(&mut self, is_dir: bool) {
+        match self.limit {
+            IoThrottle::EntriesPerSecond(rate) => {
+                self.entries += 1;
+                let due = Duration::from_nanos(
+                    self.entries.saturating_mul(1_000_000_000) / u64::from(rate.max(1)),
+                );
+                if let Some(ahead) = due.checked_sub(self.started.elapsed()) {
+                    thread::sleep(ahead);
                 }
+            }
+            IoThrottle::SleepEveryDirs { dirs, sleep } => {
+                if is_dir {
+                    self.dirs += 1;
+                    if self.dirs >= dirs.max(1) {
+                        self.dirs = 0;
+                        thread::sleep(sleep);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Git state of one walk, mapping walked paths into the repository
+struct GitWalk {
+    root: PathBuf,
+    canonical_root: PathBuf,
+    rules: Option<GitIgnore>,
+    /// Sets of files the walk is restricted to
+    #[cfg(feature = "git")]
+    file_sets: Vec<git::GitFiles>,
+}
+
+impl GitWalk {
+    fn new(root_path: &Path, config: &Config) -> Result<Option<Self>> {
+        let restricted = config.git_tracked || config.changed_since.is_some();
+        if !config.respect_gitignore && !restricted {
+            return Ok(None);
+        }
+        #[cfg(not(feature = "git"))]
+        if restricted {
+            return Err(crate::FileSearchError::feature_disabled("git"));
+        }
+
+        let root = root_path.to_path_buf();
+        let Ok(canonical_root) = root.canonicalize() else {
+            return Ok(None);
+        };
+        let rules = config
+            .respect_gitignore
+            .then(|| GitIgnore::for_root(&canonical_root))
+            .filter(|rules| rules.repo_root().is_some());
+        #[cfg(feature = "git")]
+        let file_sets = {
+            let mut file_sets = Vec::new();
+            if config.git_tracked {
+                file_sets.push(git::GitFiles::tracked(&canonical_root)?);
+            }
+            if let Some(since) = &config.changed_since {
+                file_sets.push(git::GitFiles::changed_since(&canonical_root, since)?);
+            }
+            file_sets
+        };
+
+        Ok(Some(Self {
+            root,
+            canonical_root,
+            rules,
+            #[cfg(feature = "git")]
+            file_sets,
+        }))
+    }
+
+    fn is_ignored(&mut self, entry: &DirEntry, config: &Config) -> bool {
+        if entry.depth() == 0 {
+            return false;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(&self.root)
+            .unwrap_or(entry.path());
+        let path = self.canonical_root.join(relative);
+        let is_dir = entry.file_type().is_dir();
+
+        #[cfg(feature = "git")]
+        if self
+            .file_sets
+            .iter()
+            .any(|files| !files.contains(&path, is_dir))
+        {
+            return true;
+        }
+
+        let Some(rules) = self.rules.as_mut() else {
+            return false;
+        };
+        if entry.file_name() == ".git" {
+            return true;
+        }
+        if is_dir
+            && git::nested_checkout(entry.path()).is_some_and(|kind| !kind.is_descended(config))
+        {
+            return true;
+        }
+        rules.find_match(&path, is_dir).is_some()
+    }
+}
+
+/// Check that a walk can start at `root`, so a bad root is reported precisely
+/// rather than as an error entry from the walk
+///
+/// A root that is not a directory is accepted and walked as a single entry,
+/// following a symlink to it. A directory root must be listable. A root still
+/// being checked at the `search_timeout` deadline is accepted, and the walk
+/// then ends at once.
+fn validate_root(
+    root: &Path,
+    config: &Config,
+    probe: &mut IoProbe,
+    stats: &mut WalkStats,
+) -> Result<()> {
+    let invalid = |reason: String| crate::FileSearchError::invalid_path(root, reason);
+    let timed_out = || invalid("did not respond within the IO timeout".to_string());
+
+    let target = root.to_path_buf();
+    let metadata = match probe.run(move || std::fs::metadata(&target), config, stats) {
+        Some(Ok(metadata)) => metadata,
+        Some(Err(e)) if e.kind() == io::ErrorKind::NotFound => {
+            let reason = if std::fs::symlink_metadata(root).is_ok() {
+                "is a broken symbolic link"
             } else {
-                return filename == pattern || path.to_string_lossy().contains(pattern);
+                "does not exist"
+            };
+            return Err(invalid(reason.to_string()));
+        }
+        Some(Err(e)) if e.kind() == io::ErrorKind::PermissionDenied => {
+            return Err(invalid("cannot be accessed: permission denied".to_string()));
+        }
+        Some(Err(e)) => return Err(invalid(format!("cannot be accessed: {e}"))),
+        // The walk ends at once with the time limit
+        None if probe.expired => return Ok(()),
+        None => return Err(timed_out()),
+    };
+    if !metadata.is_dir() {
+        return Ok(());
+    }
+
+    match probe.list(root, config, stats) {
+        Some(Ok(())) => Ok(()),
+        Some(Err(e)) if e.kind() == io::ErrorKind::PermissionDenied => Err(invalid(
+            "directory cannot be listed: permission denied".to_string(),
+        )),
+        Some(Err(e)) => Err(invalid(format!("directory cannot be listed: {e}"))),
+        None if probe.expired => Ok(()),
+        None => Err(timed_out()),
+    }
+}
+
+/// Most worker threads one walk runs IO operations on
+const MAX_IO_WORKERS: usize = 4;
+
+/// IO operation handed to an [`IoProbe`] worker
+type IoJob = Box<dyn FnOnce() + Send>;
+
+/// Worker threads that run the IO operations of one walk, so that an
+/// operation can be abandoned once it overruns the configured timeout
+///
+/// An operation that times out keeps its worker until it returns. Others run
+/// on the remaining workers, and once all [`MAX_IO_WORKERS`] are stuck on
+/// hung reads further operations time out at once rather than starting more
+/// threads that would block beside them.
+///
+/// With `search_timeout`, directory listings are watched even without an IO
+/// timeout, so that a hung one cannot hold the walk past the deadline.
+struct IoProbe {
+    workers: Vec<IoWorker>,
+    /// When the walk must stop under `search_timeout`
+    deadline: Option<Instant>,
+    /// Whether an operation was abandoned because the deadline passed
+    expired: bool,
+}
+
+struct IoWorker {
+    jobs: mpsc::Sender<IoJob>,
+    /// Whether the worker is running an operation
+    busy: Arc<AtomicBool>,
+}
+
+impl IoProbe {
+    /// Probe for a walk starting now
+    fn new(config: &Config) -> Self {
+        Self {
+            workers: Vec::new(),
+            deadline: deadline(config),
+            expired: false,
+        }
+    }
+
+    /// Check that `dir` can be listed, within the IO timeout and before the
+    /// deadline
+    fn list(
+        &mut self,
+        dir: &Path,
+        config: &Config,
+        stats: &mut WalkStats,
+    ) -> Option<io::Result<()>> {
+        let dir = dir.to_path_buf();
+        let timeout = config.io_timeout.or(self.deadline.map(|_| Duration::MAX));
+        self.run_for(
+            timeout,
+            move || std::fs::read_dir(&dir).map(|mut entries| drop(entries.next())),
+            config,
+            stats,
+        )
+    }
+
+    /// Run an IO operation under the configured timeout and retry policy
+    fn run<T, F>(&mut self, op: F, config: &Config, stats: &mut WalkStats) -> Option<io::Result<T>>
+    where
+        T: Send + 'static,
+        F: Fn() -> io::Result<T> + Clone + Send + 'static,
+    {
+        self.run_for(config.io_timeout, op, config, stats)
+    }
+
+    /// Run an IO operation for up to `timeout`, or until the deadline if that
+    /// comes sooner, retrying as configured
+    ///
+    /// Returns `None` if it timed out. A timed-out operation is given the
+    /// retry backoff to answer late but is never retried while it still
+    /// blocks; only transient errors are retried. Without a timeout the
+    /// operation simply runs on the current thread.
+    fn run_for<T, F>(
+        &mut self,
+        timeout: Option<Duration>,
+        op: F,
+        config: &Config,
+        stats: &mut WalkStats,
+    ) -> Option<io::Result<T>>
+    where
+        T: Send + 'static,
+        F: Fn() -> io::Result<T> + Clone + Send + 'static,
+    {
+        let Some(timeout) = timeout else {
+            return Some(op());
+        };
+
+        let mut backoff = config.io_retry_backoff;
+        for attempt in 0..=config.io_retries {
+            if attempt > 0 {
+                stats.retries += 1;
+                thread::sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+            }
+            let remaining = self
+                .deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if remaining == Some(Duration::ZERO) {
+                self.expired = true;
+                return None;
+            }
+
+            let (sender, receiver) = mpsc::channel();
+            let op = op.clone();
+            self.submit(Box::new(move || {
+                let _ = sender.send(op());
+            }))?;
+
+            let wait = remaining.map_or(timeout, |remaining| remaining.min(timeout));
+            let result = receiver.recv_timeout(wait).or_else(|_| {
+                if remaining == Some(wait) {
+                    self.expired = true;
+                } else if attempt < config.io_retries {
+                    thread::sleep(backoff);
+                }
+                receiver.try_recv()
+            });
+            match result {
+                Ok(Err(e)) if attempt < config.io_retries && is_transient(&e) => {}
+                Ok(result) => return Some(result),
+                Err(_) => return None,
             }
         }
-        false
+        None
+    }
+
+    /// Hand `job` to an idle worker, starting one if there is room, or
+    /// return `None` if every worker is busy
+    fn submit(&mut self, job: IoJob) -> Option<()> {
+        let worker = match self
+            .workers
+            .iter()
+            .position(|worker| !worker.busy.load(Ordering::Acquire))
+        {
+            Some(idle) => &self.workers[idle],
+            None if self.workers.len() < MAX_IO_WORKERS => {
+                self.workers.push(IoWorker::spawn());
+                self.workers.last()?
+            }
+            None => return None,
+        };
+        worker.busy.store(true, Ordering::Release);
+        worker.jobs.send(job).ok()
     }
 }
+
+impl IoWorker {
+    fn spawn() -> Self {
+        let (jobs, receiver) = mpsc::channel::<IoJob>();
+        let busy = Arc::new(AtomicBool::new(false));
+        let done = Arc::clone(&busy);
+        // Ends once the probe is dropped and any operation still running
+        // returns
+        thread::spawn(move || {
+            for job in receiver {
+                job();
+                done.store(false, Ordering::Release);
+            }
+        });
+        Self { jobs, busy }
+    }
+}
+
+/// When a walk starting now must stop under `search_timeout`
+fn deadline(config: &Config) -> Option<Instant> {
+    config
+        .search_timeout
+        .and_then(|timeout| Instant::now().checked_add(timeout))
+}
+
+/// Number of symlinks among `entry` and the directories above it, up to
+/// the root of the walk
+fn symlink_depth(entry: &DirEntry) -> usize {
+    entry
+        .path()
+        .ancestors()
+        .take(entry.depth())
+        .filter(|path| path.is_symlink())
+        .count()
+}
+
+/// Whether `entry` is a directory below the root of the walk on another
+/// file system than `device`, the root's
+fn is_other_device(entry: &DirEntry, device: u64) -> bool {
+    entry.depth() > 0
+        && entry.file_type().is_dir()
+        && file_id::device_id(entry.path()).is_some_and(|id| id != device)
+}
+
+/// Whether an error means the entry was removed or renamed mid-walk
+fn is_vanished(error: Option<&io::Error>) -> bool {
+    error.is_some_and(|e| e.kind() == io::ErrorKind::NotFound)
+}
+
+fn is_transient(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock
+    )
+}
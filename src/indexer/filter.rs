@@ -0,0 +1,73 @@
+//! Integrator-supplied predicates applied during walks
+//!
+//! A [`Filter`] registered on a [`FileSearcher`](crate::FileSearcher) or a
+//! [`FileIndexer`](crate::indexer::FileIndexer) sees every file a walk finds
+//! after the configured rules have kept it, and decides whether it is
+//! indexed. Filters can implement rules the configuration has no option for,
+//! such as size ranges, ownership or business-specific naming conventions.
+//! Directories are always descended, so a filter only ever sees files, and a
+//! file is kept only if every filter accepts it.
+//!
+//! Any `Fn(&DirEntry) -> bool + Send + Sync` closure is a filter:
+//!
+//! ```rust
+//! use whatever_find::FileSearcher;
+//! use walkdir::DirEntry;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let searcher = FileSearcher::builder()
+//!     .filter(|entry: &DirEntry| {
+//!         entry.metadata().is_ok_and(|metadata| metadata.len() < 1024 * 1024)
+//!     })
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use walkdir::DirEntry;
+
+/// Decides whether a file found by a walk is kept
+pub trait Filter: Send + Sync {
+    /// Whether the file at `entry` is kept
+    fn matches(&self, entry: &DirEntry) -> bool;
+}
+
+impl<F> Filter for F
+where
+    F: Fn(&DirEntry) -> bool + Send + Sync,
+{
+    fn matches(&self, entry: &DirEntry) -> bool {
+        self(entry)
+    }
+}
+
+/// The filters registered on a searcher or indexer
+#[derive(Clone, Default)]
+pub struct Filters(Vec<Arc<dyn Filter>>);
+
+impl Filters {
+    /// Add `filter`, which files must pass as well as those already added
+    pub fn push(&mut self, filter: Arc<dyn Filter>) {
+        self.0.push(filter);
+    }
+
+    /// Whether no filters are registered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether every filter keeps the file at `entry`
+    #[must_use]
+    pub fn matches(&self, entry: &DirEntry) -> bool {
+        self.0.iter().all(|filter| filter.matches(entry))
+    }
+}
+
+impl fmt::Debug for Filters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Filters({})", self.0.len())
+    }
+}
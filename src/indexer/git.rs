@@ -0,0 +1,582 @@
+//! Git ignore rules for walks inside repositories
+//!
+//! With `respect_gitignore` enabled, a walk skips paths excluded by the
+//! repository containing the search root. Rules come from the user's global
+//! excludes file (`core.excludesFile`, or `$XDG_CONFIG_HOME/git/ignore` by
+//! default), the repository's `.git/info/exclude`, and every `.gitignore`
+//! between the repository root and the path, each scoped to its directory.
+//! They follow gitignore syntax: patterns containing a slash are anchored to
+//! the directory of their file, a trailing slash only matches directories,
+//! `**` spans directories and `!` re-includes a path. As in git, the last
+//! matching pattern wins, and deeper `.gitignore` files take precedence over
+//! shallower ones, `.git/info/exclude` and the global excludes file.
+//!
+//! With the `git` feature, tracked and changed files are listed and the status
+//! of individual files is looked up through libgit2.
+
+use crate::config::Config;
+#[cfg(feature = "git")]
+use crate::{FileSearchError, Result};
+use std::collections::HashMap;
+#[cfg(feature = "git")]
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// One line of an ignore file
+#[derive(Debug, Clone)]
+struct Rule {
+    /// The line as written
+    line: String,
+    /// Components of the pattern, without anchoring or trailing slashes
+    components: Vec<Vec<char>>,
+    /// Whether a match re-includes the path
+    negated: bool,
+    /// Whether the pattern is matched from the directory of its file rather
+    /// than against the name alone
+    anchored: bool,
+    /// Whether only directories match
+    dir_only: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (
+                false,
+                line.strip_prefix('\\')
+                    .filter(|rest| rest.starts_with(['!', '#']))
+                    .unwrap_or(line),
+            ),
+        };
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let anchored = pattern.contains('/');
+        let components: Vec<Vec<char>> = pattern
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .map(|component| component.chars().collect())
+            .collect();
+        if components.is_empty() {
+            return None;
+        }
+        Some(Self {
+            line: line.to_string(),
+            components,
+            negated,
+            anchored,
+            dir_only,
+        })
+    }
+
+    /// Whether the rule matches the path with components `parts` below the
+    /// directory of its file
+    fn matches(&self, parts: &[Vec<char>], is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            components_match(&self.components, parts)
+        } else {
+            parts
+                .last()
+                .is_some_and(|name| component_match(&self.components[0], name))
+        }
+    }
+}
+
+/// Ignore rules loaded from one file, scoped to a directory
+#[derive(Debug, Clone)]
+struct RuleSet {
+    /// File the patterns were read from
+    source: PathBuf,
+    /// Directory the patterns are relative to
+    base: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    fn load(source: PathBuf, base: &Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(&source).ok()?;
+        Some(Self {
+            source,
+            base: base.to_path_buf(),
+            rules: contents.lines().filter_map(Rule::parse).collect(),
+        })
+    }
+
+    /// The last rule matching `path`, which decides whether it is ignored
+    fn find_match(&self, path: &Path, is_dir: bool) -> Option<&Rule> {
+        let parts: Vec<Vec<char>> = path
+            .strip_prefix(&self.base)
+            .ok()?
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy().chars().collect())
+            .collect();
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(&parts, is_dir))
+    }
+
+    /// The pattern of `rule`, one of these rules, and the file it came from,
+    /// unless the rule re-includes paths
+    fn exclusion(&self, rule: &Rule) -> Option<(String, PathBuf)> {
+        (!rule.negated).then(|| (rule.line.clone(), self.source.clone()))
+    }
+}
+
+/// Whether the pattern components match the path components `parts`, with
+/// `**` standing for any number of directories
+fn components_match(pattern: &[Vec<char>], parts: &[Vec<char>]) -> bool {
+    match pattern {
+        [] => parts.is_empty(),
+        // A trailing `**` matches everything inside, but not the directory
+        [any] if any[..] == ['*', '*'] => !parts.is_empty(),
+        [any, rest @ ..] if any[..] == ['*', '*'] => {
+            (0..=parts.len()).any(|skip| components_match(rest, &parts[skip..]))
+        }
+        [first, rest @ ..] => parts.split_first().is_some_and(|(part, parts)| {
+            component_match(first, part) && components_match(rest, parts)
+        }),
+    }
+}
+
+/// Whether one path component matches a pattern component with `*`, `?`,
+/// `[...]` classes and `\` escapes
+fn component_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', rest @ ..] => (0..=text.len()).any(|skip| component_match(rest, &text[skip..])),
+        ['?', rest @ ..] => !text.is_empty() && component_match(rest, &text[1..]),
+        ['[', class @ ..] => match (class_match(class, text.first().copied()), text) {
+            (Some((matched, len)), [_, text @ ..]) => {
+                matched && component_match(&class[len..], text)
+            }
+            (Some(_), []) => false,
+            (None, _) => literal_match('[', &pattern[1..], text),
+        },
+        ['\\', c, rest @ ..] | [c, rest @ ..] => literal_match(*c, rest, text),
+    }
+}
+
+fn literal_match(c: char, rest: &[char], text: &[char]) -> bool {
+    text.first() == Some(&c) && component_match(rest, &text[1..])
+}
+
+/// Whether `c` is in the class whose body `class` follows a `[`, and the
+/// length of the body up to and including its `]`, or `None` if the class
+/// is not closed
+fn class_match(class: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some('!' | '^'));
+    let mut i = usize::from(negated);
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let start = *class.get(i)?;
+        if start == ']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+        if class.get(i + 1) == Some(&'-') && class.get(i + 2).is_some_and(|end| *end != ']') {
+            let end = class[i + 2];
+            matched |= c.is_some_and(|c| (start..=end).contains(&c));
+            i += 3;
+        } else {
+            matched |= c == Some(start);
+            i += 1;
+        }
+    }
+}
+
+/// Git ignore rules applying beneath one search root
+#[derive(Debug, Clone, Default)]
+pub struct GitIgnore {
+    repo_root: Option<PathBuf>,
+    global: Vec<RuleSet>,
+    per_dir: HashMap<PathBuf, Option<RuleSet>>,
+}
+
+impl GitIgnore {
+    /// Load the rules for the repository containing `root`
+    ///
+    /// Outside a repository nothing is ignored.
+    #[must_use]
+    pub fn for_root(root: &Path) -> Self {
+        let Some(repo_root) = find_repo_root(root) else {
+            return Self::default();
+        };
+
+        let mut global = Vec::new();
+        if let Some(excludes_file) = global_excludes_file() {
+            global.extend(RuleSet::load(excludes_file, &repo_root));
+        }
+        if let Some(git_dir) = git_dir(&repo_root) {
+            global.extend(RuleSet::load(
+                git_dir.join("info").join("exclude"),
+                &repo_root,
+            ));
+        }
+
+        Self {
+            repo_root: Some(repo_root),
+            global,
+            per_dir: HashMap::new(),
+        }
+    }
+
+    /// Repository containing the search root, if any
+    #[must_use]
+    pub fn repo_root(&self) -> Option<&Path> {
+        self.repo_root.as_deref()
+    }
+
+    /// The pattern excluding `path`, a directory if `is_dir`, and the file
+    /// it came from, if any
+    ///
+    /// Only `path` itself is checked; callers walking a tree have already
+    /// checked its parent directories.
+    pub fn find_match(&mut self, path: &Path, is_dir: bool) -> Option<(String, PathBuf)> {
+        let repo_root = self.repo_root.clone()?;
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir().ok()?.join(path)
+        };
+        if !path.starts_with(&repo_root) || path == repo_root {
+            return None;
+        }
+
+        for dir in path.ancestors().skip(1) {
+            let rules = self
+                .per_dir
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| RuleSet::load(dir.join(".gitignore"), dir));
+            if let Some(rules) = rules {
+                if let Some(rule) = rules.find_match(&path, is_dir) {
+                    return rules.exclusion(rule);
+                }
+            }
+            if dir == repo_root {
+                break;
+            }
+        }
+
+        // `.git/info/exclude` takes precedence over the global excludes file
+        for rules in self.global.iter().rev() {
+            if let Some(rule) = rules.find_match(&path, is_dir) {
+                return rules.exclusion(rule);
+            }
+        }
+        None
+    }
+}
+
+/// Kind of checkout nested inside another repository's working tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NestedCheckout {
+    /// A git submodule
+    Submodule,
+    /// A linked worktree created with `git worktree add`
+    Worktree,
+}
+
+impl NestedCheckout {
+    /// Whether the configuration allows walking into this kind of checkout
+    #[must_use]
+    pub fn is_descended(self, config: &Config) -> bool {
+        match self {
+            Self::Submodule => config.git_submodules,
+            Self::Worktree => config.git_worktrees,
+        }
+    }
+}
+
+/// The kind of checkout rooted at `dir`, if its `.git` is a `gitdir:` file
+///
+/// Linked worktrees point into `.git/worktrees/` of their main repository;
+/// any other `gitdir:` file belongs to a submodule.
+#[must_use]
+pub fn nested_checkout(dir: &Path) -> Option<NestedCheckout> {
+    let dot_git = dir.join(".git");
+    if !dot_git.is_file() {
+        return None;
+    }
+    let git_dir = git_dir(dir)?;
+    let in_worktrees = git_dir
+        .parent()
+        .and_then(Path::file_name)
+        .is_some_and(|name| name == "worktrees");
+    Some(if in_worktrees {
+        NestedCheckout::Worktree
+    } else {
+        NestedCheckout::Submodule
+    })
+}
+
+/// A set of files in the working tree of the repository containing a root
+///
+/// Paths are absolute, beneath the canonical working directory. Directories
+/// holding listed files are recorded separately so a walk can prune the rest.
+#[cfg(feature = "git")]
+#[derive(Debug, Clone, Default)]
+pub struct GitFiles {
+    /// Listed files
+    pub files: HashSet<PathBuf>,
+    /// Directories containing at least one listed file
+    pub dirs: HashSet<PathBuf>,
+}
+
+#[cfg(feature = "git")]
+impl GitFiles {
+    /// Files in the index of the repository containing `root`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` is not inside a non-bare repository or its
+    /// index cannot be read
+    pub fn tracked(root: &Path) -> Result<Self> {
+        let (repo, workdir) = open_repo(root)?;
+        let index = repo
+            .index()
+            .map_err(|e| FileSearchError::git_error(e, root))?;
+
+        let mut files = Self::default();
+        for entry in index.iter() {
+            if let Ok(relative) = std::str::from_utf8(&entry.path) {
+                files.insert(&workdir, Path::new(relative));
+            }
+        }
+        Ok(files)
+    }
+
+    /// Files added or modified since `rev`, including untracked files
+    ///
+    /// `rev` is anything git can resolve to a commit, such as a branch, tag or
+    /// `HEAD~3`. Both committed and uncommitted changes count.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` is not inside a non-bare repository, `rev`
+    /// does not name a commit, or the diff cannot be computed
+    pub fn changed_since(root: &Path, rev: &str) -> Result<Self> {
+        let (repo, workdir) = open_repo(root)?;
+        let git_error = |e| FileSearchError::git_error(e, root);
+        let tree = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(git_error)?;
+
+        let mut options = git2::DiffOptions::new();
+        options.include_untracked(true).recurse_untracked_dirs(true);
+        let diff = repo
+            .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))
+            .map_err(git_error)?;
+
+        let mut files = Self::default();
+        for delta in diff.deltas() {
+            if delta.status() == git2::Delta::Deleted {
+                continue;
+            }
+            if let Some(relative) = delta.new_file().path() {
+                files.insert(&workdir, relative);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Whether a walk should visit `path`, an absolute canonical path
+    #[must_use]
+    pub fn contains(&self, path: &Path, is_dir: bool) -> bool {
+        if is_dir {
+            self.dirs.contains(path)
+        } else {
+            self.files.contains(path)
+        }
+    }
+
+    fn insert(&mut self, workdir: &Path, relative: &Path) {
+        let path = workdir.join(relative);
+        for dir in path.ancestors().skip(1) {
+            if !self.dirs.insert(dir.to_path_buf()) || dir == workdir {
+                break;
+            }
+        }
+        self.files.insert(path);
+    }
+}
+
+/// Git status of a file in a working tree
+#[cfg(feature = "git")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitStatus {
+    /// Tracked and unchanged
+    Clean,
+    /// Changed in the index or working tree, including staged new files
+    Modified,
+    /// Not tracked and not ignored
+    Untracked,
+    /// Excluded by the repository's ignore rules
+    Ignored,
+}
+
+#[cfg(feature = "git")]
+impl GitStatus {
+    /// One-letter code in the style of `git status --short`
+    #[must_use]
+    pub fn code(self) -> char {
+        match self {
+            Self::Clean => ' ',
+            Self::Modified => 'M',
+            Self::Untracked => '?',
+            Self::Ignored => '!',
+        }
+    }
+}
+
+#[cfg(feature = "git")]
+impl From<git2::Status> for GitStatus {
+    fn from(status: git2::Status) -> Self {
+        if status.is_ignored() {
+            Self::Ignored
+        } else if status.is_wt_new() {
+            Self::Untracked
+        } else if status.is_empty() {
+            Self::Clean
+        } else {
+            Self::Modified
+        }
+    }
+}
+
+/// Looks up the git status of files, keeping each repository open
+///
+/// Files are resolved against the closest repository above them, so files in
+/// submodules report their status within the submodule.
+#[cfg(feature = "git")]
+#[derive(Default)]
+pub struct GitStatuses {
+    /// Opened repositories by canonical working directory, `None` if opening
+    /// failed
+    repos: HashMap<PathBuf, Option<git2::Repository>>,
+}
+
+#[cfg(feature = "git")]
+impl GitStatuses {
+    /// Status of the file at `path`
+    ///
+    /// Returns `None` outside a repository or if the status cannot be read.
+    pub fn status(&mut self, path: &Path) -> Option<GitStatus> {
+        let path = path.canonicalize().ok()?;
+        let workdir = find_repo_root(path.parent()?)?;
+        let repo = self
+            .repos
+            .entry(workdir.clone())
+            .or_insert_with(|| open_repo(&workdir).ok().map(|(repo, _)| repo))
+            .as_ref()?;
+        let relative = path.strip_prefix(&workdir).ok()?;
+        repo.status_file(relative).ok().map(GitStatus::from)
+    }
+}
+
+/// Open the repository containing `root` along with its canonical working
+/// directory
+#[cfg(feature = "git")]
+fn open_repo(root: &Path) -> Result<(git2::Repository, PathBuf)> {
+    let repo = git2::Repository::discover(root).map_err(|e| FileSearchError::git_error(e, root))?;
+    let workdir = repo.workdir().ok_or_else(|| {
+        FileSearchError::invalid_path(root, "repository has no working directory")
+    })?;
+    let workdir = workdir
+        .canonicalize()
+        .map_err(|e| FileSearchError::io_error_with_path(e, "resolving repository", workdir))?;
+    Ok((repo, workdir))
+}
+
+/// Closest directory at or above `path` holding a `.git` directory or file
+#[must_use]
+pub fn find_repo_root(path: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().ok()?;
+    path.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Git directory of the repository at `repo_root`, following `gitdir:` files
+/// used by worktrees and submodules
+#[must_use]
+pub fn git_dir(repo_root: &Path) -> Option<PathBuf> {
+    let dot_git = repo_root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let contents = std::fs::read_to_string(&dot_git).ok()?;
+    let target = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("gitdir:"))?;
+    Some(repo_root.join(target.trim()))
+}
+
+/// The user's global excludes file, as git would pick it
+#[must_use]
+pub fn global_excludes_file() -> Option<PathBuf> {
+    let home = home_dir();
+    let xdg_config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".config")));
+
+    let config_files = [
+        xdg_config
+            .as_ref()
+            .map(|dir| dir.join("git").join("config")),
+        home.as_ref().map(|home| home.join(".gitconfig")),
+    ];
+    // Later files take precedence, as in git
+    let configured = config_files
+        .iter()
+        .rev()
+        .flatten()
+        .filter_map(|file| std::fs::read_to_string(file).ok())
+        .find_map(|contents| excludes_file_from_config(&contents, home.as_deref()));
+
+    configured.or_else(|| xdg_config.map(|dir| dir.join("git").join("ignore")))
+}
+
+/// Value of `core.excludesFile` in git config file `contents`
+pub(crate) fn excludes_file_from_config(contents: &str, home: Option<&Path>) -> Option<PathBuf> {
+    let mut in_core = false;
+    let mut value = None;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_core = line
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .trim()
+                .eq_ignore_ascii_case("core");
+        } else if in_core {
+            if let Some((key, raw)) = line.split_once('=') {
+                if key.trim().eq_ignore_ascii_case("excludesfile") {
+                    value = Some(raw.trim().trim_matches('"').to_string());
+                }
+            }
+        }
+    }
+
+    let value = value?;
+    match (value.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => Some(home.join(rest)),
+        _ => Some(PathBuf::from(value)),
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
@@ -0,0 +1,105 @@
+//! Directory modification times for incremental re-indexing
+//!
+//! Creating, deleting or renaming a file changes the modification time of
+//! the directory holding it. A full walk records the time of every directory
+//! it enters, so [`FileIndexer::update_index`](super::FileIndexer::update_index)
+//! can later `stat` each of them and only list those that changed, instead
+//! of walking the whole tree again.
+//!
+//! A directory modified during the walk that recorded it may have been
+//! listed before the change, yet keep the same time afterwards on file
+//! systems with coarse timestamps. Times at or after the start of the walk
+//! are therefore never trusted, so such directories are listed again by the
+//! next update.
+
+use super::cache::timestamp;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// When a directory was last modified and how deep below the root it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DirTime {
+    modified: Option<(u64, u32)>,
+    depth: usize,
+}
+
+/// Modification times of the directories seen by one walk of a root
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DirTimes {
+    root: PathBuf,
+    /// When the walk started
+    started: Option<(u64, u32)>,
+    dirs: BTreeMap<PathBuf, DirTime>,
+}
+
+/// Directories that need listing again since the times were recorded
+#[derive(Debug, Default)]
+pub(crate) struct Changes {
+    /// Directories whose entries changed, with their depth below the root
+    pub(crate) modified: Vec<(PathBuf, usize)>,
+    /// Directories whose files are out of date, whether modified or gone
+    pub(crate) stale: HashSet<PathBuf>,
+}
+
+impl DirTimes {
+    /// Times of a walk of `root` starting now
+    pub(crate) fn start(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            started: timestamp(SystemTime::now()),
+            dirs: BTreeMap::new(),
+        }
+    }
+
+    /// Whether these are the times of a walk of `root`
+    pub(crate) fn covers(&self, root: &Path) -> bool {
+        self.root == root
+    }
+
+    /// Number of directories recorded
+    pub(crate) fn len(&self) -> usize {
+        self.dirs.len()
+    }
+
+    /// Whether the walk saw `dir`
+    pub(crate) fn contains(&self, dir: &Path) -> bool {
+        self.dirs.contains_key(dir)
+    }
+
+    /// Record the current modification time of `dir`, `depth` levels below
+    /// the root
+    pub(crate) fn record(&mut self, dir: &Path, depth: usize) {
+        let modified = std::fs::metadata(dir)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(timestamp);
+        self.dirs
+            .insert(dir.to_path_buf(), DirTime { modified, depth });
+    }
+
+    /// Check every recorded directory, moving the unchanged ones into `next`
+    /// and returning the others
+    pub(crate) fn changes(&self, next: &mut Self) -> Changes {
+        let mut changes = Changes::default();
+        for (dir, time) in &self.dirs {
+            let current = match std::fs::metadata(dir) {
+                Ok(metadata) if metadata.is_dir() => metadata.modified().ok().and_then(timestamp),
+                _ => {
+                    changes.stale.insert(dir.clone());
+                    continue;
+                }
+            };
+            let trusted = time
+                .modified
+                .is_some_and(|modified| Some(modified) < self.started);
+            if trusted && current == time.modified {
+                next.dirs.insert(dir.clone(), *time);
+            } else {
+                changes.stale.insert(dir.clone());
+                changes.modified.push((dir.clone(), time.depth));
+            }
+        }
+        changes
+    }
+}
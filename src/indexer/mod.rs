@@ -1,58 +1,498 @@
+/// Directory walks on the Tokio runtime
+#[cfg(feature = "async")]
+pub mod async_walker;
+/// Sparse, compressed, encrypted and immutable file attributes
+pub mod attributes;
+/// Permission and ownership checks for security audits
+pub mod audit;
+/// Storage backends for filename indexes
+pub mod backend;
+/// Filename n-gram bloom filters for skipping shards
+pub mod bloom;
+/// Per-directory index cache kept on disk between runs
+pub mod cache;
+/// Persistent file database
+pub mod database;
+/// Differences between two file database snapshots
+pub mod diff;
+/// Drive letters and whole-machine searches
+pub mod drives;
+/// Inode numbers, Windows file IDs and hard link counts
+pub mod file_id;
+/// Sorted, contiguous in-memory filename index
+pub mod file_index;
 /// File system walker implementation
 pub mod file_walker;
+/// Predicates applied to files during walks
+pub mod filter;
+/// Git ignore rules for walks inside repositories
+pub mod git;
+/// Directory modification times for incremental re-indexing
+mod incremental;
+/// Virtual file systems such as proc and sysfs
+pub mod mounts;
+/// Bigram posting lists for selecting fuzzy search candidates
+#[cfg(feature = "fuzzy")]
+pub mod ngram;
+/// Online-only files of cloud storage clients
+pub mod placeholder;
+/// CPU and IO priority of background indexing
+pub mod priority;
+/// Several search roots walked as one
+pub mod roots;
+/// Scheduled refreshing of file database roots
+pub mod scheduler;
+/// Per-directory shards of file database roots
+pub mod shard;
+/// NTFS alternate data streams
+pub mod streams;
+/// Files in the desktop trash and the Recycle Bin
+pub mod trash;
+/// Extended attributes and macOS Finder tags
+pub mod xattr;
 
 use crate::config::Config;
+use crate::search::fold::Folding;
+use crate::search::progress::{ProgressCounter, ProgressReporter, SearchProgress};
 use crate::Result;
-use std::collections::HashMap;
+use backend::IndexBackend;
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-/// File index mapping filenames to their full paths
-pub type FileIndex = HashMap<String, Vec<PathBuf>>;
+pub use file_index::FileIndex;
+
+/// Whether the walked `entry` is recorded in indexes under `config`, for a
+/// walk starting `offset` levels below the search root
+///
+/// The root of a walk is left out when it is a directory.
+pub(crate) fn is_indexed(entry: &walkdir::DirEntry, offset: usize, config: &Config) -> bool {
+    (entry.depth() > 0 || !entry.file_type().is_dir())
+        && is_deep_enough(offset + entry.depth(), config)
+        && config
+            .file_type
+            .includes(entry.file_type(), entry.path_is_symlink())
+}
+
+/// Whether an entry `depth` levels below the search root is at least as deep
+/// as [`Config::min_depth`]
+pub(crate) fn is_deep_enough(depth: usize, config: &Config) -> bool {
+    config.min_depth.map_or(true, |min| depth >= min)
+}
 
 /// File system indexer that builds searchable indexes of files
 pub struct FileIndexer {
     config: Config,
+    stats: file_walker::WalkStats,
+    #[cfg(feature = "fuzzy")]
+    ngrams: Option<ngram::NgramIndex>,
+    /// Directory times of the last full walk, for [`FileIndexer::update_index`]
+    dir_times: Option<incremental::DirTimes>,
+    progress_reporter: Option<Arc<dyn ProgressReporter>>,
+    progress: SearchProgress,
+    filters: filter::Filters,
 }
 
 impl FileIndexer {
     /// Create a new file indexer with the given configuration
-    pub fn new(config: Config) -> Self {
-        Self { config }
+    #[must_use]
+    pub fn new(mut config: Config) -> Self {
+        config.expand_ignore_patterns();
+        Self {
+            config,
+            stats: file_walker::WalkStats::default(),
+            #[cfg(feature = "fuzzy")]
+            ngrams: None,
+            dir_times: None,
+            progress_reporter: None,
+            progress: SearchProgress::default(),
+            filters: filter::Filters::default(),
+        }
+    }
+
+    /// Report the progress of every later walk to `reporter`
+    pub fn set_progress_reporter(&mut self, reporter: Arc<dyn ProgressReporter>) {
+        self.progress_reporter = Some(reporter);
+    }
+
+    /// Keep only the files every one of `filters` accepts in later walks
+    pub fn set_filters(&mut self, filters: filter::Filters) {
+        self.filters = filters;
+    }
+
+    /// Directories and files counted by the most recent walk
+    #[must_use]
+    pub fn progress(&self) -> SearchProgress {
+        self.progress
+    }
+
+    /// Statistics from the most recent call to [`FileIndexer::build_index`]
+    #[must_use]
+    pub fn stats(&self) -> &file_walker::WalkStats {
+        &self.stats
+    }
+
+    /// Fuzzy candidate index from the most recent call to [`FileIndexer::build_index`]
+    ///
+    /// Only built when `fuzzy_candidate_index` is enabled in the configuration.
+    #[cfg(feature = "fuzzy")]
+    #[must_use]
+    pub fn ngram_index(&self) -> Option<&ngram::NgramIndex> {
+        self.ngrams.as_ref()
     }
 
     /// Build a complete file index from the given root path
     pub fn build_index(&mut self, root_path: &str) -> Result<FileIndex> {
-        let mut index = HashMap::new();
-        let walker = file_walker::FileWalker::new(&self.config);
+        let index: FileIndex = self
+            .walk_paths(root_path, false)?
+            .into_iter()
+            .filter_map(|path| backend::path_entry(path, Folding::new(&self.config)))
+            .chain(self.trash_entries())
+            .collect();
+
+        self.build_ngrams(&index);
+        Ok(index)
+    }
 
-        let entries = walker.walk(root_path)?;
+    /// Build a file index like [`FileIndexer::build_index`], walking with an
+    /// [`AsyncWalker`](async_walker::AsyncWalker) so the walk does not hold a
+    /// thread of Tokio's blocking pool throughout
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration uses a rule the async walker does
+    /// not support, filters are set, or the directory tree cannot be traversed
+    #[cfg(feature = "async")]
+    pub async fn build_index_async(&mut self, root_path: &str) -> Result<FileIndex> {
+        if !self.filters.is_empty() {
+            return Err(crate::FileSearchError::invalid_config(
+                "filters are not supported by the async walker",
+            ));
+        }
+        let walker = async_walker::AsyncWalker::new(&self.config)?;
+        let (files, stats) = walker.walk_files(root_path).await?;
+        self.stats = stats;
+        let index: FileIndex = files
+            .into_iter()
+            .filter_map(|path| backend::path_entry(path, Folding::new(&self.config)))
+            .chain(self.trash_entries())
+            .collect();
+
+        self.build_ngrams(&index);
+        Ok(index)
+    }
+
+    /// Bring `index`, built from `root_path` by this indexer, up to date by
+    /// listing only the directories modified since it was built or last
+    /// updated
+    ///
+    /// Files in unchanged directories are kept as they are, and new
+    /// directories are walked completely. If this indexer has not walked
+    /// `root_path` before, the index is rebuilt with a full walk. Returns the
+    /// number of directories listed or walked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a changed directory cannot be traversed
+    pub fn update_index(&mut self, index: &mut FileIndex, root_path: &str) -> Result<usize> {
+        let root = Path::new(root_path);
+        let Some(times) = self.dir_times.take().filter(|times| times.covers(root)) else {
+            *index = self.build_index(root_path)?;
+            return Ok(self
+                .dir_times
+                .as_ref()
+                .map_or(0, incremental::DirTimes::len));
+        };
+
+        let mut next = incremental::DirTimes::start(root);
+        let changes = times.changes(&mut next);
+        index.retain(|path| {
+            path.parent()
+                .map_or(true, |dir| !changes.stale.contains(dir))
+        });
+
+        let listing = self.walker(&Config {
+            max_depth: Some(1),
+            ..self.config.clone()
+        });
+        let mut added = Vec::new();
+        let mut new_dirs = Vec::new();
+        self.stats = file_walker::WalkStats::default();
+        for (dir, depth) in &changes.modified {
+            next.record(dir, *depth);
+            if self.config.max_depth.is_some_and(|max| *depth >= max) {
+                continue;
+            }
+            for entry in listing.walk_dir(dir)? {
+                let entry = entry?;
+                if entry.depth() == 0 {
+                    continue;
+                }
+                if is_indexed(&entry, *depth, &self.config) {
+                    added.push(entry.path().to_path_buf());
+                }
+                if entry.file_type().is_dir() && !times.contains(entry.path()) {
+                    new_dirs.push((entry.into_path(), depth + 1));
+                }
+            }
+        }
+
+        for (dir, depth) in &new_dirs {
+            let walker = self.walker(&Config {
+                max_depth: self.config.max_depth.map(|max| max - depth),
+                ..self.config.clone()
+            });
+            for entry in walker.walk_dir(dir)? {
+                let entry = entry?;
+                if entry.file_type().is_dir() {
+                    next.record(entry.path(), depth + entry.depth());
+                }
+                if is_indexed(&entry, *depth, &self.config) {
+                    added.push(entry.into_path());
+                }
+            }
+        }
+
+        index.extend(
+            added
+                .into_iter()
+                .filter_map(|path| backend::path_entry(path, Folding::new(&self.config))),
+        );
+        self.build_ngrams(index);
+        self.dir_times = Some(next);
+        Ok(changes.modified.len() + new_dirs.len())
+    }
+
+    /// Rebuild the fuzzy candidate index of `index`, if enabled
+    fn build_ngrams(&mut self, index: &FileIndex) {
+        #[cfg(feature = "fuzzy")]
+        {
+            self.ngrams = self
+                .config
+                .fuzzy_candidate_index
+                .then(|| ngram::NgramIndex::build(index));
+        }
+        #[cfg(not(feature = "fuzzy"))]
+        let _ = index;
+    }
+
+    /// Build an index of the directories beneath `root_path`, keyed by their
+    /// names
+    ///
+    /// `root_path` itself is left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory tree cannot be traversed
+    pub fn build_dir_index(&mut self, root_path: &str) -> Result<FileIndex> {
+        Ok(self
+            .walk_paths(root_path, true)?
+            .into_iter()
+            .filter_map(|path| backend::path_entry(path, Folding::new(&self.config)))
+            .collect())
+    }
+
+    /// Count the directories a walk of `root_path` enters, `root_path`
+    /// included
+    ///
+    /// Nothing is indexed, so this pass is quicker than building an index and
+    /// leaves the file system caches warm for it. The result is meant for
+    /// [`SearchHandle::set_total_dirs`](crate::search::handle::SearchHandle::set_total_dirs).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory tree cannot be traversed
+    pub fn count_dirs(&mut self, root_path: &str) -> Result<usize> {
+        let walker = self.walker(&self.config);
+
+        let (entries, stats) = walker.walk_with_stats(root_path)?;
+        self.stats = stats;
+        let mut dirs = 0;
         for entry_result in entries {
-            let entry = entry_result?;
+            if entry_result?.file_type().is_dir() {
+                dirs += 1;
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// Walk `root_path` and record every file in `backend`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory tree cannot be traversed
+    pub fn build_into<B: IndexBackend + ?Sized>(
+        &mut self,
+        root_path: &str,
+        backend: &mut B,
+    ) -> Result<()> {
+        for path in self.walk_paths(root_path, false)? {
+            backend::insert_path(backend, &path, Folding::new(&self.config));
+        }
+        for (name, path) in self.trash_entries() {
+            backend.insert(name, path);
+        }
+        Ok(())
+    }
+
+    /// Walk `root_path` and pass every file to `visit` as soon as it is
+    /// found, with its index key, instead of building an index
+    ///
+    /// Trashed files, when `include_trash` is enabled, follow once the walk
+    /// is done. Returns [`ControlFlow::Break`] if `visit` stopped early.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory tree cannot be traversed
+    pub fn for_each_file(
+        &mut self,
+        root_path: &str,
+        mut visit: impl FnMut(&str, &Path) -> ControlFlow<()>,
+    ) -> Result<ControlFlow<()>> {
+        let walker = self.walker(&self.config);
+        let folding = Folding::new(&self.config);
+        let mut counter = ProgressCounter::new(self.progress_reporter.as_ref());
+        let mut failed = None;
+        let mut flow = ControlFlow::Continue(());
+
+        self.stats = walker.walk_each(root_path, |entry_result| {
+            let entry = match entry_result {
+                Ok(entry) => entry,
+                Err(e) => {
+                    failed = Some(e);
+                    return ControlFlow::Break(());
+                }
+            };
+            if entry.file_type().is_dir() {
+                counter.dir();
+            }
+            if !is_indexed(&entry, 0, &self.config) {
+                return ControlFlow::Continue(());
+            }
             if entry.file_type().is_file() {
-                let path = entry.path();
-                if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                    let key = if self.config.case_sensitive {
-                        filename.to_string()
-                    } else {
-                        filename.to_lowercase()
-                    };
-
-                    index
-                        .entry(key)
-                        .or_insert_with(Vec::new)
-                        .push(path.to_path_buf());
+                counter.file();
+            }
+            let name = entry.file_name().to_string_lossy();
+            flow = visit(&backend::name_key(&name, folding), entry.path());
+            flow
+        })?;
+        self.progress = counter.finish();
+        if let Some(e) = failed {
+            return Err(e.into());
+        }
+
+        if flow.is_break() {
+            return Ok(flow);
+        }
+        Ok(self
+            .trash_entries()
+            .iter()
+            .try_for_each(|(name, path)| visit(name, path)))
+    }
+
+    /// Walk `root_path` and collect every entry [`Config::file_type`] asks
+    /// for, or every directory below it
+    fn walk_paths(&mut self, root_path: &str, directories: bool) -> Result<Vec<PathBuf>> {
+        let walker = self.walker(&self.config);
+        let mut times = (!directories).then(|| incremental::DirTimes::start(Path::new(root_path)));
+        let mut counter = ProgressCounter::new(self.progress_reporter.as_ref());
+        let mut paths = Vec::new();
+        let mut failed = None;
+
+        self.stats = walker.walk_each(root_path, |entry_result| {
+            let entry = match entry_result {
+                Ok(entry) => entry,
+                Err(e) => {
+                    failed = Some(e);
+                    return ControlFlow::Break(());
                 }
+            };
+            if entry.file_type().is_dir() {
+                counter.dir();
+                if let Some(times) = times.as_mut() {
+                    times.record(entry.path(), entry.depth());
+                }
+            } else if !directories && entry.file_type().is_file() {
+                counter.file();
+            }
+            let wanted = if directories {
+                entry.file_type().is_dir()
+                    && entry.depth() > 0
+                    && is_deep_enough(entry.depth(), &self.config)
+            } else {
+                is_indexed(&entry, 0, &self.config)
+            };
+            if wanted {
+                paths.push(entry.into_path());
             }
+            ControlFlow::Continue(())
+        })?;
+        self.progress = counter.finish();
+        if let Some(e) = failed {
+            return Err(e.into());
+        }
+        if times.is_some() {
+            self.dir_times = times;
         }
+        Ok(paths)
+    }
 
-        Ok(index)
+    /// Build a file index from `root_path` under the control of `handle`
+    ///
+    /// The index holds every file the handle has found, including those
+    /// carried over from a checkpoint, so a stopped walk yields a partial
+    /// index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory tree cannot be traversed
+    pub fn build_index_with_handle(
+        &mut self,
+        root_path: &str,
+        handle: &crate::search::handle::SearchHandle,
+    ) -> Result<FileIndex> {
+        let walker = self.walker(&self.config);
+
+        let (entries, stats) = walker.walk_with_handle(root_path, handle)?;
+        self.stats = stats;
+        for entry_result in entries {
+            entry_result?;
+        }
+
+        Ok(handle
+            .files()
+            .into_iter()
+            .filter_map(|path| backend::path_entry(path, Folding::new(&self.config)))
+            .chain(self.trash_entries())
+            .collect())
+    }
+
+    /// A walker for `config` applying this indexer's filters
+    fn walker(&self, config: &Config) -> file_walker::FileWalker {
+        file_walker::FileWalker::new(config).with_filters(self.filters.clone())
+    }
+
+    /// Index entries for the trashed files, keyed by their original names,
+    /// when `include_trash` is enabled
+    fn trash_entries(&self) -> Vec<(String, PathBuf)> {
+        if !self.config.include_trash {
+            return Vec::new();
+        }
+        trash::trashed_files(&self.config)
+            .into_iter()
+            .filter_map(|file| {
+                let name =
+                    backend::name_key(&file.name()?.to_string_lossy(), Folding::new(&self.config));
+                Some((name, file.path))
+            })
+            .collect()
     }
 
     /// Check if a path should be ignored based on configuration
     pub fn should_ignore(&self, path: &Path) -> bool {
         if self.config.ignore_hidden {
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.starts_with('.') {
+            if let Some(name) = path.file_name() {
+                if name.to_string_lossy().starts_with('.') {
                     return true;
                 }
             }
@@ -68,15 +508,11 @@ impl FileIndexer {
     }
 
     fn matches_pattern(&self, path: &Path, pattern: &str) -> bool {
-        if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-            if pattern.contains('*') {
-                let regex_pattern = pattern.replace("*", ".*");
-                if let Ok(regex) = regex::Regex::new(&regex_pattern) {
-                    return regex.is_match(filename);
-                }
-            } else {
-                return filename == pattern || path.to_string_lossy().contains(pattern);
+        if let Some(filename) = path.file_name().map(|n| n.to_string_lossy()) {
+            if pattern.contains(['*', '?']) {
+                return crate::search::matcher::wildcard_match(pattern, &filename);
             }
+            return filename == pattern || crate::search::matcher::path_contains(path, pattern);
         }
         false
     }
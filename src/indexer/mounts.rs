@@ -0,0 +1,134 @@
+//! Virtual file systems mounted below a search root
+//!
+//! Linux mounts kernel interfaces such as `/proc`, `/sys` and `/dev` as file
+//! systems. Walking them is slow, can block on device files and yields
+//! entries such as `/proc/*/fd` that are not files anyone searches for. Mounts
+//! are read from `/proc/self/mounts`, and those whose type is listed in
+//! [`Config::pseudo_filesystems`] are skipped. Other platforms have no such
+//! table to read, so nothing is skipped there.
+
+use crate::config::Config;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use walkdir::DirEntry;
+
+/// File system types skipped by default
+pub const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// A mounted file system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    /// Directory the file system is mounted on
+    pub point: PathBuf,
+    /// File system type, such as `ext4` or `proc`
+    pub fs_type: String,
+}
+
+/// The file systems currently mounted, empty where they cannot be listed
+#[must_use]
+pub fn mounts() -> Vec<Mount> {
+    std::fs::read_to_string("/proc/self/mounts")
+        .map(|table| parse_mounts(&table))
+        .unwrap_or_default()
+}
+
+/// Parse a mount table in the `/proc/self/mounts` format
+fn parse_mounts(table: &str) -> Vec<Mount> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            Some(Mount {
+                point: PathBuf::from(unescape(fields.next()?)),
+                fs_type: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Undo the octal escapes (`\040` for a space) of a mount table field
+fn unescape(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        let code = rest
+            .get(i + 1..i + 4)
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        if let Some(byte) = code {
+            out.push(char::from(byte));
+            rest = &rest[i + 4..];
+        } else {
+            out.push('\\');
+            rest = &rest[i + 1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The pseudo file systems mounted below the root of one walk
+#[derive(Debug, Default)]
+pub(crate) struct PseudoMounts {
+    /// File system types by mount point, relative to the walk's root
+    types: HashMap<PathBuf, String>,
+}
+
+impl PseudoMounts {
+    /// Pseudo file systems below `root`, which itself is never skipped
+    pub(crate) fn for_root(root: &Path, config: &Config) -> Self {
+        if config.pseudo_filesystems.is_empty() {
+            return Self::default();
+        }
+        let Ok(root) = root.canonicalize() else {
+            return Self::default();
+        };
+        let types = mounts()
+            .into_iter()
+            .filter(|mount| config.pseudo_filesystems.contains(&mount.fs_type))
+            .filter_map(|mount| {
+                let relative = mount.point.strip_prefix(&root).ok()?;
+                (!relative.as_os_str().is_empty()).then(|| (relative.to_path_buf(), mount.fs_type))
+            })
+            .collect();
+        Self { types }
+    }
+
+    /// Type of the pseudo file system mounted at `relative`, a path relative to
+    /// the walk's root
+    pub(crate) fn fs_type(&self, relative: &Path) -> Option<&str> {
+        self.types.get(relative).map(String::as_str)
+    }
+
+    /// Whether `entry`, found by walking `root`, is the mount point of a
+    /// pseudo file system
+    pub(crate) fn is_mounted_at(&self, entry: &DirEntry, root: &Path) -> bool {
+        entry.depth() > 0
+            && entry.file_type().is_dir()
+            && entry
+                .path()
+                .strip_prefix(root)
+                .is_ok_and(|relative| self.fs_type(relative).is_some())
+    }
+}
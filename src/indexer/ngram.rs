@@ -0,0 +1,70 @@
+//! Bigram posting lists used to narrow fuzzy search to likely candidates
+//!
+//! Every filename key of an [`IndexBackend`] is listed under each of its character
+//! bigrams. Fuzzy search then only scores filenames sharing at least one bigram
+//! with the query, which trades recall for not scanning the whole index: a
+//! filename that matches only as a scattered subsequence can be missed.
+
+use crate::indexer::backend::IndexBackend;
+use std::collections::{HashMap, HashSet};
+
+/// Length of the character n-grams in the posting lists
+const NGRAM_LEN: usize = 2;
+
+/// Map from filename bigrams to the index keys containing them
+#[derive(Debug, Clone, Default)]
+pub struct NgramIndex {
+    postings: HashMap<String, Vec<String>>,
+}
+
+impl NgramIndex {
+    /// Build posting lists for every filename key in `index`
+    #[must_use]
+    pub fn build<B: IndexBackend + ?Sized>(index: &B) -> Self {
+        let mut postings: HashMap<String, Vec<String>> = HashMap::new();
+        index.for_each_name(&mut |name, _| {
+            let grams: HashSet<String> = ngrams(name).collect();
+            for gram in grams {
+                postings.entry(gram).or_default().push(name.to_string());
+            }
+        });
+        Self { postings }
+    }
+
+    /// Number of distinct bigrams recorded
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.postings.len()
+    }
+
+    /// Whether no bigrams are recorded
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.postings.is_empty()
+    }
+
+    /// Index keys sharing at least one bigram with `query`
+    ///
+    /// `query` must already be case-folded the same way as the index keys.
+    /// Returns `None` when the query is too short to select candidates, in
+    /// which case every filename has to be scored.
+    #[must_use]
+    pub fn candidates(&self, query: &str) -> Option<HashSet<&str>> {
+        if query.chars().count() < NGRAM_LEN {
+            return None;
+        }
+        Some(
+            ngrams(query)
+                .filter_map(|gram| self.postings.get(&gram))
+                .flatten()
+                .map(String::as_str)
+                .collect(),
+        )
+    }
+}
+
+fn ngrams(text: &str) -> impl Iterator<Item = String> + '_ {
+    let chars: Vec<char> = text.chars().collect();
+    (0..chars.len().saturating_sub(NGRAM_LEN - 1))
+        .map(move |start| chars[start..start + NGRAM_LEN].iter().collect())
+}
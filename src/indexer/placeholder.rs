@@ -0,0 +1,86 @@
+//! Online-only files of cloud storage clients
+//!
+//! OneDrive, iCloud Drive and Dropbox can keep files in the cloud and leave
+//! only a placeholder on disk, downloading the contents when the file is
+//! opened. Windows marks such placeholders with the recall-on-open,
+//! recall-on-data-access or offline attribute bits, and macOS with the
+//! dataless file flag. Both are read from metadata alone, which never
+//! triggers a download. Other platforms have no placeholders to detect.
+
+use std::path::Path;
+use walkdir::DirEntry;
+
+/// Whether the file at `path` is a cloud placeholder whose contents are not
+/// on disk
+#[must_use]
+pub fn is_placeholder(path: &Path) -> bool {
+    imp::DETECTABLE
+        && std::fs::symlink_metadata(path).is_ok_and(|metadata| imp::is_placeholder(&metadata))
+}
+
+/// Whether placeholders can be detected on this platform at all
+#[cfg(feature = "async")]
+pub(crate) const DETECTABLE: bool = imp::DETECTABLE;
+
+/// Whether `metadata`, read without following symlinks, is that of a cloud
+/// placeholder
+#[cfg(feature = "async")]
+pub(crate) fn metadata_is_placeholder(metadata: &std::fs::Metadata) -> bool {
+    imp::DETECTABLE && imp::is_placeholder(metadata)
+}
+
+/// Like [`is_placeholder`], reusing the metadata a directory listing
+/// already provides where the platform has it
+pub(crate) fn entry_is_placeholder(entry: &DirEntry) -> bool {
+    imp::DETECTABLE
+        && entry
+            .metadata()
+            .is_ok_and(|metadata| imp::is_placeholder(&metadata))
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::fs::Metadata;
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x0000_1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+
+    pub const DETECTABLE: bool = true;
+
+    pub fn is_placeholder(metadata: &Metadata) -> bool {
+        !metadata.is_dir()
+            && metadata.file_attributes()
+                & (FILE_ATTRIBUTE_OFFLINE
+                    | FILE_ATTRIBUTE_RECALL_ON_OPEN
+                    | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+                != 0
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod imp {
+    use std::fs::Metadata;
+    use std::os::darwin::fs::MetadataExt;
+
+    /// The file's contents are held by a file provider, not on disk
+    const SF_DATALESS: u32 = 0x4000_0000;
+
+    pub const DETECTABLE: bool = true;
+
+    pub fn is_placeholder(metadata: &Metadata) -> bool {
+        metadata.is_file() && metadata.st_flags() & SF_DATALESS != 0
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "ios")))]
+mod imp {
+    use std::fs::Metadata;
+
+    pub const DETECTABLE: bool = false;
+
+    pub fn is_placeholder(_metadata: &Metadata) -> bool {
+        false
+    }
+}
@@ -0,0 +1,181 @@
+//! Lowering the CPU and IO priority of background indexing
+//!
+//! Only the calling thread is lowered, so an application that indexes on one
+//! of its threads keeps the rest of its threads responsive. Linux and Android
+//! give the thread the lowest nice value and the idle IO class, macOS the
+//! Darwin background policy for threads and Windows the thread background
+//! mode. Other platforms have no portable way to lower a single thread, so
+//! nothing changes there.
+//!
+//! The priority is restored when the [`Background`] guard is dropped. Linux
+//! only lets a thread raise its nice value back with `CAP_SYS_NICE` or a
+//! high enough `RLIMIT_NICE`, so without them the thread stays at the lowest
+//! CPU priority.
+
+use std::marker::PhantomData;
+
+/// Lowered priority of the thread that called [`enter_background`], restored
+/// when dropped
+#[must_use = "the priority is restored as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct Background {
+    saved: Option<imp::Saved>,
+    /// The priority belongs to the thread that lowered it
+    _thread: PhantomData<*const ()>,
+}
+
+impl Background {
+    /// Whether the priority was lowered
+    ///
+    /// `false` if the platform does not support it or refused the request.
+    #[must_use]
+    pub fn is_lowered(&self) -> bool {
+        self.saved.is_some()
+    }
+}
+
+impl Drop for Background {
+    fn drop(&mut self) {
+        if let Some(saved) = self.saved.take() {
+            imp::restore(&saved);
+        }
+    }
+}
+
+/// Lower the CPU and IO scheduling priority of the calling thread until the
+/// returned guard is dropped
+pub fn enter_background() -> Background {
+    Background {
+        saved: imp::enter_background(),
+        _thread: PhantomData,
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod imp {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+
+    /// The thread and the priorities it had before
+    #[derive(Debug)]
+    pub struct Saved {
+        tid: libc::c_long,
+        nice: libc::c_int,
+        ioprio: libc::c_long,
+    }
+
+    pub fn enter_background() -> Option<Saved> {
+        // SAFETY: plain syscalls on the calling thread, named by its own id
+        unsafe {
+            let tid = libc::syscall(libc::SYS_gettid);
+            let who = libc::id_t::try_from(tid).ok()?;
+            let saved = Saved {
+                tid,
+                nice: libc::getpriority(libc::PRIO_PROCESS, who),
+                ioprio: libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, tid),
+            };
+            let cpu = libc::setpriority(libc::PRIO_PROCESS, who, 19) == 0;
+            let io = libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PROCESS,
+                tid,
+                IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+            ) == 0;
+            if cpu || io {
+                Some(saved)
+            } else {
+                None
+            }
+        }
+    }
+
+    pub fn restore(saved: &Saved) {
+        let Ok(who) = libc::id_t::try_from(saved.tid) else {
+            return;
+        };
+        // SAFETY: plain syscalls on the thread that saved the priorities
+        unsafe {
+            libc::setpriority(libc::PRIO_PROCESS, who, saved.nice);
+            if saved.ioprio >= 0 {
+                libc::syscall(
+                    libc::SYS_ioprio_set,
+                    IOPRIO_WHO_PROCESS,
+                    saved.tid,
+                    saved.ioprio,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod imp {
+    #[derive(Debug)]
+    pub struct Saved;
+
+    pub fn enter_background() -> Option<Saved> {
+        // SAFETY: plain syscall on the calling thread with constant arguments
+        let lowered =
+            unsafe { libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, libc::PRIO_DARWIN_BG) == 0 };
+        lowered.then_some(Saved)
+    }
+
+    pub fn restore(_saved: &Saved) {
+        // SAFETY: plain syscall on the calling thread with constant arguments
+        unsafe {
+            libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, 0);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::ffi::c_void;
+
+    const THREAD_MODE_BACKGROUND_BEGIN: i32 = 0x0001_0000;
+    const THREAD_MODE_BACKGROUND_END: i32 = 0x0002_0000;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> *mut c_void;
+        fn SetThreadPriority(thread: *mut c_void, priority: i32) -> i32;
+    }
+
+    #[derive(Debug)]
+    pub struct Saved;
+
+    pub fn enter_background() -> Option<Saved> {
+        // SAFETY: the pseudo handle from GetCurrentThread is always valid
+        let lowered =
+            unsafe { SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN) != 0 };
+        lowered.then_some(Saved)
+    }
+
+    pub fn restore(_saved: &Saved) {
+        // SAFETY: the pseudo handle from GetCurrentThread is always valid
+        unsafe {
+            SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_END);
+        }
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    windows
+)))]
+mod imp {
+    #[derive(Debug)]
+    pub enum Saved {}
+
+    pub fn enter_background() -> Option<Saved> {
+        None
+    }
+
+    pub fn restore(saved: &Saved) {
+        match *saved {}
+    }
+}
@@ -0,0 +1,99 @@
+//! Indexing several search roots at once
+//!
+//! [`build_index`] walks each root on its own thread and merges their files
+//! into one index, so a single query ranks the matches of every root
+//! together. Roots that are the same directory or lie inside another root
+//! are only walked once, by [`distinct`].
+
+use super::filter::Filters;
+use super::{FileIndex, FileIndexer};
+use crate::config::Config;
+use crate::error::FileSearchError;
+use std::path::{Path, PathBuf};
+
+/// `roots` without those naming a directory already among them or lying
+/// inside one of them, in their original order
+///
+/// Roots are compared by their canonical paths, falling back to the paths as
+/// given for roots that cannot be resolved, and kept as they were written so
+/// that results are reported below them.
+#[must_use]
+pub fn distinct<P: AsRef<Path>>(roots: &[P]) -> Vec<&Path> {
+    let resolved: Vec<(&Path, PathBuf)> = roots
+        .iter()
+        .map(|root| {
+            let root = root.as_ref();
+            (
+                root,
+                root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            )
+        })
+        .collect();
+    resolved
+        .iter()
+        .enumerate()
+        .filter(|(i, (_, path))| {
+            !resolved.iter().enumerate().any(|(j, (_, other))| {
+                // Of two equal roots, the first is kept
+                if path == other {
+                    j < *i
+                } else {
+                    path.starts_with(other)
+                }
+            })
+        })
+        .map(|(_, (root, _))| *root)
+        .collect()
+}
+
+/// Index the files below `roots` that pass `filters`, walking each root on
+/// its own thread
+///
+/// Roots that cannot be walked are left out of the index and returned with
+/// their errors. The trash is indexed only once, along with the first root,
+/// when `include_trash` is enabled.
+#[must_use]
+pub fn build_index<P: AsRef<Path> + Sync>(
+    roots: &[P],
+    config: &Config,
+    filters: &Filters,
+) -> (FileIndex, Vec<(PathBuf, FileSearchError)>) {
+    let results: Vec<_> = std::thread::scope(|scope| {
+        let walks: Vec<_> = roots
+            .iter()
+            .enumerate()
+            .map(|(i, root)| {
+                let config = Config {
+                    include_trash: config.include_trash && i == 0,
+                    ..config.clone()
+                };
+                scope.spawn(move || {
+                    let root = root.as_ref();
+                    let root = root.to_str().ok_or_else(|| {
+                        FileSearchError::invalid_path(root, "Contains invalid UTF-8")
+                    })?;
+                    let mut indexer = FileIndexer::new(config);
+                    indexer.set_filters(filters.clone());
+                    indexer.build_index(root)
+                })
+            })
+            .collect();
+        walks
+            .into_iter()
+            .map(|walk| {
+                walk.join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+
+    let mut index = FileIndex::new();
+    let mut failed = Vec::new();
+    for (root, result) in roots.iter().zip(results) {
+        match result {
+            Ok(root_index) => index.extend(root_index),
+            Err(e) => failed.push((root.as_ref().to_path_buf(), e)),
+        }
+    }
+    (index, failed)
+}
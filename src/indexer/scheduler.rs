@@ -0,0 +1,190 @@
+//! Scheduling of periodic database refreshes across several roots
+//!
+//! Each configured root carries its own refresh interval, extra ignore rules
+//! and priority. A single [`IndexScheduler`] decides which roots are due and
+//! refreshes them in priority order, using the timestamps recorded in the
+//! [`Database`] so that schedules survive restarts.
+
+use crate::config::Config;
+use crate::indexer::database::Database;
+use crate::indexer::priority;
+use crate::Result;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "config")]
+use serde::{Deserialize, Serialize};
+
+/// Indexing settings for a single root directory
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub struct IndexRoot {
+    /// Directory to index
+    pub path: PathBuf,
+    /// How long an index of this root stays fresh before it is rebuilt
+    pub refresh_interval: Duration,
+    /// Ignore patterns applied to this root in addition to the global ones
+    pub ignore_patterns: Vec<String>,
+    /// Roots with a higher priority are refreshed first when several are due
+    pub priority: i32,
+}
+
+impl IndexRoot {
+    /// Create a root refreshed hourly with default priority and no extra ignore rules
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            refresh_interval: Duration::from_secs(60 * 60),
+            ignore_patterns: Vec::new(),
+            priority: 0,
+        }
+    }
+
+    /// Set how often this root is refreshed
+    #[must_use]
+    pub fn refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Add an ignore pattern that only applies to this root
+    #[must_use]
+    pub fn ignore_pattern<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.ignore_patterns.push(pattern.into());
+        self
+    }
+
+    /// Set the refresh priority of this root
+    #[must_use]
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// Keeps a [`Database`] fresh by re-indexing each configured root on its own schedule
+#[derive(Debug, Clone)]
+pub struct IndexScheduler {
+    config: Config,
+    roots: Vec<IndexRoot>,
+}
+
+impl IndexScheduler {
+    /// Create a scheduler for the roots listed in `config.index_roots`
+    #[must_use]
+    pub fn new(config: Config) -> Self {
+        let roots = config.index_roots.clone();
+        Self { config, roots }
+    }
+
+    /// Add another root to the schedule
+    pub fn add_root(&mut self, root: IndexRoot) {
+        self.roots.push(root);
+    }
+
+    /// Roots managed by this scheduler
+    #[must_use]
+    pub fn roots(&self) -> &[IndexRoot] {
+        &self.roots
+    }
+
+    /// Roots whose index is missing or stale at `now`, highest priority first
+    #[must_use]
+    pub fn due_roots(&self, database: &Database, now: SystemTime) -> Vec<&IndexRoot> {
+        let mut due: Vec<&IndexRoot> = self
+            .roots
+            .iter()
+            .filter(|root| Self::time_until_due(root, database, now).is_zero())
+            .collect();
+        due.sort_by_key(|root| std::cmp::Reverse(root.priority));
+        due
+    }
+
+    /// Time remaining until the next root becomes due, or `None` without roots
+    #[must_use]
+    pub fn next_due_in(&self, database: &Database, now: SystemTime) -> Option<Duration> {
+        self.roots
+            .iter()
+            .map(|root| Self::time_until_due(root, database, now))
+            .min()
+    }
+
+    /// Re-index every due root, highest priority first
+    ///
+    /// Returns the paths of the roots that were refreshed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a due root cannot be traversed
+    pub fn run_pending(&self, database: &mut Database) -> Result<Vec<PathBuf>> {
+        let due: Vec<IndexRoot> = self
+            .due_roots(database, SystemTime::now())
+            .into_iter()
+            .cloned()
+            .collect();
+
+        let mut refreshed = Vec::with_capacity(due.len());
+        for root in due {
+            database.index_root(&root.path, &self.root_config(&root))?;
+            refreshed.push(root.path);
+        }
+        Ok(refreshed)
+    }
+
+    /// Refresh roots as they become due, saving the database after each round
+    ///
+    /// Runs until `should_stop` returns `true`, which is checked after every round.
+    /// With `background_priority` configured, the calling thread runs at low CPU
+    /// and IO priority where the platform allows it, and gets its priority back
+    /// when this returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a root cannot be traversed or the database cannot be saved
+    pub fn run<F: FnMut() -> bool>(
+        &self,
+        database: &mut Database,
+        database_path: &Path,
+        mut should_stop: F,
+    ) -> Result<()> {
+        let _background = self
+            .config
+            .background_priority
+            .then(priority::enter_background);
+        loop {
+            if !self.run_pending(database)?.is_empty() {
+                database.save(database_path)?;
+            }
+            if should_stop() {
+                return Ok(());
+            }
+            let wait = self
+                .next_due_in(database, SystemTime::now())
+                .unwrap_or(Duration::from_secs(60));
+            std::thread::sleep(wait.max(Duration::from_secs(1)));
+        }
+    }
+
+    fn root_config(&self, root: &IndexRoot) -> Config {
+        let mut config = self.config.clone();
+        config
+            .ignore_patterns
+            .extend(root.ignore_patterns.iter().cloned());
+        config
+    }
+
+    fn time_until_due(root: &IndexRoot, database: &Database, now: SystemTime) -> Duration {
+        let Some(indexed_at) = root
+            .path
+            .canonicalize()
+            .ok()
+            .and_then(|path| database.roots().iter().find(|r| r.path == path))
+            .map(|r| UNIX_EPOCH + Duration::from_secs(r.indexed_at()))
+        else {
+            return Duration::ZERO;
+        };
+
+        let age = now.duration_since(indexed_at).unwrap_or(Duration::ZERO);
+        root.refresh_interval.saturating_sub(age)
+    }
+}
@@ -0,0 +1,173 @@
+//! Independently built, refreshed and searched slices of a database root
+//!
+//! Every top-level directory of an indexed root becomes its own shard, and the
+//! files directly inside the root form one more shard with an empty prefix.
+
+use crate::config::Config;
+use crate::error::FileSearchError;
+use crate::indexer::bloom::NgramBloom;
+use crate::indexer::{backend, file_walker::FileWalker, FileIndex, FileIndexer};
+use crate::search::fold::Folding;
+use crate::Result;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+/// Files recorded for one top-level directory of an indexed root
+#[derive(Debug, Clone)]
+pub struct Shard {
+    /// Top-level directory this shard covers, relative to the root
+    ///
+    /// Empty for the shard holding the files directly inside the root.
+    pub prefix: PathBuf,
+    /// Seconds since the Unix epoch at which the shard was indexed
+    pub indexed_at: u64,
+    /// File paths relative to the shard directory
+    pub entries: Vec<PathBuf>,
+    /// Filename trigram summary used to skip the shard for queries it cannot match
+    pub bloom: NgramBloom,
+}
+
+impl Shard {
+    /// Walk the files belonging to the shard `prefix` of `root`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shard directory cannot be traversed
+    pub fn build(root: &Path, prefix: &Path, config: &Config) -> Result<Self> {
+        let mut shard_config = config.clone();
+        let dir = if prefix.as_os_str().is_empty() {
+            shard_config.max_depth = Some(1);
+            root.to_path_buf()
+        } else {
+            shard_config.max_depth = config.max_depth.map(|depth| depth.saturating_sub(1));
+            root.join(prefix)
+        };
+
+        let mut entries = Vec::new();
+        for entry_result in FileWalker::new(&shard_config).walk(&dir.to_string_lossy())? {
+            let entry = entry_result.map_err(|e| FileSearchError::walkdir_error(e, &dir))?;
+            if super::is_indexed(&entry, usize::from(!prefix.as_os_str().is_empty()), config) {
+                if let Ok(relative) = entry.path().strip_prefix(&dir) {
+                    entries.push(relative.to_path_buf());
+                }
+            }
+        }
+        entries.sort();
+        let names: Vec<_> = entries
+            .iter()
+            .filter_map(|entry| entry.file_name().map(|n| n.to_string_lossy()))
+            .collect();
+        let bloom = NgramBloom::from_names(names.iter().map(AsRef::as_ref));
+
+        Ok(Self {
+            prefix: prefix.to_path_buf(),
+            indexed_at: super::database::unix_now(),
+            entries,
+            bloom,
+        })
+    }
+
+    /// Prefixes of all shards of `root`: one per top-level directory plus the root itself
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the root cannot be listed
+    pub fn prefixes(root: &Path, config: &Config) -> Result<Vec<PathBuf>> {
+        let mut top_level = config.clone();
+        top_level.max_depth = Some(1);
+
+        let mut prefixes = vec![PathBuf::new()];
+        for entry_result in FileWalker::new(&top_level).walk(&root.to_string_lossy())? {
+            let entry = entry_result.map_err(|e| FileSearchError::walkdir_error(e, root))?;
+            if entry.depth() == 1 && entry.file_type().is_dir() {
+                prefixes.push(PathBuf::from(entry.file_name()));
+            }
+        }
+        Ok(prefixes)
+    }
+
+    /// Number of files recorded in this shard
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether this shard records no files
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether this shard can hold files beneath `subpath` (relative to the root)
+    #[must_use]
+    pub fn may_contain(&self, subpath: &Path) -> bool {
+        if subpath.as_os_str().is_empty() {
+            return true;
+        }
+        !self.prefix.as_os_str().is_empty() && subpath.starts_with(&self.prefix)
+    }
+
+    /// Build a searchable index of this shard's files beneath `subpath`
+    ///
+    /// Paths are rebased onto `search_root`, which is where `subpath` lives on disk.
+    #[must_use]
+    pub fn index(&self, search_root: &Path, subpath: &Path, config: &Config) -> FileIndex {
+        let indexer = FileIndexer::new(config.clone());
+
+        let mut entries = Vec::new();
+        for entry in &self.entries {
+            let full = self.prefix.join(entry);
+            let Ok(relative) = full.strip_prefix(subpath) else {
+                continue;
+            };
+            if let Some(max_depth) = config.max_depth {
+                if relative.components().count() > max_depth {
+                    continue;
+                }
+            }
+            if relative
+                .iter()
+                .any(|component| indexer.should_ignore(Path::new(component)))
+            {
+                continue;
+            }
+            entries.extend(backend::path_entry(
+                search_root.join(relative),
+                Folding::new(config),
+            ));
+        }
+        entries.into_iter().collect()
+    }
+}
+
+/// Apply `f` to every item, spreading the work over the available CPUs
+pub(crate) fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let workers = std::thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(items.len())
+        .max(1);
+    let chunk_size = (items.len() + workers - 1) / workers;
+    if workers == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(|| chunk.iter().map(&f).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,117 @@
+//! NTFS alternate data streams
+//!
+//! Besides its main contents, a file on NTFS can carry named data streams,
+//! such as the `Zone.Identifier` stream Windows attaches to downloads. They
+//! are listed with `FindFirstStreamW`; other platforms have no such streams.
+
+use std::path::Path;
+
+/// A named data stream attached to a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlternateStream {
+    /// Stream name without the leading colon and `:$DATA` suffix
+    pub name: String,
+    /// Size of the stream in bytes
+    pub size: u64,
+}
+
+/// Alternate data streams of the file at `path`, excluding its main stream
+///
+/// Returns an empty list on platforms without alternate data streams or if
+/// the streams cannot be listed.
+#[must_use]
+pub fn alternate_streams(path: &Path) -> Vec<AlternateStream> {
+    imp::alternate_streams(path)
+}
+
+/// Stream name as reported by Windows, e.g. `:Zone.Identifier:$DATA`, reduced
+/// to its bare name, or `None` for the unnamed main stream
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(crate) fn stream_name(raw: &str) -> Option<&str> {
+    let name = raw.strip_prefix(':')?;
+    let name = name.strip_suffix(":$DATA").unwrap_or(name);
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::{stream_name, AlternateStream};
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+
+    const FIND_STREAM_INFO_STANDARD: i32 = 0;
+    const MAX_PATH: usize = 260;
+
+    #[repr(C)]
+    struct Win32FindStreamData {
+        stream_size: i64,
+        stream_name: [u16; MAX_PATH + 36],
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn FindFirstStreamW(
+            file_name: *const u16,
+            info_level: i32,
+            find_stream_data: *mut c_void,
+            flags: u32,
+        ) -> *mut c_void;
+        fn FindNextStreamW(find_stream: *mut c_void, find_stream_data: *mut c_void) -> i32;
+        fn FindClose(find_file: *mut c_void) -> i32;
+    }
+
+    fn to_stream(data: &Win32FindStreamData) -> Option<AlternateStream> {
+        let len = data
+            .stream_name
+            .iter()
+            .position(|&unit| unit == 0)
+            .unwrap_or(data.stream_name.len());
+        let raw = String::from_utf16_lossy(&data.stream_name[..len]);
+        Some(AlternateStream {
+            name: stream_name(&raw)?.to_string(),
+            size: u64::try_from(data.stream_size).unwrap_or(0),
+        })
+    }
+
+    pub fn alternate_streams(path: &Path) -> Vec<AlternateStream> {
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut data = Win32FindStreamData {
+            stream_size: 0,
+            stream_name: [0; MAX_PATH + 36],
+        };
+        let data_ptr: *mut c_void = std::ptr::addr_of_mut!(data).cast();
+
+        // SAFETY: `wide` is NUL-terminated and `data` matches the layout of
+        // WIN32_FIND_STREAM_DATA for the standard info level
+        let handle =
+            unsafe { FindFirstStreamW(wide.as_ptr(), FIND_STREAM_INFO_STANDARD, data_ptr, 0) };
+        // INVALID_HANDLE_VALUE
+        if handle as isize == -1 {
+            return Vec::new();
+        }
+
+        let mut streams = Vec::new();
+        loop {
+            streams.extend(to_stream(&data));
+            // SAFETY: `handle` is a valid find handle and `data_ptr` points to
+            // the same buffer as above
+            if unsafe { FindNextStreamW(handle, data_ptr) } == 0 {
+                break;
+            }
+        }
+        // SAFETY: `handle` is a valid find handle that is not used afterwards
+        unsafe { FindClose(handle) };
+        streams
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use super::AlternateStream;
+    use std::path::Path;
+
+    pub fn alternate_streams(_path: &Path) -> Vec<AlternateStream> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,418 @@
+//! Files in the desktop trash
+//!
+//! Searching the trash answers "I deleted it but need it back". Trashed files
+//! do not always keep their names: the Windows Recycle Bin stores each one as
+//! `$R` plus a random suffix next to a `$I` file recording its original path,
+//! and the freedesktop.org trash may append a number to avoid clashes while
+//! recording the original path in a `.trashinfo` file. [`trashed_files`] lists
+//! every file in the current user's trash together with the path it was
+//! deleted from, so searches can match the original name. The macOS Trash
+//! keeps names but not origins, which Finder stores in its own format.
+//!
+//! Trashed directories are listed file by file. The hidden file and ignore
+//! pattern rules of the configuration apply to trash contents as well.
+
+use super::file_walker::FileWalker;
+use crate::config::Config;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// A file in the trash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashedFile {
+    /// Where the file is now, inside the trash
+    pub path: PathBuf,
+    /// Where the file was before it was trashed, if the trash records it
+    pub original_path: Option<PathBuf>,
+}
+
+impl TrashedFile {
+    /// Name the file had before it was trashed, or its current name if that
+    /// is not recorded
+    #[must_use]
+    pub fn name(&self) -> Option<&OsStr> {
+        self.original_path
+            .as_deref()
+            .and_then(Path::file_name)
+            .or_else(|| self.path.file_name())
+    }
+}
+
+/// Trash directories of the current user on this platform
+///
+/// The freedesktop.org home trash, the macOS `~/.Trash`, or the Recycle Bin
+/// folder of the current user on every drive. Directories that do not exist
+/// are left out.
+#[must_use]
+pub fn trash_locations() -> Vec<PathBuf> {
+    imp::locations()
+}
+
+/// Every file in the current user's trash
+#[must_use]
+pub fn trashed_files(config: &Config) -> Vec<TrashedFile> {
+    trash_locations()
+        .iter()
+        .flat_map(|location| imp::list(location, config))
+        .collect()
+}
+
+/// Where `path`, a file in the current user's trash, was deleted from
+///
+/// Returns `None` for files outside the trash and where the trash does not
+/// record origins.
+#[must_use]
+pub fn original_path(path: &Path) -> Option<PathBuf> {
+    trash_locations()
+        .iter()
+        .find_map(|location| imp::original_path(location, path))
+}
+
+/// Files in a freedesktop.org trash directory, such as `~/.local/share/Trash`
+///
+/// Original paths come from the `.trashinfo` files; relative ones are taken
+/// from the parent of `trash`, as for the `.Trash-$uid` directory at the top
+/// of a mounted volume.
+#[must_use]
+pub fn freedesktop_trash(trash: &Path, config: &Config) -> Vec<TrashedFile> {
+    let walker = FileWalker::new(config);
+    let mut trashed = Vec::new();
+    for item in top_level(&trash.join("files"), &walker) {
+        let original = freedesktop_original(trash, &item);
+        list_item(&item, original, &walker, &mut trashed);
+    }
+    trashed
+}
+
+/// Where `path`, a file in the freedesktop.org trash directory `trash`, was
+/// deleted from
+#[must_use]
+pub fn freedesktop_original_path(trash: &Path, path: &Path) -> Option<PathBuf> {
+    let files = trash.join("files");
+    let (item, rest) = split_item(&files, path)?;
+    Some(original_with_rest(
+        freedesktop_original(trash, &files.join(item))?,
+        rest,
+    ))
+}
+
+/// Files in one user's Recycle Bin folder, such as `C:\$Recycle.Bin\<SID>`
+///
+/// Only items with a readable `$I` file are listed, named after the path it
+/// records.
+#[must_use]
+pub fn recycle_bin(dir: &Path, config: &Config) -> Vec<TrashedFile> {
+    let walker = FileWalker::new(config);
+    let mut trashed = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return trashed;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(suffix) = name.to_str().and_then(|name| name.strip_prefix("$I")) else {
+            continue;
+        };
+        let Some(original) = fs::read(entry.path())
+            .ok()
+            .and_then(|info| parse_recycle_bin_info(&info))
+        else {
+            continue;
+        };
+        let item = dir.join(format!("$R{suffix}"));
+        let excluded = original
+            .file_name()
+            .is_some_and(|name| walker.excludes_name(Path::new(name)));
+        if !excluded {
+            list_item(&item, Some(original), &walker, &mut trashed);
+        }
+    }
+    trashed
+}
+
+/// Where `path`, a file in a Recycle Bin folder, was deleted from
+#[must_use]
+pub fn recycle_bin_original_path(path: &Path) -> Option<PathBuf> {
+    let item = path.ancestors().find(|ancestor| {
+        ancestor
+            .file_name()
+            .and_then(OsStr::to_str)
+            .is_some_and(|name| name.starts_with("$R"))
+    })?;
+    let suffix = &item.file_name()?.to_str()?[2..];
+    let info = fs::read(item.with_file_name(format!("$I{suffix}"))).ok()?;
+    let rest = path.strip_prefix(item).ok()?;
+    Some(original_with_rest(parse_recycle_bin_info(&info)?, rest))
+}
+
+/// Original path recorded in the contents of a Recycle Bin `$I` file
+///
+/// Version 1 files (Windows Vista to 8.1) hold a fixed 260 character path,
+/// version 2 files (Windows 10 and later) a length-prefixed one. Both start
+/// with the version, the file size and the deletion time as 64-bit numbers.
+fn parse_recycle_bin_info(info: &[u8]) -> Option<PathBuf> {
+    let version = u64::from_le_bytes(info.get(..8)?.try_into().ok()?);
+    let name = match version {
+        1 => info.get(24..24 + 520)?,
+        2 => {
+            let len = u32::from_le_bytes(info.get(24..28)?.try_into().ok()?);
+            info.get(28..28 + usize::try_from(len).ok()?.checked_mul(2)?)?
+        }
+        _ => return None,
+    };
+    let units: Vec<u16> = name
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    if units.is_empty() {
+        return None;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStringExt;
+        Some(PathBuf::from(std::ffi::OsString::from_wide(&units)))
+    }
+    #[cfg(not(windows))]
+    {
+        Some(PathBuf::from(String::from_utf16_lossy(&units)))
+    }
+}
+
+/// Original path of `item`, an entry directly inside the `files` directory of
+/// the freedesktop.org trash `trash`
+fn freedesktop_original(trash: &Path, item: &Path) -> Option<PathBuf> {
+    let mut info_name = item.file_name()?.to_os_string();
+    info_name.push(".trashinfo");
+    let info = fs::read_to_string(trash.join("info").join(info_name)).ok()?;
+    let mut in_group = false;
+    for line in info.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_group = line == "[Trash Info]";
+        } else if let Some(encoded) = line.strip_prefix("Path=").filter(|_| in_group) {
+            let path = bytes_to_path(percent_decode(encoded));
+            return Some(if path.is_absolute() {
+                path
+            } else {
+                trash.parent().unwrap_or(Path::new("")).join(path)
+            });
+        }
+    }
+    None
+}
+
+fn percent_decode(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+#[cfg(unix)]
+fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Entries directly inside `dir` that the walker's name rules let through
+fn top_level(dir: &Path, walker: &FileWalker) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| !walker.excludes_name(path))
+        .collect()
+}
+
+/// Record the trashed `item`, or every file beneath it if it is a directory
+fn list_item(
+    item: &Path,
+    original: Option<PathBuf>,
+    walker: &FileWalker,
+    trashed: &mut Vec<TrashedFile>,
+) {
+    let Ok(metadata) = fs::symlink_metadata(item) else {
+        return;
+    };
+    if !metadata.is_dir() {
+        trashed.push(TrashedFile {
+            path: item.to_path_buf(),
+            original_path: original,
+        });
+        return;
+    }
+    let files = WalkDir::new(item)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| !walker.excludes_name(entry.path()))
+        .flatten()
+        .filter(|entry| !entry.file_type().is_dir());
+    for entry in files {
+        let rest = entry.path().strip_prefix(item).unwrap_or(entry.path());
+        trashed.push(TrashedFile {
+            original_path: original
+                .as_ref()
+                .map(|original| original_with_rest(original.clone(), rest)),
+            path: entry.into_path(),
+        });
+    }
+}
+
+/// The entry of `dir` that holds `path`, and the rest of `path` below it
+fn split_item<'a>(dir: &Path, path: &'a Path) -> Option<(&'a OsStr, &'a Path)> {
+    let relative = path.strip_prefix(dir).ok()?;
+    let mut components = relative.components();
+    match components.next()? {
+        Component::Normal(item) => Some((item, components.as_path())),
+        _ => None,
+    }
+}
+
+fn original_with_rest(original: PathBuf, rest: &Path) -> PathBuf {
+    if rest.as_os_str().is_empty() {
+        original
+    } else {
+        original.join(rest)
+    }
+}
+
+/// The freedesktop.org home trash: `$XDG_DATA_HOME/Trash`, or
+/// `~/.local/share/Trash` if that is unset or relative
+#[cfg(all(unix, not(target_os = "macos")))]
+pub(crate) fn home_trash() -> Option<PathBuf> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            Some(
+                crate::config::tilde::home_dir()?
+                    .join(".local")
+                    .join("share"),
+            )
+        })?;
+    Some(data_dir.join("Trash"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use super::{freedesktop_original_path, freedesktop_trash, home_trash, TrashedFile};
+    use crate::config::Config;
+    use std::path::{Path, PathBuf};
+
+    pub fn locations() -> Vec<PathBuf> {
+        home_trash()
+            .into_iter()
+            .filter(|trash| trash.is_dir())
+            .collect()
+    }
+
+    pub fn list(location: &Path, config: &Config) -> Vec<TrashedFile> {
+        freedesktop_trash(location, config)
+    }
+
+    pub fn original_path(location: &Path, path: &Path) -> Option<PathBuf> {
+        freedesktop_original_path(location, path)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::{list_item, top_level, FileWalker, TrashedFile};
+    use crate::config::tilde::home_dir;
+    use crate::config::Config;
+    use std::path::{Path, PathBuf};
+
+    pub fn locations() -> Vec<PathBuf> {
+        home_dir()
+            .map(|home| home.join(".Trash"))
+            .into_iter()
+            .filter(|trash| trash.is_dir())
+            .collect()
+    }
+
+    pub fn list(location: &Path, config: &Config) -> Vec<TrashedFile> {
+        let walker = FileWalker::new(config);
+        let mut trashed = Vec::new();
+        for item in top_level(location, &walker) {
+            list_item(&item, None, &walker, &mut trashed);
+        }
+        trashed
+    }
+
+    pub fn original_path(_location: &Path, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::{recycle_bin, recycle_bin_original_path, TrashedFile};
+    use crate::config::Config;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// The folders of every drive's Recycle Bin that the current user can
+    /// read, which are their own
+    pub fn locations() -> Vec<PathBuf> {
+        (b'A'..=b'Z')
+            .map(|drive| PathBuf::from(format!("{}:\\$Recycle.Bin", char::from(drive))))
+            .filter_map(|bin| fs::read_dir(bin).ok())
+            .flat_map(|entries| entries.flatten())
+            .map(|entry| entry.path())
+            .filter(|dir| fs::read_dir(dir).is_ok())
+            .collect()
+    }
+
+    pub fn list(location: &Path, config: &Config) -> Vec<TrashedFile> {
+        recycle_bin(location, config)
+    }
+
+    pub fn original_path(location: &Path, path: &Path) -> Option<PathBuf> {
+        path.starts_with(location)
+            .then(|| recycle_bin_original_path(path))
+            .flatten()
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use super::TrashedFile;
+    use crate::config::Config;
+    use std::path::{Path, PathBuf};
+
+    pub fn locations() -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    pub fn list(_location: &Path, _config: &Config) -> Vec<TrashedFile> {
+        Vec::new()
+    }
+
+    pub fn original_path(_location: &Path, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+}
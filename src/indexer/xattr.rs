@@ -0,0 +1,219 @@
+//! Extended attributes and macOS Finder tags
+//!
+//! Linux, Android and macOS expose extended attributes; elsewhere files never
+//! carry any. Finder tags are stored by macOS as a binary property list in the
+//! `com.apple.metadata:_kMDItemUserTags` attribute, one `name\ncolor` string
+//! per tag. Symbolic links are inspected themselves rather than followed.
+
+use std::path::Path;
+
+/// Extended attribute holding the Finder tags of a file
+pub const FINDER_TAGS_XATTR: &str = "com.apple.metadata:_kMDItemUserTags";
+
+/// Whether the file at `path` carries the extended attribute `name`
+#[must_use]
+pub fn has_xattr(path: &Path, name: &str) -> bool {
+    imp::xattr_len(path, name).is_some()
+}
+
+/// Value of the extended attribute `name` of the file at `path`
+#[must_use]
+pub fn read_xattr(path: &Path, name: &str) -> Option<Vec<u8>> {
+    imp::read_xattr(path, name)
+}
+
+/// Names of the Finder tags on the file at `path`
+#[must_use]
+pub fn finder_tags(path: &Path) -> Vec<String> {
+    read_xattr(path, FINDER_TAGS_XATTR)
+        .map(|plist| parse_tag_plist(&plist))
+        .unwrap_or_default()
+}
+
+/// Tag names from a binary property list holding an array of strings
+///
+/// Each string is a tag name optionally followed by a newline and a color
+/// number, which is dropped. Malformed input yields no tags.
+pub(crate) fn parse_tag_plist(data: &[u8]) -> Vec<String> {
+    let Some(plist) = BinaryPlist::parse(data) else {
+        return Vec::new();
+    };
+    let Some(refs) = plist.array(plist.top_object) else {
+        return Vec::new();
+    };
+    refs.into_iter()
+        .filter_map(|object| plist.string(object))
+        .map(|tag| match tag.split_once('\n') {
+            Some((name, _color)) => name.to_string(),
+            None => tag,
+        })
+        .collect()
+}
+
+/// The parts of a `bplist00` file needed to read an array of strings
+struct BinaryPlist<'a> {
+    data: &'a [u8],
+    offsets: Vec<usize>,
+    object_ref_size: usize,
+    top_object: usize,
+}
+
+impl<'a> BinaryPlist<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        if !data.starts_with(b"bplist00") || data.len() < 40 {
+            return None;
+        }
+        let trailer = &data[data.len() - 32..];
+        let offset_int_size = usize::from(trailer[6]);
+        let object_ref_size = usize::from(trailer[7]);
+        let num_objects = read_uint(&trailer[8..16])?;
+        let top_object = read_uint(&trailer[16..24])?;
+        let table_offset = read_uint(&trailer[24..32])?;
+
+        let table_len = num_objects.checked_mul(offset_int_size)?;
+        let table = data.get(table_offset..table_offset.checked_add(table_len)?)?;
+        let offsets = table
+            .chunks(offset_int_size.max(1))
+            .map(read_uint)
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            data,
+            offsets,
+            object_ref_size,
+            top_object,
+        })
+    }
+
+    /// Marker type nibble, length and start of the payload of `object`
+    fn header(&self, object: usize) -> Option<(u8, usize, usize)> {
+        let start = *self.offsets.get(object)?;
+        let marker = *self.data.get(start)?;
+        let (kind, short_len) = (marker >> 4, usize::from(marker & 0x0f));
+        if short_len != 0x0f {
+            return Some((kind, short_len, start + 1));
+        }
+        // Longer lengths follow as an integer object
+        let int_marker = *self.data.get(start + 1)?;
+        if int_marker >> 4 != 0x1 {
+            return None;
+        }
+        let int_size = 1usize << (int_marker & 0x0f);
+        let len = read_uint(self.data.get(start + 2..start + 2 + int_size)?)?;
+        Some((kind, len, start + 2 + int_size))
+    }
+
+    fn array(&self, object: usize) -> Option<Vec<usize>> {
+        let (kind, len, start) = self.header(object)?;
+        if kind != 0xa {
+            return None;
+        }
+        let refs = self
+            .data
+            .get(start..start.checked_add(len.checked_mul(self.object_ref_size)?)?)?;
+        refs.chunks(self.object_ref_size.max(1))
+            .map(read_uint)
+            .collect()
+    }
+
+    fn string(&self, object: usize) -> Option<String> {
+        let (kind, len, start) = self.header(object)?;
+        match kind {
+            0x5 => {
+                let bytes = self.data.get(start..start.checked_add(len)?)?;
+                Some(String::from_utf8_lossy(bytes).into_owned())
+            }
+            0x6 => {
+                let bytes = self
+                    .data
+                    .get(start..start.checked_add(len.checked_mul(2)?)?)?;
+                let units: Vec<u16> = bytes
+                    .chunks(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Big-endian unsigned integer of up to eight bytes
+fn read_uint(bytes: &[u8]) -> Option<usize> {
+    if bytes.is_empty() || bytes.len() > 8 {
+        return None;
+    }
+    let value = bytes
+        .iter()
+        .fold(0u64, |value, byte| (value << 8) | u64::from(*byte));
+    usize::try_from(value).ok()
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+mod imp {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    fn c_strings(path: &Path, name: &str) -> Option<(CString, CString)> {
+        Some((
+            CString::new(path.as_os_str().as_bytes()).ok()?,
+            CString::new(name).ok()?,
+        ))
+    }
+
+    /// Size of the attribute value, or its contents when `buf` is large enough
+    fn get(path: &CString, name: &CString, buf: &mut [u8]) -> Option<usize> {
+        let (ptr, len) = if buf.is_empty() {
+            (std::ptr::null_mut(), 0)
+        } else {
+            (buf.as_mut_ptr().cast(), buf.len())
+        };
+        // SAFETY: both strings are NUL-terminated and `ptr` is either null with
+        // a zero length or valid for `len` bytes
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let result = unsafe { libc::lgetxattr(path.as_ptr(), name.as_ptr(), ptr, len) };
+        // SAFETY: as above
+        #[cfg(target_os = "macos")]
+        let result = unsafe {
+            libc::getxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                ptr,
+                len,
+                0,
+                libc::XATTR_NOFOLLOW,
+            )
+        };
+        usize::try_from(result).ok()
+    }
+
+    pub fn xattr_len(path: &Path, name: &str) -> Option<usize> {
+        let (path, name) = c_strings(path, name)?;
+        get(&path, &name, &mut [])
+    }
+
+    pub fn read_xattr(path: &Path, name: &str) -> Option<Vec<u8>> {
+        let (path, name) = c_strings(path, name)?;
+        let mut buf = vec![0; get(&path, &name, &mut [])?];
+        if buf.is_empty() {
+            return Some(buf);
+        }
+        let len = get(&path, &name, &mut buf)?;
+        buf.truncate(len);
+        Some(buf)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+mod imp {
+    use std::path::Path;
+
+    pub fn xattr_len(_path: &Path, _name: &str) -> Option<usize> {
+        None
+    }
+
+    pub fn read_xattr(_path: &Path, _name: &str) -> Option<Vec<u8>> {
+        None
+    }
+}
@@ -10,6 +10,10 @@
 //! - **High Performance**: Efficient file indexing and searching
 //! - **Configurable**: Extensive configuration options for search behavior
 //! - **Async Support**: Optional async operations with the `async` feature
+//! - **Editor Integration**: A JSON-RPC protocol over stdio with the `rpc` feature, or Node.js bindings with the `node` feature
+//! - **GUI Event Stream**: Progress, warnings and matches as JSON lines with the `config` feature
+//! - **File Actions**: Trash, move, copy, rename or link results in bulk, or pack them into an archive with the `archive` feature
+//! - **Git Aware**: Optional gitignore handling, and git-tracked or changed-file search with the `git` feature
 //!
 //! ## Quick Start
 //!
@@ -35,6 +39,10 @@
 //! - **Regex**: Full regular expression support
 //! - **Fuzzy**: Typo-tolerant fuzzy matching
 //!
+//! Glob, regex and fuzzy matching are enabled by default through the `glob`,
+//! `regex` and `fuzzy` cargo features. Building with `default-features = false`
+//! leaves a minimal substring-only core without the `glob` and `regex` crates.
+//!
 //! ## Examples
 //!
 //! ### Basic Usage
@@ -116,16 +124,29 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+/// Bulk delete, move and copy operations on search results
+pub mod actions;
 /// Configuration management for file search operations
 pub mod config;
 /// Error types and handling
 pub mod error;
+/// Newline-delimited JSON events for graphical frontends
+#[cfg(feature = "config")]
+pub mod events;
 /// File system indexing functionality
 pub mod indexer;
+/// Node.js bindings for Electron apps and editor extensions
+#[cfg(feature = "node")]
+pub mod node;
+/// JSON-RPC protocol for editor integration
+#[cfg(feature = "rpc")]
+pub mod rpc;
 /// Search engine implementation with various modes
 pub mod search;
 
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Result type used throughout the library
 pub type Result<T> = std::result::Result<T, crate::error::FileSearchError>;
@@ -154,6 +175,13 @@ pub type Result<T> = std::result::Result<T, crate::error::FileSearchError>;
 #[derive(Debug, Clone)]
 pub struct FileSearcherBuilder {
     config: crate::config::Config,
+    decorators: crate::search::decorate::Decorators,
+    filters: crate::indexer::filter::Filters,
+    matcher: crate::search::custom::CustomMatcher,
+    #[cfg(feature = "fuzzy")]
+    scorer: crate::search::scorer::CustomScorer,
+    progress: crate::search::progress::Reporter,
+    prewarm: Option<PathBuf>,
 }
 
 impl Default for FileSearcherBuilder {
@@ -167,6 +195,13 @@ impl FileSearcherBuilder {
     pub fn new() -> Self {
         Self {
             config: crate::config::Config::default(),
+            decorators: crate::search::decorate::Decorators::default(),
+            filters: crate::indexer::filter::Filters::default(),
+            matcher: crate::search::custom::CustomMatcher::default(),
+            #[cfg(feature = "fuzzy")]
+            scorer: crate::search::scorer::CustomScorer::default(),
+            progress: crate::search::progress::Reporter::default(),
+            prewarm: None,
         }
     }
 
@@ -197,6 +232,19 @@ impl FileSearcherBuilder {
         self
     }
 
+    /// Set the minimum depth of reported entries
+    ///
+    /// Combined with [`max_depth`](FileSearcherBuilder::max_depth), a depth
+    /// of 2 for both finds only entries exactly two levels below the root.
+    ///
+    /// # Arguments
+    /// * `depth` - Minimum depth, 1 being the entries directly in the root
+    #[must_use]
+    pub fn min_depth(mut self, depth: usize) -> Self {
+        self.config.min_depth = Some(depth);
+        self
+    }
+
     /// Set whether to ignore hidden files and directories
     ///
     /// # Arguments
@@ -215,6 +263,18 @@ impl FileSearcherBuilder {
         self
     }
 
+    /// Set whether names and queries are compared in Unicode NFC form
+    ///
+    /// Decomposed names, as macOS stores them, then match queries typed with
+    /// precomposed letters, and case-insensitive searches use full case
+    /// folding, so `STRASSE` finds `straße.txt`. Building fails unless the
+    /// `unicode` feature is enabled.
+    #[must_use]
+    pub fn unicode_normalization(mut self, enabled: bool) -> Self {
+        self.config.unicode_normalization = enabled;
+        self
+    }
+
     /// Add a pattern to ignore during search
     ///
     /// # Arguments
@@ -274,575 +334,5625 @@ impl FileSearcherBuilder {
         self
     }
 
-    /// Set the configuration directly
+    /// Set whether walks stay on the file system of the search root
     ///
-    /// This overwrites any previously configured settings.
-    pub fn config(mut self, config: crate::config::Config) -> Self {
-        self.config = config;
+    /// Mount points of network shares, external drives and virtual file
+    /// systems below the root are skipped, which keeps a search from `/`
+    /// from wandering into slow or huge mounts.
+    #[must_use]
+    pub fn same_file_system(mut self, enabled: bool) -> Self {
+        self.config.same_file_system = enabled;
         self
     }
 
-    /// Validate the configuration and build the `FileSearcher`
+    /// Set which kinds of entries are indexed and found
     ///
-    /// # Errors
-    /// Returns an error if the configuration is invalid
-    pub fn build(self) -> Result<FileSearcher> {
-        // Validate configuration
-        if let Some(depth) = self.config.max_depth {
-            if depth == 0 {
-                return Err(crate::error::FileSearchError::invalid_config(
-                    "max_depth cannot be 0. Use unlimited_depth() for no limit or set a positive value."
-                ));
-            }
-        }
+    /// [`FileType::Dirs`](crate::config::FileType::Dirs) finds directories
+    /// such as `node_modules` by their own names.
+    #[must_use]
+    pub fn file_type(mut self, file_type: crate::config::FileType) -> Self {
+        self.config.file_type = file_type;
+        self
+    }
 
-        if let Some(size) = self.config.max_file_size {
-            if size == 0 {
-                return Err(crate::error::FileSearchError::invalid_config(
-                    "max_file_size cannot be 0. Use unlimited_file_size() for no limit or set a positive value."
-                ));
-            }
-        }
+    /// Set the time allowed for a single directory or metadata read
+    ///
+    /// Entries that do not respond in time, such as directories on a hung
+    /// network mount, are skipped instead of blocking the search. Reads run
+    /// on a few worker threads per walk, and once all of them are stuck on
+    /// hung reads further entries are skipped at once until one returns.
+    ///
+    /// # Arguments
+    /// * `timeout` - Maximum time to wait for each IO operation
+    #[must_use]
+    pub fn io_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.config.io_timeout = Some(timeout);
+        self
+    }
 
-        // Validate ignore patterns
-        for pattern in &self.config.ignore_patterns {
-            if pattern.is_empty() {
-                return Err(crate::error::FileSearchError::invalid_config(
-                    "ignore patterns cannot be empty",
-                ));
-            }
-        }
+    /// Set how many times a transiently failing read is retried before it is
+    /// skipped
+    #[must_use]
+    pub fn io_retries(mut self, retries: u32) -> Self {
+        self.config.io_retries = retries;
+        self
+    }
 
-        Ok(FileSearcher {
-            config: self.config,
-        })
+    /// Set the delay before the first retry, doubled after each further attempt
+    #[must_use]
+    pub fn io_retry_backoff(mut self, backoff: std::time::Duration) -> Self {
+        self.config.io_retry_backoff = backoff;
+        self
     }
 
-    /// Build the `FileSearcher` without validation
+    /// Limit how fast the file system is walked
     ///
-    /// This method skips configuration validation and should only be used
-    /// when you're certain the configuration is valid.
-    pub fn build_unchecked(self) -> FileSearcher {
-        FileSearcher {
-            config: self.config,
-        }
+    /// Useful for background indexing of slow disks or network storage.
+    #[must_use]
+    pub fn io_throttle(mut self, throttle: crate::config::IoThrottle) -> Self {
+        self.config.io_throttle = Some(throttle);
+        self
     }
-}
 
-/// Main entry point for the file search library
-///
-/// This struct provides a high-level interface for searching files with various patterns.
-/// It handles indexing, pattern detection, and search execution.
-#[derive(Debug)]
-pub struct FileSearcher {
-    config: crate::config::Config,
-}
+    /// Set whether background indexing runs at low CPU and IO priority
+    ///
+    /// Applies to [`IndexScheduler::run`](crate::indexer::scheduler::IndexScheduler::run),
+    /// which lowers the priority of the thread it runs on until it returns.
+    #[must_use]
+    pub fn background_priority(mut self, enabled: bool) -> Self {
+        self.config.background_priority = enabled;
+        self
+    }
 
-impl Default for FileSearcher {
-    fn default() -> Self {
-        Self::new()
+    /// Set the lowest score a fuzzy match needs to be reported
+    ///
+    /// Higher thresholds return fewer, closer matches and let the scorer give
+    /// up on poor candidates sooner.
+    ///
+    /// # Arguments
+    /// * `min_score` - Threshold between 0.0 and 1.0 (default: 0.3)
+    #[must_use]
+    pub fn fuzzy_min_score(mut self, min_score: f64) -> Self {
+        self.config.fuzzy_min_score = min_score;
+        self
     }
-}
 
-impl FileSearcher {
-    /// Creates a new `FileSearcher` with default configuration
+    /// Set the weights of the measures combined into fuzzy scores
     ///
-    /// # Examples
+    /// See [`FuzzyScoringConfig`](crate::config::FuzzyScoringConfig) for how
+    /// names are scored.
+    #[must_use]
+    pub fn fuzzy_scoring(mut self, scoring: crate::config::FuzzyScoringConfig) -> Self {
+        self.config.fuzzy_scoring = scoring;
+        self
+    }
+
+    /// Follow symlinks, through chains of at most `depth` links
     ///
-    /// ```rust
-    /// use whatever_find::FileSearcher;
+    /// Symlinks that lead back into a directory being walked are skipped.
+    /// A depth of 0 leaves symlinks unfollowed, as by default.
+    #[must_use]
+    pub fn max_symlink_depth(mut self, depth: usize) -> Self {
+        self.config.max_symlink_depth = Some(depth);
+        self
+    }
+
+    /// Stop a walk once it reaches more than `max` directories
     ///
-    /// let searcher = FileSearcher::new();
-    /// ```
-    pub fn new() -> Self {
-        Self {
-            config: crate::config::Config::default(),
-        }
+    /// Searches stopped by a limit fail with
+    /// [`FileSearchError::LimitExceeded`], which carries the matches found
+    /// before the limit.
+    #[must_use]
+    pub fn max_dirs(mut self, max: usize) -> Self {
+        self.config.max_dirs = Some(max);
+        self
     }
 
-    /// Creates a new `FileSearcherBuilder` for fluent configuration
+    /// Stop a walk once it reaches more than `max` files and directories
     ///
-    /// # Examples
+    /// Searches stopped by a limit fail with
+    /// [`FileSearchError::LimitExceeded`], which carries the matches found
+    /// before the limit.
+    #[must_use]
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.config.max_entries = Some(max);
+        self
+    }
+
+    /// Stop a search once it has run for longer than `timeout`
     ///
-    /// ```rust
-    /// use whatever_find::FileSearcher;
+    /// The clock starts when the search does. Searches stopped by the timeout
+    /// fail with [`FileSearchError::LimitExceeded`], which carries the matches
+    /// found before the deadline, instead of running on over slow network
+    /// mounts. A directory listing still pending at the deadline is abandoned,
+    /// and matching stops there too. Pair it with
+    /// [`io_timeout`](Self::io_timeout) to also skip single hanging reads
+    /// well before the deadline.
+    #[must_use]
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.config.search_timeout = Some(timeout);
+        self
+    }
+
+    /// Also skip virtual file systems of type `fs_type` where they are
+    /// mounted below the search root
+    #[must_use]
+    pub fn pseudo_filesystem<S: Into<String>>(mut self, fs_type: S) -> Self {
+        self.config.pseudo_filesystems.push(fs_type.into());
+        self
+    }
+
+    /// Walk into virtual file systems such as `/proc` and `/sys` like any
+    /// other directory
+    #[must_use]
+    pub fn clear_pseudo_filesystems(mut self) -> Self {
+        self.config.pseudo_filesystems.clear();
+        self
+    }
+
+    /// Set whether substring queries only match whole words
     ///
-    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let searcher = FileSearcher::builder()
-    ///     .max_depth(3)
-    ///     .ignore_hidden(false)
-    ///     .case_sensitive(true)
-    ///     .build()?;
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn builder() -> FileSearcherBuilder {
-        FileSearcherBuilder::new()
+    /// Words of a file name are delimited by anything but letters and digits,
+    /// so `log` matches `error.log` and `log_parser.rs` but not
+    /// `catalog.pdf`.
+    #[must_use]
+    pub fn word_boundaries(mut self, enabled: bool) -> Self {
+        self.config.word_boundaries = enabled;
+        self
     }
 
-    /// Creates a new `FileSearcher` with custom configuration
+    /// Set whether glob patterns match whole paths instead of file names
     ///
-    /// # Examples
+    /// `*` then stays within one path component and `**` spans any number
+    /// of them, so `src/**/*.rs` finds every `.rs` file under a `src`
+    /// directory. Patterns containing a path separator match whole paths
+    /// either way.
+    #[must_use]
+    pub fn match_full_path(mut self, enabled: bool) -> Self {
+        self.config.match_full_path = enabled;
+        self
+    }
+
+    /// Set whether substring, regex and fuzzy queries match the path relative
+    /// to the search root instead of the file name
     ///
-    /// ```rust
-    /// use whatever_find::{FileSearcher, Config};
+    /// `src/help` then finds `src/helper.rs`, and `^src/` every file under
+    /// a top-level `src` directory.
+    #[must_use]
+    pub fn match_relative_path(mut self, enabled: bool) -> Self {
+        self.config.match_relative_path = enabled;
+        self
+    }
+
+    /// Set which search modes automatic detection may choose
     ///
-    /// let config = Config {
-    ///     case_sensitive: true,
-    ///     max_depth: Some(3),
-    ///     ..Default::default()
-    /// };
-    /// let searcher = FileSearcher::with_config(config);
-    /// ```
-    pub fn with_config(config: crate::config::Config) -> Self {
-        Self { config }
+    /// See [`DetectionPolicy`](crate::search::detect::DetectionPolicy) for
+    /// preferring globs or never detecting regular expressions.
+    #[must_use]
+    pub fn detection_policy(mut self, policy: crate::search::detect::DetectionPolicy) -> Self {
+        self.config.detection = policy;
+        self
     }
 
-    /// Searches for files using automatic pattern detection
+    /// Set how much fuzzy ranking favours shallow paths
     ///
-    /// This method automatically detects whether the query is a glob pattern,
-    /// regular expression, or simple substring and uses the appropriate search mode.
+    /// Each match ranks as if its score were `penalty` lower per component
+    /// of its path, so a file two levels down beats an equally named one
+    /// deep inside a build directory. Reported scores are not changed.
     ///
     /// # Arguments
+    /// * `penalty` - Score deducted per path component (default: 0.01, 0.0
+    ///   to rank by score alone)
+    #[must_use]
+    pub fn depth_penalty(mut self, penalty: f64) -> Self {
+        self.config.depth_penalty = penalty;
+        self
+    }
+
+    /// Multiply the fuzzy ranking score of files ending in `.extension` by
+    /// `weight`
     ///
-    /// * `root_path` - The directory to search in
-    /// * `query` - The search pattern
+    /// Weights above 1.0 boost an extension and weights below demote it, so
+    /// `extension_weight("rs", 1.2)` and `extension_weight("lock", 0.5)` favour
+    /// sources over lock files. Compound extensions such as `min.js` take
+    /// precedence over `js`. Reported scores are not changed.
+    #[must_use]
+    pub fn extension_weight<S: Into<String>>(mut self, extension: S, weight: f64) -> Self {
+        self.config
+            .extension_weights
+            .insert(extension.into(), weight);
+        self
+    }
+
+    /// Set how much fuzzy ranking favours recently modified files
     ///
-    /// # Returns
+    /// Results rank by `(1 - weight) * score + weight * recency`, where
+    /// recency is 1.0 for a file modified just now and halves every
+    /// [`recency_half_life`](Self::recency_half_life). Launchers that open
+    /// what the user was just working on want a weight of around 0.3.
+    /// Reported scores are not changed.
     ///
-    /// A vector of matching file paths
+    /// # Arguments
+    /// * `weight` - Share of the ranking between 0.0 and 1.0 (default: 0.0)
+    #[must_use]
+    pub fn recency_weight(mut self, weight: f64) -> Self {
+        self.config.recency_weight = weight;
+        self
+    }
+
+    /// Set the age at which a file counts as half as recent as one modified
+    /// just now (default: 7 days)
+    #[must_use]
+    pub fn recency_half_life(mut self, half_life: std::time::Duration) -> Self {
+        self.config.recency_half_life = half_life;
+        self
+    }
+
+    /// Set whether substring, regex and glob results list shallower paths
+    /// first, each depth in path order (default: false, plain path order)
+    #[must_use]
+    pub fn shallow_first(mut self, enabled: bool) -> Self {
+        self.config.shallow_first = enabled;
+        self
+    }
+
+    /// Set the order results are returned in (default: relevance, best
+    /// match first for fuzzy queries and by path otherwise)
+    #[must_use]
+    pub fn sort(mut self, order: crate::search::SortOrder) -> Self {
+        self.config.sort = order;
+        self
+    }
+
+    /// Set whether fuzzy search narrows candidates with a bigram index
     ///
-    /// # Errors
+    /// Indexing records the bigrams of every filename, and fuzzy search then
+    /// only scores filenames sharing at least one bigram with the query. This
+    /// is much faster on large trees but can miss scattered subsequence matches.
+    #[must_use]
+    pub fn fuzzy_candidate_index(mut self, enabled: bool) -> Self {
+        self.config.fuzzy_candidate_index = enabled;
+        self
+    }
+
+    /// Match the filenames of large indexes against queries on `threads`
+    /// threads of a pool of the searcher's own, instead of rayon's global pool
     ///
-    /// Returns an error if the search fails or if the pattern is invalid
+    /// Passing 1 matches on the calling thread. Only has an effect with the
+    /// `parallel` feature, which otherwise always matches serially.
+    #[must_use]
+    pub fn match_threads(mut self, threads: usize) -> Self {
+        self.config.match_threads = Some(threads);
+        self
+    }
+
+    /// Reject regex patterns longer than `len` bytes
     ///
-    /// # Examples
+    /// Together with [`regex_size_limit`](Self::regex_size_limit), this keeps
+    /// hostile patterns from services that accept user-supplied queries.
+    #[must_use]
+    pub fn max_regex_len(mut self, len: usize) -> Self {
+        self.config.max_regex_len = Some(len);
+        self
+    }
+
+    /// Limit the compiled size of a regex to `bytes`
     ///
-    /// ```ignore
-    /// use whatever_find::FileSearcher;
-    /// use std::path::Path;
+    /// Patterns such as `\w{1000}{1000}` expand enormously when compiled;
+    /// they fail with an error instead of exhausting memory.
+    #[must_use]
+    pub fn regex_size_limit(mut self, bytes: usize) -> Self {
+        self.config.regex_size_limit = Some(bytes);
+        self
+    }
+
+    /// Limit the lazy DFA cache of a regex to `bytes`
     ///
-    /// let searcher = FileSearcher::new();
-    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let results = searcher.search_auto(Path::new("."), "*.rs")?;
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn search_auto(&self, root_path: &Path, query: &str) -> Result<Vec<PathBuf>> {
-        let mut indexer = crate::indexer::FileIndexer::new(self.config.clone());
-        let index = indexer.build_index(root_path.to_str().ok_or_else(|| {
-            crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
-        })?)?;
+    /// Matching stays correct with a small cache but may run slower.
+    #[must_use]
+    pub fn regex_dfa_size_limit(mut self, bytes: usize) -> Self {
+        self.config.regex_dfa_size_limit = Some(bytes);
+        self
+    }
 
-        let search_engine = crate::search::SearchEngine::new(self.config.clone());
-        search_engine.search_auto(&index, query)
+    /// Set whether files vanishing mid-search are skipped rather than reported
+    ///
+    /// Enabled by default. Skipped entries are counted in
+    /// [`WalkStats::vanished`](crate::indexer::file_walker::WalkStats::vanished).
+    #[must_use]
+    pub fn tolerate_races(mut self, tolerate: bool) -> Self {
+        self.config.tolerate_races = tolerate;
+        self
     }
 
-    /// Searches for files using automatic pattern detection, returning the detected mode
+    /// Set whether paths ignored by git are skipped
     ///
-    /// Similar to `search_auto`, but also returns information about which search mode
-    /// was automatically selected.
+    /// Honors `.gitignore` files, `.git/info/exclude` and the user's global
+    /// `core.excludesFile` for the repository containing the search root.
+    /// Outside a repository this has no effect.
+    #[must_use]
+    pub fn respect_gitignore(mut self, enabled: bool) -> Self {
+        self.config.respect_gitignore = enabled;
+        self
+    }
+
+    /// Set whether gitignore mode descends into git submodules
     ///
-    /// # Returns
+    /// Submodules are skipped by default when
+    /// [`respect_gitignore`](Self::respect_gitignore) is on; without it they
+    /// are always searched.
+    #[must_use]
+    pub fn git_submodules(mut self, enabled: bool) -> Self {
+        self.config.git_submodules = enabled;
+        self
+    }
+
+    /// Set whether gitignore mode descends into linked git worktrees
     ///
-    /// A tuple containing the matching file paths and the detected search mode
+    /// Like [`git_submodules`](Self::git_submodules), this only matters when
+    /// [`respect_gitignore`](Self::respect_gitignore) is on.
+    #[must_use]
+    pub fn git_worktrees(mut self, enabled: bool) -> Self {
+        self.config.git_worktrees = enabled;
+        self
+    }
+
+    /// Set whether only files in the git index are searched
     ///
-    /// # Examples
+    /// Build artifacts and other untracked files are never visited. Searching
+    /// outside a repository then fails, as does searching in a build without
+    /// the `git` feature.
+    #[must_use]
+    pub fn git_tracked(mut self, enabled: bool) -> Self {
+        self.config.git_tracked = enabled;
+        self
+    }
+
+    /// Only search files added or modified since a git revision
     ///
-    /// ```ignore
-    /// use whatever_find::{FileSearcher, SearchMode};
-    /// use std::path::Path;
+    /// `rev` can be a branch, tag, commit or expression such as `HEAD~3`.
+    /// Uncommitted and untracked files count as changed. Requires the `git`
+    /// feature.
+    #[must_use]
+    pub fn changed_since<S: Into<String>>(mut self, rev: S) -> Self {
+        self.config.changed_since = Some(rev.into());
+        self
+    }
+
+    /// Only report files flagged by at least one of `checks`
     ///
-    /// let searcher = FileSearcher::new();
-    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let (results, mode) = searcher.search_auto_with_mode(Path::new("."), "*.rs")?;
-    /// assert_eq!(mode, SearchMode::Glob);
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn search_auto_with_mode(
-        &self,
-        root_path: &Path,
-        query: &str,
-    ) -> Result<(Vec<PathBuf>, crate::search::SearchMode)> {
-        let mut indexer = crate::indexer::FileIndexer::new(self.config.clone());
-        let index = indexer.build_index(root_path.to_str().ok_or_else(|| {
-            crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
-        })?)?;
+    /// Directories are still walked in full; only the reported files are
+    /// narrowed. Checks rely on Unix permissions and flag nothing elsewhere.
+    #[must_use]
+    pub fn audit_checks<I>(mut self, checks: I) -> Self
+    where
+        I: IntoIterator<Item = crate::config::AuditCheck>,
+    {
+        self.config.audit_checks = checks.into_iter().collect();
+        self
+    }
 
-        let search_engine = crate::search::SearchEngine::new(self.config.clone());
-        search_engine.search_auto_with_mode(&index, query)
+    /// Only report files carrying the extended attribute `name`
+    ///
+    /// May be given several times; files must carry every attribute, e.g.
+    /// `com.apple.quarantine` for downloads on macOS. Extended attributes are
+    /// read on Linux, Android and macOS; elsewhere no file matches.
+    #[must_use]
+    pub fn require_xattr<S: Into<String>>(mut self, name: S) -> Self {
+        self.config.required_xattrs.push(name.into());
+        self
     }
 
-    /// Searches for files using a specific search mode
+    /// Only report files carrying the macOS Finder tag `tag`
     ///
-    /// This method allows you to force a specific search mode, bypassing automatic detection.
+    /// May be given several times; files must carry every tag. Tags are
+    /// matched by name, ignoring case.
+    #[must_use]
+    pub fn require_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.config.required_tags.push(tag.into());
+        self
+    }
+
+    /// Set whether [`FileSearcher::search_matches`] lists NTFS alternate data streams
     ///
-    /// # Arguments
+    /// Streams such as `Zone.Identifier` are only found on Windows.
+    #[must_use]
+    pub fn alternate_streams(mut self, enabled: bool) -> Self {
+        self.config.alternate_streams = enabled;
+        self
+    }
+
+    /// Only report files having the storage attribute `attribute`
     ///
-    /// * `root_path` - The directory to search in
-    /// * `query` - The search pattern
-    /// * `mode` - The search mode to use
+    /// May be given several times; files must have every attribute. Platforms
+    /// that cannot report an attribute never match it.
+    #[must_use]
+    pub fn require_attribute(
+        mut self,
+        attribute: crate::indexer::attributes::FileAttribute,
+    ) -> Self {
+        self.config.required_attributes.push(attribute);
+        self
+    }
+
+    /// Set whether [`FileSearcher::search_matches`] reads storage attributes
+    #[must_use]
+    pub fn file_attributes(mut self, enabled: bool) -> Self {
+        self.config.file_attributes = enabled;
+        self
+    }
+
+    /// Set whether [`FileSearcher::search_matches`] looks up each file's git
+    /// status
     ///
-    /// # Examples
+    /// Requires the `git` feature; files outside a repository have no status.
+    #[must_use]
+    pub fn git_status(mut self, enabled: bool) -> Self {
+        self.config.git_status = enabled;
+        self
+    }
+
+    /// Write result paths with the separators of `style`
+    #[must_use]
+    pub fn path_style(mut self, style: crate::config::PathStyle) -> Self {
+        self.config.path_style = style;
+        self
+    }
+
+    /// Whether a leading `~` or `~user` in search roots and ignore patterns
+    /// is expanded to a home directory
     ///
-    /// ```ignore
-    /// use whatever_find::{FileSearcher, SearchMode};
-    /// use std::path::Path;
+    /// Enabled by default, for paths that did not pass through a shell.
+    #[must_use]
+    pub fn expand_tilde(mut self, enabled: bool) -> Self {
+        self.config.expand_tilde = enabled;
+        self
+    }
+
+    /// Only report files whose hard link count matches `links`
     ///
-    /// let searcher = FileSearcher::new();
-    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let results = searcher.search(Path::new("."), "test", SearchMode::Substring)?;
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn search(
-        &self,
-        root_path: &Path,
-        query: &str,
-        mode: crate::search::SearchMode,
-    ) -> Result<Vec<PathBuf>> {
-        let mut indexer = crate::indexer::FileIndexer::new(self.config.clone());
-        let index = indexer.build_index(root_path.to_str().ok_or_else(|| {
-            crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
-        })?)?;
+    /// For example `LinkCount::MoreThan(1)` finds files with other hard links.
+    #[must_use]
+    pub fn link_count(mut self, links: crate::config::LinkCount) -> Self {
+        self.config.link_count = Some(links);
+        self
+    }
+
+    /// Only report files whose name is as long as `length` requires, counted
+    /// in characters
+    #[must_use]
+    pub fn name_length(mut self, length: crate::config::LengthFilter) -> Self {
+        self.config.name_length = Some(length);
+        self
+    }
 
-        let search_engine = crate::search::SearchEngine::new(self.config.clone());
+    /// Only report files whose full path is as long as `length` requires,
+    /// counted in characters
+    ///
+    /// For example `LengthFilter::LongerThan(240)` finds paths close to the
+    /// Windows `MAX_PATH` limit.
+    #[must_use]
+    pub fn path_length(mut self, length: crate::config::LengthFilter) -> Self {
+        self.config.path_length = Some(length);
+        self
+    }
 
-        match mode {
-            crate::search::SearchMode::Substring => {
-                Ok(search_engine.search_substring(&index, query))
-            }
-            crate::search::SearchMode::Glob => search_engine.search_glob(&index, query),
-            crate::search::SearchMode::Regex => search_engine.search_regex(&index, query),
-            crate::search::SearchMode::Fuzzy => Ok(search_engine
-                .search_fuzzy(&index, query)
-                .into_iter()
-                .map(|(path, _)| path)
-                .collect()),
-        }
+    /// Set how online-only files of cloud storage clients are handled
+    ///
+    /// Placeholders are indexed by name only by default, so that a search
+    /// never downloads them.
+    #[must_use]
+    pub fn cloud_placeholders(mut self, policy: crate::config::CloudPlaceholders) -> Self {
+        self.config.cloud_placeholders = policy;
+        self
     }
 
-    /// Performs fuzzy search and returns scored results
+    /// Whether searches also cover the trash or Recycle Bin
     ///
-    /// Fuzzy search is tolerant of typos and returns results ranked by relevance score.
+    /// Trashed files are matched by the name they had before deletion, and
+    /// found at their current location inside the trash.
+    #[must_use]
+    pub fn include_trash(mut self, enabled: bool) -> Self {
+        self.config.include_trash = enabled;
+        self
+    }
+
+    /// Save `preset` under `name` for [`FileSearcher::run_preset`]
     ///
-    /// # Returns
+    /// A preset of the same name is replaced.
+    #[must_use]
+    pub fn preset<S: Into<String>>(
+        mut self,
+        name: S,
+        preset: crate::search::preset::Preset,
+    ) -> Self {
+        self.config.presets.insert(name.into(), preset);
+        self
+    }
+
+    /// Register `decorator` to add metadata to every result of
+    /// [`FileSearcher::search_matches`]
     ///
-    /// A vector of tuples containing file paths and their relevance scores (0.0 to 1.0)
+    /// Decorators run in the order they are registered.
+    #[must_use]
+    pub fn decorator(mut self, decorator: impl ResultDecorator + 'static) -> Self {
+        self.decorators.push(std::sync::Arc::new(decorator));
+        self
+    }
+
+    /// Keep only the files `filter` accepts when walking, in addition to the
+    /// configured rules
+    ///
+    /// Files must pass every registered filter. See
+    /// [`indexer::filter`](crate::indexer::filter) for details.
+    #[must_use]
+    pub fn filter(mut self, filter: impl Filter + 'static) -> Self {
+        self.filters.push(std::sync::Arc::new(filter));
+        self
+    }
+
+    /// Match [`SearchMode::Custom`] queries with `matcher`
+    ///
+    /// See [`search::custom`](crate::search::custom) for details.
+    #[must_use]
+    pub fn matcher(mut self, matcher: impl PatternMatcher + 'static) -> Self {
+        self.matcher = crate::search::custom::CustomMatcher(Some(std::sync::Arc::new(matcher)));
+        self
+    }
+
+    /// Score fuzzy matches with `scorer` instead of the built-in scoring
+    ///
+    /// See [`search::scorer`](crate::search::scorer) for details.
+    #[cfg(feature = "fuzzy")]
+    #[must_use]
+    pub fn scorer(mut self, scorer: impl crate::search::scorer::Scorer + 'static) -> Self {
+        self.scorer = crate::search::scorer::CustomScorer(Some(std::sync::Arc::new(scorer)));
+        self
+    }
+
+    /// Report the progress of walks and searches to `reporter`
+    ///
+    /// See [`ProgressReporter`] for when reports are made.
+    #[must_use]
+    pub fn progress_reporter(mut self, reporter: impl ProgressReporter + 'static) -> Self {
+        self.progress = crate::search::progress::Reporter(Some(std::sync::Arc::new(reporter)));
+        self
+    }
+
+    /// Start indexing `root` on a background thread as soon as the searcher
+    /// is built
+    ///
+    /// See [`FileSearcher::warm_up`].
+    #[must_use]
+    pub fn prewarm<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.prewarm = Some(root.into());
+        self
+    }
+
+    /// Set the configuration directly
+    ///
+    /// This overwrites any previously configured settings.
+    pub fn config(mut self, config: crate::config::Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Validate the configuration and build the `FileSearcher`
+    ///
+    /// # Errors
+    /// Returns an error if the configuration is invalid
+    pub fn build(self) -> Result<FileSearcher> {
+        // Validate configuration
+        if let Some(depth) = self.config.max_depth {
+            if depth == 0 {
+                return Err(crate::error::FileSearchError::invalid_config(
+                    "max_depth cannot be 0. Use unlimited_depth() for no limit or set a positive value."
+                ));
+            }
+            if self.config.min_depth.is_some_and(|min| min > depth) {
+                return Err(crate::error::FileSearchError::invalid_config(
+                    "min_depth cannot be greater than max_depth.",
+                ));
+            }
+        }
+
+        if let Some(size) = self.config.max_file_size {
+            if size == 0 {
+                return Err(crate::error::FileSearchError::invalid_config(
+                    "max_file_size cannot be 0. Use unlimited_file_size() for no limit or set a positive value."
+                ));
+            }
+        }
+
+        if self
+            .config
+            .io_timeout
+            .is_some_and(|timeout| timeout.is_zero())
+        {
+            return Err(crate::error::FileSearchError::invalid_config(
+                "io_timeout cannot be 0. Leave it unset to wait indefinitely.",
+            ));
+        }
+
+        match self.config.io_throttle {
+            Some(crate::config::IoThrottle::EntriesPerSecond(0)) => {
+                return Err(crate::error::FileSearchError::invalid_config(
+                    "io_throttle rate cannot be 0 entries per second.",
+                ));
+            }
+            Some(crate::config::IoThrottle::SleepEveryDirs { dirs: 0, .. }) => {
+                return Err(crate::error::FileSearchError::invalid_config(
+                    "io_throttle cannot pause every 0 directories.",
+                ));
+            }
+            _ => {}
+        }
+
+        self.validate_ranking()?;
+
+        if self.config.unicode_normalization && !cfg!(feature = "unicode") {
+            return Err(crate::error::FileSearchError::feature_disabled("unicode"));
+        }
+
+        if self
+            .config
+            .search_timeout
+            .is_some_and(|timeout| timeout.is_zero())
+        {
+            return Err(crate::error::FileSearchError::invalid_config(
+                "search_timeout cannot be 0. Leave it unset for no limit.",
+            ));
+        }
+
+        let limits = [
+            ("max_dirs", self.config.max_dirs),
+            ("max_entries", self.config.max_entries),
+            ("max_regex_len", self.config.max_regex_len),
+            ("regex_size_limit", self.config.regex_size_limit),
+            ("regex_dfa_size_limit", self.config.regex_dfa_size_limit),
+        ];
+        for (name, limit) in limits {
+            if limit == Some(0) {
+                return Err(crate::error::FileSearchError::invalid_config(format!(
+                    "{name} cannot be 0. Leave it unset to use the default."
+                )));
+            }
+        }
+
+        // Validate ignore patterns
+        for pattern in &self.config.ignore_patterns {
+            if pattern.is_empty() {
+                return Err(crate::error::FileSearchError::invalid_config(
+                    "ignore patterns cannot be empty",
+                ));
+            }
+        }
+
+        Ok(self.build_unchecked())
+    }
+
+    /// Check the settings that score and rank fuzzy matches
+    fn validate_ranking(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.config.fuzzy_min_score) {
+            return Err(crate::error::FileSearchError::invalid_config(
+                "fuzzy_min_score must be between 0.0 and 1.0.",
+            ));
+        }
+
+        if !self.config.fuzzy_scoring.is_valid() {
+            return Err(crate::error::FileSearchError::invalid_config(
+                "fuzzy_scoring weights must be finite numbers of at least 0.0, not all 0.0.",
+            ));
+        }
+
+        if !(self.config.depth_penalty >= 0.0 && self.config.depth_penalty.is_finite()) {
+            return Err(crate::error::FileSearchError::invalid_config(
+                "depth_penalty must be a finite number of at least 0.0.",
+            ));
+        }
+
+        for (extension, weight) in &self.config.extension_weights {
+            if !(*weight >= 0.0 && weight.is_finite()) {
+                return Err(crate::error::FileSearchError::invalid_config(format!(
+                    "Weight of extension '{extension}' must be a finite number of at least 0.0."
+                )));
+            }
+        }
+
+        if !(0.0..=1.0).contains(&self.config.recency_weight) {
+            return Err(crate::error::FileSearchError::invalid_config(
+                "recency_weight must be between 0.0 and 1.0.",
+            ));
+        }
+
+        if self.config.recency_half_life.is_zero() {
+            return Err(crate::error::FileSearchError::invalid_config(
+                "recency_half_life cannot be 0.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Build the `FileSearcher` without validation
+    ///
+    /// This method skips configuration validation and should only be used
+    /// when you're certain the configuration is valid.
+    pub fn build_unchecked(self) -> FileSearcher {
+        let mut searcher = FileSearcher {
+            config: self.config,
+            decorators: self.decorators,
+            filters: self.filters,
+            matcher: self.matcher,
+            #[cfg(feature = "fuzzy")]
+            scorer: self.scorer,
+            progress: self.progress,
+            warm: None,
+        };
+        if let Some(root) = self.prewarm {
+            searcher.warm_up(root);
+        }
+        searcher
+    }
+}
+
+/// Main entry point for the file search library
+///
+/// This struct provides a high-level interface for searching files with various patterns.
+/// It handles indexing, pattern detection, and search execution.
+#[derive(Debug)]
+pub struct FileSearcher {
+    config: crate::config::Config,
+    decorators: crate::search::decorate::Decorators,
+    filters: crate::indexer::filter::Filters,
+    matcher: crate::search::custom::CustomMatcher,
+    #[cfg(feature = "fuzzy")]
+    scorer: crate::search::scorer::CustomScorer,
+    progress: crate::search::progress::Reporter,
+    warm: Option<std::sync::Arc<crate::search::warm::WarmIndex>>,
+}
+
+impl Default for FileSearcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSearcher {
+    /// Creates a new `FileSearcher` with default configuration
     ///
     /// # Examples
     ///
-    /// ```ignore
+    /// ```rust
     /// use whatever_find::FileSearcher;
-    /// use std::path::Path;
     ///
     /// let searcher = FileSearcher::new();
-    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let results = searcher.search_fuzzy(Path::new("."), "mian")?; // finds "main"
-    ///
-    /// for (file, score) in results {
-    ///     println!("{} (score: {:.2})", file.display(), score);
-    /// }
+    /// ```
+    pub fn new() -> Self {
+        Self::with_config(crate::config::Config::default())
+    }
+
+    /// Creates a new `FileSearcherBuilder` for fluent configuration
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use whatever_find::FileSearcher;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let searcher = FileSearcher::builder()
+    ///     .max_depth(3)
+    ///     .ignore_hidden(false)
+    ///     .case_sensitive(true)
+    ///     .build()?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn search_fuzzy(&self, root_path: &Path, query: &str) -> Result<Vec<(PathBuf, f64)>> {
+    pub fn builder() -> FileSearcherBuilder {
+        FileSearcherBuilder::new()
+    }
+
+    /// Creates a new `FileSearcher` with custom configuration
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use whatever_find::{FileSearcher, Config};
+    ///
+    /// let config = Config {
+    ///     case_sensitive: true,
+    ///     max_depth: Some(3),
+    ///     ..Default::default()
+    /// };
+    /// let searcher = FileSearcher::with_config(config);
+    /// ```
+    pub fn with_config(config: crate::config::Config) -> Self {
+        Self {
+            config,
+            decorators: crate::search::decorate::Decorators::default(),
+            filters: crate::indexer::filter::Filters::default(),
+            matcher: crate::search::custom::CustomMatcher::default(),
+            #[cfg(feature = "fuzzy")]
+            scorer: crate::search::scorer::CustomScorer::default(),
+            progress: crate::search::progress::Reporter::default(),
+            warm: None,
+        }
+    }
+
+    /// A search engine with this searcher's configuration, custom matcher
+    /// and scorer
+    fn engine(&self) -> crate::search::SearchEngine {
+        let mut engine = crate::search::SearchEngine::new(self.config.clone());
+        if let Some(matcher) = &self.matcher.0 {
+            engine.set_matcher(std::sync::Arc::clone(matcher));
+        }
+        #[cfg(feature = "fuzzy")]
+        if let Some(scorer) = &self.scorer.0 {
+            engine.set_scorer(std::sync::Arc::clone(scorer));
+        }
+        engine
+    }
+
+    /// A search engine like [`FileSearcher::engine`] that matches paths
+    /// relative to `root` with [`Config::match_relative_path`]
+    fn engine_at(&self, root: &Path) -> crate::search::SearchEngine {
+        let mut engine = self.engine();
+        engine.set_root(root);
+        engine
+    }
+
+    /// A search engine like [`FileSearcher::engine_at`] that stops matching
+    /// once [`Config::search_timeout`] has passed from now
+    fn timed_engine_at(&self, root: &Path) -> crate::search::SearchEngine {
+        let mut engine = self.engine_at(root);
+        engine.set_deadline(
+            self.config
+                .search_timeout
+                .and_then(|timeout| std::time::Instant::now().checked_add(timeout)),
+        );
+        engine
+    }
+
+    /// The limit that cut a search short, either in the walk or while
+    /// `engine` matched the files found
+    fn limit_exceeded(
+        &self,
+        walk: Option<crate::indexer::file_walker::TraversalLimit>,
+        engine: &crate::search::SearchEngine,
+    ) -> Option<crate::indexer::file_walker::TraversalLimit> {
+        walk.or_else(|| {
+            self.config
+                .search_timeout
+                .filter(|_| engine.was_cut_short())
+                .map(crate::indexer::file_walker::TraversalLimit::Time)
+        })
+    }
+
+    /// An indexer with this searcher's configuration, filters and progress
+    /// reporter
+    fn indexer(&self) -> crate::indexer::FileIndexer {
         let mut indexer = crate::indexer::FileIndexer::new(self.config.clone());
+        indexer.set_filters(self.filters.clone());
+        if let Some(reporter) = &self.progress.0 {
+            indexer.set_progress_reporter(std::sync::Arc::clone(reporter));
+        }
+        indexer
+    }
+
+    /// Searches for files using automatic pattern detection
+    ///
+    /// This method automatically detects whether the query is a glob pattern,
+    /// regular expression, or simple substring and uses the appropriate search mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_path` - The directory to search in
+    /// * `query` - The search pattern
+    ///
+    /// # Returns
+    ///
+    /// A vector of matching file paths
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or if the pattern is invalid
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use whatever_find::FileSearcher;
+    /// use std::path::Path;
+    ///
+    /// let searcher = FileSearcher::new();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let results = searcher.search_auto(Path::new("."), "*.rs")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_auto(&self, root_path: &Path, query: &str) -> Result<Vec<PathBuf>> {
+        let search_engine = self.engine_at(root_path);
+        crate::search::validate_query(query, search_engine.detect_search_mode(query))?;
+
+        let mut indexer = self.indexer();
+        let index = indexer.build_index(root_path.to_str().ok_or_else(|| {
+            crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
+        })?)?;
+
+        let results = search_engine.search_auto(&index, query)?;
+        self.progress.matches(indexer.progress(), results.len());
+        Ok(results)
+    }
+
+    /// Searches for files using automatic pattern detection, returning the detected mode
+    ///
+    /// Similar to `search_auto`, but also returns information about which search mode
+    /// was automatically selected.
+    ///
+    /// # Returns
+    ///
+    /// A tuple containing the matching file paths and the detected search mode
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use whatever_find::{FileSearcher, SearchMode};
+    /// use std::path::Path;
+    ///
+    /// let searcher = FileSearcher::new();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (results, mode) = searcher.search_auto_with_mode(Path::new("."), "*.rs")?;
+    /// assert_eq!(mode, SearchMode::Glob);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_auto_with_mode(
+        &self,
+        root_path: &Path,
+        query: &str,
+    ) -> Result<(Vec<PathBuf>, crate::search::SearchMode)> {
+        let search_engine = self.engine_at(root_path);
+        crate::search::validate_query(query, search_engine.detect_search_mode(query))?;
+
+        let mut indexer = self.indexer();
         let index = indexer.build_index(root_path.to_str().ok_or_else(|| {
             crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
         })?)?;
 
-        let search_engine = crate::search::SearchEngine::new(self.config.clone());
-        Ok(search_engine.search_fuzzy(&index, query))
-    }
+        let (results, mode) = search_engine.search_auto_with_mode(&index, query)?;
+        self.progress.matches(indexer.progress(), results.len());
+        Ok((results, mode))
+    }
+
+    /// Walks `root_path` once and returns its index, for running many queries
+    /// with [`FileSearcher::search_in_index`]
+    ///
+    /// The index is a snapshot: files created or deleted afterwards are only
+    /// seen by indexing again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root_path` is not valid UTF-8 or cannot be walked
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use whatever_find::{FileSearcher, SearchMode};
+    /// use std::path::Path;
+    ///
+    /// let searcher = FileSearcher::new();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let index = searcher.index(Path::new("."))?;
+    /// let sources = searcher.search_in_index(&index, "*.rs", SearchMode::Glob)?;
+    /// let tests = searcher.search_in_index(&index, "test", SearchMode::Substring)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn index(&self, root_path: &Path) -> Result<FileIndex> {
+        let mut indexer = self.indexer();
+        indexer.build_index(root_path.to_str().ok_or_else(|| {
+            crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
+        })?)
+    }
+
+    /// Indexes `root_path` like [`FileSearcher::index`] through the on-disk
+    /// cache at `cache_path`
+    ///
+    /// Only directories modified since the cache was saved are listed again,
+    /// and the refreshed cache is saved back. A cache for another root, or
+    /// one that cannot be read, is rebuilt from scratch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root_path` cannot be walked or the cache cannot
+    /// be saved
+    pub fn index_cached(&self, root_path: &Path, cache_path: &Path) -> Result<FileIndex> {
+        let cache = crate::indexer::cache::IndexCache::open(cache_path, root_path, &self.config)?;
+        Ok(cache.to_index(&self.config))
+    }
+
+    /// Searches `index`, built by [`FileSearcher::index`], without walking
+    /// the file system again
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is invalid
+    pub fn search_in_index(
+        &self,
+        index: &FileIndex,
+        query: &str,
+        mode: crate::search::SearchMode,
+    ) -> Result<Vec<PathBuf>> {
+        crate::search::validate_query(query, mode)?;
+        self.engine().search(index, query, mode)
+    }
+
+    /// Searches `index` like [`FileSearcher::search_in_index`], detecting the
+    /// search mode from the query
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is invalid
+    pub fn search_auto_in_index(&self, index: &FileIndex, query: &str) -> Result<Vec<PathBuf>> {
+        let search_engine = self.engine();
+        crate::search::validate_query(query, search_engine.detect_search_mode(query))?;
+        search_engine.search_auto(index, query)
+    }
+
+    /// Searches for files using a specific search mode
+    ///
+    /// This method allows you to force a specific search mode, bypassing automatic detection.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_path` - The directory to search in
+    /// * `query` - The search pattern
+    /// * `mode` - The search mode to use
+    ///
+    /// If a traversal limit such as
+    /// [`max_entries`](crate::config::Config::max_entries) stops the walk
+    /// early, the matches found so far are returned inside
+    /// [`FileSearchError::LimitExceeded`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use whatever_find::{FileSearcher, SearchMode};
+    /// use std::path::Path;
+    ///
+    /// let searcher = FileSearcher::new();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let results = searcher.search(Path::new("."), "test", SearchMode::Substring)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search(
+        &self,
+        root_path: &Path,
+        query: &str,
+        mode: crate::search::SearchMode,
+    ) -> Result<Vec<PathBuf>> {
+        crate::search::validate_query(query, mode)?;
+
+        let search_engine = self.timed_engine_at(root_path);
+        let warm = self.warm_index(root_path);
+        let fresh;
+        let built = if let Some(built) = &warm {
+            built.as_ref()
+        } else {
+            fresh = crate::search::warm::BuiltIndex::build(root_path, self.indexer())?;
+            &fresh
+        };
+
+        let results = search_engine.search(&built.index, query, mode)?;
+        self.progress
+            .matches(built.indexer.progress(), results.len());
+        match self.limit_exceeded(built.indexer.stats().limit_exceeded, &search_engine) {
+            Some(limit) => Err(crate::error::FileSearchError::limit_exceeded(
+                limit, results,
+            )),
+            None => Ok(results),
+        }
+    }
+
+    /// Searches like [`FileSearcher::search`] under the control of `handle`
+    ///
+    /// Another thread can pause, resume or stop the walk through a clone of
+    /// the handle, and take a [`Checkpoint`](crate::search::handle::Checkpoint)
+    /// to continue it later with [`SearchHandle::from_checkpoint`]. A stopped
+    /// search returns the matches among the files found so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or if the pattern is invalid
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use whatever_find::{FileSearcher, SearchHandle, SearchMode};
+    /// use std::path::Path;
+    ///
+    /// let searcher = FileSearcher::new();
+    /// let handle = SearchHandle::new();
+    /// let worker = handle.clone();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let search = std::thread::spawn(move || {
+    ///     searcher.search_with_handle(Path::new("."), "*.rs", SearchMode::Glob, &worker)
+    /// });
+    /// handle.pause(); // yield to foreground work
+    /// handle.resume();
+    /// let results = search.join().unwrap()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_with_handle(
+        &self,
+        root_path: &Path,
+        query: &str,
+        mode: crate::search::SearchMode,
+        handle: &SearchHandle,
+    ) -> Result<Vec<PathBuf>> {
+        crate::search::validate_query(query, mode)?;
+
+        let search_engine = self.timed_engine_at(root_path);
+        let mut indexer = self.indexer();
+        let index = indexer.build_index_with_handle(
+            root_path.to_str().ok_or_else(|| {
+                crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
+            })?,
+            handle,
+        )?;
+
+        let results = search_engine.search(&index, query, mode)?;
+        match self.limit_exceeded(indexer.stats().limit_exceeded, &search_engine) {
+            Some(limit) => Err(crate::error::FileSearchError::limit_exceeded(
+                limit, results,
+            )),
+            None => Ok(results),
+        }
+    }
+
+    /// Searches like [`FileSearcher::search`], passing each match to `visit`
+    /// as soon as the walk finds it
+    ///
+    /// The search stops as soon as `visit` returns [`ControlFlow::Break`],
+    /// whose value is then returned, so a caller that only needs the first
+    /// match need not wait for the rest of the tree. Matches arrive in walk
+    /// order rather than sorted, and fuzzy matches are not ranked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or if the pattern is invalid. A
+    /// traversal limit that stops the walk early is reported as
+    /// [`FileSearchError::LimitExceeded`] without results, since every match
+    /// was already passed to `visit`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use whatever_find::{FileSearcher, SearchMode};
+    /// use std::ops::ControlFlow;
+    /// use std::path::Path;
+    ///
+    /// let searcher = FileSearcher::new();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let first = searcher.search_with(Path::new("."), "main", SearchMode::Substring, |path| {
+    ///     ControlFlow::Break(path.to_path_buf())
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_with<B>(
+        &self,
+        root_path: &Path,
+        query: &str,
+        mode: crate::search::SearchMode,
+        mut visit: impl FnMut(&Path) -> ControlFlow<B>,
+    ) -> Result<ControlFlow<B>> {
+        let search_engine = self.engine_at(root_path);
+        let matches = search_engine.file_matcher(query, mode)?;
+        let root = root_path.to_str().ok_or_else(|| {
+            crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
+        })?;
+
+        // Reports during the walk include the matches visited so far
+        let found = std::sync::Arc::new(AtomicUsize::new(0));
+        let mut indexer = self.indexer();
+        if let Some(reporter) = self.progress.0.clone() {
+            let found = std::sync::Arc::clone(&found);
+            indexer.set_progress_reporter(std::sync::Arc::new(
+                move |progress: &crate::search::progress::SearchProgress| {
+                    reporter.report(&crate::search::progress::SearchProgress {
+                        matches: found.load(Ordering::Relaxed),
+                        ..*progress
+                    });
+                },
+            ));
+        }
+        let mut stopped = None;
+        let flow = indexer.for_each_file(root, |name, path| {
+            if !matches(name, path) {
+                return ControlFlow::Continue(());
+            }
+            found.fetch_add(1, Ordering::Relaxed);
+            match visit(path) {
+                ControlFlow::Continue(()) => ControlFlow::Continue(()),
+                ControlFlow::Break(value) => {
+                    stopped = Some(value);
+                    ControlFlow::Break(())
+                }
+            }
+        })?;
+
+        if let (ControlFlow::Break(()), Some(value)) = (flow, stopped) {
+            return Ok(ControlFlow::Break(value));
+        }
+        match indexer.stats().limit_exceeded {
+            Some(limit) => Err(crate::error::FileSearchError::limit_exceeded(
+                limit,
+                Vec::new(),
+            )),
+            None => Ok(ControlFlow::Continue(())),
+        }
+    }
+
+    /// Searches with a structured query such as
+    /// `name:*.rs size:>1M mtime:<7d ext:rs,toml`
+    ///
+    /// The query is parsed by [`Query::parse`](crate::search::query::Query::parse)
+    /// and evaluated while walking, reading the metadata only of files whose
+    /// name and extension already match. Results are ordered like those of
+    /// [`FileSearcher::search`]. If a traversal limit stops the walk early,
+    /// the matches found so far are returned inside
+    /// [`FileSearchError::LimitExceeded`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query or one of its name patterns is invalid,
+    /// or if `root_path` cannot be traversed
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use whatever_find::FileSearcher;
+    /// use std::path::Path;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let searcher = FileSearcher::new();
+    /// for file in searcher.search_query(Path::new("."), "ext:rs,toml size:>1k mtime:<7d")? {
+    ///     println!("{}", file.display());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_query(&self, root_path: &Path, query: &str) -> Result<Vec<PathBuf>> {
+        self.search_by_query(root_path, &crate::search::query::Query::parse(query)?)
+    }
+
+    /// Searches with a [`Query`](crate::search::query::Query) built in code,
+    /// such as one combined with `Query::all`, `Query::any` and `Query::not`
+    ///
+    /// Evaluated like [`FileSearcher::search_query`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if one of the query's name patterns is invalid, or if
+    /// `root_path` cannot be traversed
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use whatever_find::search::query::Query;
+    /// use whatever_find::FileSearcher;
+    /// use std::path::Path;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let query = Query::all([
+    ///     "ext:rs".parse()?,
+    ///     Query::name("test"),
+    ///     Query::not(Query::path("vendor")),
+    /// ]);
+    /// let tests = FileSearcher::new().search_by_query(Path::new("."), &query)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_by_query(
+        &self,
+        root_path: &Path,
+        query: &crate::search::query::Query,
+    ) -> Result<Vec<PathBuf>> {
+        let search_engine = self.engine_at(root_path);
+        let matches = query.matcher(&search_engine)?;
+        let root = root_path.to_str().ok_or_else(|| {
+            crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
+        })?;
+
+        let mut indexer = self.indexer();
+        let mut results = Vec::new();
+        // The visitor never stops the walk
+        let _ = indexer.for_each_file(root, |key, path| {
+            if matches(key, path) {
+                results.push(path.to_path_buf());
+            }
+            ControlFlow::Continue(())
+        })?;
+        search_engine.sort_matches(&mut results);
+
+        match indexer.stats().limit_exceeded {
+            Some(limit) => Err(crate::error::FileSearchError::limit_exceeded(
+                limit, results,
+            )),
+            None => Ok(results),
+        }
+    }
+
+    /// Searches every directory in `roots` in one call, using automatic
+    /// pattern detection
+    ///
+    /// See [`FileSearcher::search_multi`] for how the roots are combined.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is invalid or a root cannot be walked
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use whatever_find::FileSearcher;
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let searcher = FileSearcher::new();
+    /// let roots = [PathBuf::from("src"), PathBuf::from("examples")];
+    /// for file in searcher.search_auto_multi(&roots, "*.rs")? {
+    ///     println!("{}", file.display());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_auto_multi(&self, roots: &[PathBuf], query: &str) -> Result<Vec<PathBuf>> {
+        let mode = self.engine().detect_search_mode(query);
+        self.search_multi(roots, query, mode)
+    }
+
+    /// Searches every directory in `roots` in one call, with a specific
+    /// search mode
+    ///
+    /// The roots are walked in parallel and their files searched as one
+    /// index, so the results of all roots are ranked and sorted together.
+    /// A root that is the same directory as an earlier one, or lies inside
+    /// another root, is not walked again, so no file is reported twice.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is invalid or a root cannot be walked
+    pub fn search_multi(
+        &self,
+        roots: &[PathBuf],
+        query: &str,
+        mode: crate::search::SearchMode,
+    ) -> Result<Vec<PathBuf>> {
+        crate::search::validate_query(query, mode)?;
+
+        let roots = crate::indexer::roots::distinct(roots);
+        let (index, mut failed) =
+            crate::indexer::roots::build_index(&roots, &self.config, &self.filters);
+        if !failed.is_empty() {
+            return Err(failed.swap_remove(0).1);
+        }
+
+        self.engine().search(&index, query, mode)
+    }
+
+    /// Searches every drive in `drives` at once, such as those from
+    /// [`drives::available`](crate::indexer::drives::available)
+    ///
+    /// The drives are walked in parallel. A drive that cannot be walked is
+    /// skipped as long as another one could be.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is invalid, the search fails or no
+    /// drive could be walked
+    pub fn search_drives(
+        &self,
+        drives: &[crate::indexer::drives::Drive],
+        query: &str,
+        mode: crate::search::SearchMode,
+    ) -> Result<Vec<PathBuf>> {
+        crate::search::validate_query(query, mode)?;
+
+        let roots: Vec<&Path> = drives.iter().map(|drive| drive.root.as_path()).collect();
+        let (index, mut failed) =
+            crate::indexer::roots::build_index(&roots, &self.config, &self.filters);
+        if failed.len() == drives.len() && !failed.is_empty() {
+            return Err(failed.swap_remove(0).1);
+        }
+
+        self.engine().search(&index, query, mode)
+    }
+
+    /// Searches like [`FileSearcher::search`], attaching per-file metadata
+    ///
+    /// Metadata that is costly to gather, such as alternate data streams, is
+    /// only filled in when enabled in the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search itself fails
+    pub fn search_matches(
+        &self,
+        root_path: &Path,
+        query: &str,
+        mode: crate::search::SearchMode,
+    ) -> Result<Vec<FileMatch>> {
+        let paths = self.search(root_path, query, mode)?.into_iter();
+        #[cfg(feature = "git")]
+        let mut statuses = crate::indexer::git::GitStatuses::default();
+        #[cfg(feature = "git")]
+        let matches = paths.map(|path| FileMatch::with_statuses(path, &self.config, &mut statuses));
+        #[cfg(not(feature = "git"))]
+        let matches = paths.map(|path| FileMatch::new(path, &self.config));
+        Ok(matches
+            .map(|mut file_match| {
+                self.decorators.apply(&mut file_match);
+                file_match
+            })
+            .collect())
+    }
+
+    /// Searches like [`FileSearcher::search`], reading the size of every
+    /// result for totals
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search itself fails
+    pub fn search_results(
+        &self,
+        root_path: &Path,
+        query: &str,
+        mode: crate::search::SearchMode,
+    ) -> Result<SearchResults> {
+        Ok(SearchResults::new(self.search(root_path, query, mode)?))
+    }
+
+    /// Searches like [`FileSearcher::search`], reading the size,
+    /// modification time and type of every result once
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search itself fails
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use whatever_find::{FileSearcher, SearchMode};
+    /// use std::path::Path;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let searcher = FileSearcher::new();
+    /// for result in searcher.search_detailed(Path::new("."), ".rs", SearchMode::Substring)? {
+    ///     println!("{} ({} bytes)", result.path.display(), result.size);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_detailed(
+        &self,
+        root_path: &Path,
+        query: &str,
+        mode: crate::search::SearchMode,
+    ) -> Result<Vec<SearchResult>> {
+        let results = self.search(root_path, query, mode)?;
+        self.detailed(query, mode, results.into_iter().map(|path| (path, None)))
+    }
+
+    /// Searches like [`FileSearcher::search_auto`], reading the size,
+    /// modification time and type of every result once
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search itself fails
+    pub fn search_auto_detailed(&self, root_path: &Path, query: &str) -> Result<Vec<SearchResult>> {
+        let (results, mode) = self.search_auto_with_mode(root_path, query)?;
+        self.detailed(query, mode, results.into_iter().map(|path| (path, None)))
+    }
+
+    /// Searches like [`FileSearcher::search_fuzzy`], reading the size,
+    /// modification time and type of every result once and keeping its score
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search itself fails
+    #[cfg(feature = "fuzzy")]
+    pub fn search_fuzzy_detailed(
+        &self,
+        root_path: &Path,
+        query: &str,
+    ) -> Result<Vec<SearchResult>> {
+        let results = self.search_fuzzy(root_path, query)?;
+        self.detailed(
+            query,
+            crate::search::SearchMode::Fuzzy,
+            results.into_iter().map(|(path, score)| (path, Some(score))),
+        )
+    }
+
+    /// Read the metadata of every result and find the spans `query` matched
+    /// in its file name
+    fn detailed(
+        &self,
+        query: &str,
+        mode: crate::search::SearchMode,
+        results: impl Iterator<Item = (PathBuf, Option<f64>)>,
+    ) -> Result<Vec<SearchResult>> {
+        let search_engine = self.engine();
+        let spans = search_engine.span_finder(query, mode)?;
+        Ok(results
+            .map(|(path, score)| {
+                let spans = path
+                    .file_name()
+                    .map(|name| spans(&name.to_string_lossy()))
+                    .unwrap_or_default();
+                SearchResult {
+                    score,
+                    spans,
+                    ..SearchResult::new(path)
+                }
+            })
+            .collect())
+    }
+
+    /// Directories below `root_path` directly containing at least
+    /// `min_files` matches of `query`, those with the most first
+    ///
+    /// Useful for triaging datasets and photo libraries, such as finding
+    /// the folders with over 100 `*.raw` files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search itself fails
+    pub fn search_directories(
+        &self,
+        root_path: &Path,
+        query: &str,
+        mode: crate::search::SearchMode,
+        min_files: usize,
+    ) -> Result<Vec<crate::search::aggregate::DirectoryCount>> {
+        let results = self.search(root_path, query, mode)?;
+        Ok(crate::search::aggregate::directories_with_at_least(
+            &results, min_files,
+        ))
+    }
+
+    /// Counts and sizes of every indexed file below `root_path`, grouped by
+    /// extension and top-level directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root_path` cannot be indexed
+    pub fn size_report(&self, root_path: &Path) -> Result<crate::search::report::SizeReport> {
+        let mut indexer = self.indexer();
+        let index = indexer.build_index(root_path.to_str().ok_or_else(|| {
+            crate::error::FileSearchError::invalid_path(root_path, "Contains invalid UTF-8")
+        })?)?;
+        let results: SearchResults = index.into_iter().map(|(_, path)| path).collect();
+        Ok(crate::search::report::SizeReport::new(root_path, &results))
+    }
+
+    /// Runs the preset saved under `name` in the configuration
+    ///
+    /// Searches the preset's root, or the current directory if it has none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such preset or the search fails
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use whatever_find::FileSearcher;
+    ///
+    /// let searcher = FileSearcher::new();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let downloads = searcher.run_preset("recent-downloads")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_preset(&self, name: &str) -> Result<Vec<PathBuf>> {
+        let preset = self.config.presets.get(name).ok_or_else(|| {
+            crate::error::FileSearchError::invalid_config(format!("No preset named '{name}'"))
+        })?;
+        let root = preset.root.as_deref().unwrap_or(Path::new("."));
+        self.search_preset(root, preset)
+    }
+
+    /// Runs `preset` in `root_path`, ignoring the preset's own root
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or if the pattern is invalid
+    pub fn search_preset(
+        &self,
+        root_path: &Path,
+        preset: &crate::search::preset::Preset,
+    ) -> Result<Vec<PathBuf>> {
+        let mode = preset
+            .mode
+            .unwrap_or_else(|| self.engine().detect_search_mode(&preset.query));
+        #[cfg(feature = "fuzzy")]
+        if mode == crate::search::SearchMode::Fuzzy {
+            let results = self.search_fuzzy(root_path, &preset.query)?;
+            return Ok(preset.apply(results.into_iter().map(|(path, _)| path).collect()));
+        }
+        Ok(preset.apply(self.search(root_path, &preset.query, mode)?))
+    }
+
+    /// Performs fuzzy search and returns scored results
+    ///
+    /// Fuzzy search is tolerant of typos and returns results ranked by relevance score.
+    ///
+    /// # Returns
+    ///
+    /// A vector of tuples containing file paths and their relevance scores (0.0 to 1.0)
+    ///
+    /// If a traversal limit stops the walk early, the matches found so far
+    /// are returned best first inside [`FileSearchError::LimitExceeded`],
+    /// without their scores.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use whatever_find::FileSearcher;
+    /// use std::path::Path;
+    ///
+    /// let searcher = FileSearcher::new();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let results = searcher.search_fuzzy(Path::new("."), "mian")?; // finds "main"
+    ///
+    /// for (file, score) in results {
+    ///     println!("{} (score: {:.2})", file.display(), score);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "fuzzy")]
+    pub fn search_fuzzy(&self, root_path: &Path, query: &str) -> Result<Vec<(PathBuf, f64)>> {
+        crate::search::validate_query(query, crate::search::SearchMode::Fuzzy)?;
+
+        let search_engine = self.timed_engine_at(root_path);
+        let warm = self.warm_index(root_path);
+        let fresh;
+        let built = if let Some(built) = &warm {
+            built.as_ref()
+        } else {
+            fresh = crate::search::warm::BuiltIndex::build(root_path, self.indexer())?;
+            &fresh
+        };
+
+        let results = match built.indexer.ngram_index() {
+            Some(ngrams) => search_engine.search_fuzzy_candidates(&built.index, ngrams, query),
+            None => search_engine.search_fuzzy(&built.index, query),
+        };
+        self.progress
+            .matches(built.indexer.progress(), results.len());
+        match self.limit_exceeded(built.indexer.stats().limit_exceeded, &search_engine) {
+            Some(limit) => Err(crate::error::FileSearchError::limit_exceeded(
+                limit,
+                results.into_iter().map(|(path, _)| path).collect(),
+            )),
+            None => Ok(results),
+        }
+    }
+
+    /// Finds the files beneath `root` with the given inode number or Windows file ID
+    ///
+    /// Walks `root` with the current configuration, so ignored files are not
+    /// found. IDs are only unique per volume and hard links share one, hence
+    /// several paths may be returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` cannot be traversed
+    pub fn find_by_file_id(&self, root: &Path, id: u64) -> Result<Vec<PathBuf>> {
+        let root_path = root.to_str().ok_or_else(|| {
+            crate::error::FileSearchError::invalid_path(root, "Contains invalid UTF-8")
+        })?;
+        let walker = crate::indexer::file_walker::FileWalker::new(&self.config)
+            .with_filters(self.filters.clone());
+
+        let mut found = Vec::new();
+        for entry in walker.walk(root_path)? {
+            let entry = entry?;
+            if entry.file_type().is_file()
+                && crate::indexer::file_id::file_id(entry.path()) == Some(id)
+            {
+                found.push(entry.into_path());
+            }
+        }
+        Ok(found)
+    }
+
+    /// Explains why `path` would not be found when searching beneath `root`
+    ///
+    /// Reports the first rule that excludes the path: a hidden file or parent
+    /// directory, an ignore pattern, a git ignore rule or nested checkout, the
+    /// depth limit, the file size limit or the audit checks. Returns `None` if
+    /// the path would be searched.
+    #[must_use]
+    pub fn explain_ignore(
+        &self,
+        root: &Path,
+        path: &Path,
+    ) -> Option<crate::indexer::file_walker::IgnoreReason> {
+        crate::indexer::file_walker::FileWalker::new(&self.config).explain(root, path)
+    }
+
+    /// Register `decorator` to add metadata to every result of
+    /// [`FileSearcher::search_matches`], after those already registered
+    pub fn add_decorator(&mut self, decorator: impl ResultDecorator + 'static) {
+        self.decorators.push(std::sync::Arc::new(decorator));
+    }
+
+    /// Gets the current configuration
+    #[must_use]
+    pub fn config(&self) -> &crate::config::Config {
+        &self.config
+    }
+
+    /// Starts indexing `root` on a background thread
+    ///
+    /// Later searches of exactly this root path wait for the walk to finish,
+    /// if it has not yet, and reuse its index instead of walking again, until
+    /// the next warm-up or [`FileSearcher::set_config`]. Progress is reported
+    /// by [`FileSearcher::index_state`].
+    pub fn warm_up<P: Into<PathBuf>>(&mut self, root: P) {
+        self.warm = Some(crate::search::warm::WarmIndex::start(
+            root.into(),
+            self.indexer(),
+        ));
+    }
+
+    /// Progress of the latest warm-up, or `None` if there was none
+    #[must_use]
+    pub fn index_state(&self) -> Option<crate::search::warm::IndexState> {
+        self.warm.as_ref().map(|warm| warm.state())
+    }
+
+    /// The index built by the warm-up of `root_path`, once it is ready
+    fn warm_index(
+        &self,
+        root_path: &Path,
+    ) -> Option<std::sync::Arc<crate::search::warm::BuiltIndex>> {
+        self.warm.as_ref()?.wait(root_path)
+    }
+
+    /// Updates the configuration
+    ///
+    /// An index built by [`FileSearcher::warm_up`] is dropped, as it was
+    /// walked with the previous configuration.
+    pub fn set_config(&mut self, config: crate::config::Config) {
+        self.config = config;
+        self.warm = None;
+    }
+
+    /// Asynchronous version of `search_auto`
+    ///
+    /// This method runs the search operation on a background thread to avoid blocking
+    /// the current thread. Requires the `async` feature to be enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use file_search::FileSearcher;
+    /// use std::path::Path;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let searcher = FileSearcher::new();
+    ///     let results = searcher.search_auto_async(Path::new("."), "*.rs").await?;
+    ///     println!("Found {} files", results.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn search_auto_async(&self, root_path: &Path, query: &str) -> Result<Vec<PathBuf>> {
+        let searcher = self.clone();
+        let root_path = root_path.to_path_buf();
+        let query = query.to_string();
+
+        run_blocking(move || searcher.search_auto(&root_path, &query)).await
+    }
+
+    /// Asynchronous version of `search_auto_with_mode`
+    #[cfg(feature = "async")]
+    pub async fn search_auto_with_mode_async(
+        &self,
+        root_path: &Path,
+        query: &str,
+    ) -> Result<(Vec<PathBuf>, crate::search::SearchMode)> {
+        let searcher = self.clone();
+        let root_path = root_path.to_path_buf();
+        let query = query.to_string();
+
+        run_blocking(move || searcher.search_auto_with_mode(&root_path, &query)).await
+    }
+
+    /// Asynchronous version of `search`
+    #[cfg(feature = "async")]
+    pub async fn search_async(
+        &self,
+        root_path: &Path,
+        query: &str,
+        mode: crate::search::SearchMode,
+    ) -> Result<Vec<PathBuf>> {
+        let searcher = self.clone();
+        let root_path = root_path.to_path_buf();
+        let query = query.to_string();
+
+        run_blocking(move || searcher.search(&root_path, &query, mode)).await
+    }
+
+    /// Asynchronous version of `search_fuzzy`
+    #[cfg(all(feature = "async", feature = "fuzzy"))]
+    pub async fn search_fuzzy_async(
+        &self,
+        root_path: &Path,
+        query: &str,
+    ) -> Result<Vec<(PathBuf, f64)>> {
+        let searcher = self.clone();
+        let root_path = root_path.to_path_buf();
+        let query = query.to_string();
+
+        run_blocking(move || searcher.search_fuzzy(&root_path, &query)).await
+    }
+
+    /// Stream of the files matching `query` below `root_path`, detecting the
+    /// search mode like [`FileSearcher::search_auto`]
+    ///
+    /// Matches are sent as the walk finds them, in walk order, as with
+    /// [`FileSearcher::search_with`]. The walk runs on Tokio's blocking thread
+    /// pool and waits whenever [`STREAM_BUFFER`] matches are still unread, so a
+    /// slow consumer holds it back, and dropping the stream stops it. An error
+    /// is sent as the last item. Must be called from within a Tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use tokio_stream::StreamExt;
+    /// use whatever_find::FileSearcher;
+    /// use std::path::Path;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let searcher = FileSearcher::new();
+    ///     let mut results = searcher.search_stream(Path::new("."), "*.rs");
+    ///     while let Some(path) = results.next().await {
+    ///         println!("{}", path?.display());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn search_stream(
+        &self,
+        root_path: &Path,
+        query: &str,
+    ) -> impl tokio_stream::Stream<Item = Result<PathBuf>> {
+        let searcher = self.clone();
+        let root_path = root_path.to_path_buf();
+        let query = query.to_string();
+        let (sender, receiver) = tokio::sync::mpsc::channel(STREAM_BUFFER);
+
+        tokio::task::spawn_blocking(move || {
+            let mode = crate::search::SearchEngine::new(searcher.config.clone())
+                .detect_search_mode(&query);
+            let outcome = searcher.search_with(&root_path, &query, mode, |path| {
+                match sender.blocking_send(Ok(path.to_path_buf())) {
+                    Ok(()) => ControlFlow::Continue(()),
+                    // The stream was dropped
+                    Err(_) => ControlFlow::Break(()),
+                }
+            });
+            if let Err(e) = outcome {
+                // Nobody is left to tell if the stream was dropped
+                let _ = sender.blocking_send(Err(e));
+            }
+        });
+        tokio_stream::wrappers::ReceiverStream::new(receiver)
+    }
+}
+
+// Clone implementation needed for async support
+impl Clone for FileSearcher {
+    fn clone(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            decorators: self.decorators.clone(),
+            filters: self.filters.clone(),
+            matcher: self.matcher.clone(),
+            #[cfg(feature = "fuzzy")]
+            scorer: self.scorer.clone(),
+            progress: self.progress.clone(),
+            warm: self.warm.clone(),
+        }
+    }
+}
+
+/// Matches a [`FileSearcher::search_stream`] walk gets ahead of its consumer
+/// before waiting
+#[cfg(feature = "async")]
+pub const STREAM_BUFFER: usize = 256;
+
+/// Run a blocking search on tokio's blocking thread pool
+///
+/// A panic inside the task is resumed on the caller, while a cancelled task is
+/// reported as [`FileSearchError::AsyncTask`](crate::error::FileSearchError::AsyncTask).
+#[cfg(feature = "async")]
+async fn run_blocking<T, F>(search: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(search).await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(crate::error::FileSearchError::async_task(e)),
+    }
+}
+
+// Re-export commonly used types
+pub use crate::config::Config;
+pub use crate::error::FileSearchError;
+pub use crate::indexer::filter::Filter;
+pub use crate::indexer::FileIndex;
+pub use crate::search::custom::PatternMatcher;
+pub use crate::search::decorate::ResultDecorator;
+pub use crate::search::file_match::FileMatch;
+pub use crate::search::handle::SearchHandle;
+pub use crate::search::progress::{ProgressReporter, SearchProgress};
+pub use crate::search::result::{FileKind, SearchResult};
+pub use crate::search::results::SearchResults;
+pub use crate::search::shared::SharedSearcher;
+pub use crate::search::{SearchMode, SortOrder};
+
+// FileSearcherBuilder is already defined in this module, no need to re-export
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_structure() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        // Create test files
+        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("lib.rs"), "pub mod lib;").unwrap();
+        fs::write(root.join("config.toml"), "[config]").unwrap();
+        fs::write(root.join("README.md"), "# Test").unwrap();
+
+        // Create subdirectory
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src").join("test.rs"), "test code").unwrap();
+        fs::write(root.join("src").join("helper.rs"), "helper code").unwrap();
+
+        // Create hidden file
+        fs::write(root.join(".hidden"), "hidden content").unwrap();
+
+        temp_dir
+    }
+
+    fn test_config() -> crate::config::Config {
+        crate::config::Config {
+            ignore_hidden: false,
+            ignore_patterns: vec![], // Clear all ignore patterns for testing
+            case_sensitive: false,
+            max_depth: None,
+            max_file_size: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_basic_search() {
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::with_config(test_config());
+
+        let results = searcher.search_auto(temp_dir.path(), "*.rs").unwrap();
+        // Should find main.rs, lib.rs, src/test.rs, src/helper.rs
+        assert!(
+            results.len() >= 4,
+            "Expected at least 4 .rs files, found {}",
+            results.len()
+        );
+    }
+
+    #[test]
+    fn test_substring_search() {
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::with_config(test_config());
+
+        let results = searcher
+            .search(temp_dir.path(), "main", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0]
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .contains("main"));
+    }
+
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_glob_search() {
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::with_config(test_config());
+
+        let results = searcher
+            .search(temp_dir.path(), "*.rs", SearchMode::Glob)
+            .unwrap();
+        assert!(results.len() >= 4);
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_regex_search() {
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::with_config(test_config());
+
+        let results = searcher
+            .search(temp_dir.path(), r".*\.rs$", SearchMode::Regex)
+            .unwrap();
+        assert!(results.len() >= 4);
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_regex_limits() {
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .max_regex_len(12)
+            .regex_size_limit(10_000)
+            .regex_dfa_size_limit(10_000)
+            .build()
+            .unwrap();
+
+        let results = searcher
+            .search(temp_dir.path(), r"^main\.rs$", SearchMode::Regex)
+            .unwrap();
+        assert_eq!(results, vec![temp_dir.path().join("main.rs")]);
+
+        let too_long = searcher.search(temp_dir.path(), r"^(main|lib)\.rs$", SearchMode::Regex);
+        assert!(matches!(
+            too_long,
+            Err(FileSearchError::InvalidQuery { .. })
+        ));
+        let too_big = searcher.search(temp_dir.path(), r"\w{100}{100}", SearchMode::Regex);
+        assert!(matches!(too_big, Err(FileSearchError::InvalidRegex { .. })));
+
+        assert!(FileSearcher::builder().regex_size_limit(0).build().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_fuzzy_search() {
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::with_config(test_config());
+
+        let results = searcher.search_fuzzy(temp_dir.path(), "man").unwrap(); // should find "main"
+        assert!(!results.is_empty());
+
+        // Check that results are scored
+        for (_, score) in &results {
+            assert!(*score >= 0.0 && *score <= 1.0);
+        }
+
+        // Verify we found main.rs
+        let found_main = results
+            .iter()
+            .any(|(path, _)| path.file_name().unwrap().to_str().unwrap() == "main.rs");
+        assert!(found_main, "Should find main.rs with fuzzy search 'man'");
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_fuzzy_min_score() {
+        let temp_dir = create_test_structure();
+
+        let lenient = FileSearcher::with_config(test_config());
+        let results = lenient.search_fuzzy(temp_dir.path(), "mian.rs").unwrap();
+        assert!(results.iter().any(|(path, _)| path.ends_with("main.rs")));
+
+        let strict = FileSearcher::with_config(crate::config::Config {
+            fuzzy_min_score: 0.95,
+            ..test_config()
+        });
+        let results = strict.search_fuzzy(temp_dir.path(), "mian.rs").unwrap();
+        assert!(results.is_empty());
+        let results = strict.search_fuzzy(temp_dir.path(), "main.rs").unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_fuzzy_scoring() {
+        use crate::config::FuzzyScoringConfig;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let scores = |scoring: FuzzyScoringConfig| {
+            FileSearcher::with_config(crate::config::Config {
+                fuzzy_scoring: scoring,
+                ..test_config()
+            })
+            .search_fuzzy(root, "hlpr")
+            .unwrap()
+        };
+
+        // Only the proportions of the weights matter
+        let default = scores(FuzzyScoringConfig::default());
+        let doubled = scores(FuzzyScoringConfig {
+            levenshtein: 0.8,
+            subsequence: 0.8,
+            ngram: 0.4,
+        });
+        assert!(!default.is_empty());
+        assert_eq!(default.len(), doubled.len());
+        for ((path, score), (doubled_path, doubled_score)) in default.iter().zip(&doubled) {
+            assert_eq!(path, doubled_path);
+            assert!((score - doubled_score).abs() < 1e-9);
+        }
+        let in_order = scores(FuzzyScoringConfig {
+            levenshtein: 0.0,
+            subsequence: 1.0,
+            ngram: 0.0,
+        });
+        assert_eq!(in_order[0].0, root.join("src").join("helper.rs"));
+        assert!(in_order[0].1 > default[0].1);
+
+        for invalid in [
+            FuzzyScoringConfig {
+                levenshtein: -0.1,
+                ..FuzzyScoringConfig::default()
+            },
+            FuzzyScoringConfig {
+                levenshtein: 0.0,
+                subsequence: 0.0,
+                ngram: 0.0,
+            },
+        ] {
+            assert!(FileSearcher::builder()
+                .fuzzy_scoring(invalid)
+                .build()
+                .is_err());
+        }
+
+        let prefixes = FileSearcher::builder()
+            .config(test_config())
+            .scorer(|name: &str, query: &str| if name.starts_with(query) { 0.8 } else { 0.0 })
+            .build()
+            .unwrap();
+        let results = prefixes.search_fuzzy(root, "MA").unwrap();
+        assert_eq!(results, [(root.join("main.rs"), 0.8)]);
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_fuzzy_top_k() {
+        let temp_dir = create_test_structure();
+        let index = crate::indexer::FileIndexer::new(test_config())
+            .build_index(temp_dir.path().to_str().unwrap())
+            .unwrap();
+        let engine = crate::search::SearchEngine::new(test_config());
+
+        let all = engine.search_fuzzy(&index, "rs");
+        assert!(all.len() > 2);
+        assert_eq!(engine.search_fuzzy_top_k(&index, "rs", 2, 0.0), all[..2]);
+        assert_eq!(engine.search_fuzzy_top_k(&index, "rs", 100, 0.0), all);
+        assert!(engine.search_fuzzy_top_k(&index, "rs", 0, 0.0).is_empty());
+
+        let min_score = all[1].1;
+        let above = engine.search_fuzzy_top_k(&index, "rs", 100, min_score);
+        assert!(!above.is_empty());
+        assert!(above.iter().all(|(_, score)| *score >= min_score));
+        assert_eq!(
+            above.len(),
+            all.iter().filter(|(_, score)| *score >= min_score).count()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn test_unicode_normalization() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        // Decomposed, as macOS stores names
+        fs::write(root.join("cafe\u{301}.txt"), "").unwrap();
+        fs::write(root.join("straße.txt"), "").unwrap();
+
+        let plain = FileSearcher::with_config(test_config());
+        assert!(plain
+            .search(root, "café", SearchMode::Substring)
+            .unwrap()
+            .is_empty());
+
+        let searcher = FileSearcher::with_config(Config {
+            unicode_normalization: true,
+            ..test_config()
+        });
+        assert_eq!(
+            searcher
+                .search(root, "CAFÉ", SearchMode::Substring)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            searcher
+                .search(root, "STRASSE", SearchMode::Substring)
+                .unwrap()
+                .len(),
+            1
+        );
+        #[cfg(feature = "glob")]
+        assert_eq!(
+            searcher
+                .search(root, "café.*", SearchMode::Glob)
+                .unwrap()
+                .len(),
+            1
+        );
+        #[cfg(feature = "regex")]
+        assert_eq!(
+            searcher
+                .search(root, "^café", SearchMode::Regex)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Spans cover the whole decomposed letter
+        let engine = searcher.engine();
+        assert_eq!(
+            engine
+                .match_spans("cafe\u{301}.txt", "é", SearchMode::Substring)
+                .unwrap(),
+            vec![3..6]
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_non_utf8_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let invalid = root.join(OsStr::from_bytes(b"caf\xe9.txt"));
+        fs::write(&invalid, "").unwrap();
+
+        let searcher = FileSearcher::with_config(test_config());
+        // Matched on the lossy form, with the path kept as it is on disk
+        let results = searcher
+            .search(root, "caf\u{fffd}", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(results, vec![invalid.clone()]);
+        #[cfg(feature = "glob")]
+        assert_eq!(
+            searcher.search(root, "caf?.txt", SearchMode::Glob).unwrap(),
+            vec![invalid.clone()]
+        );
+        let detailed = searcher
+            .search_detailed(root, "caf", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(detailed.len(), 1);
+        assert_eq!(detailed[0].spans, [0..3]);
+        assert_eq!(detailed[0].size, 0);
+
+        // Incremental updates list changed and new directories with such names
+        let changed = root.join(OsStr::from_bytes(b"d\xe9"));
+        fs::create_dir(&changed).unwrap();
+        fs::write(changed.join("one.rs"), "").unwrap();
+        let root_str = root.to_str().unwrap();
+        let mut indexer = crate::indexer::FileIndexer::new(test_config());
+        let mut index = indexer.build_index(root_str).unwrap();
+        fs::write(changed.join("two.rs"), "").unwrap();
+        let added = root.join(OsStr::from_bytes(b"e\xe9"));
+        fs::create_dir(&added).unwrap();
+        fs::write(added.join("three.rs"), "").unwrap();
+        indexer.update_index(&mut index, root_str).unwrap();
+        let fresh = crate::indexer::FileIndexer::new(test_config())
+            .build_index(root_str)
+            .unwrap();
+        let mut updated: Vec<_> = index.iter().collect();
+        updated.sort();
+        let mut expected: Vec<_> = fresh.iter().collect();
+        expected.sort();
+        assert_eq!(updated, expected);
+        assert!(updated
+            .iter()
+            .any(|(_, paths)| paths.contains(&added.join("three.rs"))));
+
+        // The on-disk cache descends into them and keeps their names through
+        // a save and load
+        let cache_dir = TempDir::new().unwrap();
+        let cache_path = cache_dir.path().join("index.cache");
+        let cache =
+            crate::indexer::cache::IndexCache::open(&cache_path, root, &test_config()).unwrap();
+        assert!(cache.files().any(|path| path == added.join("three.rs")));
+        let mut reloaded = crate::indexer::cache::IndexCache::load(&cache_path).unwrap();
+        assert_eq!(reloaded, cache);
+        assert_eq!(reloaded.refresh(&test_config()).unwrap(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_full_path_glob() {
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src/nested")).unwrap();
+        fs::write(root.join("src/nested/deep.rs"), "").unwrap();
+        let searcher = FileSearcher::with_config(test_config());
+        let glob = |searcher: &FileSearcher, pattern: &str| {
+            searcher.search(root, pattern, SearchMode::Glob).unwrap()
+        };
+
+        assert_eq!(
+            glob(&searcher, "src/**/*.rs"),
+            vec![
+                root.join("src/helper.rs"),
+                root.join("src/nested/deep.rs"),
+                root.join("src/test.rs"),
+            ]
+        );
+        // `*` stays within one directory in path patterns
+        assert_eq!(glob(&searcher, "src/*.rs").len(), 2);
+        assert_eq!(
+            glob(&searcher, &format!("{}/*.rs", root.display())),
+            vec![root.join("lib.rs"), root.join("main.rs")]
+        );
+        assert_eq!(
+            searcher.engine().detect_search_mode("src/**/*.rs"),
+            SearchMode::Glob
+        );
+
+        assert_eq!(
+            glob(&searcher, "nested/*"),
+            vec![root.join("src/nested/deep.rs")]
+        );
+
+        // Relative patterns match below any directory, so a separator-free
+        // pattern finds the same files as a file name pattern
+        let full = FileSearcher::with_config(Config {
+            match_full_path: true,
+            ..test_config()
+        });
+        assert_eq!(glob(&full, "*.rs"), glob(&searcher, "*.rs"));
+        assert_eq!(glob(&full, "*.rs").len(), 5);
+
+        // Directories above the search root take no part in the match
+        let proj = root.join("src/nested");
+        fs::create_dir(proj.join("src")).unwrap();
+        fs::write(proj.join("src/inner.rs"), "").unwrap();
+        let below = |pattern: &str| searcher.search(&proj, pattern, SearchMode::Glob).unwrap();
+        assert_eq!(below("src/**/*.rs"), vec![proj.join("src/inner.rs")]);
+        assert!(below("nested/*.rs").is_empty());
+    }
+
+    #[test]
+    fn test_file_type() {
+        use crate::config::FileType;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("src"), root.join("src-link")).unwrap();
+
+        let search = |file_type, query: &str| {
+            let searcher = FileSearcher::with_config(Config {
+                file_type,
+                ..test_config()
+            });
+            let mut paths = searcher.search(root, query, SearchMode::Substring).unwrap();
+            paths.sort();
+            paths
+        };
+
+        assert!(search(FileType::Files, "src").is_empty());
+        assert_eq!(search(FileType::Dirs, "src"), vec![root.join("src")]);
+        assert!(search(FileType::Dirs, "main").is_empty());
+        #[cfg(unix)]
+        {
+            assert_eq!(
+                search(FileType::Symlinks, "src"),
+                vec![root.join("src-link")]
+            );
+            assert_eq!(
+                search(FileType::All, "src"),
+                vec![root.join("src"), root.join("src-link")]
+            );
+        }
+
+        // Cached indexes record directories alongside files
+        let cache_dir = TempDir::new().unwrap();
+        let searcher = FileSearcher::with_config(Config {
+            file_type: FileType::All,
+            ..test_config()
+        });
+        let index = searcher
+            .index_cached(root, &cache_dir.path().join("index.cache"))
+            .unwrap();
+        let found = searcher
+            .search_in_index(&index, "src", SearchMode::Substring)
+            .unwrap();
+        assert!(found.contains(&root.join("src")));
+        assert!(!found.contains(&root.to_path_buf()));
+    }
+
+    #[test]
+    fn test_min_depth() {
+        use crate::indexer::file_walker::IgnoreReason;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src/nested")).unwrap();
+        fs::write(root.join("src/nested/deep.rs"), "deep").unwrap();
+
+        let depth_range = Config {
+            min_depth: Some(2),
+            max_depth: Some(2),
+            ..test_config()
+        };
+        let searcher = FileSearcher::with_config(depth_range.clone());
+        let mut paths = searcher.search(root, "rs", SearchMode::Substring).unwrap();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![root.join("src/helper.rs"), root.join("src/test.rs")]
+        );
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("main.rs")),
+            Some(IgnoreReason::MinDepth {
+                depth: 1,
+                min_depth: 2,
+            })
+        );
+
+        // Cached indexes list each directory on its own, at its depth
+        let cache_dir = TempDir::new().unwrap();
+        let index = searcher
+            .index_cached(root, &cache_dir.path().join("index.cache"))
+            .unwrap();
+        let mut cached = searcher
+            .search_in_index(&index, "rs", SearchMode::Substring)
+            .unwrap();
+        cached.sort();
+        assert_eq!(cached, paths);
+
+        let deep = FileSearcher::with_config(Config {
+            max_depth: None,
+            min_depth: Some(3),
+            ..depth_range
+        });
+        assert_eq!(
+            deep.search(root, "rs", SearchMode::Substring).unwrap(),
+            vec![root.join("src/nested/deep.rs")]
+        );
+
+        assert!(FileSearcher::builder()
+            .max_depth(1)
+            .min_depth(2)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_relative_path_match() {
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+
+        let relative = FileSearcher::with_config(Config {
+            match_relative_path: true,
+            ..test_config()
+        });
+        let search = |searcher: &FileSearcher, query: &str, mode| {
+            let mut paths = searcher.search(root, query, mode).unwrap();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            search(&relative, "src/help", SearchMode::Substring),
+            vec![root.join("src/helper.rs")]
+        );
+        assert!(search(
+            &FileSearcher::with_config(test_config()),
+            "src/help",
+            SearchMode::Substring
+        )
+        .is_empty());
+        // Directories above the root are not part of the relative path
+        let parent = root.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(search(&relative, &format!("{parent}/main"), SearchMode::Substring).is_empty());
+
+        #[cfg(feature = "regex")]
+        assert_eq!(
+            search(&relative, "^src/.*\\.rs$", SearchMode::Regex),
+            vec![root.join("src/helper.rs"), root.join("src/test.rs")]
+        );
+
+        #[cfg(feature = "fuzzy")]
+        {
+            let results = relative.search_fuzzy(root, "src/helper").unwrap();
+            assert_eq!(results[0].0, root.join("src/helper.rs"));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_depth_ranking() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let deep = root.join("a").join("b").join("c");
+        fs::create_dir_all(&deep).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        // The deep file sorts first by path, so only depth can put it last
+        fs::write(deep.join("main.rs"), "deep").unwrap();
+        fs::write(root.join("src/main.rs"), "shallow").unwrap();
+
+        let searcher = FileSearcher::with_config(test_config());
+        let results = searcher.search_fuzzy(root, "main.rs").unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].0.starts_with(root.join("src")));
+        assert!((results[0].1 - results[1].1).abs() < f64::EPSILON);
+
+        let flat = FileSearcher::with_config(crate::config::Config {
+            depth_penalty: 0.0,
+            ..test_config()
+        });
+        let results = flat.search_fuzzy(root, "main.rs").unwrap();
+        assert!(results[0].0.starts_with(root.join("a")));
+
+        let paths = searcher
+            .search(root, "main", crate::search::SearchMode::Substring)
+            .unwrap();
+        assert!(paths[0].starts_with(root.join("a")));
+        let shallow = FileSearcher::with_config(crate::config::Config {
+            shallow_first: true,
+            ..test_config()
+        });
+        let paths = shallow
+            .search(root, "main", crate::search::SearchMode::Substring)
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+        assert!(paths[0].starts_with(root.join("src")));
+
+        let invalid = FileSearcher::builder().depth_penalty(-1.0).build();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_recency_ranking() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("a_old")).unwrap();
+        fs::create_dir_all(root.join("b_new")).unwrap();
+        fs::write(root.join("a_old/notes.txt"), "old").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        fs::write(root.join("b_new/notes.txt"), "new").unwrap();
+
+        let searcher = FileSearcher::with_config(test_config());
+        let results = searcher.search_fuzzy(root, "notes.txt").unwrap();
+        assert!(results[0].0.starts_with(root.join("a_old")));
+
+        let recent = FileSearcher::with_config(crate::config::Config {
+            recency_weight: 0.5,
+            recency_half_life: std::time::Duration::from_millis(100),
+            ..test_config()
+        });
+        let results = recent.search_fuzzy(root, "notes.txt").unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].0.starts_with(root.join("b_new")));
+        assert!((results[0].1 - results[1].1).abs() < f64::EPSILON);
+
+        assert!(FileSearcher::builder().recency_weight(1.5).build().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_extension_weights() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for name in ["app.js", "app.min.js", "app.rs", ".app"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+
+        let searcher = FileSearcher::builder()
+            .ignore_hidden(false)
+            .extension_weight("min.js", 0.1)
+            .extension_weight(".JS", 2.0)
+            .build()
+            .unwrap();
+        let results = searcher.search_fuzzy(root, "app").unwrap();
+        let names: Vec<_> = results
+            .iter()
+            .map(|(path, _)| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names.first(), Some(&"app.js"));
+        assert_eq!(names.last(), Some(&"app.min.js"));
+
+        assert!(FileSearcher::builder()
+            .extension_weight("lock", -1.0)
+            .build()
+            .is_err());
+        assert!(FileSearcher::builder()
+            .recency_half_life(std::time::Duration::ZERO)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_extension_weights_with_recency() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("notes.txt"), "old").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        fs::write(root.join("notes.rs"), "new").unwrap();
+
+        // The newer file's recency must not outweigh its extension weight
+        let searcher = FileSearcher::with_config(crate::config::Config {
+            recency_weight: 0.5,
+            recency_half_life: std::time::Duration::from_millis(100),
+            extension_weights: [("rs".to_string(), 0.0), ("txt".to_string(), 10.0)].into(),
+            ..test_config()
+        });
+        let results = searcher.search_fuzzy(root, "notes").unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, root.join("notes.txt"));
+    }
+
+    #[test]
+    fn test_traversal_limits() {
+        use crate::indexer::file_walker::TraversalLimit;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let limited = |config: crate::config::Config| {
+            FileSearcher::with_config(config).search(root, ".rs", SearchMode::Substring)
+        };
+
+        match limited(crate::config::Config {
+            max_entries: Some(3),
+            ..test_config()
+        }) {
+            Err(FileSearchError::LimitExceeded { limit, partial }) => {
+                assert_eq!(limit, TraversalLimit::Entries(3));
+                assert!(partial.len() < 4);
+            }
+            other => panic!("expected the entry limit, got {other:?}"),
+        }
+        assert!(matches!(
+            limited(crate::config::Config {
+                max_dirs: Some(1),
+                ..test_config()
+            }),
+            Err(FileSearchError::LimitExceeded {
+                limit: TraversalLimit::Dirs(1),
+                ..
+            })
+        ));
+        let all = limited(crate::config::Config {
+            max_dirs: Some(2),
+            max_entries: Some(9),
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(all.len(), 4);
+        assert!(FileSearcher::builder().max_entries(0).build().is_err());
+
+        let timeout = std::time::Duration::from_nanos(1);
+        let mut config = test_config();
+        config.search_timeout = Some(timeout);
+        match limited(config) {
+            Err(FileSearchError::LimitExceeded { limit, partial }) => {
+                assert_eq!(limit, TraversalLimit::Time(timeout));
+                assert!(partial.len() < 4);
+            }
+            other => panic!("expected the time limit, got {other:?}"),
+        }
+        let patient = FileSearcher::builder()
+            .config(test_config())
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .unwrap();
+        assert_eq!(
+            patient
+                .search(root, ".rs", SearchMode::Substring)
+                .unwrap()
+                .len(),
+            4
+        );
+        assert!(FileSearcher::builder()
+            .timeout(std::time::Duration::ZERO)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_timeout_with_slow_sources() {
+        use crate::indexer::file_walker::TraversalLimit;
+        use std::time::{Duration, Instant};
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let timeout = Duration::from_millis(250);
+        let slow = Duration::from_millis(100);
+
+        // A walk whose files each take a while to read
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .timeout(timeout)
+            .filter(move |_: &walkdir::DirEntry| {
+                std::thread::sleep(slow);
+                true
+            })
+            .build()
+            .unwrap();
+        let started = Instant::now();
+        match searcher.search(root, ".rs", SearchMode::Substring) {
+            Err(FileSearchError::LimitExceeded { limit, .. }) => {
+                assert_eq!(limit, TraversalLimit::Time(timeout));
+            }
+            other => panic!("expected the time limit, got {other:?}"),
+        }
+        assert!(started.elapsed() < timeout + 3 * slow);
+
+        // Matching that outlasts the deadline stops there, keeping the
+        // matches found before it
+        let searcher = FileSearcher::builder()
+            .config(crate::config::Config {
+                match_threads: Some(1),
+                ..test_config()
+            })
+            .timeout(timeout)
+            .matcher(move |_: &str, _: &str| {
+                std::thread::sleep(slow);
+                true
+            })
+            .build()
+            .unwrap();
+        let started = Instant::now();
+        match searcher.search(root, "x", SearchMode::Custom) {
+            Err(FileSearchError::LimitExceeded { limit, partial }) => {
+                assert_eq!(limit, TraversalLimit::Time(timeout));
+                assert!(!partial.is_empty() && partial.len() < 7);
+            }
+            other => panic!("expected the time limit, got {other:?}"),
+        }
+        assert!(started.elapsed() < timeout + 3 * slow);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_max_symlink_depth() {
+        use crate::indexer::file_walker::IgnoreReason;
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("real")).unwrap();
+        fs::create_dir_all(root.join("other")).unwrap();
+        fs::write(root.join("other/deep.txt"), "deep").unwrap();
+        symlink(root.join("real"), root.join("link")).unwrap();
+        symlink(root.join("other"), root.join("real/nested")).unwrap();
+        // A loop back to the root is skipped rather than failing the walk
+        symlink(root, root.join("real/back")).unwrap();
+
+        let count = |depth: Option<usize>| {
+            FileSearcher::with_config(crate::config::Config {
+                max_symlink_depth: depth,
+                ..test_config()
+            })
+            .search(root, "deep", SearchMode::Substring)
+            .unwrap()
+            .len()
+        };
+        assert_eq!(count(None), 1);
+        assert_eq!(count(Some(1)), 2);
+        assert_eq!(count(Some(2)), 3);
+
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .max_symlink_depth(1)
+            .build()
+            .unwrap();
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("link/nested/deep.txt")),
+            Some(IgnoreReason::SymlinkDepth {
+                path: root.join("link/nested"),
+                max_symlink_depth: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_word_boundaries() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for name in ["error.log", "log_parser.rs", "catalog.pdf", "LOG-2024.txt"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .word_boundaries(true)
+            .build()
+            .unwrap();
+        let names: Vec<_> = searcher
+            .search(root, "log", SearchMode::Substring)
+            .unwrap()
+            .into_iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["LOG-2024.txt", "error.log", "log_parser.rs"]);
+
+        let all = FileSearcher::with_config(test_config())
+            .search(root, "log", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(all.len(), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_fuzzy_path_components() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("docs").join("src_old")).unwrap();
+        fs::write(root.join("src/main.rs"), "main").unwrap();
+        fs::write(root.join("srcmain_backup.txt"), "backup").unwrap();
+        fs::write(root.join("docs/src_old/main.rs"), "old").unwrap();
+
+        // A high threshold keeps the parts from matching the temporary
+        // directory's random name
+        let searcher = FileSearcher::with_config(crate::config::Config {
+            fuzzy_min_score: 0.7,
+            ..test_config()
+        });
+        let results = searcher.search_fuzzy(root, "src/mai").unwrap();
+        let paths: Vec<_> = results.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![root.join("src/main.rs"), root.join("docs/src_old/main.rs")]
+        );
+
+        let results = searcher.search_fuzzy(root, "src/main.rs").unwrap();
+        assert!((results[0].1 - 1.0).abs() < f64::EPSILON);
+
+        // Directory parts match from right to left, nearest directory first
+        let results = searcher.search_fuzzy(root, "docs/src/main").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, root.join("docs/src_old/main.rs"));
+        assert!(searcher
+            .search_fuzzy(root, "src/docs/main")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "fuzzy")]
+    fn test_fuzzy_candidate_index() {
+        let temp_dir = create_test_structure();
+        let config = crate::config::Config {
+            fuzzy_candidate_index: true,
+            ..test_config()
+        };
+
+        let mut indexer = crate::indexer::FileIndexer::new(config.clone());
+        let index = indexer
+            .build_index(temp_dir.path().to_str().unwrap())
+            .unwrap();
+        let ngrams = indexer.ngram_index().unwrap();
+        assert!(!ngrams.is_empty());
+
+        let candidates = ngrams.candidates("hlper").unwrap();
+        assert!(candidates.contains("helper.rs"));
+        assert!(!candidates.contains("readme.md"));
+        assert!(ngrams.candidates("h").is_none());
+
+        let engine = crate::search::SearchEngine::new(config);
+        let full = engine.search_fuzzy(&index, "hlper");
+        let narrowed = engine.search_fuzzy_candidates(&index, ngrams, "hlper");
+        assert_eq!(full, narrowed);
+    }
+
+    #[test]
+    #[cfg(all(feature = "regex", feature = "glob"))]
+    fn test_auto_detection() {
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::with_config(test_config());
+
+        // Should detect as glob
+        let (results, mode) = searcher
+            .search_auto_with_mode(temp_dir.path(), "*.rs")
+            .unwrap();
+        assert_eq!(mode, SearchMode::Glob);
+        assert!(results.len() >= 4);
+
+        // Should detect as regex
+        let (results, mode) = searcher
+            .search_auto_with_mode(temp_dir.path(), r"\.rs$")
+            .unwrap();
+        assert_eq!(mode, SearchMode::Regex);
+        assert!(results.len() >= 4);
+
+        // Should detect as substring
+        let (results, mode) = searcher
+            .search_auto_with_mode(temp_dir.path(), "main")
+            .unwrap();
+        assert_eq!(mode, SearchMode::Substring);
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    #[cfg(all(feature = "regex", feature = "glob"))]
+    fn test_explain_detection() {
+        use crate::search::detect::{DetectionPolicy, Reason, Signal};
+
+        let engine = crate::search::SearchEngine::new(test_config());
+        let report = engine.explain_detection("report (final).pdf");
+        assert_eq!(report.mode, SearchMode::Regex);
+        assert_eq!(report.reason, Reason::RegexSyntax);
+        assert_eq!(report.signals, [Signal::Group]);
+        let report = engine.explain_detection("file[0-9]*.txt");
+        assert_eq!(report.mode, SearchMode::Regex);
+        assert_eq!(report.signals, [Signal::CharacterClass, Signal::Wildcard]);
+        assert_eq!(
+            engine.explain_detection("a(b*").reason,
+            Reason::AmbiguousWildcards
+        );
+        assert_eq!(engine.explain_detection("main").reason, Reason::PlainText);
+        assert_eq!(
+            engine.explain_detection("*.rs").to_string(),
+            "glob search: the query has wildcards and no regex syntax (found wildcards)"
+        );
+
+        let never_regex = crate::search::SearchEngine::new(Config {
+            detection: DetectionPolicy {
+                regex: false,
+                ..DetectionPolicy::default()
+            },
+            ..test_config()
+        });
+        let report = never_regex.explain_detection("report (final).pdf");
+        assert_eq!(report.mode, SearchMode::Substring);
+        assert_eq!(report.reason, Reason::Disabled(SearchMode::Regex));
+
+        let prefer_glob = FileSearcher::builder()
+            .config(test_config())
+            .detection_policy(DetectionPolicy {
+                prefer_glob: true,
+                ..DetectionPolicy::default()
+            })
+            .build()
+            .unwrap();
+        let report = prefer_glob.engine().explain_detection("file[0-9]*.txt");
+        assert_eq!(report.mode, SearchMode::Glob);
+        assert_eq!(report.reason, Reason::PreferGlob);
+        assert_eq!(
+            prefer_glob.engine().detect_search_mode(r"^main\.rs$"),
+            SearchMode::Regex
+        );
+    }
+
+    #[test]
+    fn test_literal_mode() {
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::write(root.join("report (final).pdf"), "").unwrap();
+        fs::write(root.join("c++[1].txt"), "").unwrap();
+        let searcher = FileSearcher::with_config(test_config());
+
+        for query in ["report (final)", "C++[1]", "(FINAL).pdf"] {
+            let results = searcher.search(root, query, SearchMode::Literal).unwrap();
+            assert_eq!(results.len(), 1, "{query}");
+        }
+        assert!(searcher
+            .search(root, "report.*", SearchMode::Literal)
+            .unwrap()
+            .is_empty());
+        let detailed = searcher
+            .search_detailed(root, "(final)", SearchMode::Literal)
+            .unwrap();
+        assert_eq!(detailed[0].spans, [7..14]);
+        assert!(searcher.search(root, "", SearchMode::Literal).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_builder_pattern() {
+        let temp_dir = create_test_structure();
+
+        // Test that the builder pattern works
+        let searcher = FileSearcher::builder()
+            .ignore_hidden(false)
+            .clear_ignore_patterns() // Clear defaults first
+            .case_sensitive(false)
+            .build()
+            .unwrap();
+
+        let results = searcher.search_auto(temp_dir.path(), "*.rs").unwrap();
+        // Should find all .rs files with builder configuration
+        assert!(results.len() >= 4, "Builder pattern should work correctly");
+    }
+
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_ignore_patterns() {
+        let temp_dir = create_test_structure();
+
+        let searcher = FileSearcher::builder()
+            .ignore_hidden(false)
+            .clear_ignore_patterns() // Clear defaults first
+            .ignore_pattern("*.md")
+            .build()
+            .unwrap();
+
+        let results = searcher.search_auto(temp_dir.path(), "*").unwrap();
+        // Should not include README.md
+        assert!(!results.iter().any(|p| p
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))));
+    }
+
+    #[test]
+    fn test_index_backend() {
+        use crate::indexer::backend::IndexBackend;
+
+        /// Flat list backend standing in for an external store
+        #[derive(Default)]
+        struct ListBackend(Vec<(String, PathBuf)>);
+
+        impl IndexBackend for ListBackend {
+            fn insert(&mut self, name: String, path: PathBuf) {
+                self.0.push((name, path));
+            }
+
+            fn for_each_name(&self, f: &mut dyn FnMut(&str, &[PathBuf])) {
+                for (name, path) in &self.0 {
+                    f(name, std::slice::from_ref(path));
+                }
+            }
+
+            fn lookup_exact(&self, name: &str) -> Vec<PathBuf> {
+                self.0
+                    .iter()
+                    .filter(|(n, _)| n == name)
+                    .map(|(_, path)| path.clone())
+                    .collect()
+            }
+
+            fn name_count(&self) -> usize {
+                self.0.len()
+            }
+        }
+
+        let temp_dir = create_test_structure();
+        let mut backend = ListBackend::default();
+        let mut indexer = crate::indexer::FileIndexer::new(test_config());
+        indexer
+            .build_into(temp_dir.path().to_str().unwrap(), &mut backend)
+            .unwrap();
+        let index = indexer
+            .build_index(temp_dir.path().to_str().unwrap())
+            .unwrap();
+        assert_eq!(backend.path_count(), index.path_count());
+
+        let engine = crate::search::SearchEngine::new(test_config());
+        assert_eq!(
+            engine
+                .search(&backend, "HELPER", SearchMode::Substring)
+                .unwrap(),
+            engine
+                .search(&index, "HELPER", SearchMode::Substring)
+                .unwrap()
+        );
+        assert_eq!(backend.lookup_exact("main.rs").len(), 1);
+    }
+
+    #[test]
+    fn test_file_index_layout() {
+        use crate::indexer::backend::IndexBackend;
+
+        let entry = |name: &str, path: &str| (name.to_string(), PathBuf::from(path));
+        let mut index: FileIndex = vec![
+            entry("b.rs", "x/b.rs"),
+            entry("a.rs", "x/a.rs"),
+            entry("b.rs", "y/b.rs"),
+        ]
+        .into_iter()
+        .collect();
+        index.extend(vec![entry("c.rs", "x/c.rs"), entry("b.rs", "z/b.rs")]);
+        index.insert("0.rs".to_string(), PathBuf::from("x/0.rs"));
+
+        let keys: Vec<&str> = index.iter().map(|(name, _)| name).collect();
+        assert_eq!(keys, vec!["0.rs", "a.rs", "b.rs", "c.rs"]);
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.path_count(), 6);
+        // Paths of one key stay in the order they were added
+        assert_eq!(
+            index.get("b.rs").unwrap(),
+            [
+                PathBuf::from("x/b.rs"),
+                PathBuf::from("y/b.rs"),
+                PathBuf::from("z/b.rs")
+            ]
+        );
+        assert!(index.get("d.rs").is_none());
+        assert_eq!(index.lookup_exact("a.rs"), vec![PathBuf::from("x/a.rs")]);
+
+        let rebuilt: FileIndex = index.clone().into_iter().collect();
+        assert_eq!(rebuilt, index);
+        assert!(FileIndex::new().is_empty());
+    }
+
+    #[test]
+    #[cfg(all(feature = "config", feature = "fuzzy"))]
+    fn test_dir_history() {
+        use crate::search::history::DirHistory;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("work").join("report")).unwrap();
+        fs::create_dir_all(root.join("archive").join("reports")).unwrap();
+        let mut indexer = crate::indexer::FileIndexer::new(test_config());
+        let dirs = indexer.build_dir_index(root.to_str().unwrap()).unwrap();
+        assert!(dirs.get("report").is_some());
+        assert!(dirs
+            .iter()
+            .all(|(_, paths)| paths.iter().all(|p| p.is_dir())));
+
+        let engine = crate::search::SearchEngine::new(test_config());
+        let now = 1_000_000;
+        let mut history = DirHistory::new();
+        let best = history.jump(&engine, &dirs, "report", now).unwrap();
+        assert!(best.ends_with("work/report"));
+
+        // Frequent recent visits outweigh a slightly better name match
+        let reports = root.join("archive").join("reports");
+        for _ in 0..3 {
+            history.record(&reports, now);
+        }
+        assert!((history.frecency(&reports, now) - 12.0).abs() < f64::EPSILON);
+        let weeks_later = now + 2 * 7 * 24 * 3600;
+        assert!((history.frecency(&reports, weeks_later) - 0.75).abs() < f64::EPSILON);
+        let best = history.jump(&engine, &dirs, "report", now).unwrap();
+        assert_eq!(best, reports.canonicalize().unwrap());
+
+        let path = root.join("state").join("history.json");
+        history.save(&path).unwrap();
+        assert_eq!(DirHistory::load(&path).unwrap(), history);
+        assert_eq!(
+            DirHistory::load(&root.join("missing.json")).unwrap(),
+            DirHistory::new()
+        );
+    }
+
+    #[test]
+    fn test_file_actions() {
+        use crate::actions::{self, DeleteMode, Operation};
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let target = root.join("target");
+        fs::create_dir(&target).unwrap();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("src").join("main.rs"), "").unwrap();
+        let files = [
+            root.join("main.rs"),
+            root.join("src").join("main.rs"),
+            root.join("README.md"),
+        ];
+        fs::write(target.join("README.md"), "").unwrap();
+
+        // A dry run changes nothing and reports the clashes up front
+        let plan = actions::copy_to(&files, &target, true).unwrap();
+        assert_eq!(
+            plan.operations,
+            vec![Operation::Copy {
+                from: files[0].clone(),
+                to: target.join("main.rs"),
+            }]
+        );
+        assert_eq!(plan.errors.len(), 2);
+        assert!(!target.join("main.rs").exists());
+
+        let report = actions::copy_to(&files, &target, false).unwrap();
+        assert_eq!(report.operations, plan.operations);
+        assert!(target.join("main.rs").exists());
+        assert!(files[0].exists());
+
+        let moved = actions::move_to(&files[1..2], &root.join("docs"), false).unwrap();
+        assert!(moved.is_success());
+        assert!(!files[1].exists());
+        assert!(root.join("docs").join("main.rs").exists());
+        assert!(actions::move_to(&files, &files[0], false).is_err());
+
+        let report = actions::delete(&files[..2], DeleteMode::Permanent, false);
+        assert_eq!(report.operations, vec![Operation::Delete(files[0].clone())]);
+        assert_eq!(report.errors[0].0, files[1]);
+        assert!(!files[0].exists());
+    }
+
+    #[test]
+    fn test_search_results_sizes() {
+        use crate::search::results::SizeTotal;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::write(root.join("src").join("big.RS"), "x".repeat(100)).unwrap();
+        let searcher = FileSearcher::with_config(test_config());
+        let results = searcher
+            .search_results(root, ".rs", SearchMode::Substring)
+            .unwrap();
+
+        // main.rs, lib.rs, test.rs, helper.rs and big.RS
+        assert_eq!(results.len(), 5);
+        assert_eq!(results.total_size(), 12 + 12 + 9 + 11 + 100);
+        let by_extension = results.size_by_extension();
+        assert_eq!(by_extension.len(), 1);
+        assert_eq!(
+            by_extension["rs"],
+            SizeTotal {
+                files: 5,
+                bytes: 144
+            }
+        );
+        let by_directory = results.size_by_directory();
+        assert_eq!(by_directory[root].bytes, 24);
+        assert_eq!(by_directory[&root.join("src")].files, 3);
+
+        // Directories and vanished files count no bytes
+        let results: SearchResults = vec![root.join("src"), root.join("gone")].into();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results.total_size(), 0);
+        assert_eq!(results.size_by_extension()[""].files, 2);
+    }
+
+    #[test]
+    fn test_size_report() {
+        use crate::search::results::SizeTotal;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src").join("nested")).unwrap();
+        fs::write(root.join("src").join("nested").join("deep.rs"), "12345").unwrap();
+        let report = FileSearcher::with_config(test_config())
+            .size_report(root)
+            .unwrap();
+
+        assert_eq!(report.total.files, 8);
+        assert_eq!(
+            report.by_directory["src"],
+            SizeTotal {
+                files: 3,
+                bytes: 9 + 11 + 5
+            }
+        );
+        assert_eq!(report.by_directory["."].files, 5);
+        assert_eq!(report.by_extension[""].files, 1);
+        assert_eq!(report.by_extension["rs"].files, 5);
+
+        let mut csv = Vec::new();
+        report.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("group,key,files,bytes\ntotal,,8,"));
+        assert!(csv.contains("\ndirectory,src,3,25\n"));
+
+        let mut table = Vec::new();
+        report.write_table(&mut table).unwrap();
+        assert!(String::from_utf8(table).unwrap().contains("(none)"));
+    }
+
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_search_query() {
+        use crate::search::query::{Bound, Query};
+        use std::time::Duration;
+
+        let query =
+            Query::parse(r#"name:*.rs size:>1M mtime:<7d ext:.rs,TOML "my notes" C:x"#).unwrap();
+        assert_eq!(
+            query,
+            Query {
+                names: vec!["*.rs".into(), "my notes".into(), "C:x".into()],
+                extensions: vec!["rs".into(), "TOML".into()],
+                size: vec![Bound::Over(1 << 20)],
+                age: vec![Bound::Under(Duration::from_secs(7 * 24 * 60 * 60))],
+                ..Query::default()
+            }
+        );
+        for invalid in ["size:big", "mtime:>7y", "ext:", "name:\"open"] {
+            assert!(
+                matches!(
+                    Query::parse(invalid),
+                    Err(FileSearchError::InvalidQuery { .. })
+                ),
+                "{invalid}"
+            );
+        }
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::write(root.join("big.rs"), vec![b'x'; 2048]).unwrap();
+        let searcher = FileSearcher::with_config(test_config());
+        let names = |query: &str| {
+            searcher
+                .search_query(root, query)
+                .unwrap()
+                .iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names("size:>1k"), ["big.rs"]);
+        assert_eq!(names("ext:rs size:<12"), ["helper.rs", "test.rs"]);
+        assert_eq!(names("name:*.md"), ["README.md"]);
+        assert_eq!(names("ext:toml,md mtime:<1h"), ["README.md", "config.toml"]);
+        assert!(names("ext:toml mtime:>1d").is_empty());
+        assert_eq!(names("lib size:12"), ["lib.rs"]);
+        assert_eq!(names("ext:rs path:src"), ["helper.rs", "test.rs"]);
+
+        let by_query = |query: &Query| {
+            let mut names: Vec<_> = searcher
+                .search_by_query(root, query)
+                .unwrap()
+                .iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+        let rust_outside_src = Query::all([Query::name("*.rs"), Query::not(Query::path("src"))]);
+        assert_eq!(by_query(&rust_outside_src), ["big.rs", "lib.rs", "main.rs"]);
+        let either = Query::any([Query::name("main"), "ext:md".parse().unwrap()]);
+        assert_eq!(by_query(&either), ["README.md", "main.rs"]);
+        assert_eq!(
+            by_query(&Query::all([either, Query::not(Query::name("*.md"))])),
+            ["main.rs"]
+        );
+        assert!(by_query(&Query::any([])).is_empty());
+        assert_eq!(by_query(&Query::all([])).len(), 8);
+    }
+
+    #[test]
+    #[cfg(not(feature = "glob"))]
+    fn test_search_query_without_glob() {
+        use crate::search::query::Query;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let searcher = FileSearcher::with_config(test_config());
+        assert!(matches!(
+            searcher.search_query(root, "name:*.md"),
+            Err(FileSearchError::FeatureDisabled { feature: "glob" })
+        ));
+        assert!(matches!(
+            searcher.search_by_query(root, &Query::not(Query::name("*.md"))),
+            Err(FileSearchError::FeatureDisabled { feature: "glob" })
+        ));
+        assert_eq!(searcher.search_query(root, "ext:md").unwrap().len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_walk_filters() {
+        use walkdir::DirEntry;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .filter(|entry: &DirEntry| entry.metadata().is_ok_and(|m| m.len() < 13))
+            .filter(|entry: &DirEntry| !entry.path().ends_with("lib.rs"))
+            .build()
+            .unwrap();
+        let mut found: Vec<_> = searcher
+            .search(root, "*.rs", SearchMode::Glob)
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        found.sort();
+        // Files in src are still found, so directories are not filtered
+        assert_eq!(found, ["helper.rs", "main.rs", "test.rs"]);
+
+        let roots = [root.to_path_buf()];
+        assert_eq!(
+            searcher
+                .search_multi(&roots, "*.rs", SearchMode::Glob)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_custom_matcher() {
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let unregistered = FileSearcher::with_config(test_config());
+        assert!(matches!(
+            unregistered.search(root, "x", SearchMode::Custom),
+            Err(FileSearchError::InvalidConfig { .. })
+        ));
+
+        // Names with the letters of the query in any order
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .matcher(|name: &str, query: &str| {
+                let stem = name.split('.').next().unwrap_or(name);
+                let mut letters: Vec<char> = stem.chars().collect();
+                let mut wanted: Vec<char> = query.chars().collect();
+                letters.sort_unstable();
+                wanted.sort_unstable();
+                letters == wanted
+            })
+            .build()
+            .unwrap();
+        let found = searcher.search(root, "nima", SearchMode::Custom).unwrap();
+        assert_eq!(found, [root.join("main.rs")]);
+        let detailed = searcher
+            .search_detailed(root, "nima", SearchMode::Custom)
+            .unwrap();
+        assert_eq!(detailed[0].spans, [0..7]);
+
+        let mut streamed = Vec::new();
+        let flow = searcher
+            .search_with(root, "pelhre", SearchMode::Custom, |path| {
+                streamed.push(path.to_path_buf());
+                ControlFlow::<()>::Continue(())
+            })
+            .unwrap();
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(streamed, [root.join("src").join("helper.rs")]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "regex", feature = "glob"))]
+    fn test_compiled_query() {
+        use crate::search::SearchEngine;
+
+        let temp_dir = create_test_structure();
+        let other = TempDir::new().unwrap();
+        fs::write(other.path().join("other.rs"), "").unwrap();
+        let engine = SearchEngine::new(test_config());
+        let indexes: Vec<FileIndex> = [temp_dir.path(), other.path()]
+            .iter()
+            .map(|root| {
+                crate::indexer::FileIndexer::new(test_config())
+                    .build_index(root.to_str().unwrap())
+                    .unwrap()
+            })
+            .collect();
+
+        for (query, mode) in [
+            (r"^(main|other)\.rs$", SearchMode::Regex),
+            ("*.RS", SearchMode::Glob),
+            ("READ", SearchMode::Substring),
+        ] {
+            let compiled = engine.compile(query, mode).unwrap();
+            assert_eq!(compiled.query(), query);
+            assert_eq!(compiled.mode(), mode);
+            for index in &indexes {
+                assert_eq!(
+                    engine.search_compiled(index, &compiled),
+                    engine.search(index, query, mode).unwrap()
+                );
+            }
+        }
+        assert_eq!(
+            engine
+                .search_compiled(
+                    &indexes[1],
+                    &engine.compile("*.rs", SearchMode::Glob).unwrap()
+                )
+                .len(),
+            1
+        );
+        assert!(matches!(
+            engine.compile("(", SearchMode::Regex),
+            Err(FileSearchError::InvalidRegex { .. })
+        ));
+        assert!(matches!(
+            engine.compile("x", SearchMode::Custom),
+            Err(FileSearchError::InvalidConfig { .. })
+        ));
+
+        let shared = SharedSearcher::new(temp_dir.path(), test_config()).unwrap();
+        let compiled = shared.compile("main", SearchMode::Substring).unwrap();
+        assert_eq!(
+            shared.search_compiled(&compiled),
+            [temp_dir.path().join("main.rs")]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_search_multi() {
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let other = TempDir::new().unwrap();
+        fs::write(other.path().join("other.rs"), "").unwrap();
+        let searcher = FileSearcher::with_config(test_config());
+
+        // The nested and repeated roots are already covered by `root`
+        let roots = [
+            root.join("src"),
+            other.path().to_path_buf(),
+            root.to_path_buf(),
+            root.join("."),
+        ];
+        assert_eq!(
+            crate::indexer::roots::distinct(&roots),
+            [other.path(), root]
+        );
+        let results = searcher.search_auto_multi(&roots, "*.rs").unwrap();
+        assert_eq!(results.len(), 5);
+        assert!(results.contains(&other.path().join("other.rs")));
+        assert!(results.contains(&root.join("src").join("helper.rs")));
+
+        let results = searcher
+            .search_multi(&roots, "other", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(results, [other.path().join("other.rs")]);
+
+        let missing = [root.to_path_buf(), root.with_extension("missing")];
+        assert!(searcher.search_auto_multi(&missing, "main").is_err());
+    }
+
+    #[test]
+    fn test_sort_order() {
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let sorted = |order| {
+            let searcher = FileSearcher::builder()
+                .config(test_config())
+                .sort(order)
+                .build()
+                .unwrap();
+            let paths = searcher.search(root, ".rs", SearchMode::Substring).unwrap();
+            paths
+                .iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            sorted(SortOrder::Relevance),
+            ["lib.rs", "main.rs", "helper.rs", "test.rs"]
+        );
+        assert_eq!(
+            sorted(SortOrder::Name),
+            ["helper.rs", "lib.rs", "main.rs", "test.rs"]
+        );
+        // Sizes are 12, 12, 11 and 9 bytes; equal sizes keep their path order
+        assert_eq!(
+            sorted(SortOrder::Size),
+            ["lib.rs", "main.rs", "helper.rs", "test.rs"]
+        );
+        assert_eq!(
+            sorted(SortOrder::Depth),
+            ["lib.rs", "main.rs", "helper.rs", "test.rs"]
+        );
+
+        fs::write(root.join("src").join("test.rs"), "touched").unwrap();
+        let future = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(root.join("src").join("test.rs"))
+            .unwrap()
+            .set_modified(future)
+            .unwrap();
+        assert_eq!(sorted(SortOrder::Modified)[0], "test.rs");
+
+        #[cfg(feature = "fuzzy")]
+        {
+            let searcher = FileSearcher::builder()
+                .config(test_config())
+                .sort(SortOrder::Name)
+                .build()
+                .unwrap();
+            let results = searcher.search_fuzzy(root, ".rs").unwrap();
+            assert!(results.windows(2).all(|pair| {
+                pair[0].0.file_name().unwrap().to_ascii_lowercase()
+                    <= pair[1].0.file_name().unwrap().to_ascii_lowercase()
+            }));
+        }
+    }
+
+    #[test]
+    fn test_search_presets() {
+        use crate::search::preset::{Preset, SortOrder};
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let searcher = FileSearcherBuilder::new()
+            .config(test_config())
+            .preset(
+                "largest-rust",
+                Preset {
+                    mode: Some(SearchMode::Substring),
+                    root: Some(root.to_path_buf()),
+                    extensions: vec![".RS".to_string()],
+                    sort: SortOrder::Size,
+                    limit: Some(2),
+                    ..Preset::new("")
+                },
+            )
+            .preset(
+                "small",
+                Preset {
+                    max_size: Some(10),
+                    sort: SortOrder::Name,
+                    ..Preset::new(".rs")
+                },
+            )
+            .build()
+            .unwrap();
+
+        // main.rs and lib.rs (12 bytes) beat helper.rs (11) and test.rs (9)
+        let largest = searcher.run_preset("largest-rust").unwrap();
+        assert_eq!(largest.len(), 2);
+        assert!(largest.iter().all(|path| {
+            path.file_name().unwrap() == "main.rs" || path.file_name().unwrap() == "lib.rs"
+        }));
+
+        let small = searcher
+            .search_preset(root, &searcher.config().presets["small"])
+            .unwrap();
+        assert_eq!(small, vec![root.join("src").join("test.rs")]);
+
+        assert!(matches!(
+            searcher.run_preset("missing"),
+            Err(FileSearchError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cloud_placeholders() {
+        use crate::config::CloudPlaceholders;
+        use crate::indexer::placeholder;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        // Files that are fully on disk are never placeholders
+        assert!(!placeholder::is_placeholder(&root.join("main.rs")));
+        for policy in CloudPlaceholders::ALL {
+            let searcher = FileSearcherBuilder::new()
+                .config(test_config())
+                .cloud_placeholders(policy)
+                .build()
+                .unwrap();
+            let results = searcher.search(root, ".rs", SearchMode::Substring).unwrap();
+            assert_eq!(results.len(), 4, "policy {}", policy.name());
+            let file_match = FileMatch::new(root.join("main.rs"), searcher.config());
+            assert!(!file_match.online_only);
+        }
+    }
+
+    #[test]
+    fn test_trash_listing() {
+        use crate::indexer::trash::{self, TrashedFile};
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config();
+
+        // freedesktop.org trash with a renamed file and a trashed directory
+        let trash_dir = temp_dir.path().join("Trash");
+        fs::create_dir_all(trash_dir.join("files").join("project")).unwrap();
+        fs::create_dir_all(trash_dir.join("info")).unwrap();
+        fs::write(trash_dir.join("files").join("report.pdf.2"), "pdf").unwrap();
+        fs::write(
+            trash_dir.join("files").join("project").join("notes.txt"),
+            "",
+        )
+        .unwrap();
+        fs::write(
+            trash_dir.join("info").join("report.pdf.2.trashinfo"),
+            "[Trash Info]\nPath=/home/me/my%20report.pdf\nDeletionDate=2024-01-01T00:00:00\n",
+        )
+        .unwrap();
+        fs::write(
+            trash_dir.join("info").join("project.trashinfo"),
+            "[Trash Info]\nPath=work/project\n",
+        )
+        .unwrap();
+        let mut trashed = trash::freedesktop_trash(&trash_dir, &config);
+        trashed.sort_by(|a, b| a.path.cmp(&b.path));
+        let notes = trash_dir.join("files").join("project").join("notes.txt");
+        assert_eq!(
+            trashed,
+            vec![
+                TrashedFile {
+                    path: notes.clone(),
+                    original_path: Some(temp_dir.path().join("work/project/notes.txt")),
+                },
+                TrashedFile {
+                    path: trash_dir.join("files").join("report.pdf.2"),
+                    original_path: Some(PathBuf::from("/home/me/my report.pdf")),
+                },
+            ]
+        );
+        assert_eq!(trashed[1].name().unwrap(), "my report.pdf");
+        assert_eq!(
+            trash::freedesktop_original_path(&trash_dir, &notes),
+            Some(temp_dir.path().join("work/project/notes.txt"))
+        );
+
+        // Recycle Bin item with a version 2 $I file
+        let bin = temp_dir.path().join("S-1-5-21");
+        fs::create_dir(&bin).unwrap();
+        let original: Vec<u16> = "C:\\Users\\me\\budget.xlsx\0".encode_utf16().collect();
+        let mut info = Vec::new();
+        info.extend_from_slice(&2u64.to_le_bytes());
+        info.extend_from_slice(&1234u64.to_le_bytes());
+        info.extend_from_slice(&0u64.to_le_bytes());
+        info.extend_from_slice(&u32::try_from(original.len()).unwrap().to_le_bytes());
+        info.extend(original.iter().flat_map(|unit| unit.to_le_bytes()));
+        fs::write(bin.join("$IAB12CD.xlsx"), info).unwrap();
+        fs::write(bin.join("$RAB12CD.xlsx"), "").unwrap();
+        let trashed = trash::recycle_bin(&bin, &config);
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].path, bin.join("$RAB12CD.xlsx"));
+        assert_eq!(
+            trashed[0].original_path,
+            Some(PathBuf::from("C:\\Users\\me\\budget.xlsx"))
+        );
+        assert_eq!(
+            trash::recycle_bin_original_path(&bin.join("$RAB12CD.xlsx")),
+            trashed[0].original_path
+        );
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn test_trash_symlink() {
+        use crate::actions::{self, DeleteMode};
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        std::env::set_var("XDG_DATA_HOME", root.join("data"));
+        fs::write(root.join("target.txt"), "kept").unwrap();
+        let link = root.join("link.txt");
+        std::os::unix::fs::symlink(root.join("target.txt"), &link).unwrap();
+
+        let report = actions::delete(&[&link], DeleteMode::Trash, false);
+        assert!(report.is_success(), "{:?}", report.errors);
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert_eq!(fs::read_to_string(root.join("target.txt")).unwrap(), "kept");
+        let trash = root.join("data").join("Trash");
+        assert!(trash.join("files").join("link.txt").is_symlink());
+        let info = fs::read_to_string(trash.join("info").join("link.txt.trashinfo")).unwrap();
+        assert!(info.contains(&format!("Path={}\n", link.display())));
+    }
+
+    #[test]
+    fn test_link_farm() {
+        use crate::actions::{self, Layout, LinkKind};
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let farm = root.join("farm");
+        fs::create_dir(&farm).unwrap();
+        fs::write(root.join("src").join("main.rs"), "fn main() {}").unwrap();
+        let files = [root.join("main.rs"), root.join("src").join("main.rs")];
+
+        // Flattening puts both files on the same name
+        let flat =
+            actions::link_to(&files, &farm, LinkKind::Hard, &Layout::Flatten, false).unwrap();
+        assert_eq!(flat.operations.len(), 1);
+        assert_eq!(flat.errors[0].0, files[1]);
+        assert_eq!(
+            fs::read_to_string(farm.join("main.rs")).unwrap(),
+            "fn main() {}"
+        );
+
+        let preserve = Layout::Preserve(root.to_path_buf());
+        let tree = farm.join("tree");
+        fs::create_dir(&tree).unwrap();
+        let plan = actions::link_to(&files, &tree, LinkKind::Hard, &preserve, true).unwrap();
+        assert_eq!(plan.operations.len(), 2);
+        assert!(!tree.join("src").exists());
+        assert!(
+            actions::link_to(&files, &tree, LinkKind::Hard, &preserve, false)
+                .unwrap()
+                .is_success()
+        );
+        assert!(tree.join("src").join("main.rs").is_file());
+
+        let elsewhere = Layout::Preserve(root.join("src"));
+        let outside =
+            actions::link_to(&files[..1], &tree, LinkKind::Hard, &elsewhere, true).unwrap();
+        assert_eq!(outside.errors.len(), 1);
+
+        #[cfg(unix)]
+        {
+            let links = farm.join("links");
+            fs::create_dir(&links).unwrap();
+            let report = actions::link_to(
+                &files[..1],
+                &links,
+                LinkKind::Symbolic,
+                &Layout::Flatten,
+                false,
+            )
+            .unwrap();
+            assert!(report.is_success());
+            let target = fs::read_link(links.join("main.rs")).unwrap();
+            assert!(target.is_absolute());
+            assert_eq!(target, files[0].canonicalize().unwrap());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "archive")]
+    fn test_archive_results() {
+        use crate::actions::{self, ArchiveFormat, Operation};
+        use std::io::Read;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let files = [
+            root.join("main.rs"),
+            root.join("src").join("test.rs"),
+            root.join("missing.rs"),
+        ];
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("logs.TGZ")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(ArchiveFormat::from_path(Path::new("logs.rar")), None);
+
+        let dest = root.join("out.tar.gz");
+        let plan = actions::archive(&files[..2], &dest, root, ArchiveFormat::TarGz, true).unwrap();
+        assert_eq!(
+            plan.operations[1],
+            Operation::Archive {
+                from: files[1].clone(),
+                entry: PathBuf::from("src/test.rs"),
+            }
+        );
+        assert!(!dest.exists());
+
+        // Unreadable files are reported and left out of the archive
+        let report = actions::archive(&files, &dest, root, ArchiveFormat::TarGz, false).unwrap();
+        assert_eq!(report.operations.len(), 2);
+        assert_eq!(report.errors[0].0, files[2]);
+        let mut tar =
+            tar::Archive::new(flate2::read::GzDecoder::new(fs::File::open(&dest).unwrap()));
+        let mut entries = Vec::new();
+        for entry in tar.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+            entries.push((entry.path().unwrap().into_owned(), content));
+        }
+        assert_eq!(
+            entries,
+            vec![
+                (PathBuf::from("main.rs"), "fn main() {}".to_string()),
+                (PathBuf::from("src/test.rs"), "test code".to_string()),
+            ]
+        );
+        assert!(actions::archive(&files, &dest, root, ArchiveFormat::TarGz, false).is_err());
+
+        let dest = root.join("out.zip");
+        actions::archive(&files[..2], &dest, root, ArchiveFormat::Zip, false).unwrap();
+        let mut zip = zip::ZipArchive::new(fs::File::open(&dest).unwrap()).unwrap();
+        let mut content = String::new();
+        zip.by_name("src/test.rs")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "test code");
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_batch_rename() {
+        use crate::actions::{self, Operation, Rename};
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for name in ["IMG_001.jpg", "IMG_002.JPG", "vacation_002.jpg", "notes"] {
+            fs::write(root.join(name), "").unwrap();
+        }
+        let files = [
+            root.join("IMG_001.jpg"),
+            root.join("IMG_002.JPG"),
+            root.join("notes"),
+        ];
+
+        let numbered = Rename::new("{stem}_{n}.{ext}");
+        assert_eq!(
+            numbered.new_name(&files[0], 1).as_deref(),
+            Some("IMG_001_1.jpg")
+        );
+        assert_eq!(numbered.new_name(&files[2], 3).as_deref(), Some("notes_3."));
+
+        let renamer =
+            Rename::with_regex(r"IMG_(?<id>\d+)\.jpg", false, "vacation_$id.${id}$$").unwrap();
+        assert_eq!(
+            renamer.new_name(&files[0], 1).as_deref(),
+            Some("vacation_001.001$")
+        );
+        assert_eq!(renamer.new_name(&files[2], 3), None);
+
+        let renamer = Rename::with_regex(r"IMG_(\d+)\.jpg", false, "vacation_$1.jpg").unwrap();
+        let plan = actions::rename(&files, &renamer, true);
+        assert_eq!(
+            plan.operations,
+            vec![Operation::Rename {
+                from: files[0].clone(),
+                to: root.join("vacation_001.jpg"),
+            }]
+        );
+        // One clash with an existing file, one name the regex does not match
+        assert_eq!(plan.errors.len(), 2);
+        assert!(files[0].exists());
+
+        // Two files of a batch may not end up with the same name
+        let clash = actions::rename(&files[..2], &Rename::new("same.{ext}"), true);
+        assert_eq!(clash.operations.len(), 2);
+        let clash = actions::rename(&files[..2], &Rename::new("same"), true);
+        assert_eq!(clash.operations.len(), 1);
+        assert_eq!(clash.errors[0].0, files[1]);
+        assert!(!actions::rename(&files[..1], &Rename::new("a/b"), true).is_success());
+
+        let report = actions::rename(&files, &renamer, false);
+        assert_eq!(report.operations, plan.operations);
+        assert!(!files[0].exists());
+        assert!(root.join("vacation_001.jpg").exists());
+
+        // A symlink renamed onto its own target collides with the target
+        #[cfg(unix)]
+        {
+            let link = root.join("link.jpg");
+            std::os::unix::fs::symlink(root.join("vacation_001.jpg"), &link).unwrap();
+            let onto_target = Rename::new("vacation_001.{ext}");
+            let plan = actions::rename(&[&link], &onto_target, true);
+            assert!(plan.operations.is_empty());
+            assert_eq!(plan.errors.len(), 1);
+            let report = actions::rename(&[&link], &onto_target, false);
+            assert!(!report.is_success());
+            assert!(root.join("vacation_001.jpg").is_file());
+            assert!(link.is_symlink());
+        }
+    }
+
+    #[test]
+    fn test_explain_ignore() {
+        use crate::indexer::file_walker::IgnoreReason;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("build")).unwrap();
+        fs::write(root.join("build").join("out.rs"), "").unwrap();
+        fs::write(root.join("src").join("big.rs"), "x".repeat(64)).unwrap();
+
+        let searcher = FileSearcher::builder()
+            .clear_ignore_patterns()
+            .ignore_pattern("build")
+            .max_depth(1)
+            .max_file_size(32)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join(".hidden")),
+            Some(IgnoreReason::Hidden {
+                path: root.join(".hidden")
+            })
+        );
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("build").join("out.rs")),
+            Some(IgnoreReason::Pattern {
+                pattern: "build".to_string(),
+                path: root.join("build")
+            })
+        );
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("src").join("big.rs")),
+            Some(IgnoreReason::MaxDepth {
+                depth: 2,
+                max_depth: 1
+            })
+        );
+        assert_eq!(searcher.explain_ignore(root, &root.join("main.rs")), None);
+
+        let searcher = FileSearcher::builder().max_file_size(32).build().unwrap();
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("src").join("big.rs")),
+            Some(IgnoreReason::MaxFileSize {
+                size: 64,
+                max_file_size: 32
+            })
+        );
+    }
+
+    #[test]
+    fn test_ignore_file() {
+        use crate::config::ignore_file::IgnoreFile;
+
+        let ignore_file = IgnoreFile::parse(
+            "# build output\n\n/dist/\n**/cache\n*.o\ntest_?.rs\n!keep.o\nsrc/**/*.md\n\\#notes\n",
+        );
+        assert_eq!(
+            ignore_file.patterns,
+            vec!["dist", "cache", "*.o", "test_?.rs", "#notes"]
+        );
+        assert_eq!(ignore_file.unsupported, vec!["!keep.o", "src/**/*.md"]);
+
+        let temp_dir = create_test_structure();
+        let ignore_path = temp_dir.path().join("ignore");
+        fs::write(&ignore_path, "*.toml\nsrc/\n").unwrap();
+        let mut config = test_config();
+        config
+            .ignore_patterns
+            .extend(IgnoreFile::load(&ignore_path).unwrap().patterns);
+
+        let searcher = FileSearcher::with_config(config);
+        let results = searcher
+            .search(temp_dir.path(), "", SearchMode::Substring)
+            .unwrap();
+        assert!(!results.iter().any(|p| p.ends_with("config.toml")));
+        assert!(!results.iter().any(|p| p.ends_with("helper.rs")));
+        assert!(results.iter().any(|p| p.ends_with("main.rs")));
+
+        assert!(IgnoreFile::load(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_respect_gitignore() {
+        use crate::indexer::file_walker::IgnoreReason;
+        use crate::indexer::git::excludes_file_from_config;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".git").join("info")).unwrap();
+        fs::write(root.join(".git").join("info").join("exclude"), "*.md\n").unwrap();
+        fs::write(root.join(".gitignore"), "config.toml\n").unwrap();
+        fs::write(root.join("src").join(".gitignore"), "helper.rs\n").unwrap();
+        fs::write(root.join("helper.rs"), "not ignored here").unwrap();
+
+        let search = |respect| {
+            let config = crate::config::Config {
+                respect_gitignore: respect,
+                ..test_config()
+            };
+            FileSearcher::with_config(config)
+                .search(root, "", SearchMode::Substring)
+                .unwrap()
+        };
+
+        let results = search(true);
+        assert!(!results.iter().any(|p| p.ends_with("README.md")));
+        assert!(!results.iter().any(|p| p.ends_with("config.toml")));
+        assert!(!results.iter().any(|p| p.ends_with("src/helper.rs")));
+        assert!(!results
+            .iter()
+            .any(|p| p.components().any(|c| c.as_os_str() == ".git")));
+        assert!(results.iter().any(|p| p == &root.join("helper.rs")));
+        assert!(results.iter().any(|p| p.ends_with("src/test.rs")));
+
+        assert!(search(false).iter().any(|p| p.ends_with("README.md")));
+
+        let searcher = FileSearcher::builder()
+            .respect_gitignore(true)
+            .build()
+            .unwrap();
+        let root = root.canonicalize().unwrap();
+        let reason = searcher.explain_ignore(&root, &root.join("src").join("helper.rs"));
+        assert!(matches!(
+            reason,
+            Some(IgnoreReason::GitIgnore { ref pattern, ref source, .. })
+                if pattern == "helper.rs" && source.ends_with("src/.gitignore")
+        ));
+
+        let home = Path::new("/home/user");
+        assert_eq!(
+            excludes_file_from_config(
+                "[user]\n\tname = x\n[core]\n\texcludesFile = ~/.gitignore_global\n",
+                Some(home)
+            ),
+            Some(home.join(".gitignore_global"))
+        );
+        assert_eq!(
+            excludes_file_from_config("[user]\n\texcludesfile = /tmp/x\n", Some(home)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_gitignore_semantics() {
+        use crate::indexer::file_walker::IgnoreReason;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(
+            root.join(".gitignore"),
+            "/build\n*.log\n!keep.log\ndocs/\nsrc/**/gen\n",
+        )
+        .unwrap();
+        for file in [
+            "build/a.txt",
+            "src/build/b.txt",
+            "x.log",
+            "keep.log",
+            "sub/y.log",
+            "sub/z.log",
+            "docs/guide.md",
+            "src/docs",
+            "src/gen",
+            "src/deep/gen",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::write(root.join("sub").join(".gitignore"), "!z.log\n").unwrap();
+
+        let searcher = FileSearcher::with_config(crate::config::Config {
+            respect_gitignore: true,
+            ..test_config()
+        });
+        let mut found: Vec<_> = searcher
+            .search(&root, "", SearchMode::Substring)
+            .unwrap()
+            .into_iter()
+            .filter_map(|path| {
+                let relative = path.strip_prefix(&root).ok()?;
+                (!relative.ends_with(".gitignore")).then(|| relative.to_path_buf())
+            })
+            .collect();
+        found.sort();
+        // Anchored patterns only match from their directory, negations
+        // re-include in order and directory patterns skip files
+        let expected: Vec<PathBuf> = ["keep.log", "src/build/b.txt", "src/docs", "sub/z.log"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(found, expected);
+
+        assert!(matches!(
+            searcher.explain_ignore(&root, &root.join("sub").join("y.log")),
+            Some(IgnoreReason::GitIgnore { ref pattern, .. }) if pattern == "*.log"
+        ));
+        assert_eq!(searcher.explain_ignore(&root, &root.join("keep.log")), None);
+    }
+
+    #[test]
+    fn test_git_nested_checkouts() {
+        use crate::indexer::file_walker::IgnoreReason;
+        use crate::indexer::git::NestedCheckout;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join(".git").join("modules").join("vendor")).unwrap();
+        fs::create_dir_all(root.join(".git").join("worktrees").join("feature")).unwrap();
+        fs::create_dir(root.join("vendor")).unwrap();
+        fs::write(
+            root.join("vendor").join(".git"),
+            "gitdir: ../.git/modules/vendor\n",
+        )
+        .unwrap();
+        fs::write(root.join("vendor").join("vendored.rs"), "").unwrap();
+        fs::create_dir(root.join("feature")).unwrap();
+        let worktree_git_dir = root.join(".git").join("worktrees").join("feature");
+        fs::write(
+            root.join("feature").join(".git"),
+            format!("gitdir: {}\n", worktree_git_dir.display()),
+        )
+        .unwrap();
+        fs::write(root.join("feature").join("branch.rs"), "").unwrap();
+
+        let search = |submodules, worktrees| {
+            let config = crate::config::Config {
+                respect_gitignore: true,
+                git_submodules: submodules,
+                git_worktrees: worktrees,
+                ..test_config()
+            };
+            FileSearcher::with_config(config)
+                .search(&root, ".rs", SearchMode::Substring)
+                .unwrap()
+        };
+        let has = |results: &[PathBuf], name: &str| results.iter().any(|p| p.ends_with(name));
+
+        let results = search(false, false);
+        assert!(has(&results, "main.rs"));
+        assert!(!has(&results, "vendored.rs"));
+        assert!(!has(&results, "branch.rs"));
+
+        let results = search(true, false);
+        assert!(has(&results, "vendored.rs"));
+        assert!(!has(&results, "branch.rs"));
+
+        let results = search(false, true);
+        assert!(!has(&results, "vendored.rs"));
+        assert!(has(&results, "branch.rs"));
+
+        let searcher = FileSearcher::builder()
+            .respect_gitignore(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            searcher.explain_ignore(&root, &root.join("vendor").join("vendored.rs")),
+            Some(IgnoreReason::NestedCheckout {
+                kind: NestedCheckout::Submodule,
+                path: root.join("vendor"),
+            })
+        );
+        assert_eq!(
+            searcher.explain_ignore(&root, &root.join("feature")),
+            Some(IgnoreReason::NestedCheckout {
+                kind: NestedCheckout::Worktree,
+                path: root.join("feature"),
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_git_tracked() {
+        use crate::indexer::file_walker::IgnoreReason;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let repo = git2::Repository::init(&root).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("main.rs")).unwrap();
+        index.add_path(Path::new("src/test.rs")).unwrap();
+        index.write().unwrap();
+
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .git_tracked(true)
+            .build()
+            .unwrap();
+        let mut results = searcher.search(&root, "", SearchMode::Substring).unwrap();
+        results.sort();
+        assert_eq!(
+            results,
+            vec![root.join("main.rs"), root.join("src").join("test.rs")]
+        );
+
+        assert_eq!(
+            searcher.explain_ignore(&root, &root.join("src").join("helper.rs")),
+            Some(IgnoreReason::Untracked {
+                path: root.join("src").join("helper.rs"),
+            })
+        );
+
+        let outside = TempDir::new().unwrap();
+        assert!(searcher
+            .search(outside.path(), "", SearchMode::Substring)
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_changed_since() {
+        use crate::indexer::file_walker::IgnoreReason;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let repo = git2::Repository::init(&root).unwrap();
+        let mut index = repo.index().unwrap();
+        for path in ["main.rs", "README.md", "src/test.rs"] {
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])
+            .unwrap();
+        fs::write(root.join("main.rs"), "fn main() { changed(); }").unwrap();
+
+        let search = |rev: &str| {
+            FileSearcher::builder()
+                .config(test_config())
+                .changed_since(rev)
+                .build()
+                .unwrap()
+                .search(&root, "", SearchMode::Substring)
+        };
+        let results = search("HEAD").unwrap();
+        assert!(results.contains(&root.join("main.rs")));
+        assert!(results.contains(&root.join("lib.rs")));
+        assert!(results.contains(&root.join("src").join("helper.rs")));
+        assert!(!results.contains(&root.join("README.md")));
+        assert!(!results.contains(&root.join("src").join("test.rs")));
+
+        assert!(search("no-such-branch").is_err());
+
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .changed_since("HEAD")
+            .build()
+            .unwrap();
+        assert_eq!(
+            searcher.explain_ignore(&root, &root.join("README.md")),
+            Some(IgnoreReason::Unchanged {
+                path: root.join("README.md"),
+                since: "HEAD".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_git_status() {
+        use crate::indexer::git::GitStatus;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::write(root.join(".gitignore"), "*.toml\n").unwrap();
+        let repo = git2::Repository::init(&root).unwrap();
+        let mut index = repo.index().unwrap();
+        for path in ["main.rs", "README.md", ".gitignore"] {
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        fs::write(root.join("main.rs"), "fn main() { edited() }").unwrap();
+
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .git_status(true)
+            .build()
+            .unwrap();
+        let matches = searcher
+            .search_matches(&root, "", SearchMode::Substring)
+            .unwrap();
+        let status_of = |name: &str| {
+            matches
+                .iter()
+                .find(|m| m.path == root.join(name))
+                .and_then(|m| m.git_status)
+        };
+        assert_eq!(status_of("README.md"), Some(GitStatus::Clean));
+        assert_eq!(status_of("main.rs"), Some(GitStatus::Modified));
+        assert_eq!(status_of("lib.rs"), Some(GitStatus::Untracked));
+        assert_eq!(status_of("config.toml"), Some(GitStatus::Ignored));
+
+        let outside = create_test_structure();
+        let matches = searcher
+            .search_matches(outside.path(), "main", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(matches[0].git_status, None);
+    }
+
+    #[test]
+    fn test_database_diff() {
+        use crate::indexer::database::Database;
+        use crate::indexer::diff::DatabaseDiff;
+        use crate::search::SearchEngine;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let old = Database::build(&[&root], &test_config()).unwrap();
+
+        fs::remove_file(root.join("README.md")).unwrap();
+        fs::write(root.join("new.rs"), "").unwrap();
+        fs::rename(root.join("src").join("helper.rs"), root.join("helper.rs")).unwrap();
+        let new = Database::build(&[&root], &test_config()).unwrap();
+
+        let diff = DatabaseDiff::between(&old, &new);
+        assert_eq!(diff.added, vec![root.join("new.rs")]);
+        assert_eq!(diff.removed, vec![root.join("README.md")]);
+        assert_eq!(
+            diff.renamed,
+            vec![(root.join("src").join("helper.rs"), root.join("helper.rs"))]
+        );
+        assert!(DatabaseDiff::between(&new, &new).is_empty());
+
+        let engine = SearchEngine::new(test_config());
+        let filtered = diff.matching(&engine, ".rs").unwrap();
+        assert_eq!(filtered.added, diff.added);
+        assert!(filtered.removed.is_empty());
+        assert_eq!(filtered.renamed, diff.renamed);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_audit_checks() {
+        use crate::config::AuditCheck;
+        use crate::indexer::file_walker::IgnoreReason;
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let set_mode = |path: &Path, mode| {
+            fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+        };
+        set_mode(&root.join("src").join("helper.rs"), 0o666);
+        set_mode(&root.join("main.rs"), 0o4755);
+
+        let search = |checks: &[AuditCheck]| {
+            let mut results = FileSearcher::builder()
+                .config(test_config())
+                .audit_checks(checks.iter().copied())
+                .build()
+                .unwrap()
+                .search(root, "", SearchMode::Substring)
+                .unwrap();
+            results.sort();
+            results
+        };
+
+        assert_eq!(
+            search(&[AuditCheck::WorldWritable]),
+            vec![root.join("src").join("helper.rs")]
+        );
+        assert_eq!(search(&[AuditCheck::SetId]), vec![root.join("main.rs")]);
+        assert_eq!(
+            search(&AuditCheck::ALL),
+            vec![root.join("main.rs"), root.join("src").join("helper.rs")]
+        );
+        assert!(search(&[]).len() > 2);
+
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .audit_checks([AuditCheck::SetId])
+            .build()
+            .unwrap();
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("lib.rs")),
+            Some(IgnoreReason::NotFlagged {
+                path: root.join("lib.rs"),
+            })
+        );
+        assert_eq!(searcher.explain_ignore(root, &root.join("main.rs")), None);
+    }
+
+    #[test]
+    fn test_finder_tags() {
+        use crate::indexer::xattr::parse_tag_plist;
+
+        let mut plist = b"bplist00".to_vec();
+        plist.extend([0xa2, 1, 2]);
+        plist.extend(b"\x55Red\n6");
+        plist.extend(b"\x54Work");
+        plist.extend([8, 11, 17]);
+        plist.extend([0, 0, 0, 0, 0, 0, 1, 1]);
+        plist.extend(3u64.to_be_bytes());
+        plist.extend(0u64.to_be_bytes());
+        plist.extend(22u64.to_be_bytes());
+        assert_eq!(parse_tag_plist(&plist), vec!["Red", "Work"]);
+
+        assert!(parse_tag_plist(b"bplist00").is_empty());
+        assert!(parse_tag_plist(&plist[..30]).is_empty());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_required_xattrs() {
+        use crate::indexer::file_walker::IgnoreReason;
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let tagged = root.join("main.rs");
+        let path = CString::new(tagged.as_os_str().as_bytes()).unwrap();
+        let name = CString::new("user.whatever_find_test").unwrap();
+        // SAFETY: NUL-terminated strings and a valid value buffer
+        let set =
+            unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), b"1".as_ptr().cast(), 1, 0) };
+        if set != 0 {
+            // The file system backing the temporary directory has no user xattrs
+            return;
+        }
+
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .require_xattr("user.whatever_find_test")
+            .build()
+            .unwrap();
+        let results = searcher.search(root, "", SearchMode::Substring).unwrap();
+        assert_eq!(results, vec![tagged]);
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("lib.rs")),
+            Some(IgnoreReason::MissingXattr {
+                name: "user.whatever_find_test".to_string(),
+                path: root.join("lib.rs"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_search_handle() {
+        use crate::search::handle::Checkpoint;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let searcher = FileSearcher::with_config(test_config());
+
+        // A paused search cannot finish until it is resumed
+        let handle = SearchHandle::new();
+        handle.pause();
+        let worker = handle.clone();
+        let worker_root = root.to_path_buf();
+        let search = std::thread::spawn(move || {
+            FileSearcher::with_config(test_config()).search_with_handle(
+                &worker_root,
+                ".rs",
+                SearchMode::Substring,
+                &worker,
+            )
+        });
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!search.is_finished());
+        handle.resume();
+        assert_eq!(search.join().unwrap().unwrap().len(), 4);
+        assert!(handle.is_finished());
+        assert_eq!(handle.checkpoint().completed_dirs, vec![root.to_path_buf()]);
+
+        // A stopped search returns what it found so far
+        let handle = SearchHandle::new();
+        handle.stop();
+        let results = searcher
+            .search_with_handle(root, ".rs", SearchMode::Substring, &handle)
+            .unwrap();
+        assert!(results.is_empty());
+        assert!(!handle.is_finished());
+
+        // Resuming skips completed directories and keeps their files
+        let handle = SearchHandle::from_checkpoint(Checkpoint {
+            completed_dirs: vec![root.join("src")],
+            files: vec![root.join("src").join("test.rs")],
+        });
+        let mut results = searcher
+            .search_with_handle(root, ".rs", SearchMode::Substring, &handle)
+            .unwrap();
+        results.sort();
+        assert_eq!(
+            results,
+            vec![
+                root.join("lib.rs"),
+                root.join("main.rs"),
+                root.join("src").join("test.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_searcher() {
+        use std::sync::Arc;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let searcher = Arc::new(SharedSearcher::new(root, test_config()).unwrap());
+        let snapshot = searcher.snapshot();
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let searcher = Arc::clone(&searcher);
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        let results = searcher.search("main", SearchMode::Substring).unwrap();
+                        assert!(!results.is_empty());
+                    }
+                })
+            })
+            .collect();
+
+        fs::write(root.join("src").join("main_helper.rs"), "").unwrap();
+        searcher.refresh().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        let results = searcher.search("main", SearchMode::Substring).unwrap();
+        assert_eq!(results.len(), 2);
+        // Snapshots taken before the refresh are unaffected by it
+        let engine = crate::search::SearchEngine::new(test_config());
+        let old_results = engine
+            .search(snapshot.as_ref(), "main", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(old_results, vec![root.join("main.rs")]);
+    }
+
+    #[test]
+    fn test_search_directories() {
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("raw")).unwrap();
+        for i in 0..3 {
+            fs::write(root.join("raw").join(format!("img{i}.rs")), "raw").unwrap();
+        }
+
+        let searcher = FileSearcher::with_config(test_config());
+        let dirs = searcher
+            .search_directories(root, ".rs", SearchMode::Substring, 2)
+            .unwrap();
+        let counts: Vec<_> = dirs
+            .iter()
+            .map(|dir| (dir.path.clone(), dir.files))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                (root.join("raw"), 3),
+                (root.to_path_buf(), 2),
+                (root.join("src"), 2)
+            ]
+        );
+
+        let dirs = searcher
+            .search_directories(root, ".rs", SearchMode::Substring, 3)
+            .unwrap();
+        assert_eq!(dirs.len(), 1);
+    }
+
+    #[test]
+    fn test_search_matches() {
+        use crate::indexer::streams::stream_name;
+
+        assert_eq!(
+            stream_name(":Zone.Identifier:$DATA"),
+            Some("Zone.Identifier")
+        );
+        assert_eq!(stream_name("::$DATA"), None);
+        assert_eq!(stream_name("no-colon"), None);
+
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .alternate_streams(true)
+            .build()
+            .unwrap();
+        let matches = searcher
+            .search_matches(temp_dir.path(), "main", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].path.ends_with("main.rs"));
+        if !cfg!(windows) {
+            assert!(matches[0].alternate_streams.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_search_detailed() {
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::with_config(test_config());
+        let results = searcher
+            .search_detailed(temp_dir.path(), "main", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert!(result.path.ends_with("main.rs"));
+        assert_eq!(result.kind, FileKind::File);
+        assert_eq!(result.size, fs::metadata(&result.path).unwrap().len());
+        assert!(result.modified.is_some());
+        assert_eq!(result.score, None);
+        assert_eq!(result.spans, vec![0..4]);
+
+        let auto = searcher
+            .search_auto_detailed(temp_dir.path(), "*.rs")
+            .unwrap();
+        assert!(auto.iter().all(|result| result.kind == FileKind::File));
+
+        #[cfg(feature = "fuzzy")]
+        {
+            let fuzzy = searcher
+                .search_fuzzy_detailed(temp_dir.path(), "man")
+                .unwrap();
+            assert!(!fuzzy.is_empty());
+            assert!(fuzzy.iter().all(|result| result.score.is_some()));
+        }
+
+        let missing = SearchResult::new(temp_dir.path().join("missing"));
+        assert_eq!(missing.kind, FileKind::Other);
+        assert_eq!(missing.size, 0);
+        assert_eq!(missing.modified, None);
+    }
+
+    #[test]
+    fn test_match_spans() {
+        let engine = crate::search::SearchEngine::new(test_config());
+        let spans = |name: &str, query: &str, mode| engine.match_spans(name, query, mode).unwrap();
+
+        assert_eq!(
+            spans("test_test.rs", "TEST", SearchMode::Substring),
+            vec![0..4, 5..9]
+        );
+        assert!(spans("main.rs", "lib", SearchMode::Substring).is_empty());
+        // 'İ' lowercases to two characters, so offsets map back to the name
+        assert_eq!(spans("İx.rs", "x", SearchMode::Substring), vec![2..3]);
+        #[cfg(feature = "regex")]
+        assert_eq!(
+            spans("a1b22.txt", r"\d+", SearchMode::Regex),
+            vec![1..2, 3..5]
+        );
+        #[cfg(feature = "glob")]
+        assert_eq!(spans("main.rs", "*.rs", SearchMode::Glob), vec![0..7]);
+        #[cfg(feature = "fuzzy")]
+        {
+            assert_eq!(
+                spans("main.rs", "src/mn", SearchMode::Fuzzy),
+                vec![0..1, 3..4]
+            );
+            assert_eq!(spans("main.rs", "ain", SearchMode::Fuzzy), vec![1..4]);
+            assert!(spans("main.rs", "mian", SearchMode::Fuzzy).is_empty());
+        }
+
+        let words = crate::search::SearchEngine::new(crate::config::Config {
+            word_boundaries: true,
+            ..test_config()
+        });
+        assert_eq!(
+            words
+                .match_spans("log_catalog.log", "log", SearchMode::Substring)
+                .unwrap(),
+            vec![0..3, 12..15]
+        );
+    }
+
+    #[test]
+    fn test_result_decorators() {
+        struct Project(&'static str);
+
+        impl ResultDecorator for Project {
+            fn decorate(&self, file_match: &mut FileMatch) {
+                file_match
+                    .metadata
+                    .insert("project".to_string(), self.0.to_string());
+            }
+        }
+
+        let temp_dir = create_test_structure();
+        let mut searcher = FileSearcher::builder()
+            .config(test_config())
+            .decorator(Project("demo"))
+            .decorator(|file_match: &mut FileMatch| {
+                let size = fs::metadata(&file_match.path).unwrap().len();
+                file_match
+                    .metadata
+                    .insert("size".to_string(), size.to_string());
+            })
+            .build()
+            .unwrap();
+        searcher.add_decorator(|file_match: &mut FileMatch| {
+            file_match
+                .metadata
+                .insert("project".to_string(), "renamed".to_string());
+        });
 
-    /// Gets the current configuration
-    #[must_use]
-    pub fn config(&self) -> &crate::config::Config {
-        &self.config
+        let matches = searcher
+            .search_matches(temp_dir.path(), "main", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].metadata["project"], "renamed");
+        assert_eq!(matches[0].metadata["size"], "12");
+
+        let plain = FileSearcher::with_config(test_config())
+            .search_matches(temp_dir.path(), "main", SearchMode::Substring)
+            .unwrap();
+        assert!(plain[0].metadata.is_empty());
     }
 
-    /// Updates the configuration
-    pub fn set_config(&mut self, config: crate::config::Config) {
-        self.config = config;
+    #[test]
+    #[cfg(unix)]
+    fn test_file_attributes() {
+        use crate::indexer::attributes::FileAttribute;
+        use crate::indexer::file_walker::IgnoreReason;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let sparse = root.join("sparse.img");
+        fs::File::create(&sparse)
+            .unwrap()
+            .set_len(16 * 1024 * 1024)
+            .unwrap();
+
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .require_attribute(FileAttribute::Sparse)
+            .file_attributes(true)
+            .build()
+            .unwrap();
+        let matches = searcher
+            .search_matches(root, "", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, sparse);
+        assert!(matches[0].attributes.is_some_and(|a| a.sparse));
+
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("main.rs")),
+            Some(IgnoreReason::MissingAttribute {
+                attribute: FileAttribute::Sparse,
+                path: root.join("main.rs"),
+            })
+        );
+
+        let plain = FileSearcher::with_config(test_config())
+            .search_matches(root, "main", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(plain[0].attributes, None);
     }
 
-    /// Asynchronous version of `search_auto`
-    ///
-    /// This method runs the search operation on a background thread to avoid blocking
-    /// the current thread. Requires the `async` feature to be enabled.
-    ///
-    /// # Examples
-    ///
-    /// ```rust,ignore
-    /// use file_search::FileSearcher;
-    /// use std::path::Path;
-    ///
-    /// #[tokio::main]
-    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     let searcher = FileSearcher::new();
-    ///     let results = searcher.search_auto_async(Path::new("."), "*.rs").await?;
-    ///     println!("Found {} files", results.len());
-    ///     Ok(())
-    /// }
-    /// ```
-    #[cfg(feature = "async")]
-    pub async fn search_auto_async(&self, root_path: &Path, query: &str) -> Result<Vec<PathBuf>> {
-        let searcher = self.clone();
-        let root_path = root_path.to_path_buf();
-        let query = query.to_string();
+    #[test]
+    #[cfg(unix)]
+    fn test_find_by_file_id() {
+        use std::os::unix::fs::MetadataExt;
 
-        tokio::task::spawn_blocking(move || searcher.search_auto(&root_path, &query))
-            .await
-            .map_err(|e| {
-                crate::error::FileSearchError::invalid_config(format!("Async task failed: {e}"))
-            })?
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::hard_link(root.join("main.rs"), root.join("src").join("main_link.rs")).unwrap();
+        let id = fs::metadata(root.join("main.rs")).unwrap().ino();
+
+        let searcher = FileSearcher::with_config(test_config());
+        let mut found = searcher.find_by_file_id(root, id).unwrap();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![root.join("main.rs"), root.join("src").join("main_link.rs")]
+        );
+        assert_eq!(
+            crate::indexer::file_id::file_id(&root.join("main.rs")),
+            Some(id)
+        );
     }
 
-    /// Asynchronous version of `search_auto_with_mode`
-    #[cfg(feature = "async")]
-    pub async fn search_auto_with_mode_async(
-        &self,
-        root_path: &Path,
-        query: &str,
-    ) -> Result<(Vec<PathBuf>, crate::search::SearchMode)> {
-        let searcher = self.clone();
-        let root_path = root_path.to_path_buf();
-        let query = query.to_string();
+    #[test]
+    fn test_link_count() {
+        use crate::config::LinkCount;
+        use crate::indexer::file_walker::IgnoreReason;
 
-        tokio::task::spawn_blocking(move || searcher.search_auto_with_mode(&root_path, &query))
-            .await
-            .map_err(|e| {
-                crate::error::FileSearchError::invalid_config(format!("Async task failed: {e}"))
-            })?
+        assert_eq!("2".parse(), Ok(LinkCount::Exactly(2)));
+        assert_eq!("+1".parse(), Ok(LinkCount::MoreThan(1)));
+        assert_eq!("-3".parse(), Ok(LinkCount::FewerThan(3)));
+        assert!("many".parse::<LinkCount>().is_err());
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        fs::hard_link(root.join("main.rs"), root.join("src").join("main_link.rs")).unwrap();
+
+        let search = |links| {
+            let mut results = FileSearcher::builder()
+                .config(test_config())
+                .link_count(links)
+                .build()
+                .unwrap()
+                .search(root, "", SearchMode::Substring)
+                .unwrap();
+            results.sort();
+            results
+        };
+        assert_eq!(
+            search(LinkCount::MoreThan(1)),
+            vec![root.join("main.rs"), root.join("src").join("main_link.rs")]
+        );
+        assert!(!search(LinkCount::Exactly(1)).contains(&root.join("main.rs")));
+        assert!(search(LinkCount::Exactly(1)).contains(&root.join("lib.rs")));
+
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .link_count(LinkCount::MoreThan(1))
+            .build()
+            .unwrap();
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("lib.rs")),
+            Some(IgnoreReason::LinkCount {
+                links: 1,
+                required: LinkCount::MoreThan(1),
+                path: root.join("lib.rs"),
+            })
+        );
     }
 
-    /// Asynchronous version of `search`
-    #[cfg(feature = "async")]
-    pub async fn search_async(
-        &self,
-        root_path: &Path,
-        query: &str,
-        mode: crate::search::SearchMode,
-    ) -> Result<Vec<PathBuf>> {
-        let searcher = self.clone();
-        let root_path = root_path.to_path_buf();
-        let query = query.to_string();
+    #[test]
+    fn test_length_filters() {
+        use crate::config::LengthFilter;
+        use crate::indexer::file_walker::IgnoreReason;
 
-        tokio::task::spawn_blocking(move || searcher.search(&root_path, &query, mode))
-            .await
-            .map_err(|e| {
-                crate::error::FileSearchError::invalid_config(format!("Async task failed: {e}"))
-            })?
+        assert_eq!("7".parse(), Ok(LengthFilter::Exactly(7)));
+        assert_eq!("+240".parse(), Ok(LengthFilter::LongerThan(240)));
+        assert_eq!("-8".parse(), Ok(LengthFilter::ShorterThan(8)));
+        assert!("long".parse::<LengthFilter>().is_err());
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+
+        let mut results = FileSearcher::builder()
+            .config(test_config())
+            .name_length(LengthFilter::Exactly(7))
+            .build()
+            .unwrap()
+            .search(root, "", SearchMode::Substring)
+            .unwrap();
+        results.sort();
+        assert_eq!(
+            results,
+            vec![
+                root.join(".hidden"),
+                root.join("main.rs"),
+                root.join("src").join("test.rs")
+            ]
+        );
+
+        let nested = root.join("src").join("helper.rs");
+        let limit = nested.to_string_lossy().chars().count() - 1;
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .path_length(LengthFilter::LongerThan(limit))
+            .build()
+            .unwrap();
+        assert_eq!(
+            searcher.search(root, "", SearchMode::Substring).unwrap(),
+            vec![nested]
+        );
+        assert_eq!(
+            searcher.explain_ignore(root, &root.join("lib.rs")),
+            Some(IgnoreReason::Length {
+                name_only: false,
+                length: root.join("lib.rs").to_string_lossy().chars().count(),
+                required: LengthFilter::LongerThan(limit),
+                path: root.join("lib.rs"),
+            })
+        );
     }
 
-    /// Asynchronous version of `search_fuzzy`
-    #[cfg(feature = "async")]
-    pub async fn search_fuzzy_async(
-        &self,
-        root_path: &Path,
-        query: &str,
-    ) -> Result<Vec<(PathBuf, f64)>> {
-        let searcher = self.clone();
-        let root_path = root_path.to_path_buf();
-        let query = query.to_string();
+    #[test]
+    fn test_search_drives() {
+        use crate::indexer::drives::{self, Drive, DriveKind};
 
-        tokio::task::spawn_blocking(move || searcher.search_fuzzy(&root_path, &query))
-            .await
-            .map_err(|e| {
-                crate::error::FileSearchError::invalid_config(format!("Async task failed: {e}"))
-            })?
+        assert!(drives::available().iter().all(|drive| drive.root.is_dir()));
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let drive = |path: PathBuf| Drive {
+            root: path,
+            kind: DriveKind::Fixed,
+        };
+        let searcher = FileSearcher::with_config(test_config());
+
+        fs::create_dir(root.join("other")).unwrap();
+        fs::write(root.join("other").join("test.rs"), "").unwrap();
+        let mut results = searcher
+            .search_drives(
+                &[
+                    drive(root.join("src")),
+                    drive(root.join("missing")),
+                    drive(root.join("other")),
+                ],
+                "test.rs",
+                SearchMode::Substring,
+            )
+            .unwrap();
+        results.sort();
+        assert_eq!(
+            results,
+            vec![
+                root.join("other").join("test.rs"),
+                root.join("src").join("test.rs")
+            ]
+        );
+
+        assert!(searcher
+            .search_drives(
+                &[drive(root.join("missing"))],
+                "test",
+                SearchMode::Substring
+            )
+            .is_err());
     }
-}
 
-// Clone implementation needed for async support
-impl Clone for FileSearcher {
-    fn clone(&self) -> Self {
-        Self {
-            config: self.config.clone(),
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_pseudo_filesystems() {
+        use crate::indexer::file_walker::IgnoreReason;
+        use crate::indexer::FileIndexer;
+
+        if !Path::new("/proc/cpuinfo").exists() {
+            return;
         }
+        // Only list the root directory itself
+        let top_level = |config: Config| {
+            FileIndexer::new(Config {
+                max_depth: Some(1),
+                ..config
+            })
+            .build_dir_index("/")
+            .unwrap()
+        };
+        assert!(top_level(test_config()).get("proc").is_none());
+        assert!(top_level(Config {
+            pseudo_filesystems: Vec::new(),
+            ..test_config()
+        })
+        .get("proc")
+        .is_some());
+
+        let searcher = FileSearcher::with_config(test_config());
+        assert_eq!(
+            searcher.explain_ignore(Path::new("/"), Path::new("/proc/cpuinfo")),
+            Some(IgnoreReason::PseudoFilesystem {
+                fs_type: "proc".to_string(),
+                path: PathBuf::from("/proc"),
+            })
+        );
+        assert_eq!(
+            searcher.explain_ignore(Path::new("/proc"), Path::new("/proc/cpuinfo")),
+            None
+        );
     }
-}
 
-// Re-export commonly used types
-pub use crate::config::Config;
-pub use crate::error::FileSearchError;
-pub use crate::indexer::FileIndex;
-pub use crate::search::SearchMode;
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_same_file_system() {
+        use crate::indexer::file_id::device_id;
+        use crate::indexer::file_walker::IgnoreReason;
+        use crate::indexer::FileIndexer;
 
-// FileSearcherBuilder is already defined in this module, no need to re-export
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let same = Config {
+            same_file_system: true,
+            ..test_config()
+        };
+        let search = |config: &Config| {
+            let mut paths = FileSearcher::with_config(config.clone())
+                .search(root, "rs", SearchMode::Substring)
+                .unwrap();
+            paths.sort();
+            paths
+        };
+        assert_eq!(search(&same), search(&test_config()));
+        assert_eq!(search(&same).len(), 4);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
+        // /proc is a mount of its own wherever it exists
+        if !Path::new("/proc/cpuinfo").exists()
+            || device_id(Path::new("/")) == device_id(Path::new("/proc"))
+        {
+            return;
+        }
+        let same = Config {
+            pseudo_filesystems: Vec::new(),
+            max_depth: Some(1),
+            ..same
+        };
+        let top_level = FileIndexer::new(same.clone()).build_dir_index("/").unwrap();
+        assert!(top_level.get("proc").is_none());
+        assert_eq!(
+            FileSearcher::with_config(same)
+                .explain_ignore(Path::new("/"), Path::new("/proc/cpuinfo")),
+            Some(IgnoreReason::OtherFileSystem {
+                path: PathBuf::from("/proc"),
+            })
+        );
+    }
 
-    fn create_test_structure() -> TempDir {
-        let temp_dir = TempDir::new().unwrap();
+    #[test]
+    fn test_warm_up() {
+        use crate::search::warm::IndexState;
+
+        let temp_dir = create_test_structure();
         let root = temp_dir.path();
+        let mut searcher = FileSearcher::builder()
+            .config(test_config())
+            .prewarm(root)
+            .build()
+            .unwrap();
+        assert_ne!(searcher.index_state(), None);
+        let expected = vec![root.join("src").join("helper.rs")];
+        assert_eq!(
+            searcher
+                .search(root, "helper", SearchMode::Substring)
+                .unwrap(),
+            expected
+        );
+        assert_eq!(searcher.index_state(), Some(IndexState::Ready));
 
-        // Create test files
-        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
-        fs::write(root.join("lib.rs"), "pub mod lib;").unwrap();
-        fs::write(root.join("config.toml"), "[config]").unwrap();
-        fs::write(root.join("README.md"), "# Test").unwrap();
+        // The warm index is a snapshot, other roots are walked as usual
+        fs::write(root.join("helper2.rs"), "").unwrap();
+        assert_eq!(
+            searcher
+                .search(root, "helper", SearchMode::Substring)
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            searcher
+                .search(
+                    &root.join("src").join(".."),
+                    "helper",
+                    SearchMode::Substring
+                )
+                .unwrap()
+                .len(),
+            2
+        );
 
-        // Create subdirectory
-        fs::create_dir(root.join("src")).unwrap();
-        fs::write(root.join("src").join("test.rs"), "test code").unwrap();
-        fs::write(root.join("src").join("helper.rs"), "helper code").unwrap();
+        searcher.set_config(test_config());
+        assert_eq!(searcher.index_state(), None);
+        searcher.warm_up(root);
+        assert_eq!(
+            searcher
+                .search(root, "helper", SearchMode::Substring)
+                .unwrap()
+                .len(),
+            2
+        );
 
-        // Create hidden file
-        fs::write(root.join(".hidden"), "hidden content").unwrap();
+        searcher.warm_up(root.join("missing"));
+        assert!(searcher
+            .search(&root.join("missing"), "helper", SearchMode::Substring)
+            .is_err());
+        assert_eq!(searcher.index_state(), Some(IndexState::Failed));
+    }
 
-        temp_dir
+    #[test]
+    fn test_search_in_index() {
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let searcher = FileSearcher::with_config(test_config());
+        let index = searcher.index(root).unwrap();
+
+        fs::write(root.join("main2.rs"), "").unwrap();
+        assert_eq!(
+            searcher
+                .search_in_index(&index, "main", SearchMode::Substring)
+                .unwrap(),
+            vec![root.join("main.rs")]
+        );
+        assert_eq!(
+            searcher.search_auto_in_index(&index, "helper").unwrap(),
+            vec![root.join("src").join("helper.rs")]
+        );
+        assert!(searcher
+            .search_in_index(&index, "", SearchMode::Glob)
+            .is_err());
+        assert_eq!(
+            searcher
+                .search(root, "main", SearchMode::Substring)
+                .unwrap()
+                .len(),
+            2
+        );
     }
 
-    fn test_config() -> crate::config::Config {
-        crate::config::Config {
-            ignore_hidden: false,
-            ignore_patterns: vec![], // Clear all ignore patterns for testing
-            case_sensitive: false,
-            max_depth: None,
-            max_file_size: None,
+    #[test]
+    fn test_search_with() {
+        use std::ops::ControlFlow;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        let searcher = FileSearcher::with_config(test_config());
+
+        let queries = [
+            ("rs", SearchMode::Substring),
+            #[cfg(feature = "regex")]
+            (r"^(main|lib)\.rs$", SearchMode::Regex),
+            #[cfg(feature = "glob")]
+            ("*.RS", SearchMode::Glob),
+            #[cfg(feature = "fuzzy")]
+            ("src/helpr", SearchMode::Fuzzy),
+        ];
+        for (query, mode) in queries {
+            let mut found = Vec::new();
+            let flow = searcher
+                .search_with(root, query, mode, |path| {
+                    found.push(path.to_path_buf());
+                    ControlFlow::<()>::Continue(())
+                })
+                .unwrap();
+            assert_eq!(flow, ControlFlow::Continue(()));
+            found.sort();
+            let mut expected = searcher.search(root, query, mode).unwrap();
+            expected.sort();
+            assert_eq!(found, expected, "{query}");
         }
+
+        let mut visits = 0;
+        let first = searcher
+            .search_with(root, ".rs", SearchMode::Substring, |path| {
+                visits += 1;
+                ControlFlow::Break(path.to_path_buf())
+            })
+            .unwrap();
+        assert_eq!(visits, 1);
+        assert!(matches!(first, ControlFlow::Break(path) if path.extension().unwrap() == "rs"));
+
+        assert!(searcher
+            .search_with(root, "", SearchMode::Glob, |_| {
+                ControlFlow::<()>::Continue(())
+            })
+            .is_err());
     }
 
     #[test]
-    fn test_basic_search() {
+    fn test_progress_reporter() {
+        use std::ops::ControlFlow;
+        use std::sync::{Arc, Mutex};
+
         let temp_dir = create_test_structure();
-        let searcher = FileSearcher::with_config(test_config());
+        let root = temp_dir.path();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        let searcher = FileSearcher::builder()
+            .config(test_config())
+            .progress_reporter(move |progress: &SearchProgress| {
+                sink.lock().unwrap().push(*progress);
+            })
+            .build()
+            .unwrap();
+        let last = || *reports.lock().unwrap().last().unwrap();
 
-        let results = searcher.search_auto(temp_dir.path(), "*.rs").unwrap();
-        // Should find main.rs, lib.rs, src/test.rs, src/helper.rs
-        assert!(
-            results.len() >= 4,
-            "Expected at least 4 .rs files, found {}",
-            results.len()
+        let results = searcher.search(root, ".rs", SearchMode::Substring).unwrap();
+        assert_eq!(
+            last(),
+            SearchProgress {
+                dirs_scanned: 2,
+                files_indexed: 7,
+                matches: results.len(),
+            }
         );
+
+        let mut visited = 0;
+        let flow = searcher
+            .search_with(root, "main", SearchMode::Substring, |_| {
+                visited += 1;
+                ControlFlow::<()>::Continue(())
+            })
+            .unwrap();
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(visited, 1);
+        assert_eq!(last().matches, 1);
+        assert_eq!(last().files_indexed, 7);
+
+        let mut indexer = crate::indexer::FileIndexer::new(test_config());
+        let count = Arc::new(Mutex::new(0));
+        let counted = Arc::clone(&count);
+        indexer.set_progress_reporter(Arc::new(move |_: &SearchProgress| {
+            *counted.lock().unwrap() += 1;
+        }));
+        indexer.build_index(root.to_str().unwrap()).unwrap();
+        assert_eq!(indexer.progress().files_indexed, 7);
+        assert!(*count.lock().unwrap() >= 1);
     }
 
     #[test]
-    fn test_substring_search() {
+    fn test_match_threads() {
+        let index: FileIndex = (0..20_000)
+            .map(|i| {
+                let name = format!("file_{i}.rs");
+                let path = PathBuf::from(format!("/data/{}/{name}", i % 7));
+                (name, path)
+            })
+            .collect();
+        let serial = FileSearcher::builder()
+            .config(test_config())
+            .match_threads(1)
+            .build()
+            .unwrap();
+        let pooled = FileSearcher::builder()
+            .config(test_config())
+            .match_threads(3)
+            .build()
+            .unwrap();
+        let global = FileSearcher::with_config(test_config());
+
+        let queries = [
+            ("file_12", SearchMode::Substring),
+            #[cfg(feature = "regex")]
+            (r"^file_1\d{3}\.rs$", SearchMode::Regex),
+            #[cfg(feature = "glob")]
+            ("file_1?3.rs", SearchMode::Glob),
+            #[cfg(feature = "fuzzy")]
+            ("fle_1234", SearchMode::Fuzzy),
+        ];
+        for (query, mode) in queries {
+            let expected = serial.search_in_index(&index, query, mode).unwrap();
+            assert!(!expected.is_empty(), "{query}");
+            assert_eq!(
+                pooled.search_in_index(&index, query, mode).unwrap(),
+                expected,
+                "{query}"
+            );
+            assert_eq!(
+                global.search_in_index(&index, query, mode).unwrap(),
+                expected,
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_cache() {
+        use crate::indexer::cache::IndexCache;
+
         let temp_dir = create_test_structure();
-        let searcher = FileSearcher::with_config(test_config());
+        let root = temp_dir.path().join("tree");
+        fs::create_dir_all(root.join("a").join("deep")).unwrap();
+        fs::create_dir(root.join("b")).unwrap();
+        fs::write(root.join("a").join("deep").join("one.rs"), "").unwrap();
+        fs::write(root.join("b").join("two.rs"), "").unwrap();
+        let cache_path = temp_dir.path().join("cache").join("index.cache");
+        let config = test_config();
 
-        let results = searcher
-            .search(temp_dir.path(), "main", SearchMode::Substring)
-            .unwrap();
-        assert_eq!(results.len(), 1);
-        assert!(results[0]
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .contains("main"));
+        let searcher = FileSearcher::with_config(config.clone());
+        let index = searcher.index_cached(&root, &cache_path).unwrap();
+        assert_eq!(index.path_count(), 2);
+
+        let mut cache = IndexCache::load(&cache_path).unwrap();
+        assert_eq!(cache, IndexCache::build(&root, &config).unwrap());
+        assert_eq!(cache.refresh(&config).unwrap(), 0);
+
+        fs::write(root.join("a").join("deep").join("three.rs"), "").unwrap();
+        fs::remove_dir_all(root.join("b")).unwrap();
+        fs::create_dir(root.join("c")).unwrap();
+        fs::write(root.join("c").join("four.rs"), "").unwrap();
+        // The root and a/deep changed, c is new
+        assert_eq!(cache.refresh(&config).unwrap(), 3);
+        let mut files: Vec<PathBuf> = cache.files().collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                root.join("a").join("deep").join("one.rs"),
+                root.join("a").join("deep").join("three.rs"),
+                root.join("c").join("four.rs"),
+            ]
+        );
+
+        let index = searcher.index_cached(&root, &cache_path).unwrap();
+        assert_eq!(
+            searcher
+                .search_in_index(&index, "four", SearchMode::Substring)
+                .unwrap(),
+            vec![root.join("c").join("four.rs")]
+        );
+        let other = searcher.index_cached(temp_dir.path(), &cache_path).unwrap();
+        assert!(other.path_count() > 3);
+
+        // A directory changed within the timestamp tick it was listed in
+        // keeps its time, so times from after the listing started are
+        // checked again
+        #[cfg(unix)]
+        {
+            let dir = root.join("c");
+            let tick = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+            let stamp = || fs::File::open(&dir).unwrap().set_modified(tick).unwrap();
+            stamp();
+            let mut cache = IndexCache::build(&root, &config).unwrap();
+            fs::write(dir.join("five.rs"), "").unwrap();
+            stamp();
+            assert_eq!(cache.refresh(&config).unwrap(), 1);
+            assert!(cache.files().any(|path| path == dir.join("five.rs")));
+        }
     }
 
     #[test]
-    fn test_glob_search() {
+    fn test_update_index() {
         let temp_dir = create_test_structure();
-        let searcher = FileSearcher::with_config(test_config());
+        let root = temp_dir.path().join("tree");
+        fs::create_dir_all(root.join("a").join("deep")).unwrap();
+        fs::create_dir_all(root.join("b").join("inner")).unwrap();
+        fs::write(root.join("a").join("deep").join("one.rs"), "").unwrap();
+        fs::write(root.join("b").join("inner").join("two.rs"), "").unwrap();
+        let root_str = root.to_str().unwrap();
 
-        let results = searcher
-            .search(temp_dir.path(), "*.rs", SearchMode::Glob)
-            .unwrap();
-        assert!(results.len() >= 4);
-    }
+        let mut indexer = crate::indexer::FileIndexer::new(test_config());
+        let mut index = FileIndex::new();
+        // Nothing was walked yet, so the whole tree is
+        assert_eq!(indexer.update_index(&mut index, root_str).unwrap(), 5);
+        assert_eq!(index.path_count(), 2);
+        assert_eq!(indexer.update_index(&mut index, root_str).unwrap(), 0);
 
-    #[test]
-    fn test_regex_search() {
-        let temp_dir = create_test_structure();
-        let searcher = FileSearcher::with_config(test_config());
+        fs::write(root.join("a").join("deep").join("three.rs"), "").unwrap();
+        fs::remove_dir_all(root.join("b")).unwrap();
+        fs::create_dir_all(root.join("c").join("new")).unwrap();
+        fs::write(root.join("c").join("four.rs"), "").unwrap();
+        fs::write(root.join("c").join("new").join("five.rs"), "").unwrap();
+        // The root and a/deep changed, c is new
+        assert_eq!(indexer.update_index(&mut index, root_str).unwrap(), 3);
 
-        let results = searcher
-            .search(temp_dir.path(), r".*\.rs$", SearchMode::Regex)
+        let fresh = crate::indexer::FileIndexer::new(test_config())
+            .build_index(root_str)
             .unwrap();
-        assert!(results.len() >= 4);
+        let mut updated: Vec<_> = index.iter().collect();
+        updated.sort();
+        let mut expected: Vec<_> = fresh.iter().collect();
+        expected.sort();
+        assert_eq!(updated, expected);
+        assert_eq!(index.path_count(), 4);
+
+        #[cfg(feature = "fuzzy")]
+        {
+            let mut config = test_config();
+            config.fuzzy_candidate_index = true;
+            let mut indexer = crate::indexer::FileIndexer::new(config);
+            let mut index = indexer.build_index(root_str).unwrap();
+            fs::write(root.join("a").join("six.rs"), "").unwrap();
+            assert_eq!(indexer.update_index(&mut index, root_str).unwrap(), 1);
+            assert!(indexer
+                .ngram_index()
+                .unwrap()
+                .candidates("six")
+                .is_some_and(|keys| keys.contains("six.rs")));
+        }
     }
 
     #[test]
-    fn test_fuzzy_search() {
+    fn test_disabled_modes() {
         let temp_dir = create_test_structure();
         let searcher = FileSearcher::with_config(test_config());
 
-        let results = searcher.search_fuzzy(temp_dir.path(), "man").unwrap(); // should find "main"
-        assert!(!results.is_empty());
-
-        // Check that results are scored
-        for (_, score) in &results {
-            assert!(*score >= 0.0 && *score <= 1.0);
+        for (mode, enabled) in [
+            (SearchMode::Substring, true),
+            (SearchMode::Glob, cfg!(feature = "glob")),
+            (SearchMode::Regex, cfg!(feature = "regex")),
+            (SearchMode::Fuzzy, cfg!(feature = "fuzzy")),
+        ] {
+            let result = searcher.search(temp_dir.path(), "main", mode);
+            assert_eq!(result.is_ok(), enabled, "{mode:?}");
+            if !enabled {
+                assert!(matches!(
+                    result,
+                    Err(FileSearchError::FeatureDisabled { .. })
+                ));
+            }
         }
+    }
 
-        // Verify we found main.rs
-        let found_main = results
-            .iter()
-            .any(|(path, _)| path.file_name().unwrap().to_str().unwrap() == "main.rs");
-        assert!(found_main, "Should find main.rs with fuzzy search 'man'");
+    #[test]
+    fn test_wildcard_match() {
+        use crate::search::matcher::wildcard_match;
+
+        assert!(wildcard_match("*.log", "build.log"));
+        assert!(!wildcard_match("*.log", "catalog.rs"));
+        assert!(wildcard_match("test_?.rs", "test_1.rs"));
+        assert!(!wildcard_match("test_?.rs", "test_10.rs"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(wildcard_match("*", ""));
     }
 
     #[test]
-    fn test_auto_detection() {
-        let temp_dir = create_test_structure();
-        let searcher = FileSearcher::with_config(test_config());
+    fn test_path_separators() {
+        use crate::search::matcher::{normalize_separators, path_contains};
 
-        // Should detect as glob
-        let (results, mode) = searcher
-            .search_auto_with_mode(temp_dir.path(), "*.rs")
-            .unwrap();
-        assert_eq!(mode, SearchMode::Glob);
-        assert!(results.len() >= 4);
+        let path = Path::new("src").join("search").join("mod.rs");
+        assert!(path_contains(&path, "src/search"));
+        assert_eq!(normalize_separators("a/b"), "a/b");
+        if cfg!(windows) {
+            assert!(path_contains(&path, "src\\search"));
+            assert_eq!(normalize_separators("a\\b/c"), "a/b/c");
+        } else {
+            // Backslashes are valid in Unix filenames
+            assert!(!path_contains(&path, "src\\search"));
+            assert_eq!(normalize_separators("a\\b"), "a\\b");
+        }
 
-        // Should detect as regex
-        let (results, mode) = searcher
-            .search_auto_with_mode(temp_dir.path(), r"\.rs$")
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::builder()
+            .ignore_hidden(false)
+            .clear_ignore_patterns()
+            .ignore_pattern("src/helper")
+            .build()
             .unwrap();
-        assert_eq!(mode, SearchMode::Regex);
-        assert!(results.len() >= 4);
-
-        // Should detect as substring
-        let (results, mode) = searcher
-            .search_auto_with_mode(temp_dir.path(), "main")
+        let results = searcher
+            .search(temp_dir.path(), "", SearchMode::Substring)
             .unwrap();
-        assert_eq!(mode, SearchMode::Substring);
-        assert_eq!(results.len(), 1);
+        assert!(!results.is_empty());
+        assert!(!results.iter().any(|p| p.ends_with("helper.rs")));
     }
 
     #[test]
-    fn test_builder_pattern() {
-        let temp_dir = create_test_structure();
+    fn test_path_style() {
+        use crate::config::PathStyle;
+
+        let path = Path::new("src").join("search").join("mod.rs");
+        assert_eq!(PathStyle::Forward.display(&path), "src/search/mod.rs");
+        assert_eq!(
+            PathStyle::Native.display(Path::new("src/search/mod.rs")),
+            path.to_string_lossy()
+        );
+        if !cfg!(windows) {
+            // Not a separator on Unix, so it stays
+            assert_eq!(PathStyle::Forward.display(Path::new("a\\b")), "a\\b");
+        }
 
-        // Test that the builder pattern works
         let searcher = FileSearcher::builder()
-            .ignore_hidden(false)
-            .clear_ignore_patterns() // Clear defaults first
-            .case_sensitive(false)
+            .path_style(PathStyle::Forward)
             .build()
             .unwrap();
+        assert_eq!(searcher.config().path_style, PathStyle::Forward);
+    }
 
-        let results = searcher.search_auto(temp_dir.path(), "*.rs").unwrap();
-        // Should find all .rs files with builder configuration
-        assert!(results.len() >= 4, "Builder pattern should work correctly");
+    #[test]
+    fn test_tilde_expansion() {
+        use crate::config::tilde::{expand_tilde, home_dir};
+
+        assert_eq!(expand_tilde("a/~b"), "a/~b");
+        assert_eq!(expand_tilde("~no-such-user-here/x"), "~no-such-user-here/x");
+        if let Some(home) = home_dir() {
+            let home = home.to_string_lossy();
+            assert_eq!(expand_tilde("~"), home);
+            assert_eq!(expand_tilde("~/Documents"), format!("{home}/Documents"));
+        }
+        #[cfg(unix)]
+        if let Some(root) = crate::config::tilde::user_home_dir("root") {
+            assert_eq!(expand_tilde("~root/bin"), format!("{}/bin", root.display()));
+        }
+
+        // Ignore patterns are expanded once, when the indexer is created
+        if let Some(home) = home_dir() {
+            let config = crate::config::Config {
+                ignore_patterns: vec!["~".to_string()],
+                ..crate::config::Config::default()
+            };
+            let file = home.join("notes.txt");
+            let indexer = crate::indexer::FileIndexer::new(config.clone());
+            assert!(indexer.should_ignore(&file));
+            let literal = crate::indexer::FileIndexer::new(crate::config::Config {
+                expand_tilde: false,
+                ..config
+            });
+            assert!(!literal.should_ignore(&file));
+        }
+
+        let literal = FileSearcher::builder().expand_tilde(false).build().unwrap();
+        assert!(literal
+            .search(Path::new("~"), "helper", SearchMode::Substring)
+            .is_err());
     }
 
     #[test]
-    fn test_ignore_patterns() {
-        let temp_dir = create_test_structure();
+    fn test_env_var_expansion() {
+        use crate::config::env::expand_env_vars;
+        use crate::indexer::scheduler::IndexRoot;
 
-        let searcher = FileSearcher::builder()
-            .ignore_hidden(false)
-            .clear_ignore_patterns() // Clear defaults first
-            .ignore_pattern("*.md")
-            .build()
-            .unwrap();
+        std::env::set_var("WHATEVER_FIND_TEST_DIR", "/data");
+        assert_eq!(expand_env_vars("$WHATEVER_FIND_TEST_DIR/a"), "/data/a");
+        assert_eq!(expand_env_vars("${WHATEVER_FIND_TEST_DIR}x"), "/datax");
+        assert_eq!(expand_env_vars("%WHATEVER_FIND_TEST_DIR%\\a"), "/data\\a");
+        // Unset, malformed and non-references are kept
+        assert_eq!(
+            expand_env_vars("$WHATEVER_FIND_UNSET/$RECYCLE.BIN"),
+            "$WHATEVER_FIND_UNSET/$RECYCLE.BIN"
+        );
+        assert_eq!(expand_env_vars("100% ${ $1 %%"), "100% ${ $1 %%");
 
-        let results = searcher.search_auto(temp_dir.path(), "*").unwrap();
-        // Should not include README.md
-        assert!(!results.iter().any(|p| p
-            .extension()
-            .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))));
+        let mut config = crate::config::Config {
+            ignore_patterns: vec!["$WHATEVER_FIND_TEST_DIR/cache".to_string()],
+            index_roots: vec![IndexRoot::new("${WHATEVER_FIND_TEST_DIR}/docs")
+                .ignore_pattern("%WHATEVER_FIND_TEST_DIR%")],
+            ..crate::config::Config::default()
+        };
+        config.expand_env_vars();
+        assert_eq!(config.ignore_patterns, vec!["/data/cache".to_string()]);
+        assert_eq!(config.index_roots[0].path, Path::new("/data/docs"));
+        assert_eq!(
+            config.index_roots[0].ignore_patterns,
+            vec!["/data".to_string()]
+        );
     }
 
     #[test]
@@ -869,6 +5979,38 @@ mod tests {
         assert_eq!(results.len(), 0);
     }
 
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_case_insensitive_glob() {
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+        // 'İ' lowercases to two characters, which used to break `?`
+        fs::write(root.join("İstanbul.txt"), "").unwrap();
+        fs::write(root.join("Äpfel.txt"), "").unwrap();
+        fs::write(root.join("Notes.MD"), "").unwrap();
+        let searcher = FileSearcher::with_config(test_config());
+
+        let results = searcher
+            .search(root, "?stanbul.TXT", SearchMode::Glob)
+            .unwrap();
+        assert_eq!(results, vec![root.join("İstanbul.txt")]);
+
+        let results = searcher.search(root, "ä*", SearchMode::Glob).unwrap();
+        assert_eq!(results, vec![root.join("Äpfel.txt")]);
+
+        // Classes keep their ranges and match either case
+        let results = searcher
+            .search(root, "[m-o]*.md", SearchMode::Glob)
+            .unwrap();
+        assert_eq!(results, vec![root.join("Notes.MD")]);
+        for class in ["[À-Ö]pfel.txt", "[à-ö]PFEL.txt", "[!a-z]pfel.txt"] {
+            let results = searcher.search(root, class, SearchMode::Glob).unwrap();
+            assert_eq!(results, vec![root.join("Äpfel.txt")], "{class}");
+        }
+        let results = searcher.search(root, "İ*", SearchMode::Glob).unwrap();
+        assert_eq!(results, vec![root.join("İstanbul.txt")]);
+    }
+
     #[test]
     fn test_builder_validation() {
         // Test invalid max_depth
@@ -879,6 +6021,22 @@ mod tests {
         let result = FileSearcher::builder().max_file_size(0).build();
         assert!(result.is_err());
 
+        // Test zero IO timeout
+        let result = FileSearcher::builder()
+            .io_timeout(std::time::Duration::ZERO)
+            .build();
+        assert!(result.is_err());
+
+        // Test zero IO throttle rate
+        let result = FileSearcher::builder()
+            .io_throttle(crate::config::IoThrottle::EntriesPerSecond(0))
+            .build();
+        assert!(result.is_err());
+
+        // Test out-of-range fuzzy threshold
+        let result = FileSearcher::builder().fuzzy_min_score(1.5).build();
+        assert!(result.is_err());
+
         // Test empty ignore pattern
         let mut builder = FileSearcher::builder();
         builder.config.ignore_patterns.push(String::new());
@@ -886,6 +6044,70 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_io_timeout_walk() {
+        let temp_dir = create_test_structure();
+        let mut config = test_config();
+        config.io_timeout = Some(std::time::Duration::from_secs(5));
+        config.max_file_size = Some(1024);
+
+        let mut indexer = crate::indexer::FileIndexer::new(config);
+        let index = indexer
+            .build_index(temp_dir.path().to_str().unwrap())
+            .unwrap();
+        assert_eq!(index.path_count(), 7);
+        assert!(indexer.stats().timed_out.is_empty());
+        assert_eq!(indexer.stats().retries, 0);
+        assert_eq!(indexer.stats().vanished, 0);
+    }
+
+    #[test]
+    fn test_vanished_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        let mut config = test_config();
+        config.max_file_size = Some(1024);
+
+        // The first file kept deletes the other, already listed, before its
+        // size is read
+        let mut filters = crate::indexer::filter::Filters::default();
+        let listed = root.clone();
+        filters.push(std::sync::Arc::new(move |entry: &walkdir::DirEntry| {
+            for name in ["a.txt", "b.txt"] {
+                let other = listed.join(name);
+                if entry.path() != other {
+                    let _ = fs::remove_file(other);
+                }
+            }
+            true
+        }));
+        let mut indexer = crate::indexer::FileIndexer::new(config);
+        indexer.set_filters(filters);
+        let index = indexer.build_index(root.to_str().unwrap()).unwrap();
+        assert_eq!(index.path_count(), 1);
+        assert_eq!(indexer.stats().vanished, 1);
+    }
+
+    #[test]
+    fn test_io_throttle_walk() {
+        let temp_dir = create_test_structure();
+        let mut config = test_config();
+        config.io_throttle = Some(crate::config::IoThrottle::SleepEveryDirs {
+            dirs: 1,
+            sleep: std::time::Duration::from_millis(20),
+        });
+
+        let started = std::time::Instant::now();
+        let mut indexer = crate::indexer::FileIndexer::new(config);
+        let index = indexer
+            .build_index(temp_dir.path().to_str().unwrap())
+            .unwrap();
+        assert_eq!(index.path_count(), 7);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(40));
+    }
+
     #[test]
     fn test_error_handling() {
         let searcher = FileSearcher::with_config(test_config());
@@ -900,7 +6122,411 @@ mod tests {
         assert!(result.is_err());
     }
 
-    #[cfg(feature = "async")]
+    #[test]
+    fn test_query_validation() {
+        use crate::search::{validate_query, MAX_QUERY_LEN};
+
+        let temp_dir = create_test_structure();
+        let searcher = FileSearcher::with_config(test_config());
+        let is_invalid_query = |result: Result<Vec<PathBuf>>| {
+            matches!(result, Err(FileSearchError::InvalidQuery { .. }))
+        };
+
+        // An empty substring query lists every file
+        let all = searcher.search(temp_dir.path(), "", SearchMode::Substring);
+        assert_eq!(all.unwrap().len(), 7);
+        assert!(is_invalid_query(searcher.search(
+            temp_dir.path(),
+            "",
+            SearchMode::Glob
+        )));
+        assert!(is_invalid_query(
+            searcher.search_auto(temp_dir.path(), "  \t")
+        ));
+        let long = "a".repeat(MAX_QUERY_LEN + 1);
+        assert!(is_invalid_query(
+            searcher.search_auto(temp_dir.path(), &long)
+        ));
+
+        assert!(validate_query("[ab", SearchMode::Glob).is_err());
+        assert!(validate_query("*[!]ab", SearchMode::Glob).is_err());
+        assert!(validate_query("[]ab]*.rs", SearchMode::Glob).is_ok());
+        assert!(validate_query("[!a]*[0-9].rs", SearchMode::Glob).is_ok());
+    }
+
+    #[test]
+    fn test_root_validation() {
+        let searcher = FileSearcher::with_config(test_config());
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path();
+
+        let missing = root.join("missing");
+        match searcher.search(&missing, "", SearchMode::Substring) {
+            Err(FileSearchError::InvalidPath { path, reason }) => {
+                assert_eq!(path, missing);
+                assert_eq!(reason, "does not exist");
+            }
+            other => panic!("expected InvalidPath, got {other:?}"),
+        }
+
+        // A file root is searched as a single file
+        let file = root.join("main.rs");
+        let results = searcher
+            .search(&file, "main", SearchMode::Substring)
+            .unwrap();
+        assert_eq!(results, vec![file]);
+
+        #[cfg(unix)]
+        {
+            let link = root.join("dangling");
+            std::os::unix::fs::symlink(root.join("gone"), &link).unwrap();
+            let error = searcher
+                .search(&link, "", SearchMode::Substring)
+                .unwrap_err();
+            assert!(error.to_string().contains("broken symbolic link"));
+        }
+    }
+
+    #[test]
+    fn test_database_roundtrip() {
+        let temp_dir = create_test_structure();
+        let db_dir = TempDir::new().unwrap();
+        let db_path = db_dir.path().join("files.db");
+
+        let database =
+            crate::indexer::database::Database::build(&[temp_dir.path()], &test_config()).unwrap();
+        database.save(&db_path).unwrap();
+        let loaded = crate::indexer::database::Database::load(&db_path).unwrap();
+        assert_eq!(loaded.len(), database.len());
+        assert!(loaded.covers(&temp_dir.path().join("src")));
+
+        let index = loaded
+            .to_index(&temp_dir.path().join("src"), &test_config())
+            .unwrap();
+        let engine = crate::search::SearchEngine::new(test_config());
+        let results = engine.search_substring(&index, ".rs");
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|p| p.starts_with(temp_dir.path())));
+
+        // Shards are searched in parallel and refreshed independently
+        let mut database = loaded;
+        fs::write(temp_dir.path().join("src").join("extra.rs"), "").unwrap();
+        let results = database
+            .search(
+                temp_dir.path(),
+                ".rs",
+                SearchMode::Substring,
+                &test_config(),
+            )
+            .unwrap();
+        assert_eq!(results.len(), 4);
+        database
+            .refresh_shard(temp_dir.path(), Path::new("src"), &test_config())
+            .unwrap();
+        let results = database
+            .search(
+                temp_dir.path(),
+                ".rs",
+                SearchMode::Substring,
+                &test_config(),
+            )
+            .unwrap();
+        assert_eq!(results.len(), 5);
+
+        // Corrupt files are rejected rather than misread
+        fs::write(&db_path, b"not a database").unwrap();
+        assert!(matches!(
+            crate::indexer::database::Database::load(&db_path),
+            Err(FileSearchError::InvalidDatabase { .. })
+        ));
+    }
+
+    #[test]
+    fn test_shard_bloom_filters() {
+        use crate::indexer::bloom::NgramBloom;
+
+        let temp_dir = create_test_structure();
+        let database =
+            crate::indexer::database::Database::build(&[temp_dir.path()], &test_config()).unwrap();
+        let shards = &database.roots()[0].shards;
+        let root_shard = shards.iter().find(|s| s.prefix == Path::new("")).unwrap();
+        let src_shard = shards
+            .iter()
+            .find(|s| s.prefix == Path::new("src"))
+            .unwrap();
+
+        let helper = NgramBloom::query_ngrams("HELPER", SearchMode::Substring);
+        assert!(src_shard.bloom.may_contain_all(&helper));
+        assert!(!root_shard.bloom.may_contain_all(&helper));
+
+        let toml = NgramBloom::query_ngrams("*.toml", SearchMode::Glob);
+        assert!(root_shard.bloom.may_contain_all(&toml));
+        assert!(!src_shard.bloom.may_contain_all(&toml));
+        // Only one of several alternatives need be in a name
+        assert_eq!(
+            NgramBloom::query_ngrams("help{er,ing}.*", SearchMode::Glob),
+            ["hel", "elp"]
+        );
+
+        // Regex queries cannot be summarised and never skip shards
+        assert!(NgramBloom::query_ngrams(r"help\w+", SearchMode::Regex).is_empty());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_background_priority() {
+        // SAFETY: plain syscalls reading the nice value of the calling thread
+        let nice = || unsafe {
+            let tid = libc::id_t::try_from(libc::syscall(libc::SYS_gettid)).unwrap();
+            libc::getpriority(libc::PRIO_PROCESS, tid)
+        };
+        let before = nice();
+        std::thread::spawn(move || {
+            let background = crate::indexer::priority::enter_background();
+            assert!(background.is_lowered());
+            assert_eq!(nice(), 19);
+        })
+        .join()
+        .unwrap();
+        // Other threads keep their priority
+        assert_eq!(nice(), before);
+    }
+
+    #[test]
+    fn test_scheduler_priorities() {
+        use crate::indexer::scheduler::{IndexRoot, IndexScheduler};
+        use std::time::{Duration, SystemTime};
+
+        let temp_dir = create_test_structure();
+        let config = crate::config::Config {
+            index_roots: vec![
+                IndexRoot::new(temp_dir.path()).priority(1),
+                IndexRoot::new(temp_dir.path().join("src"))
+                    .priority(5)
+                    .refresh_interval(Duration::from_secs(60)),
+            ],
+            ..test_config()
+        };
+        let scheduler = IndexScheduler::new(config);
+        let mut database = crate::indexer::database::Database::new();
+
+        let due = scheduler.due_roots(&database, SystemTime::now());
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0].priority, 5);
+
+        let refreshed = scheduler.run_pending(&mut database).unwrap();
+        assert_eq!(refreshed.len(), 2);
+        assert!(scheduler.due_roots(&database, SystemTime::now()).is_empty());
+
+        // The src root expires first
+        let later = SystemTime::now() + Duration::from_secs(120);
+        let due = scheduler.due_roots(&database, later);
+        assert_eq!(due.len(), 1);
+        assert!(due[0].path.ends_with("src"));
+    }
+
+    #[cfg(feature = "node")]
+    #[test]
+    fn test_node_search() {
+        use crate::node::{self, SearchOptions};
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path().to_str().unwrap();
+        let mut streamed = Vec::new();
+        let options = SearchOptions {
+            mode: Some("fuzzy".to_string()),
+            limit: Some(1),
+            include_hidden: Some(true),
+            ..SearchOptions::default()
+        };
+        let matches = node::run(root, "mian.rs", &options, |found| {
+            streamed.push(found.clone());
+        })
+        .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].path.ends_with("main.rs"));
+        assert!(matches[0].score.is_some());
+        assert_eq!(streamed.len(), 1);
+        assert!(streamed[0].score.is_some());
+
+        // Temporary directories have hidden names
+        let options = SearchOptions {
+            include_hidden: Some(true),
+            ..SearchOptions::default()
+        };
+        let matches = node::run(root, "helper", &options, |_| {}).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].score, None);
+
+        // Other searches stop at the limit with what they streamed
+        let mut streamed = Vec::new();
+        let options = SearchOptions {
+            limit: Some(2),
+            include_hidden: Some(true),
+            ..SearchOptions::default()
+        };
+        let matches = node::run(root, ".rs", &options, |found| {
+            streamed.push(found.clone());
+        })
+        .unwrap();
+        assert_eq!(matches.len(), 2);
+        streamed.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(streamed, matches);
+
+        let options = SearchOptions {
+            fuzzy_min_score: Some(1.5),
+            ..SearchOptions::default()
+        };
+        assert!(node::run(root, "main", &options, |_| {}).is_err());
+
+        let options = SearchOptions {
+            mode: Some("telepathy".to_string()),
+            ..SearchOptions::default()
+        };
+        assert!(node::run(root, "main", &options, |_| {}).is_err());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_event_stream() {
+        use crate::events::{self, EventWriter};
+
+        let temp_dir = create_test_structure();
+        let mut events = EventWriter::new(Vec::new());
+        let matches = events::search(
+            temp_dir.path(),
+            ".rs",
+            SearchMode::Substring,
+            &test_config(),
+            false,
+            &mut events,
+        )
+        .unwrap();
+        assert_eq!(matches, 4);
+
+        let output = String::from_utf8(events.into_inner()).unwrap();
+        let events: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events[0]["event"], "index-progress");
+        // The last progress event reports the finished walk
+        let progress = events
+            .iter()
+            .rfind(|event| event["event"] == "index-progress")
+            .unwrap();
+        assert_eq!(progress["dirs"], 2);
+        assert_eq!(progress["files"], 7);
+        let found = events
+            .iter()
+            .filter(|event| event["event"] == "match")
+            .count();
+        assert_eq!(found, 4);
+        let done = events.last().unwrap();
+        assert_eq!(done["event"], "done");
+        assert_eq!(done["files_indexed"], 7);
+        assert_eq!(done["matches"], 4);
+        assert!(progress.get("percent").is_none());
+
+        // Counting directories first adds a percentage
+        let mut events = EventWriter::new(Vec::new());
+        events::search(
+            temp_dir.path(),
+            ".rs",
+            SearchMode::Substring,
+            &test_config(),
+            true,
+            &mut events,
+        )
+        .unwrap();
+        let output = String::from_utf8(events.into_inner()).unwrap();
+        let progress: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .filter(|event: &serde_json::Value| event["event"] == "index-progress")
+            .collect();
+        assert!(progress.iter().all(|event| event["total_dirs"] == 2));
+        assert_eq!(progress.last().unwrap()["percent"], 100.0);
+    }
+
+    #[test]
+    fn test_progress_percent() {
+        use crate::indexer::FileIndexer;
+        use crate::search::handle::Progress;
+
+        let temp_dir = create_test_structure();
+        let mut indexer = FileIndexer::new(test_config());
+        let total = indexer
+            .count_dirs(temp_dir.path().to_str().unwrap())
+            .unwrap();
+        assert_eq!(total, 2);
+
+        let handle = SearchHandle::new();
+        assert_eq!(handle.progress().percent(), None);
+        handle.set_total_dirs(total);
+        let progress = Progress {
+            dirs: 1,
+            ..handle.progress()
+        };
+        assert_eq!(progress.percent(), Some(50.0));
+        let overrun = Progress {
+            dirs: 3,
+            ..progress
+        };
+        assert_eq!(overrun.percent(), Some(100.0));
+    }
+
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn test_rpc_server() {
+        use crate::rpc::RpcServer;
+        use serde_json::json;
+
+        let temp_dir = create_test_structure();
+        let root = temp_dir.path().to_string_lossy();
+        let config = json!({ "ignore_hidden": false, "ignore_patterns": [] });
+        let input: String = [
+            json!({ "id": 1, "method": "query", "params": { "query": "main" } }),
+            json!({
+                "id": 2,
+                "method": "initialize",
+                "params": { "root": root, "config": config },
+            }),
+            json!({
+                "id": 3,
+                "method": "query",
+                "params": { "query": ".rs", "mode": "substring", "limit": 2 },
+            }),
+            json!({ "id": 4, "method": "shutdown" }),
+            json!({ "id": 5, "method": "query", "params": { "query": "main" } }),
+        ]
+        .iter()
+        .map(|request| request.to_string() + "\n")
+        .collect();
+
+        let mut output = Vec::new();
+        RpcServer::new()
+            .serve(input.as_bytes(), &mut output)
+            .unwrap();
+        let messages: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(messages[0]["id"], 1);
+        assert_eq!(messages[0]["error"]["code"], -32002);
+        assert_eq!(messages[1]["result"]["files"], 7);
+        // Two streamed matches, then the response to the query
+        assert_eq!(messages[2]["method"], "match");
+        assert_eq!(messages[3]["params"]["id"], 3);
+        assert_eq!(messages[4]["result"]["count"], 2);
+        assert_eq!(messages[5]["id"], 4);
+        // Nothing is answered after shutdown
+        assert_eq!(messages.len(), 6);
+    }
+
+    #[cfg(all(feature = "async", feature = "glob"))]
     #[tokio::test]
     async fn test_async_search() {
         let temp_dir = create_test_structure();
@@ -912,4 +6538,104 @@ mod tests {
             .unwrap();
         assert!(results.len() >= 4);
     }
+
+    #[cfg(all(feature = "async", feature = "glob"))]
+    #[tokio::test]
+    async fn test_search_stream() {
+        use tokio_stream::StreamExt;
+
+        let temp_dir = create_test_structure();
+        for i in 0..2 * STREAM_BUFFER {
+            fs::write(temp_dir.path().join(format!("gen_{i}.rs")), "").unwrap();
+        }
+        let searcher = FileSearcher::with_config(test_config());
+
+        let mut found: Vec<PathBuf> = searcher
+            .search_stream(temp_dir.path(), "*.rs")
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        found.sort();
+        let expected = searcher.search_auto(temp_dir.path(), "*.rs").unwrap();
+        assert_eq!(found, expected);
+
+        // Dropping the stream early stops the walk instead of blocking it
+        let mut stream = Box::pin(searcher.search_stream(temp_dir.path(), "*.rs"));
+        assert!(stream.next().await.unwrap().is_ok());
+        drop(stream);
+
+        let mut stream = Box::pin(searcher.search_stream(&temp_dir.path().join("missing"), "*.rs"));
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_walker() {
+        use crate::indexer::async_walker::AsyncWalker;
+        use crate::indexer::file_walker::TraversalLimit;
+        use crate::indexer::FileIndexer;
+
+        let temp_dir = create_test_structure();
+        fs::create_dir_all(temp_dir.path().join("src").join("nested")).unwrap();
+        fs::write(
+            temp_dir.path().join("src").join("nested").join("deep.rs"),
+            "",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("big.bin"), vec![0u8; 2048]).unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let mut ignoring = test_config();
+        ignoring.ignore_hidden = true;
+        ignoring.ignore_patterns = vec!["helper*".to_string()];
+        let configs = [
+            test_config(),
+            ignoring,
+            Config {
+                max_depth: Some(2),
+                max_file_size: Some(1024),
+                name_length: Some("-8".parse().unwrap()),
+                ..test_config()
+            },
+        ];
+        for config in configs {
+            let mut indexer = FileIndexer::new(config.clone());
+            let expected = indexer.build_index(root).unwrap();
+            let index = indexer.build_index_async(root).await.unwrap();
+            assert_eq!(index, expected, "{config:?}");
+        }
+
+        let limited = AsyncWalker::new(&Config {
+            max_entries: Some(3),
+            ..test_config()
+        })
+        .unwrap();
+        let (files, stats) = limited.walk_files(root).await.unwrap();
+        assert!(files.len() < 3);
+        assert_eq!(stats.limit_exceeded, Some(TraversalLimit::Entries(3)));
+
+        assert!(AsyncWalker::new(&Config {
+            respect_gitignore: true,
+            ..test_config()
+        })
+        .is_err());
+        let walker = AsyncWalker::new(&test_config()).unwrap();
+        assert!(walker
+            .walk_files(temp_dir.path().join("missing").to_str().unwrap())
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_task_error() {
+        let handle = tokio::spawn(tokio::time::sleep(std::time::Duration::from_secs(60)));
+        handle.abort();
+        let error = FileSearchError::from(handle.await.unwrap_err());
+
+        assert!(matches!(error, FileSearchError::AsyncTask { .. }));
+        assert!(error.to_string().contains("cancelled"));
+        assert!(std::error::Error::source(&error).is_some());
+    }
 }
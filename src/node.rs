@@ -0,0 +1,250 @@
+//! Node.js bindings for Electron apps and editor extensions
+//!
+//! Exposes an asynchronous `search` to JavaScript that resolves to the
+//! matches with their scores and metadata, and hands each match to an
+//! optional callback as soon as the walk finds it, so an extension can fill
+//! its result list without spawning the CLI and parsing its output:
+//!
+//! ```js
+//! const { search } = require('./whatever_find.node');
+//!
+//! const matches = await search('/home/me/project', 'mian.rs', { mode: 'fuzzy' },
+//!   (match) => list.append(match.path));
+//! ```
+//!
+//! Build the addon as a dynamic library and rename it to end in `.node`:
+//!
+//! ```text
+//! cargo rustc --release --lib --features node --crate-type cdylib
+//! ```
+//!
+//! Node's API is looked up in the host process when the addon is loaded, so
+//! no Node headers or libraries are needed to build it.
+
+use crate::config::Config;
+use crate::indexer::{FileIndex, FileIndexer};
+use crate::search::file_match::FileMatch;
+use crate::search::{validate_query, SearchEngine, SearchMode};
+use crate::FileSearchError;
+use napi::bindgen_prelude::AsyncTask;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, Task};
+use napi_derive::napi;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+/// Options of a search, all optional
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// `auto` (the default), `substring`, `literal`, `glob`, `regex` or `fuzzy`
+    pub mode: Option<String>,
+    /// Report at most this many matches
+    ///
+    /// Searches other than fuzzy ones stop walking once they have found this
+    /// many. Fuzzy searches walk on and resolve to the best ones, so the
+    /// callback may have seen others.
+    pub limit: Option<u32>,
+    /// Deepest directory level to search
+    pub max_depth: Option<u32>,
+    /// Whether hidden files are searched too (default: false)
+    pub include_hidden: Option<bool>,
+    /// Whether matching is case sensitive (default: false)
+    pub case_sensitive: Option<bool>,
+    /// Lowest fuzzy score (0.0 to 1.0) a file needs to be reported
+    pub fuzzy_min_score: Option<f64>,
+}
+
+/// A file matching the query
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// Path of the file
+    pub path: String,
+    /// Relevance of the match, for fuzzy queries only
+    pub score: Option<f64>,
+    /// Whether the file is a cloud placeholder whose contents are online only
+    pub online_only: bool,
+    /// Where the file was before it was moved to the trash
+    pub trashed_from: Option<String>,
+}
+
+impl Match {
+    fn new(file_match: FileMatch, score: Option<f64>) -> Self {
+        Self {
+            path: file_match.path.to_string_lossy().into_owned(),
+            score,
+            online_only: file_match.online_only,
+            trashed_from: file_match
+                .trashed_from
+                .map(|path| path.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+/// A search running on Node's worker pool
+pub struct SearchTask {
+    root: String,
+    query: String,
+    options: SearchOptions,
+    on_match: Option<ThreadsafeFunction<Match, ErrorStrategy::Fatal>>,
+}
+
+impl Task for SearchTask {
+    type Output = Vec<Match>;
+    type JsValue = Vec<Match>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let matches = run(&self.root, &self.query, &self.options, |found| {
+            if let Some(on_match) = &self.on_match {
+                on_match.call(found.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        });
+        matches.map_err(|e| napi::Error::from_reason(e.to_string()))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Search `root` for `query`, calling `onMatch` with every match as the walk
+/// finds it, and resolve to all matches
+///
+/// The callback sees matches in walk order; the promise resolves to them
+/// with fuzzy matches best first and all others by path.
+///
+/// # Errors
+///
+/// Returns an error if `onMatch` cannot be called from the worker pool. The
+/// promise is rejected if the mode or query is invalid or `root` cannot be
+/// indexed.
+#[napi(
+    ts_args_type = "root: string, query: string, options?: SearchOptions, onMatch?: (match: Match) => void",
+    ts_return_type = "Promise<Array<Match>>"
+)]
+pub fn search(
+    root: String,
+    query: String,
+    options: Option<SearchOptions>,
+    on_match: Option<JsFunction>,
+) -> napi::Result<AsyncTask<SearchTask>> {
+    let on_match = on_match
+        .map(|callback| {
+            callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Match>| {
+                Ok(vec![ctx.value])
+            })
+        })
+        .transpose()?;
+    Ok(AsyncTask::new(SearchTask {
+        root,
+        query,
+        options: options.unwrap_or_default(),
+        on_match,
+    }))
+}
+
+/// Walk `root`, passing each match for `query` to `found` as it is reached,
+/// then return them all ranked
+pub(crate) fn run(
+    root: &str,
+    query: &str,
+    options: &SearchOptions,
+    mut found: impl FnMut(&Match),
+) -> crate::Result<Vec<Match>> {
+    let config = config(options)?;
+    let engine = SearchEngine::new(config.clone());
+    let mode = match options.mode.as_deref() {
+        None | Some("auto") => engine.detect_search_mode(query),
+        Some("substring") => SearchMode::Substring,
+        Some("literal") => SearchMode::Literal,
+        Some("glob") => SearchMode::Glob,
+        Some("regex") => SearchMode::Regex,
+        Some("fuzzy") => SearchMode::Fuzzy,
+        Some(other) => {
+            return Err(FileSearchError::invalid_config(format!(
+                "unknown mode '{other}'"
+            )))
+        }
+    };
+    validate_query(query, mode)?;
+
+    let limit = options.limit.map_or(usize::MAX, |limit| {
+        usize::try_from(limit).unwrap_or(usize::MAX)
+    });
+    let matches = engine.file_matcher(query, mode)?;
+    let mut hits = Vec::new();
+    let mut streamed = 0;
+    // Only fuzzy searches rank what they find, so the others stop at the limit
+    let _ = FileIndexer::new(config.clone()).for_each_file(root, |key, path| {
+        if !matches(key, path) {
+            return ControlFlow::Continue(());
+        }
+        if streamed < limit {
+            streamed += 1;
+            found(&Match::new(
+                FileMatch::new(path.to_path_buf(), &config),
+                score(&engine, mode, key, path, query),
+            ));
+        }
+        hits.push((key.to_string(), path.to_path_buf()));
+        if mode != SearchMode::Fuzzy && streamed == limit {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })?;
+
+    let index: FileIndex = hits.into_iter().collect();
+    Ok(engine
+        .search_scored(&index, query, mode)?
+        .into_iter()
+        .take(limit)
+        .map(|(path, score)| Match::new(FileMatch::new(path, &config), score))
+        .collect())
+}
+
+/// Score of a match found while walking, for fuzzy queries only
+#[cfg_attr(not(feature = "fuzzy"), allow(unused_variables))]
+fn score(
+    engine: &SearchEngine,
+    mode: SearchMode,
+    key: &str,
+    path: &Path,
+    query: &str,
+) -> Option<f64> {
+    #[cfg(feature = "fuzzy")]
+    if mode == SearchMode::Fuzzy {
+        return engine.fuzzy_score(key, path, query);
+    }
+    None
+}
+
+/// The configuration `options` ask for
+///
+/// # Errors
+///
+/// Returns an error if `fuzzy_min_score` is outside 0.0 to 1.0
+fn config(options: &SearchOptions) -> crate::Result<Config> {
+    let defaults = Config::default();
+    if options
+        .fuzzy_min_score
+        .is_some_and(|score| !(0.0..=1.0).contains(&score))
+    {
+        return Err(FileSearchError::invalid_config(
+            "fuzzy_min_score must be between 0.0 and 1.0.",
+        ));
+    }
+    Ok(Config {
+        max_depth: options
+            .max_depth
+            .map(|depth| usize::try_from(depth).unwrap_or(usize::MAX))
+            .or(defaults.max_depth),
+        ignore_hidden: !options.include_hidden.unwrap_or(false),
+        case_sensitive: options.case_sensitive.unwrap_or(defaults.case_sensitive),
+        fuzzy_min_score: options.fuzzy_min_score.unwrap_or(defaults.fuzzy_min_score),
+        ..defaults
+    })
+}
@@ -0,0 +1,245 @@
+//! JSON-RPC protocol for embedding the search engine in editors
+//!
+//! [`RpcServer`] reads one JSON-RPC 2.0 request per line and writes one
+//! message per line, so an editor plugin can keep it running as a child
+//! process. The client first calls `initialize` with the root to index and an
+//! optional [`Config`]; the index is kept in memory and reused by every
+//! `query` until the next `initialize`. Each match of a query is streamed as a
+//! `match` notification carrying the query's request ID, followed by the
+//! response to the query itself.
+//!
+//! | Method       | Params                                 | Result           |
+//! |--------------|----------------------------------------|------------------|
+//! | `initialize` | `root`, optional `config`              | `{"files": n}`   |
+//! | `query`      | `query`, optional `mode` and `limit`   | `{"count": n}`   |
+//! | `shutdown`   | none                                   | `null`           |
+//!
+//! `mode` is `auto` (the default), `substring`, `literal`, `glob`, `regex` or `fuzzy`.
+//! Match notifications hold `id` and `path`, plus `score` for fuzzy queries.
+//! Requests without an ID are handled but not answered.
+
+use crate::config::Config;
+use crate::indexer::{FileIndex, FileIndexer};
+use crate::search::{validate_query, SearchEngine, SearchMode};
+use crate::{FileSearchError, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// The request was not valid JSON
+const PARSE_ERROR: i64 = -32700;
+/// The message was not a request object
+const INVALID_REQUEST: i64 = -32600;
+/// No such method
+const METHOD_NOT_FOUND: i64 = -32601;
+/// Missing or malformed parameters
+const INVALID_PARAMS: i64 = -32602;
+/// Indexing or searching failed
+const SEARCH_FAILED: i64 = -32000;
+/// `query` was called before `initialize`
+const NOT_INITIALIZED: i64 = -32002;
+
+/// Error reported to the client in place of a result
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<FileSearchError> for RpcError {
+    fn from(error: FileSearchError) -> Self {
+        Self::new(SEARCH_FAILED, error.to_string())
+    }
+}
+
+/// Index and engine set up by `initialize`
+struct Session {
+    engine: SearchEngine,
+    index: FileIndex,
+}
+
+/// Serves the JSON-RPC protocol over a pair of streams
+#[derive(Default)]
+pub struct RpcServer {
+    session: Option<Session>,
+}
+
+impl RpcServer {
+    /// Create a server that has not been initialized yet
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer requests from `input` on `output` until `shutdown` or the end
+    /// of `input`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading a request or writing a message fails;
+    /// failing requests are answered with an error response instead
+    pub fn serve<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> Result<()> {
+        for line in input.lines() {
+            let line = line.map_err(|e| FileSearchError::io_error(e, "reading RPC request"))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !self.handle(&line, &mut output)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer the request in `line`, returning `false` once it was `shutdown`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `output` fails
+    pub fn handle<W: Write>(&mut self, line: &str, output: &mut W) -> Result<bool> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                let error = RpcError::new(PARSE_ERROR, e.to_string());
+                send(output, &error_response(&Value::Null, &error))?;
+                return Ok(true);
+            }
+        };
+        let id = request.get("id").cloned();
+        let params = request.get("params").unwrap_or(&Value::Null);
+
+        let method = request.get("method").and_then(Value::as_str);
+        let outcome = match method {
+            Some("initialize") => self.initialize(params).map(|result| (Vec::new(), result)),
+            Some("query") => self.query(params).map(|matches| {
+                let count = matches.len();
+                (matches, json!({ "count": count }))
+            }),
+            Some("shutdown") => Ok((Vec::new(), Value::Null)),
+            Some(other) => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{other}'"),
+            )),
+            None => Err(RpcError::new(INVALID_REQUEST, "request has no method")),
+        };
+
+        let Some(id) = id else {
+            return Ok(method != Some("shutdown"));
+        };
+        match outcome {
+            Ok((matches, result)) => {
+                for mut params in matches {
+                    params["id"] = id.clone();
+                    send(
+                        output,
+                        &json!({ "jsonrpc": "2.0", "method": "match", "params": params }),
+                    )?;
+                }
+                send(
+                    output,
+                    &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                )?;
+            }
+            Err(error) => send(output, &error_response(&id, &error))?,
+        }
+        Ok(method != Some("shutdown"))
+    }
+
+    fn initialize(&mut self, params: &Value) -> std::result::Result<Value, RpcError> {
+        let root = params
+            .get("root")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing 'root'"))?;
+        let config = match params.get("config") {
+            Some(config) => serde_json::from_value(config.clone())
+                .map_err(|e| RpcError::new(INVALID_PARAMS, format!("invalid 'config': {e}")))?,
+            None => Config::default(),
+        };
+
+        let index = FileIndexer::new(config.clone()).build_index(root)?;
+        let files = index.path_count();
+        self.session = Some(Session {
+            engine: SearchEngine::new(config),
+            index,
+        });
+        Ok(json!({ "files": files }))
+    }
+
+    /// Parameters of one `match` notification per result, without the ID
+    fn query(&self, params: &Value) -> std::result::Result<Vec<Value>, RpcError> {
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| RpcError::new(NOT_INITIALIZED, "call 'initialize' first"))?;
+        let query = params
+            .get("query")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing 'query'"))?;
+        let limit = match params.get("limit") {
+            Some(limit) => limit
+                .as_u64()
+                .and_then(|limit| usize::try_from(limit).ok())
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "invalid 'limit'"))?,
+            None => usize::MAX,
+        };
+        let mode = match params.get("mode").and_then(Value::as_str) {
+            None | Some("auto") => session.engine.detect_search_mode(query),
+            Some("substring") => SearchMode::Substring,
+            Some("literal") => SearchMode::Literal,
+            Some("glob") => SearchMode::Glob,
+            Some("regex") => SearchMode::Regex,
+            Some("fuzzy") => SearchMode::Fuzzy,
+            Some(other) => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    format!("unknown mode '{other}'"),
+                ))
+            }
+        };
+        validate_query(query, mode).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+        let style = session.engine.config().path_style;
+
+        #[cfg(feature = "fuzzy")]
+        if mode == SearchMode::Fuzzy {
+            return Ok(session
+                .engine
+                .search_fuzzy(&session.index, query)
+                .into_iter()
+                .take(limit)
+                .map(|(path, score)| json!({ "path": style.display(&path), "score": score }))
+                .collect());
+        }
+        Ok(session
+            .engine
+            .search(&session.index, query, mode)?
+            .into_iter()
+            .take(limit)
+            .map(|path| json!({ "path": style.display(&path) }))
+            .collect())
+    }
+}
+
+fn error_response(id: &Value, error: &RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// Write `message` as one line and flush it so the client sees it at once
+fn send<W: Write>(output: &mut W, message: &Value) -> Result<()> {
+    let write = |output: &mut W| {
+        serde_json::to_writer(&mut *output, message)?;
+        output.write_all(b"\n")?;
+        output.flush()
+    };
+    write(output).map_err(|e| FileSearchError::io_error(e, "writing RPC message"))
+}
@@ -0,0 +1,43 @@
+//! Directories grouped by how many matches they hold
+//!
+//! Answers questions such as "which folders hold more than 100 `*.raw`
+//! files" by counting the matches of a search per directory that directly
+//! contains them. Only paths are needed, so no file is read or stat'ed.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "config")]
+use serde::Serialize;
+
+/// A directory and how many matches it directly contains
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize))]
+pub struct DirectoryCount {
+    /// The directory
+    pub path: PathBuf,
+    /// Number of matches directly inside it
+    pub files: usize,
+}
+
+/// Directories directly containing at least `min_files` of `paths`, those
+/// with the most first and ties by path
+#[must_use]
+pub fn directories_with_at_least(paths: &[PathBuf], min_files: usize) -> Vec<DirectoryCount> {
+    let mut counts: HashMap<&Path, usize> = HashMap::new();
+    for path in paths {
+        *counts
+            .entry(path.parent().unwrap_or(Path::new("")))
+            .or_default() += 1;
+    }
+    let mut dirs: Vec<DirectoryCount> = counts
+        .into_iter()
+        .filter(|(_, files)| *files >= min_files)
+        .map(|(path, files)| DirectoryCount {
+            path: path.to_path_buf(),
+            files,
+        })
+        .collect();
+    dirs.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.path.cmp(&b.path)));
+    dirs
+}
@@ -0,0 +1,207 @@
+//! Queries compiled once and matched against many indexes
+//!
+//! [`SearchEngine::search`](super::SearchEngine::search) parses the pattern
+//! on every call, which adds up for servers answering the same query over
+//! and over, or searching several indexes for it. A [`CompiledQuery`] from
+//! [`SearchEngine::compile`](super::SearchEngine::compile) keeps the folded
+//! substring, compiled glob or regex, or the registered custom matcher, and
+//! [`SearchEngine::search_compiled`](super::SearchEngine::search_compiled)
+//! matches it against any index without failing.
+//!
+//! ```rust
+//! use whatever_find::search::{SearchEngine, SearchMode};
+//! use whatever_find::{Config, FileIndex};
+//!
+//! # #[cfg(not(feature = "regex"))]
+//! # fn main() {}
+//! # #[cfg(feature = "regex")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let engine = SearchEngine::new(Config::default());
+//! let query = engine.compile(r"^test_.*\.rs$", SearchMode::Regex)?;
+//! for index in [FileIndex::new(), FileIndex::new()] {
+//!     let results = engine.search_compiled(&index, &query);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A compiled query folds case and checks word boundaries as the engine that
+//! compiled it is configured to, so it should be searched with that engine or
+//! one with the same configuration.
+
+use super::custom::PatternMatcher;
+#[cfg(feature = "glob")]
+use super::fold::Folding;
+use super::SearchMode;
+#[cfg(feature = "glob")]
+use crate::{FileSearchError, Result};
+#[cfg(feature = "glob")]
+use globset::GlobBuilder;
+#[cfg(any(feature = "glob", feature = "regex"))]
+use regex::Regex;
+#[cfg(feature = "glob")]
+use std::borrow::Cow;
+use std::fmt;
+#[cfg(feature = "glob")]
+use std::path::Path;
+use std::sync::Arc;
+
+/// A query checked and compiled for one search mode
+#[derive(Clone)]
+pub struct CompiledQuery {
+    pub(super) query: String,
+    pub(super) mode: SearchMode,
+    pub(super) pattern: Compiled,
+}
+
+/// The matching state of a [`CompiledQuery`]
+#[derive(Clone)]
+pub(super) enum Compiled {
+    /// The query, case-folded as configured
+    Substring(String),
+    #[cfg(feature = "glob")]
+    Glob(Glob),
+    #[cfg(feature = "regex")]
+    Regex(Regex),
+    /// Fuzzy queries are scored from the query text itself
+    #[cfg(feature = "fuzzy")]
+    Fuzzy,
+    Custom(Arc<dyn PatternMatcher>),
+}
+
+/// A glob compiled for file names, and for whole paths when it has a path
+/// separator or [`Config::match_full_path`](crate::Config::match_full_path)
+/// is set
+#[cfg(feature = "glob")]
+#[derive(Clone)]
+pub(super) struct Glob {
+    /// The pattern for file names, the last component of a path pattern
+    pub(super) name: Regex,
+    /// The pattern for whole paths, when matching them
+    pub(super) path: Option<Regex>,
+    /// Whether the path pattern is absolute, and so matches absolute paths
+    /// rather than paths below the search root
+    pub(super) absolute: bool,
+}
+
+#[cfg(feature = "glob")]
+impl Glob {
+    /// Whether `path`, normalized like the pattern, matches
+    ///
+    /// Relative path patterns match `relative(path)`, the path below the
+    /// search root, so directories above the root take no part.
+    pub(super) fn matches(
+        &self,
+        path: &Path,
+        relative: impl FnOnce(&Path) -> String,
+        folding: Folding,
+    ) -> bool {
+        match &self.path {
+            Some(pattern) => {
+                let target = if self.absolute {
+                    path.to_string_lossy()
+                } else {
+                    Cow::Owned(relative(path))
+                };
+                pattern.is_match(&folding.compose(&target))
+            }
+            None => path
+                .file_name()
+                .is_some_and(|name| self.matches_name(&name.to_string_lossy(), folding)),
+        }
+    }
+
+    /// Whether the file name `name`, normalized like the pattern, matches
+    /// the last component of the pattern
+    pub(super) fn matches_name(&self, name: &str, folding: Folding) -> bool {
+        self.name.is_match(&folding.compose(name))
+    }
+}
+
+/// `glob` compiled into a regex that matches one character at a time,
+/// folding case by Unicode's rules if `case_insensitive`, with errors
+/// reported against `pattern`
+///
+/// `globset` parses the glob, but its regex matches bytes, so `?` would take
+/// one byte of a multi-byte character and only ASCII letters would fold. The
+/// bytes it escapes non-ASCII characters into are joined back into those
+/// characters before compiling.
+#[cfg(feature = "glob")]
+pub(crate) fn glob_regex(glob: &str, pattern: &str, case_insensitive: bool) -> Result<Regex> {
+    let parsed = GlobBuilder::new(glob)
+        .case_insensitive(case_insensitive)
+        // Names hold no separators, so this only keeps `*` of path patterns
+        // within one component
+        .literal_separator(true)
+        // `\` separates components on Windows, so it never escapes
+        .backslash_escape(false)
+        .build()
+        .map_err(|e| FileSearchError::glob_error(e, pattern))?;
+    let bytes = parsed.regex();
+    let bytes = bytes.strip_prefix("(?-u)").unwrap_or(bytes);
+    Regex::new(&format!("(?s){}", join_escaped_bytes(bytes)))
+        .map_err(|e| FileSearchError::invalid_query(e.to_string(), pattern))
+}
+
+/// `regex` with every run of `\xHH` escapes of non-ASCII bytes replaced by
+/// the characters they encode
+#[cfg(feature = "glob")]
+fn join_escaped_bytes(regex: &str) -> String {
+    let mut joined = String::with_capacity(regex.len());
+    let mut bytes = Vec::new();
+    let mut rest = regex;
+    while let Some(c) = rest.chars().next() {
+        let byte = rest
+            .strip_prefix("\\x")
+            .and_then(|hex| hex.get(..2))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .filter(|byte| !byte.is_ascii());
+        if let Some(byte) = byte {
+            bytes.push(byte);
+            rest = &rest[4..];
+            continue;
+        }
+        joined.push_str(&String::from_utf8_lossy(&std::mem::take(&mut bytes)));
+        // Escapes are copied whole, so an escaped `\` never starts another
+        let len = match (c, rest[1..].chars().next()) {
+            ('\\', Some(escaped)) => 1 + escaped.len_utf8(),
+            _ => c.len_utf8(),
+        };
+        joined.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    joined.push_str(&String::from_utf8_lossy(&bytes));
+    joined
+}
+
+impl CompiledQuery {
+    /// `query`, compiled for `mode` into `pattern`
+    pub(super) fn new(query: &str, mode: SearchMode, pattern: Compiled) -> Self {
+        Self {
+            query: query.to_string(),
+            mode,
+            pattern,
+        }
+    }
+
+    /// The query as it was written
+    #[must_use]
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// The search mode the query was compiled for
+    #[must_use]
+    pub fn mode(&self) -> SearchMode {
+        self.mode
+    }
+}
+
+impl fmt::Debug for CompiledQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompiledQuery")
+            .field("query", &self.query)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,69 @@
+//! Integrator-supplied matching for [`SearchMode::Custom`]
+//!
+//! The built-in modes cover substrings, globs, regular expressions and fuzzy
+//! matching. A [`PatternMatcher`] registered on a
+//! [`FileSearcher`](crate::FileSearcher), or on a
+//! [`SearchEngine`](super::SearchEngine) directly, adds one more: searches in
+//! [`SearchMode::Custom`] keep the files whose names it accepts, so matching
+//! by sound, across scripts or by any other rule does not need changes to
+//! this crate. Custom searches are never chosen by automatic detection.
+//!
+//! The matcher sees each file name as it is on disk, whatever
+//! [`Config::case_sensitive`](crate::Config::case_sensitive) says, and is
+//! responsible for its own case handling. Any
+//! `Fn(&str, &str) -> bool + Send + Sync` closure taking the name and the
+//! query is a matcher:
+//!
+//! ```rust
+//! use whatever_find::{FileSearcher, SearchMode};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // Match names regardless of their separators, so "my file" finds
+//! // "my_file.txt" and "My-File.md"
+//! let squash = |s: &str| -> String {
+//!     s.chars()
+//!         .filter(|c| c.is_alphanumeric())
+//!         .flat_map(char::to_lowercase)
+//!         .collect()
+//! };
+//! let searcher = FileSearcher::builder()
+//!     .matcher(move |name: &str, query: &str| squash(name).contains(&squash(query)))
+//!     .build()?;
+//! let results = searcher.search(".".as_ref(), "my file", SearchMode::Custom)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SearchMode::Custom`]: super::SearchMode::Custom
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Decides whether a file name matches a query
+pub trait PatternMatcher: Send + Sync {
+    /// Whether the file name `name` matches `query`
+    fn matches(&self, name: &str, query: &str) -> bool;
+}
+
+impl<F> PatternMatcher for F
+where
+    F: Fn(&str, &str) -> bool + Send + Sync,
+{
+    fn matches(&self, name: &str, query: &str) -> bool {
+        self(name, query)
+    }
+}
+
+/// The matcher registered on a searcher, if any
+#[derive(Clone, Default)]
+pub(crate) struct CustomMatcher(pub(crate) Option<Arc<dyn PatternMatcher>>);
+
+impl fmt::Debug for CustomMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() {
+            "CustomMatcher(set)"
+        } else {
+            "CustomMatcher(none)"
+        })
+    }
+}
@@ -0,0 +1,73 @@
+//! Integrator-supplied metadata on search results
+//!
+//! A [`ResultDecorator`] registered on a [`FileSearcher`](crate::FileSearcher)
+//! sees every [`FileMatch`] before it is returned and can attach key/value
+//! pairs to [`FileMatch::metadata`], such as an icon name, the project a file
+//! belongs to or custom tags. Decorators run in the order they were
+//! registered, so a later one can read or overwrite what an earlier one set.
+//!
+//! Any `Fn(&mut FileMatch) + Send + Sync` closure is a decorator:
+//!
+//! ```rust
+//! use whatever_find::{FileMatch, FileSearcher};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let searcher = FileSearcher::builder()
+//!     .decorator(|file_match: &mut FileMatch| {
+//!         if file_match.path.extension().is_some_and(|ext| ext == "rs") {
+//!             file_match.metadata.insert("icon".into(), "rust".into());
+//!         }
+//!     })
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::file_match::FileMatch;
+use std::fmt;
+use std::sync::Arc;
+
+/// Adds metadata to search results
+pub trait ResultDecorator: Send + Sync {
+    /// Attach metadata to `file_match`
+    fn decorate(&self, file_match: &mut FileMatch);
+}
+
+impl<F> ResultDecorator for F
+where
+    F: Fn(&mut FileMatch) + Send + Sync,
+{
+    fn decorate(&self, file_match: &mut FileMatch) {
+        self(file_match);
+    }
+}
+
+/// The decorators registered on a searcher, in order
+#[derive(Clone, Default)]
+pub struct Decorators(Vec<Arc<dyn ResultDecorator>>);
+
+impl Decorators {
+    /// Append `decorator`, to run after those already registered
+    pub fn push(&mut self, decorator: Arc<dyn ResultDecorator>) {
+        self.0.push(decorator);
+    }
+
+    /// Whether no decorators are registered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Run every decorator over `file_match`
+    pub fn apply(&self, file_match: &mut FileMatch) {
+        for decorator in &self.0 {
+            decorator.decorate(file_match);
+        }
+    }
+}
+
+impl fmt::Debug for Decorators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Decorators({})", self.0.len())
+    }
+}
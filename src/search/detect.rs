@@ -0,0 +1,233 @@
+//! Why automatic detection picks a search mode
+//!
+//! [`SearchEngine::detect_search_mode`](super::SearchEngine::detect_search_mode)
+//! looks for regex syntax first, then for glob wildcards, and falls back to a
+//! substring search when it finds neither. A name such as
+//! `report (final).pdf` therefore becomes a regex that does not match
+//! itself. [`SearchEngine::explain_detection`](super::SearchEngine::explain_detection)
+//! reports which heuristics fired on a query and why its mode was chosen,
+//! and a [`DetectionPolicy`] set as
+//! [`Config::detection`](crate::Config::detection) tunes them:
+//!
+//! ```rust
+//! use whatever_find::search::detect::DetectionPolicy;
+//! use whatever_find::search::SearchEngine;
+//! use whatever_find::{Config, SearchMode};
+//!
+//! let engine = SearchEngine::new(Config {
+//!     detection: DetectionPolicy {
+//!         regex: false,
+//!         ..DetectionPolicy::default()
+//!     },
+//!     ..Config::default()
+//! });
+//! let report = engine.explain_detection("report (final).pdf");
+//! assert_eq!(report.mode, SearchMode::Substring);
+//! println!("{report}");
+//! ```
+
+use super::SearchMode;
+#[cfg(feature = "config")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Which modes automatic detection may choose
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config", serde(default))]
+pub struct DetectionPolicy {
+    /// Whether queries with regex syntax are searched as regular expressions
+    pub regex: bool,
+    /// Whether queries with `*` or `?` wildcards are searched as globs
+    pub glob: bool,
+    /// Whether queries with wildcards are searched as globs even when they
+    /// also have regex syntax, such as `file[0-9]*.txt`
+    pub prefer_glob: bool,
+}
+
+impl Default for DetectionPolicy {
+    fn default() -> Self {
+        Self {
+            regex: true,
+            glob: true,
+            prefer_glob: false,
+        }
+    }
+}
+
+/// Pattern syntax found in a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// A regex escape such as `\d`, `\w` or `\.`
+    Escape,
+    /// `^` at the start or `$` at the end
+    Anchor,
+    /// A `[...]` character class
+    CharacterClass,
+    /// A `{...}` repetition count
+    Repetition,
+    /// `|` between alternatives
+    Alternation,
+    /// A `(...)` group
+    Group,
+    /// `+` after the first character
+    Plus,
+    /// `*` or `?` wildcards
+    Wildcard,
+}
+
+impl Signal {
+    /// Whether this is regex syntax rather than a glob wildcard
+    #[must_use]
+    pub fn is_regex(self) -> bool {
+        self != Self::Wildcard
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Escape => "a regex escape",
+            Self::Anchor => "an anchor",
+            Self::CharacterClass => "a character class",
+            Self::Repetition => "a repetition count",
+            Self::Alternation => "alternation",
+            Self::Group => "a group",
+            Self::Plus => "a '+' quantifier",
+            Self::Wildcard => "wildcards",
+        })
+    }
+}
+
+/// Why detection chose the mode it did
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reason {
+    /// The query has regex syntax
+    RegexSyntax,
+    /// The query has wildcards and no regex syntax
+    Wildcards,
+    /// The query has wildcards and regex syntax, and the policy prefers globs
+    PreferGlob,
+    /// The query has wildcards but also `[`, `(`, `\` or `|`, so it is
+    /// neither a clear glob nor a clear regex
+    AmbiguousWildcards,
+    /// The query has the syntax of a mode that the policy or this build
+    /// rules out
+    Disabled(SearchMode),
+    /// The query has no pattern syntax
+    PlainText,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RegexSyntax => f.write_str("the query has regex syntax"),
+            Self::Wildcards => f.write_str("the query has wildcards and no regex syntax"),
+            Self::PreferGlob => {
+                f.write_str("the query has wildcards and regex syntax, and globs are preferred")
+            }
+            Self::AmbiguousWildcards => f.write_str(
+                "the query has wildcards but also '[', '(', '\\' or '|', so it is taken literally",
+            ),
+            Self::Disabled(mode) => write!(
+                f,
+                "the query looks like a {} pattern, but {} detection is disabled",
+                mode.feature(),
+                mode.feature()
+            ),
+            Self::PlainText => f.write_str("the query has no pattern syntax"),
+        }
+    }
+}
+
+/// What detection found in a query and the mode it chose
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionReport {
+    /// The chosen mode
+    pub mode: SearchMode,
+    /// Why it was chosen
+    pub reason: Reason,
+    /// Every heuristic that fired, in the order they are checked
+    pub signals: Vec<Signal>,
+}
+
+impl DetectionReport {
+    /// Run detection on `query` under `policy`
+    pub(super) fn new(query: &str, policy: DetectionPolicy) -> Self {
+        let signals = signals(query);
+        let regex_syntax = signals.iter().any(|signal| signal.is_regex());
+        let wildcards = signals.contains(&Signal::Wildcard);
+        let glob = cfg!(feature = "glob") && policy.glob;
+        let regex = cfg!(feature = "regex") && policy.regex;
+
+        let (mode, reason) = if wildcards && regex_syntax && glob && policy.prefer_glob {
+            (SearchMode::Glob, Reason::PreferGlob)
+        } else if regex_syntax && regex {
+            (SearchMode::Regex, Reason::RegexSyntax)
+        } else if regex_syntax {
+            (SearchMode::Substring, Reason::Disabled(SearchMode::Regex))
+        } else if wildcards && query.contains(['[', '(', '\\', '|']) {
+            (SearchMode::Substring, Reason::AmbiguousWildcards)
+        } else if wildcards && glob {
+            (SearchMode::Glob, Reason::Wildcards)
+        } else if wildcards {
+            (SearchMode::Substring, Reason::Disabled(SearchMode::Glob))
+        } else {
+            (SearchMode::Substring, Reason::PlainText)
+        };
+        Self {
+            mode,
+            reason,
+            signals,
+        }
+    }
+}
+
+impl fmt::Display for DetectionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} search: {}", self.mode.feature(), self.reason)?;
+        for (i, signal) in self.signals.iter().enumerate() {
+            f.write_str(if i == 0 { " (found " } else { ", " })?;
+            write!(f, "{signal}")?;
+        }
+        if !self.signals.is_empty() {
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+/// The pattern syntax in `query`, in the order it is checked
+fn signals(query: &str) -> Vec<Signal> {
+    const ESCAPES: [&str; 6] = [r"\d", r"\w", r"\s", r"\.", r"\^", r"\$"];
+
+    let checks = [
+        (
+            Signal::Escape,
+            ESCAPES.iter().any(|escape| query.contains(escape)),
+        ),
+        (
+            Signal::Anchor,
+            query.starts_with('^') || query.ends_with('$'),
+        ),
+        (
+            Signal::CharacterClass,
+            query.contains('[') && query.contains(']'),
+        ),
+        (
+            Signal::Repetition,
+            query.contains('{') && query.contains('}') && query.chars().any(|c| c.is_ascii_digit()),
+        ),
+        (Signal::Alternation, query.contains('|')),
+        (Signal::Group, query.contains('(') && query.contains(')')),
+        (
+            Signal::Plus,
+            query.char_indices().any(|(i, c)| i > 0 && c == '+'),
+        ),
+        (Signal::Wildcard, query.contains(['*', '?'])),
+    ];
+    checks
+        .into_iter()
+        .filter_map(|(signal, fired)| fired.then_some(signal))
+        .collect()
+}
@@ -0,0 +1,114 @@
+//! Search results with per-file metadata
+//!
+//! Plain searches return bare paths. [`FileMatch`] pairs a path with metadata
+//! that is too costly to gather for every result by default and is only
+//! filled in when the configuration asks for it.
+
+use crate::config::{CloudPlaceholders, Config};
+use crate::indexer::attributes::FileAttributes;
+#[cfg(feature = "git")]
+use crate::indexer::git::{GitStatus, GitStatuses};
+use crate::indexer::placeholder;
+use crate::indexer::streams::{self, AlternateStream};
+use crate::indexer::trash;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// A matched file together with its requested metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatch {
+    /// Path of the matched file
+    pub path: PathBuf,
+    /// NTFS alternate data streams of the file
+    ///
+    /// Only listed when `alternate_streams` is enabled in the configuration,
+    /// and always empty outside Windows.
+    pub alternate_streams: Vec<AlternateStream>,
+    /// Sparse, compressed, encrypted and immutable attributes of the file
+    ///
+    /// Only read when `file_attributes` is enabled in the configuration, and
+    /// `None` if the file could not be inspected.
+    pub attributes: Option<FileAttributes>,
+    /// Git status of the file within its repository
+    ///
+    /// Only looked up when `git_status` is enabled in the configuration, and
+    /// `None` outside a repository.
+    #[cfg(feature = "git")]
+    pub git_status: Option<GitStatus>,
+    /// Whether the file is a cloud placeholder whose contents are online only
+    ///
+    /// Streams and attributes of placeholders are only read when
+    /// `cloud_placeholders` is set to include them fully, since reading them
+    /// could download the file.
+    pub online_only: bool,
+    /// Where the file was before it was moved to the trash
+    ///
+    /// Only looked up when `include_trash` is enabled in the configuration,
+    /// and `None` for files outside the trash or whose origin is not recorded.
+    pub trashed_from: Option<PathBuf>,
+    /// Key/value pairs attached by the searcher's
+    /// [`ResultDecorator`](crate::ResultDecorator)s
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl FileMatch {
+    /// Gather the metadata `config` asks for about the file at `path`
+    #[must_use]
+    pub fn new(path: PathBuf, config: &Config) -> Self {
+        #[cfg(feature = "git")]
+        {
+            Self::with_statuses(path, config, &mut GitStatuses::default())
+        }
+        #[cfg(not(feature = "git"))]
+        {
+            Self::gather(path, config)
+        }
+    }
+
+    /// Like [`FileMatch::new`], reusing repositories already opened by
+    /// `statuses` when looking up git status
+    #[cfg(feature = "git")]
+    #[must_use]
+    pub fn with_statuses(path: PathBuf, config: &Config, statuses: &mut GitStatuses) -> Self {
+        let git_status = if config.git_status {
+            statuses.status(&path)
+        } else {
+            None
+        };
+        Self {
+            git_status,
+            ..Self::gather(path, config)
+        }
+    }
+
+    /// Gather the metadata that needs no shared state
+    fn gather(path: PathBuf, config: &Config) -> Self {
+        let online_only = placeholder::is_placeholder(&path);
+        let inspect = !online_only || config.cloud_placeholders == CloudPlaceholders::Include;
+        let alternate_streams = if config.alternate_streams && inspect {
+            streams::alternate_streams(&path)
+        } else {
+            Vec::new()
+        };
+        let attributes = if config.file_attributes && inspect {
+            FileAttributes::read(&path)
+        } else {
+            None
+        };
+        let trashed_from = if config.include_trash {
+            trash::original_path(&path)
+        } else {
+            None
+        };
+        Self {
+            path,
+            alternate_streams,
+            attributes,
+            online_only,
+            trashed_from,
+            metadata: BTreeMap::new(),
+            #[cfg(feature = "git")]
+            git_status: None,
+        }
+    }
+}
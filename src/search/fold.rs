@@ -0,0 +1,111 @@
+//! Case folding and Unicode normalization of names and queries
+//!
+//! macOS stores file names decomposed (NFD), so the "é" of "café" on disk is
+//! an "e" followed by a combining accent, while queries are usually typed
+//! precomposed (NFC) and would not match it. With
+//! [`Config::unicode_normalization`](crate::Config::unicode_normalization)
+//! set, index keys and queries are both brought to NFC, and case-insensitive
+//! matching uses full Unicode case folding instead of lowercasing, so
+//! "STRASSE" finds "straße" and 'ς' matches 'σ'. Normalization needs the
+//! `unicode` feature.
+
+use crate::config::Config;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// How names and queries are made comparable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Folding {
+    case_sensitive: bool,
+    #[cfg(feature = "unicode")]
+    normalize: bool,
+}
+
+impl Folding {
+    /// The folding `config` asks for
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            case_sensitive: config.case_sensitive,
+            #[cfg(feature = "unicode")]
+            normalize: config.unicode_normalization,
+        }
+    }
+
+    /// Whether case is kept
+    #[cfg(feature = "glob")]
+    pub(crate) fn case_sensitive(self) -> bool {
+        self.case_sensitive
+    }
+
+    /// `text` in the form names and queries are compared in
+    pub(crate) fn fold(self, text: &str) -> Cow<'_, str> {
+        #[cfg(feature = "unicode")]
+        if self.normalize && !text.is_ascii() {
+            return Cow::Owned(normalize(text, self.case_sensitive));
+        }
+        if self.case_sensitive {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(text.to_lowercase())
+        }
+    }
+
+    /// `text` normalized as configured but with its case kept, for matchers
+    /// that handle case themselves
+    #[cfg(any(feature = "glob", feature = "regex"))]
+    #[allow(clippy::unused_self)]
+    pub(crate) fn compose(self, text: &str) -> Cow<'_, str> {
+        #[cfg(feature = "unicode")]
+        if self.normalize && !text.is_ascii() {
+            return Cow::Owned(normalize(text, true));
+        }
+        Cow::Borrowed(text)
+    }
+
+    /// `text` cut into pieces that fold independently, each with its range
+    /// in `text` and its folded form
+    ///
+    /// Pieces are single characters, or with normalization a character and
+    /// the combining marks after it, which may compose with it.
+    pub(crate) fn pieces(self, text: &str) -> Vec<(Range<usize>, Cow<'_, str>)> {
+        let mut starts: Vec<usize> = text
+            .char_indices()
+            .filter(|&(_, c)| !self.joins_previous(c))
+            .map(|(start, _)| start)
+            .collect();
+        if starts.first() != Some(&0) && !text.is_empty() {
+            starts.insert(0, 0);
+        }
+        starts
+            .iter()
+            .zip(starts.iter().skip(1).chain(Some(&text.len())))
+            .map(|(&start, &end)| (start..end, self.fold(&text[start..end])))
+            .collect()
+    }
+
+    /// Whether `c` is folded together with the character before it
+    #[allow(clippy::unused_self)]
+    fn joins_previous(self, c: char) -> bool {
+        #[cfg(feature = "unicode")]
+        if self.normalize {
+            return unicode_normalization::char::canonical_combining_class(c) != 0;
+        }
+        let _ = c;
+        false
+    }
+}
+
+/// `text` in NFC, case folded unless `case_sensitive`
+///
+/// Folding decomposes first, as canonical caseless matching requires, so
+/// that precomposed and decomposed forms fold alike.
+#[cfg(feature = "unicode")]
+fn normalize(text: &str, case_sensitive: bool) -> String {
+    use unicode_normalization::UnicodeNormalization;
+
+    if case_sensitive {
+        return text.nfc().collect();
+    }
+    let decomposed: String = text.nfd().collect();
+    caseless::default_case_fold_str(&decomposed).nfc().collect()
+}
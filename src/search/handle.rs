@@ -0,0 +1,249 @@
+//! Pausable and resumable searches
+//!
+//! A [`SearchHandle`] is shared between the thread running a search and the
+//! threads controlling it. Pausing blocks the walk before its next entry until
+//! the handle is resumed, and stopping ends the walk early. The handle tracks
+//! every directory whose subtree has been walked completely along with every
+//! file found, so a [`Checkpoint`] taken from it lets a later search of the
+//! same root, even in another process, skip the finished directories.
+
+#[cfg(feature = "config")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use walkdir::DirEntry;
+
+/// Progress of a search that can be saved and resumed
+///
+/// Paths are as walked, so a checkpoint only applies to a search of the same
+/// root path with the same configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub struct Checkpoint {
+    /// Directories whose whole subtree has been walked
+    pub completed_dirs: Vec<PathBuf>,
+    /// Files found so far
+    pub files: Vec<PathBuf>,
+}
+
+/// How far a search has walked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+pub struct Progress {
+    /// Directories entered so far
+    pub dirs: usize,
+    /// Files found so far, including those from a checkpoint
+    pub files: usize,
+    /// Directories the walk is expected to enter, when they were counted
+    /// beforehand with [`SearchHandle::set_total_dirs`]
+    #[cfg_attr(
+        feature = "config",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub total_dirs: Option<usize>,
+}
+
+impl Progress {
+    /// Share of the expected directories entered so far, from 0 to 100
+    ///
+    /// Returns `None` unless the directories were counted beforehand. The
+    /// share never exceeds 100, even if directories appeared since counting.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn percent(&self) -> Option<f64> {
+        let total = self.total_dirs?;
+        if total == 0 {
+            return Some(100.0);
+        }
+        Some((self.dirs as f64 / total as f64 * 100.0).min(100.0))
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    paused: bool,
+    stopped: bool,
+    finished: bool,
+    completed_dirs: HashSet<PathBuf>,
+    files: HashSet<PathBuf>,
+    /// Directories entered but not yet left, with their depth, innermost last
+    open_dirs: Vec<(usize, PathBuf)>,
+    /// Directories entered by this walk
+    dirs_entered: usize,
+    /// Directories the walk is expected to enter
+    total_dirs: Option<usize>,
+}
+
+/// Controls a search running on another thread
+///
+/// Clones share the same search. A handle is meant for a single walk; pass
+/// a [`Checkpoint`] to [`SearchHandle::from_checkpoint`] to continue one.
+#[derive(Debug, Clone, Default)]
+pub struct SearchHandle {
+    shared: Arc<(Mutex<State>, Condvar)>,
+}
+
+impl SearchHandle {
+    /// Create a handle for a search starting from scratch
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a handle for a search continuing from `checkpoint`
+    #[must_use]
+    pub fn from_checkpoint(checkpoint: Checkpoint) -> Self {
+        let state = State {
+            completed_dirs: checkpoint.completed_dirs.into_iter().collect(),
+            files: checkpoint.files.into_iter().collect(),
+            ..State::default()
+        };
+        Self {
+            shared: Arc::new((Mutex::new(state), Condvar::new())),
+        }
+    }
+
+    /// Block the search before its next entry until [`SearchHandle::resume`]
+    pub fn pause(&self) {
+        self.state().paused = true;
+    }
+
+    /// Let a paused search continue
+    pub fn resume(&self) {
+        self.state().paused = false;
+        self.shared.1.notify_all();
+    }
+
+    /// End the search early, even while paused
+    ///
+    /// The search returns the files found so far.
+    pub fn stop(&self) {
+        self.state().stopped = true;
+        self.shared.1.notify_all();
+    }
+
+    /// Whether the search is paused
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.state().paused
+    }
+
+    /// Whether the search has walked the whole tree
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.state().finished
+    }
+
+    /// Counts of what the search has walked so far
+    ///
+    /// Cheap enough to poll from another thread while the search runs.
+    #[must_use]
+    pub fn progress(&self) -> Progress {
+        let state = self.state();
+        Progress {
+            dirs: state.dirs_entered,
+            files: state.files.len(),
+            total_dirs: state.total_dirs,
+        }
+    }
+
+    /// Tell the handle how many directories the walk will enter, so that
+    /// [`Progress::percent`] can estimate how far along it is
+    ///
+    /// [`FileIndexer::count_dirs`](crate::indexer::FileIndexer::count_dirs)
+    /// counts them with a quick walk ahead of the real one.
+    pub fn set_total_dirs(&self, total: usize) {
+        self.state().total_dirs = Some(total);
+    }
+
+    /// Snapshot of the progress so far
+    ///
+    /// Directories beneath another completed directory are left out, since
+    /// skipping the outer one skips them too.
+    #[must_use]
+    pub fn checkpoint(&self) -> Checkpoint {
+        let state = self.state();
+        let mut completed_dirs: Vec<PathBuf> = state
+            .completed_dirs
+            .iter()
+            .filter(|dir| {
+                !dir.ancestors()
+                    .skip(1)
+                    .any(|ancestor| state.completed_dirs.contains(ancestor))
+            })
+            .cloned()
+            .collect();
+        completed_dirs.sort();
+        let mut files: Vec<PathBuf> = state.files.iter().cloned().collect();
+        files.sort();
+        Checkpoint {
+            completed_dirs,
+            files,
+        }
+    }
+
+    /// Every file found so far, including those from the checkpoint
+    pub(crate) fn files(&self) -> Vec<PathBuf> {
+        self.state().files.iter().cloned().collect()
+    }
+
+    /// Wait while paused, returning `false` once the search should stop
+    pub(crate) fn wait(&self) -> bool {
+        let (lock, resumed) = &*self.shared;
+        let mut state = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        while state.paused && !state.stopped {
+            state = resumed.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+        !state.stopped
+    }
+
+    /// Whether `dir` was walked completely by an earlier search
+    pub(crate) fn is_completed(&self, dir: &Path) -> bool {
+        self.state().completed_dirs.contains(dir)
+    }
+
+    /// Record a walked entry, indexed or not, returning `false` for files
+    /// already found
+    pub(crate) fn visit(&self, entry: &DirEntry, indexed: bool) -> bool {
+        let mut state = self.state();
+        // Entering a shallower or sibling entry means the walk left every
+        // open directory at the same depth or deeper
+        while let Some((depth, _)) = state.open_dirs.last() {
+            if *depth < entry.depth() {
+                break;
+            }
+            if let Some((_, dir)) = state.open_dirs.pop() {
+                state.completed_dirs.insert(dir);
+            }
+        }
+
+        let found = indexed && state.files.insert(entry.path().to_path_buf());
+        if entry.file_type().is_dir() {
+            state.dirs_entered += 1;
+            state
+                .open_dirs
+                .push((entry.depth(), entry.path().to_path_buf()));
+            true
+        } else {
+            found || !indexed
+        }
+    }
+
+    /// Mark the walk as finished unless it was stopped early
+    pub(crate) fn finish(&self) {
+        let mut state = self.state();
+        if state.stopped {
+            return;
+        }
+        let open_dirs = std::mem::take(&mut state.open_dirs);
+        state
+            .completed_dirs
+            .extend(open_dirs.into_iter().map(|(_, dir)| dir));
+        state.finished = true;
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.shared.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,191 @@
+//! Directory visit history for frecency ranking
+//!
+//! [`DirHistory`] remembers how often each directory was jumped to and when
+//! it was last visited. As in `z` and zoxide, a directory's frecency is its
+//! visit count weighted by how recent the last visit was: four times within
+//! the last hour, twice within a day, half within a week and a quarter after
+//! that. Paths are stored canonicalized so that the same directory reached
+//! through different relative paths shares one entry.
+
+use crate::{FileSearchError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "fuzzy")]
+use crate::indexer::{backend, FileIndex};
+#[cfg(feature = "fuzzy")]
+use crate::search::fold::Folding;
+#[cfg(feature = "fuzzy")]
+use crate::search::SearchEngine;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// How often and how recently one directory was visited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Visit {
+    /// Number of visits
+    pub count: u64,
+    /// Time of the last visit in seconds since the Unix epoch
+    pub last: u64,
+}
+
+impl Visit {
+    /// Visit count weighted by the age of the last visit at `now`
+    #[must_use]
+    // Visit counts are far below 2^52
+    #[allow(clippy::cast_precision_loss)]
+    pub fn frecency(self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last);
+        let weight = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.count as f64 * weight
+    }
+}
+
+/// Visits recorded for every directory jumped to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirHistory {
+    visits: HashMap<PathBuf, Visit>,
+}
+
+impl DirHistory {
+    /// Create an empty history
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the history stored at `path`, or an empty one if there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => {
+                return Err(FileSearchError::io_error_with_path(
+                    e,
+                    "reading directory history",
+                    path,
+                ))
+            }
+        };
+        serde_json::from_str(&content)
+            .map_err(|e| FileSearchError::invalid_config(format!("Invalid directory history: {e}")))
+    }
+
+    /// Write the history to `path`, creating its directory if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string(self).map_err(|e| {
+            FileSearchError::invalid_config(format!("Directory history serialize error: {e}"))
+        })?;
+        std::fs::write(path, content)
+            .map_err(|e| FileSearchError::io_error_with_path(e, "writing directory history", path))
+    }
+
+    /// Well-known location of the history in the local data directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the local data directory cannot be determined
+    #[cfg(feature = "dirs")]
+    pub fn default_path() -> Result<PathBuf> {
+        let data_dir = dirs::data_local_dir().ok_or_else(|| {
+            FileSearchError::invalid_config("Could not determine local data directory")
+        })?;
+        Ok(data_dir.join("whatever-find").join("history.json"))
+    }
+
+    /// Record a visit to `dir` at `now`, in seconds since the Unix epoch
+    pub fn record(&mut self, dir: &Path, now: u64) {
+        let visit = self.visits.entry(canonical(dir)).or_default();
+        visit.count += 1;
+        visit.last = visit.last.max(now);
+    }
+
+    /// Visits recorded for `dir`
+    #[must_use]
+    pub fn visit(&self, dir: &Path) -> Option<Visit> {
+        self.visits.get(&canonical(dir)).copied()
+    }
+
+    /// Every directory with recorded visits
+    pub fn dirs(&self) -> impl Iterator<Item = &Path> {
+        self.visits.keys().map(PathBuf::as_path)
+    }
+
+    /// Frecency of `dir` at `now`, or 0 if it was never visited
+    #[must_use]
+    pub fn frecency(&self, dir: &Path, now: u64) -> f64 {
+        self.visit(dir).map_or(0.0, |visit| visit.frecency(now))
+    }
+
+    /// Best directory for `query` among `dirs` and the remembered directories
+    ///
+    /// `dirs` is keyed by directory name, as built by
+    /// [`FileIndexer::build_dir_index`](crate::indexer::FileIndexer::build_dir_index).
+    /// Every directory that matches fuzzily has its match score multiplied
+    /// by one plus its frecency, so frequently and recently visited
+    /// directories win among similar names. Remembered directories that no
+    /// longer exist are skipped.
+    #[cfg(feature = "fuzzy")]
+    #[must_use]
+    pub fn jump(
+        &self,
+        engine: &SearchEngine,
+        dirs: &FileIndex,
+        query: &str,
+        now: u64,
+    ) -> Option<PathBuf> {
+        let folding = Folding::new(engine.config());
+        let remembered: FileIndex = self
+            .dirs()
+            .filter(|dir| dir.is_dir())
+            .filter_map(|dir| backend::path_entry(dir.to_path_buf(), folding))
+            .collect();
+
+        let mut scores: HashMap<PathBuf, f64> = HashMap::new();
+        let matches = engine
+            .search_fuzzy(dirs, query)
+            .into_iter()
+            .chain(engine.search_fuzzy(&remembered, query));
+        for (dir, score) in matches {
+            let dir = canonical(&dir);
+            let frecency = self
+                .visits
+                .get(&dir)
+                .map_or(0.0, |visit| visit.frecency(now));
+            let score = score * (1.0 + frecency);
+            let best = scores.entry(dir).or_insert(score);
+            *best = best.max(score);
+        }
+
+        scores
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+            .map(|(dir, _)| dir)
+    }
+}
+
+fn canonical(dir: &Path) -> PathBuf {
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
@@ -1,5 +1,7 @@
+#[cfg(feature = "regex")]
 use regex::Regex;
-use std::path::Path;
+use std::borrow::Cow;
+use std::path::{self, Path};
 
 /// Types of pattern matching supported
 pub enum MatchType {
@@ -17,6 +19,7 @@ pub enum MatchType {
 pub struct Matcher {
     match_type: MatchType,
     case_sensitive: bool,
+    #[cfg(feature = "regex")]
     compiled_regex: Option<Regex>,
 }
 
@@ -26,6 +29,7 @@ impl Matcher {
         Self {
             match_type,
             case_sensitive,
+            #[cfg(feature = "regex")]
             compiled_regex: None,
         }
     }
@@ -35,6 +39,7 @@ impl Matcher {
     /// # Errors
     ///
     /// Returns a regex error if the pattern is invalid
+    #[cfg(feature = "regex")]
     pub fn with_regex(pattern: &str, case_sensitive: bool) -> Result<Self, regex::Error> {
         let flags = if case_sensitive { "" } else { "(?i)" };
         let full_pattern = format!("{flags}{pattern}");
@@ -84,6 +89,7 @@ impl Matcher {
         }
     }
 
+    #[cfg(feature = "regex")]
     fn regex_match(&self, filename: &str) -> bool {
         if let Some(ref regex) = self.compiled_regex {
             regex.is_match(filename)
@@ -92,6 +98,11 @@ impl Matcher {
         }
     }
 
+    #[cfg(not(feature = "regex"))]
+    fn regex_match(&self, _filename: &str) -> bool {
+        false
+    }
+
     fn fuzzy_match(&self, filename: &str, query: &str) -> f64 {
         let filename = if self.case_sensitive {
             filename.to_string()
@@ -140,14 +151,76 @@ impl Matcher {
 
 /// Utility function to match a path against a pattern (glob or substring)
 pub fn matches_path_pattern(path: &Path, pattern: &str) -> bool {
-    if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
+    if let Some(filename) = path.file_name().map(|n| n.to_string_lossy()) {
         if pattern.contains('*') || pattern.contains('?') {
-            if let Ok(glob) = glob::Pattern::new(pattern) {
-                return glob.matches(filename);
+            #[cfg(feature = "glob")]
+            if let Ok(glob) = super::compiled::glob_regex(pattern, pattern, false) {
+                return glob.is_match(&filename);
             }
+            #[cfg(not(feature = "glob"))]
+            return wildcard_match(pattern, &filename);
         }
         filename.contains(pattern)
     } else {
         false
     }
 }
+
+/// `text` with every path separator written as `/`
+///
+/// On Windows both `/` and `\` separate path components, so this lets
+/// patterns written with either match paths written with the other. Elsewhere
+/// `\` is an ordinary filename character and is left alone.
+#[must_use]
+pub fn normalize_separators(text: &str) -> Cow<'_, str> {
+    if text.contains(|c: char| c != '/' && path::is_separator(c)) {
+        Cow::Owned(
+            text.chars()
+                .map(|c| if path::is_separator(c) { '/' } else { c })
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Whether `pattern` occurs anywhere in `path`, regardless of which path
+/// separators either is written with
+#[must_use]
+pub fn path_contains(path: &Path, pattern: &str) -> bool {
+    normalize_separators(&path.to_string_lossy()).contains(&*normalize_separators(pattern))
+}
+
+/// Match `text` against a pattern where `*` matches any run of characters and
+/// `?` matches a single character
+///
+/// Used for ignore patterns, so it is available without the `glob` feature.
+#[must_use]
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
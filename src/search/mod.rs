@@ -1,276 +1,1116 @@
+/// Directories grouped by how many matches they hold
+pub mod aggregate;
+/// Queries compiled once and matched against many indexes
+pub mod compiled;
+/// Integrator-supplied matching modes
+pub mod custom;
+/// Integrator-supplied metadata on search results
+pub mod decorate;
+/// Explainable and configurable search mode detection
+pub mod detect;
+/// Search results with per-file metadata
+pub mod file_match;
+/// Case folding and Unicode normalization of names and queries
+pub(crate) mod fold;
+/// Pausable and resumable searches
+pub mod handle;
+/// Directory visit history for frecency ranking
+#[cfg(feature = "config")]
+pub mod history;
 /// Pattern matching implementations
 pub mod matcher;
+/// Matching filenames on several threads
+#[cfg(feature = "parallel")]
+mod parallel;
+/// Named searches kept in the configuration
+pub mod preset;
+/// Progress reports for frontends
+pub mod progress;
+/// Structured queries with attribute filters
+pub mod query;
+/// Disk inventory reports by extension and directory
+pub mod report;
+/// Search results with their file metadata
+pub mod result;
+/// Result sets with size totals
+pub mod results;
+/// Integrator-supplied fuzzy scoring
+#[cfg(feature = "fuzzy")]
+pub mod scorer;
+/// One index shared by concurrent queries
+pub mod shared;
+/// Matched ranges of file names for highlighting
+mod spans;
+/// The best fuzzy matches picked without sorting every match
+#[cfg(feature = "fuzzy")]
+mod top_k;
+/// Indexes built ahead of the first query
+pub mod warm;
 
 use crate::config::Config;
-use crate::indexer::FileIndex;
+use crate::indexer::backend::IndexBackend;
+#[cfg(feature = "fuzzy")]
+use crate::indexer::ngram::NgramIndex;
 use crate::Result;
-use glob::Pattern;
-use regex::Regex;
-use std::path::PathBuf;
+#[cfg(feature = "glob")]
+use compiled::Glob;
+use compiled::{Compiled, CompiledQuery};
+use fold::Folding;
+#[cfg(feature = "regex")]
+use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
 /// Search modes supported by the search engine
+///
+/// Only [`SearchMode::Substring`] and [`SearchMode::Literal`] are always
+/// available; the other modes need the cargo feature of the same name and
+/// fail with
+/// [`FileSearchError::FeatureDisabled`](crate::FileSearchError::FeatureDisabled) without it.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
 pub enum SearchMode {
     /// Simple substring matching
     Substring,
+    /// Substring matching of the query exactly as typed, never chosen by
+    /// automatic detection, for names such as `report (final).pdf` that
+    /// would otherwise be taken for a regex or glob
+    Literal,
     /// Shell-style glob patterns with wildcards
     Glob,
     /// Full regular expression support
     Regex,
     /// Fuzzy matching with typo tolerance
     Fuzzy,
+    /// Matching by the [`PatternMatcher`](custom::PatternMatcher) registered
+    /// on the searcher, never chosen by automatic detection
+    Custom,
 }
 
-/// Search engine that supports multiple search modes and automatic pattern detection
-pub struct SearchEngine {
-    config: Config,
+impl SearchMode {
+    /// Name of the cargo feature that provides this mode, or the name of the
+    /// mode for [`SearchMode::Literal`] and [`SearchMode::Custom`], which
+    /// need none
+    #[must_use]
+    pub fn feature(self) -> &'static str {
+        match self {
+            Self::Substring => "substring",
+            Self::Literal => "literal",
+            Self::Glob => "glob",
+            Self::Regex => "regex",
+            Self::Fuzzy => "fuzzy",
+            Self::Custom => "custom",
+        }
+    }
 }
 
-impl SearchEngine {
-    /// Create a new search engine with the given configuration
-    pub fn new(config: Config) -> Self {
-        Self { config }
-    }
+/// Order of search results
+///
+/// Orders other than [`SortOrder::Relevance`] are stable, so results that
+/// compare equal keep their relevance order. Files whose metadata cannot be
+/// read sort last by size or age.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
+pub enum SortOrder {
+    /// As the search ranks them: best match first for fuzzy queries, by
+    /// path otherwise
+    #[default]
+    Relevance,
+    /// By file name, ignoring case
+    Name,
+    /// Shallowest path first
+    Depth,
+    /// Largest first
+    Size,
+    /// Most recently modified first
+    Modified,
+}
 
-    /// Auto-detect the best search mode based on the query pattern
-    pub fn detect_search_mode(&self, query: &str) -> SearchMode {
-        // Check for regex patterns first (more specific)
-        if self.looks_like_regex(query) {
-            return SearchMode::Regex;
-        }
-
-        // Check for glob patterns
-        if self.looks_like_glob(query) {
-            return SearchMode::Glob;
-        }
-
-        // Default to substring for simple queries
-        SearchMode::Substring
-    }
-
-    fn looks_like_regex(&self, query: &str) -> bool {
-        // Common regex metacharacters that are unlikely to be in normal filenames
-        let regex_indicators = [
-            r"\d", r"\w", r"\s", r"\.", r"\^", r"\$", // Escape sequences
-            "^", "$", // Anchors
-            "[", "]", // Character classes
-            "{", "}", // Quantifiers (only if containing digits)
-            "+", // One or more (when not at start)
-            "?", // Zero or one (when not simple glob)
-            "|", // Alternation
-            "(", ")", // Groups
-        ];
-
-        // Check for escape sequences
-        if query.contains('\\') {
-            for indicator in &regex_indicators[0..6] {
-                if query.contains(indicator) {
-                    return true;
-                }
-            }
+impl SortOrder {
+    /// Every order
+    pub const ALL: [Self; 5] = [
+        Self::Relevance,
+        Self::Name,
+        Self::Depth,
+        Self::Size,
+        Self::Modified,
+    ];
+
+    /// Lowercase name, as used on the command line
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Relevance => "relevance",
+            Self::Name => "name",
+            Self::Depth => "depth",
+            Self::Size => "size",
+            Self::Modified => "modified",
         }
+    }
 
-        // Check for anchors
-        if query.starts_with('^') || query.ends_with('$') {
-            return true;
+    /// Sort `results` in this order, given the path of each
+    ///
+    /// Reads the metadata of every result once to sort by size or age.
+    pub fn sort_by_path<T>(self, results: &mut [T], path: impl Fn(&T) -> &Path) {
+        match self {
+            Self::Relevance => {}
+            Self::Name => results.sort_by_cached_key(|result| {
+                path(result)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_lowercase())
+            }),
+            Self::Depth => results.sort_by_cached_key(|result| path(result).components().count()),
+            Self::Size => results.sort_by_cached_key(|result| {
+                Reverse(
+                    std::fs::metadata(path(result))
+                        .ok()
+                        .map(|metadata| metadata.len()),
+                )
+            }),
+            Self::Modified => results.sort_by_cached_key(|result| {
+                Reverse(
+                    std::fs::metadata(path(result))
+                        .and_then(|metadata| metadata.modified())
+                        .ok(),
+                )
+            }),
         }
+    }
+}
 
-        // Check for character classes
-        if query.contains('[') && query.contains(']') {
-            return true;
+/// Longest query accepted, in bytes
+pub const MAX_QUERY_LEN: usize = 4096;
+
+/// Check that `query` is usable in `mode` before searching
+///
+/// An empty query is only accepted for substring matching, where it matches
+/// every file. Queries made only of whitespace, longer than [`MAX_QUERY_LEN`]
+/// bytes, or globs with an unterminated `[` class are rejected.
+///
+/// # Errors
+///
+/// Returns [`FileSearchError::InvalidQuery`](crate::FileSearchError::InvalidQuery)
+/// explaining what is wrong with the query
+pub fn validate_query(query: &str, mode: SearchMode) -> Result<()> {
+    let invalid = |reason: &str| Err(crate::FileSearchError::invalid_query(reason, query));
+
+    if query.is_empty() {
+        if mode == SearchMode::Substring {
+            return Ok(());
         }
+        return invalid("query is empty; only substring searches match every file");
+    }
+    if query.trim().is_empty() {
+        return invalid("query is only whitespace");
+    }
+    if query.len() > MAX_QUERY_LEN {
+        let start: String = query.chars().take(32).collect();
+        return Err(crate::FileSearchError::invalid_query(
+            format!("query is longer than {MAX_QUERY_LEN} bytes"),
+            format!("{start}..."),
+        ));
+    }
+    if mode == SearchMode::Glob && has_unterminated_class(query) {
+        return invalid("glob has a '[' character class without a closing ']'");
+    }
+    Ok(())
+}
 
-        // Check for quantifiers with braces
-        if query.contains('{') && query.contains('}') && query.chars().any(|c| c.is_ascii_digit()) {
-            return true;
+/// Whether a glob opens a `[` class that never closes
+///
+/// A `]` directly after `[` or `[!` is a literal member of the class.
+fn has_unterminated_class(pattern: &str) -> bool {
+    let mut rest = pattern;
+    while let Some(open) = rest.find('[') {
+        let class = &rest[open + 1..];
+        let class = class.strip_prefix('!').unwrap_or(class);
+        let class = class.strip_prefix(']').unwrap_or(class);
+        match class.find(']') {
+            Some(close) => rest = &class[close + 1..],
+            None => return true,
         }
+    }
+    false
+}
 
-        // Check for alternation
-        if query.contains('|') {
-            return true;
-        }
+/// Test of one file, given its index key and path, against a compiled query
+pub(crate) type FileMatcher<'a> = Box<dyn Fn(&str, &Path) -> bool + 'a>;
 
-        // Check for groups
-        if query.contains('(') && query.contains(')') {
-            return true;
-        }
+/// Byte ranges of a file name matched by a compiled query
+pub(crate) type SpanFinder<'a> = Box<dyn Fn(&str) -> Vec<Range<usize>> + 'a>;
 
-        // Check for + quantifier (but not at the start where it might be a filename)
-        if query.len() > 1 && query[1..].contains('+') {
-            return true;
+/// Search engine that supports multiple search modes and automatic pattern detection
+pub struct SearchEngine {
+    config: Config,
+    /// Matcher for [`SearchMode::Custom`]
+    custom: Option<Arc<dyn custom::PatternMatcher>>,
+    /// Scorer used in place of the built-in fuzzy scoring
+    #[cfg(feature = "fuzzy")]
+    scorer: Option<Arc<dyn scorer::Scorer>>,
+    /// Directory stripped from paths matched with
+    /// [`Config::match_relative_path`]
+    root: Option<PathBuf>,
+    /// Pool of [`Config::match_threads`] threads, built on first use
+    #[cfg(feature = "parallel")]
+    pool: std::sync::OnceLock<Option<rayon::ThreadPool>>,
+    /// When matching must stop under [`Config::search_timeout`]
+    deadline: Option<Instant>,
+    /// Whether names were left unmatched because the deadline passed
+    cut_short: AtomicBool,
+}
+
+impl SearchEngine {
+    /// Create a new search engine with the given configuration
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            custom: None,
+            #[cfg(feature = "fuzzy")]
+            scorer: None,
+            root: None,
+            #[cfg(feature = "parallel")]
+            pool: std::sync::OnceLock::new(),
+            deadline: None,
+            cut_short: AtomicBool::new(false),
         }
+    }
 
-        false
+    /// Stop matching names once `deadline` has passed
+    pub(crate) fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
     }
 
-    fn looks_like_glob(&self, query: &str) -> bool {
-        // Glob patterns contain * or ? but don't look like regex
-        if !query.contains('*') && !query.contains('?') {
-            return false;
-        }
+    /// Whether a search left names unmatched because the deadline passed
+    pub(crate) fn was_cut_short(&self) -> bool {
+        self.cut_short.load(Ordering::Relaxed)
+    }
 
-        // If it looks like regex, prefer regex over glob
-        if self.looks_like_regex(query) {
-            return false;
+    /// Whether the deadline has passed, noting that the search was cut short
+    fn past_deadline(&self) -> bool {
+        let past = self
+            .deadline
+            .is_some_and(|deadline| Instant::now() > deadline);
+        if past {
+            self.cut_short.store(true, Ordering::Relaxed);
         }
+        past
+    }
 
-        // Simple heuristics for glob vs regex with wildcards
-        // Globs usually have simpler patterns
-        let has_glob_chars = query.contains('*') || query.contains('?');
-        let has_complex_regex = query.contains('[')
-            || query.contains('(')
-            || query.contains('\\')
-            || query.contains('|');
+    /// Match paths relative to `root`, the directory the index was built
+    /// from, with relative path globs and when [`Config::match_relative_path`]
+    /// is set
+    ///
+    /// Without a root, or for paths outside it, whole paths are matched.
+    pub fn set_root(&mut self, root: impl Into<PathBuf>) {
+        self.root = Some(root.into());
+    }
 
-        has_glob_chars && !has_complex_regex
+    /// Match [`SearchMode::Custom`] queries with `matcher`
+    pub fn set_matcher(&mut self, matcher: Arc<dyn custom::PatternMatcher>) {
+        self.custom = Some(matcher);
     }
 
-    /// Smart search that auto-detects the pattern type
-    pub fn search_auto(&self, index: &FileIndex, query: &str) -> Result<Vec<PathBuf>> {
-        let mode = self.detect_search_mode(query);
+    /// Score fuzzy matches with `scorer` instead of the built-in scoring
+    #[cfg(feature = "fuzzy")]
+    pub fn set_scorer(&mut self, scorer: Arc<dyn scorer::Scorer>) {
+        self.scorer = Some(scorer);
+    }
 
-        match mode {
-            SearchMode::Regex => self.search_regex(index, query),
-            SearchMode::Glob => self.search_glob(index, query),
-            SearchMode::Substring => Ok(self.search_substring(index, query)),
-            SearchMode::Fuzzy => Ok(self
-                .search_fuzzy(index, query)
-                .into_iter()
-                .map(|(path, _)| path)
-                .collect()),
-        }
+    /// The matcher for [`SearchMode::Custom`]
+    fn custom_matcher(&self) -> Result<&Arc<dyn custom::PatternMatcher>> {
+        self.custom.as_ref().ok_or_else(|| {
+            crate::FileSearchError::invalid_config(
+                "custom searches need a matcher registered with FileSearcherBuilder::matcher",
+            )
+        })
+    }
+
+    /// Get the configuration used by this search engine
+    #[must_use]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Auto-detect the best search mode based on the query pattern
+    ///
+    /// Only modes enabled in this build and allowed by
+    /// [`Config::detection`] are ever detected.
+    pub fn detect_search_mode(&self, query: &str) -> SearchMode {
+        self.explain_detection(query).mode
+    }
+
+    /// Detect the search mode of `query` like
+    /// [`SearchEngine::detect_search_mode`], reporting which heuristics
+    /// fired and why the mode was chosen
+    #[must_use]
+    pub fn explain_detection(&self, query: &str) -> detect::DetectionReport {
+        detect::DetectionReport::new(query, self.config.detection)
+    }
+
+    /// Smart search that auto-detects the pattern type
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SearchEngine::search`] for the detected mode
+    pub fn search_auto<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        query: &str,
+    ) -> Result<Vec<PathBuf>> {
+        self.search(index, query, self.detect_search_mode(query))
     }
 
     /// Smart search with mode information returned
-    pub fn search_auto_with_mode(
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SearchEngine::search`] for the detected mode
+    pub fn search_auto_with_mode<B: IndexBackend + ?Sized>(
         &self,
-        index: &FileIndex,
+        index: &B,
         query: &str,
     ) -> Result<(Vec<PathBuf>, SearchMode)> {
         let mode = self.detect_search_mode(query);
-        let results = match mode {
-            SearchMode::Regex => self.search_regex(index, query)?,
-            SearchMode::Glob => self.search_glob(index, query)?,
-            SearchMode::Substring => self.search_substring(index, query),
-            SearchMode::Fuzzy => self
-                .search_fuzzy(index, query)
-                .into_iter()
-                .map(|(path, _)| path)
-                .collect(),
-        };
+        let results = self.search(index, query, mode)?;
 
         Ok((results, mode))
     }
 
-    /// Search using substring matching
-    pub fn search_substring(&self, index: &FileIndex, query: &str) -> Vec<PathBuf> {
-        let search_query = if self.config.case_sensitive {
-            query.to_string()
-        } else {
-            query.to_lowercase()
+    /// Search using a specific search mode, bypassing automatic detection
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails [`validate_query`] or is not a
+    /// valid pattern for `mode`, or if `mode` was disabled at compile time
+    pub fn search<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        query: &str,
+        mode: SearchMode,
+    ) -> Result<Vec<PathBuf>> {
+        Ok(self.search_compiled(index, &self.compile(query, mode)?))
+    }
+
+    /// Check and compile `query` for `mode`, to be searched with
+    /// [`SearchEngine::search_compiled`] as often as needed
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SearchEngine::search`], and
+    /// [`FileSearchError::InvalidConfig`](crate::FileSearchError::InvalidConfig)
+    /// for a custom query without a registered matcher
+    pub fn compile(&self, query: &str, mode: SearchMode) -> Result<CompiledQuery> {
+        validate_query(query, mode)?;
+        let pattern = match mode {
+            SearchMode::Substring | SearchMode::Literal => {
+                Compiled::Substring(self.fold_query(query))
+            }
+            #[cfg(feature = "glob")]
+            SearchMode::Glob => Compiled::Glob(self.compile_glob(query)?),
+            #[cfg(feature = "regex")]
+            SearchMode::Regex => Compiled::Regex(self.compile_regex(query)?),
+            #[cfg(feature = "fuzzy")]
+            SearchMode::Fuzzy => Compiled::Fuzzy,
+            SearchMode::Custom => Compiled::Custom(Arc::clone(self.custom_matcher()?)),
+            #[allow(unreachable_patterns)]
+            _ => return Err(crate::FileSearchError::feature_disabled(mode.feature())),
         };
+        Ok(CompiledQuery::new(query, mode, pattern))
+    }
 
-        let mut results = Vec::new();
+    /// Search `index` for a query compiled by [`SearchEngine::compile`]
+    ///
+    /// Fuzzy matches are returned best first, and other matches in the order
+    /// of [`SearchEngine::sort_matches`].
+    #[must_use]
+    pub fn search_compiled<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        query: &CompiledQuery,
+    ) -> Vec<PathBuf> {
+        let mut results = match &query.pattern {
+            Compiled::Substring(search_query) => {
+                self.collect_matches(index, |filename, paths, results| {
+                    self.push_matches(filename, paths, results, |target| {
+                        self.substring_matches(target, search_query)
+                    });
+                })
+            }
+            // Index keys are lowercased for case-insensitive searches, so
+            // globs and custom matchers match the original paths instead
+            #[cfg(feature = "glob")]
+            Compiled::Glob(glob) => self.collect_matches(index, |_, paths, results| {
+                results.extend(
+                    paths
+                        .iter()
+                        .filter(|path| {
+                            glob.matches(path, |path| self.relative_path(path), self.folding())
+                        })
+                        .cloned(),
+                );
+            }),
+            #[cfg(feature = "regex")]
+            Compiled::Regex(regex) => self.collect_matches(index, |filename, paths, results| {
+                self.push_matches(filename, paths, results, |target| regex.is_match(target));
+            }),
+            #[cfg(feature = "fuzzy")]
+            Compiled::Fuzzy => {
+                return self
+                    .search_fuzzy(index, &query.query)
+                    .into_iter()
+                    .map(|(path, _)| path)
+                    .collect()
+            }
+            Compiled::Custom(matcher) => self.collect_matches(index, |_, paths, results| {
+                results.extend(
+                    paths
+                        .iter()
+                        .filter(|path| custom_matches(matcher.as_ref(), &query.query, path))
+                        .cloned(),
+                );
+            }),
+        };
 
-        for (filename, paths) in index {
-            let search_target = if self.config.case_sensitive {
-                filename.clone()
-            } else {
-                filename.to_lowercase()
-            };
+        self.sort_matches(&mut results);
+        results
+    }
 
-            if search_target.contains(&search_query) {
-                results.extend(paths.clone());
-            }
+    /// Search like [`SearchEngine::search`], pairing each match with its
+    /// score for fuzzy queries
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SearchEngine::search`]
+    pub fn search_scored<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        query: &str,
+        mode: SearchMode,
+    ) -> Result<Vec<(PathBuf, Option<f64>)>> {
+        #[cfg(feature = "fuzzy")]
+        if mode == SearchMode::Fuzzy {
+            validate_query(query, mode)?;
+            return Ok(self
+                .search_fuzzy(index, query)
+                .into_iter()
+                .map(|(path, score)| (path, Some(score)))
+                .collect());
         }
+        Ok(self
+            .search(index, query, mode)?
+            .into_iter()
+            .map(|path| (path, None))
+            .collect())
+    }
 
-        results.sort();
-        results
+    /// Search using substring matching
+    pub fn search_substring<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        query: &str,
+    ) -> Vec<PathBuf> {
+        let pattern = Compiled::Substring(self.fold_query(query));
+        self.search_compiled(
+            index,
+            &CompiledQuery::new(query, SearchMode::Substring, pattern),
+        )
     }
 
-    /// Search using regular expressions
-    pub fn search_regex(&self, index: &FileIndex, pattern: &str) -> Result<Vec<PathBuf>> {
-        let flags = if self.config.case_sensitive {
-            ""
+    /// How names and queries are folded as configured
+    fn folding(&self) -> Folding {
+        Folding::new(&self.config)
+    }
+
+    /// `query` case-folded and normalized as configured, for substring
+    /// matching
+    fn fold_query(&self, query: &str) -> String {
+        self.folding().fold(query).into_owned()
+    }
+
+    /// Push the `paths` recorded under the index key `filename` whose match
+    /// target `matches` accepts
+    ///
+    /// The target is the key, or with [`Config::match_relative_path`] the
+    /// relative path of each path.
+    fn push_matches(
+        &self,
+        filename: &str,
+        paths: &[PathBuf],
+        results: &mut Vec<PathBuf>,
+        matches: impl Fn(&str) -> bool,
+    ) {
+        if self.config.match_relative_path {
+            results.extend(
+                paths
+                    .iter()
+                    .filter(|path| matches(&self.relative_path(path)))
+                    .cloned(),
+            );
+        } else if matches(filename) {
+            results.extend_from_slice(paths);
+        }
+    }
+
+    /// What substring and regex queries match for `path`, recorded under
+    /// the index key `filename`
+    fn match_target<'a>(&self, filename: &'a str, path: &Path) -> Cow<'a, str> {
+        if self.config.match_relative_path {
+            Cow::Owned(self.relative_path(path))
         } else {
-            "(?i)"
-        };
+            Cow::Borrowed(filename)
+        }
+    }
 
-        let full_pattern = format!("{}{}", flags, pattern);
-        let regex = Regex::new(&full_pattern)?;
+    /// `path` below the root set with [`SearchEngine::set_root`], with `/`
+    /// separators and normalized as configured, as matched by relative path
+    /// globs and with [`Config::match_relative_path`]
+    fn relative_path(&self, path: &Path) -> String {
+        let relative = self
+            .root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path)
+            .to_string_lossy();
+        let relative = matcher::normalize_separators(&relative);
+        #[cfg(any(feature = "glob", feature = "regex"))]
+        let relative = self.folding().compose(&relative);
+        relative.into_owned()
+    }
 
-        let mut results = Vec::new();
+    /// Whether `filename` contains `search_query`, already case-folded and
+    /// normalized as configured
+    fn substring_matches(&self, filename: &str, search_query: &str) -> bool {
+        let search_target = self.folding().fold(filename);
+
+        if self.config.word_boundaries {
+            contains_word(&search_target, search_query)
+        } else {
+            search_target.contains(search_query)
+        }
+    }
+
+    /// Search using regular expressions
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pattern` is not a valid regex or exceeds the
+    /// configured length or size limits
+    #[cfg(feature = "regex")]
+    pub fn search_regex<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        pattern: &str,
+    ) -> Result<Vec<PathBuf>> {
+        let compiled = Compiled::Regex(self.compile_regex(pattern)?);
+        Ok(self.search_compiled(
+            index,
+            &CompiledQuery::new(pattern, SearchMode::Regex, compiled),
+        ))
+    }
 
-        for (filename, paths) in index {
-            if regex.is_match(filename) {
-                results.extend(paths.clone());
+    /// Compile `pattern` within the configured length and size limits
+    #[cfg(feature = "regex")]
+    fn compile_regex(&self, pattern: &str) -> Result<Regex> {
+        if let Some(max_len) = self.config.max_regex_len {
+            if pattern.len() > max_len {
+                return Err(crate::FileSearchError::invalid_query(
+                    format!("regex is longer than the limit of {max_len} bytes"),
+                    pattern,
+                ));
             }
         }
 
-        results.sort();
-        Ok(results)
+        // Patterns are normalized like index keys but not case folded, which
+        // would change escapes such as `\D`
+        let mut builder = RegexBuilder::new(&self.folding().compose(pattern));
+        builder.case_insensitive(!self.config.case_sensitive);
+        if let Some(limit) = self.config.regex_size_limit {
+            builder.size_limit(limit);
+        }
+        if let Some(limit) = self.config.regex_dfa_size_limit {
+            builder.dfa_size_limit(limit);
+        }
+        builder
+            .build()
+            .map_err(|e| crate::FileSearchError::regex_error(e, pattern))
     }
 
     /// Search using glob patterns
-    pub fn search_glob(&self, index: &FileIndex, pattern: &str) -> Result<Vec<PathBuf>> {
-        let glob_pattern = if self.config.case_sensitive {
-            Pattern::new(pattern)?
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pattern` is not a valid glob
+    #[cfg(feature = "glob")]
+    pub fn search_glob<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        pattern: &str,
+    ) -> Result<Vec<PathBuf>> {
+        let compiled = Compiled::Glob(self.compile_glob(pattern)?);
+        Ok(self.search_compiled(
+            index,
+            &CompiledQuery::new(pattern, SearchMode::Glob, compiled),
+        ))
+    }
+
+    /// Compile `pattern` with the options matching it as configured
+    ///
+    /// Patterns with a path separator, or any pattern with
+    /// [`Config::match_full_path`], match whole paths, where `*` stays within
+    /// one component and `**` spans any number of them. Relative patterns
+    /// match below any directory under the search root, so `src/**/*.rs`
+    /// finds every `.rs` file under a `src` directory there, while a `src`
+    /// directory above the root takes no part.
+    #[cfg(feature = "glob")]
+    fn compile_glob(&self, pattern: &str) -> Result<Glob> {
+        let folding = self.folding();
+        // Case is left to the matcher, which folds classes and ranges such as
+        // `[À-Ö]` along with the text
+        let compile = |glob: &str| {
+            compiled::glob_regex(
+                &matcher::normalize_separators(&folding.compose(glob)),
+                pattern,
+                !folding.case_sensitive(),
+            )
+        };
+        let name = pattern
+            .rsplit(std::path::is_separator)
+            .next()
+            .unwrap_or(pattern);
+        let absolute = Path::new(pattern).has_root();
+        let path = if self.config.match_full_path || name.len() < pattern.len() {
+            if absolute {
+                Some(compile(pattern)?)
+            } else {
+                Some(compile(&format!("**/{pattern}"))?)
+            }
         } else {
-            // For case-insensitive matching, we'll need to check both the pattern and filenames
-            Pattern::new(&pattern.to_lowercase())?
+            None
         };
+        Ok(Glob {
+            name: compile(name)?,
+            path,
+            absolute,
+        })
+    }
 
-        let mut results = Vec::new();
+    /// Search with the registered [`PatternMatcher`](custom::PatternMatcher)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileSearchError::InvalidConfig`](crate::FileSearchError::InvalidConfig)
+    /// if no matcher is registered
+    pub fn search_custom<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        query: &str,
+    ) -> Result<Vec<PathBuf>> {
+        let compiled = Compiled::Custom(Arc::clone(self.custom_matcher()?));
+        Ok(self.search_compiled(
+            index,
+            &CompiledQuery::new(query, SearchMode::Custom, compiled),
+        ))
+    }
 
-        for (filename, paths) in index {
-            let matches = if self.config.case_sensitive {
-                glob_pattern.matches(filename)
-            } else {
-                glob_pattern.matches(&filename.to_lowercase())
-            };
+    /// Search using fuzzy matching with typo tolerance
+    ///
+    /// A query containing a path separator is scored per path component:
+    /// the part after the last separator against file names, and each part
+    /// before it, from right to left, against the nearest directory above
+    /// that scores at all. `src/mai` thus finds `src/main.rs` but not
+    /// `srcmain_backup.txt`. The score is the mean over all parts, and a file
+    /// is only reported when every part matches.
+    #[cfg(feature = "fuzzy")]
+    pub fn search_fuzzy<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        query: &str,
+    ) -> Vec<(PathBuf, f64)> {
+        let mut scored_results = self.collect_matches(index, |filename, paths, results| {
+            self.score_name(filename, paths, query, results);
+        });
+
+        self.rank_fuzzy(&mut scored_results);
+        scored_results
+    }
 
-            if matches {
-                results.extend(paths.clone());
+    /// The `k` best fuzzy matches scoring at least `min_score`, best first
+    ///
+    /// Matches are ranked as by [`SearchEngine::search_fuzzy`], but only the
+    /// best `k` are kept, in a bounded heap, so asking for the top few of
+    /// millions of matches does not sort them all. `min_score` applies on
+    /// top of [`Config::fuzzy_min_score`], and the matches kept are ordered
+    /// by [`Config::sort`] last.
+    #[cfg(feature = "fuzzy")]
+    #[must_use]
+    pub fn search_fuzzy_top_k<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        query: &str,
+        k: usize,
+        min_score: f64,
+    ) -> Vec<(PathBuf, f64)> {
+        let scored = self.collect_matches(index, |filename, paths, results| {
+            self.score_name(filename, paths, query, results);
+        });
+
+        let now = SystemTime::now();
+        let mut best = top_k::best(
+            scored
+                .into_iter()
+                .filter(|(_, score)| *score >= min_score)
+                .map(|(path, score)| (self.fuzzy_rank(&path, score, now), path, score)),
+            k,
+        );
+        self.config.sort.sort_by_path(&mut best, |(path, _)| path);
+        best
+    }
+
+    /// Fuzzy search that only scores filenames sharing a bigram with the query
+    ///
+    /// Falls back to scoring the whole index for queries shorter than a
+    /// bigram or when a [`Scorer`](scorer::Scorer) is registered.
+    #[cfg(feature = "fuzzy")]
+    #[must_use]
+    pub fn search_fuzzy_candidates<B: IndexBackend + ?Sized>(
+        &self,
+        index: &B,
+        ngrams: &NgramIndex,
+        query: &str,
+    ) -> Vec<(PathBuf, f64)> {
+        // A registered scorer may match names sharing no bigram, and
+        // relative paths are not in the bigram index
+        if self.scorer.is_some() || self.config.match_relative_path {
+            return self.search_fuzzy(index, query);
+        }
+
+        let (_, name_query) = split_path_query(query);
+        let query_key = self.fold_query(name_query);
+
+        let Some(candidates) = ngrams.candidates(&query_key) else {
+            return self.search_fuzzy(index, query);
+        };
+
+        let mut scored_results = Vec::new();
+        for filename in candidates {
+            if self.past_deadline() {
+                break;
             }
+            let paths = index.lookup_exact(filename);
+            self.score_name(filename, &paths, query, &mut scored_results);
         }
 
-        results.sort();
-        Ok(results)
+        self.rank_fuzzy(&mut scored_results);
+        scored_results
     }
 
-    /// Search using fuzzy matching with typo tolerance
+    /// Compile `query` into a test of one file at a time, given its index key
+    /// and path, for searches that report matches while walking
     ///
-    /// # Panics
+    /// A fuzzy query matches every file scoring above
+    /// [`Config::fuzzy_min_score`], without ranking.
+    pub(crate) fn file_matcher<'a>(
+        &'a self,
+        query: &'a str,
+        mode: SearchMode,
+    ) -> Result<FileMatcher<'a>> {
+        let compiled = self.compile(query, mode)?;
+        Ok(Box::new(move |filename, path| match &compiled.pattern {
+            Compiled::Substring(search_query) => {
+                self.substring_matches(&self.match_target(filename, path), search_query)
+            }
+            #[cfg(feature = "glob")]
+            Compiled::Glob(glob) => {
+                glob.matches(path, |path| self.relative_path(path), self.folding())
+            }
+            #[cfg(feature = "regex")]
+            Compiled::Regex(regex) => regex.is_match(&self.match_target(filename, path)),
+            #[cfg(feature = "fuzzy")]
+            Compiled::Fuzzy => self.fuzzy_score(filename, path, query).is_some(),
+            Compiled::Custom(matcher) => custom_matches(matcher.as_ref(), query, path),
+        }))
+    }
+
+    /// Fuzzy score of the file at `path`, indexed under `filename`, if it
+    /// scores above [`Config::fuzzy_min_score`]
+    #[cfg(feature = "fuzzy")]
+    pub(crate) fn fuzzy_score(&self, filename: &str, path: &Path, query: &str) -> Option<f64> {
+        let mut scored = Vec::new();
+        self.score_name(filename, &[path.to_path_buf()], query, &mut scored);
+        scored.pop().map(|(_, score)| score)
+    }
+
+    /// Byte ranges of the file name `name` matched by `query` in `mode`, for
+    /// highlighting
     ///
-    /// This function does not panic under normal circumstances
-    pub fn search_fuzzy(&self, index: &FileIndex, query: &str) -> Vec<(PathBuf, f64)> {
-        let mut scored_results = Vec::new();
+    /// Substring and regex searches report every occurrence, and glob and
+    /// custom searches the whole name. Fuzzy searches report the characters
+    /// matched in order by the part of the query after its last path
+    /// separator, or nothing when the name only matches with typos. Names
+    /// that do not match have no spans.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SearchEngine::search`] for an invalid query
+    pub fn match_spans(
+        &self,
+        name: &str,
+        query: &str,
+        mode: SearchMode,
+    ) -> Result<Vec<Range<usize>>> {
+        Ok(self.span_finder(query, mode)?(name))
+    }
 
-        for (filename, paths) in index {
-            let score = self.calculate_fuzzy_score(filename, query);
-            if score > 0.0 {
-                for path in paths {
-                    scored_results.push((path.clone(), score));
+    /// Compile `query` into a finder of the spans it matches in one file
+    /// name at a time, as described for [`SearchEngine::match_spans`]
+    pub(crate) fn span_finder<'a>(
+        &'a self,
+        query: &'a str,
+        mode: SearchMode,
+    ) -> Result<SpanFinder<'a>> {
+        let compiled = self.compile(query, mode)?;
+        let folding = self.folding();
+        let whole_words = self.config.word_boundaries;
+        Ok(Box::new(move |name| {
+            let whole = 0..name.len();
+            let matches_whole = match &compiled.pattern {
+                Compiled::Substring(search_query) => {
+                    return spans::substring(name, search_query, folding, whole_words)
+                }
+                #[cfg(feature = "glob")]
+                Compiled::Glob(glob) => glob.matches_name(name, folding),
+                #[cfg(feature = "regex")]
+                Compiled::Regex(regex) => {
+                    return regex
+                        .find_iter(name)
+                        .map(|found| found.range())
+                        .filter(|range| !range.is_empty())
+                        .collect()
                 }
+                #[cfg(feature = "fuzzy")]
+                Compiled::Fuzzy => {
+                    let (_, name_query) = split_path_query(query);
+                    return spans::subsequence(name, name_query, folding);
+                }
+                Compiled::Custom(matcher) => matcher.matches(name, query),
+            };
+            if matches_whole {
+                vec![whole]
+            } else {
+                Vec::new()
             }
+        }))
+    }
+
+    /// Call `matches` with every filename key of `index` and its paths and
+    /// collect what it pushes, splitting in-memory indexes across threads
+    /// with the `parallel` feature
+    ///
+    /// Names reached after the deadline are skipped.
+    fn collect_matches<B, T, F>(&self, index: &B, matches: F) -> Vec<T>
+    where
+        B: IndexBackend + ?Sized,
+        T: Send,
+        F: Fn(&str, &[PathBuf], &mut Vec<T>) + Sync,
+    {
+        let matches = |filename: &str, paths: &[PathBuf], results: &mut Vec<T>| {
+            if !self.past_deadline() {
+                matches(filename, paths, results);
+            }
+        };
+
+        #[cfg(feature = "parallel")]
+        if let Some(file_index) = index
+            .as_file_index()
+            .filter(|_| self.config.match_threads != Some(1))
+        {
+            return parallel::collect_matches(file_index, self.pool(), matches);
         }
 
-        scored_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        scored_results
+        let mut results = Vec::new();
+        index.for_each_name(&mut |filename, paths| matches(filename, paths, &mut results));
+        results
     }
 
-    fn calculate_fuzzy_score(&self, filename: &str, query: &str) -> f64 {
-        let filename_lower = if self.config.case_sensitive {
-            filename.to_string()
-        } else {
-            filename.to_lowercase()
+    /// Pool of [`Config::match_threads`] threads, or `None` for rayon's
+    /// global pool
+    #[cfg(feature = "parallel")]
+    fn pool(&self) -> Option<&rayon::ThreadPool> {
+        let threads = self.config.match_threads?;
+        self.pool
+            .get_or_init(|| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .thread_name(|i| format!("whatever-find-match-{i}"))
+                    .build()
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Order substring, regex or glob matches by path, or shallowest first
+    /// when [`Config::shallow_first`] is set, then by [`Config::sort`]
+    pub fn sort_matches(&self, results: &mut [PathBuf]) {
+        results.sort();
+        if self.config.shallow_first {
+            // The sort is stable, so paths at the same depth stay in order
+            results.sort_by_cached_key(|path| path.components().count());
+        }
+        self.config.sort.sort_by_path(results, PathBuf::as_path);
+    }
+
+    /// Order fuzzy matches best first
+    ///
+    /// For ranking only, each score is blended with the file's recency by
+    /// [`Config::recency_weight`], multiplied by the weight of the file's
+    /// extension in [`Config::extension_weights`] and lowered by
+    /// [`Config::depth_penalty`] for every component of the path, so `src/main.rs` beats
+    /// `target/debug/build/main.rs` when the names score alike. The reported
+    /// scores are left as they are, and ties go to the smaller path. Orders
+    /// other than relevance in [`Config::sort`] are applied last.
+    pub fn rank_fuzzy(&self, results: &mut Vec<(PathBuf, f64)>) {
+        let now = SystemTime::now();
+        let mut ranked: Vec<(f64, (PathBuf, f64))> = results
+            .drain(..)
+            .map(|(path, score)| (self.fuzzy_rank(&path, score, now), (path, score)))
+            .collect();
+        ranked.sort_by(|(a_rank, (a_path, _)), (b_rank, (b_path, _))| {
+            b_rank.total_cmp(a_rank).then_with(|| a_path.cmp(b_path))
+        });
+        results.extend(ranked.into_iter().map(|(_, result)| result));
+        self.config.sort.sort_by_path(results, |(path, _)| path);
+    }
+
+    /// Ranking multiplier for the extension of `path`, preferring the longest
+    /// configured extension the name ends with
+    fn extension_weight(&self, path: &Path) -> f64 {
+        let weights = &self.config.extension_weights;
+        if weights.is_empty() {
+            return 1.0;
+        }
+        let Some(name) = path.file_name() else {
+            return 1.0;
         };
+        let name = name.to_string_lossy().to_lowercase();
+        weights
+            .iter()
+            .filter_map(|(extension, weight)| {
+                let extension = extension.trim_start_matches('.').to_lowercase();
+                // A leading dot starts a hidden name, not an extension
+                let stem = name.strip_suffix(extension.as_str())?.strip_suffix('.')?;
+                (!extension.is_empty() && !stem.is_empty()).then_some((extension.len(), *weight))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map_or(1.0, |(_, weight)| weight)
+    }
 
-        let query_lower = if self.config.case_sensitive {
-            query.to_string()
-        } else {
-            query.to_lowercase()
+    /// Rank of a fuzzy match of `path` with `score`, as described for
+    /// [`SearchEngine::rank_fuzzy`]
+    #[allow(clippy::cast_precision_loss)]
+    fn fuzzy_rank(&self, path: &Path, score: f64, now: SystemTime) -> f64 {
+        let recency_weight = self.config.recency_weight;
+        let mut rank = score;
+        if recency_weight > 0.0 {
+            rank = (1.0 - recency_weight) * score + recency_weight * self.recency(path, now);
+        }
+        rank * self.extension_weight(path)
+            - self.config.depth_penalty * path.components().count() as f64
+    }
+
+    /// How recently the file at `path` was modified, from 1.0 for just now
+    /// halving every [`Config::recency_half_life`], or 0.0 when unknown
+    fn recency(&self, path: &Path, now: SystemTime) -> f64 {
+        let Ok(modified) = std::fs::metadata(path).and_then(|metadata| metadata.modified()) else {
+            return 0.0;
         };
+        // Modification times in the future count as just now
+        let age = now.duration_since(modified).unwrap_or_default();
+        0.5_f64.powf(age.as_secs_f64() / self.config.recency_half_life.as_secs_f64())
+    }
+
+    #[cfg(feature = "fuzzy")]
+    // Queries have far fewer than 2^52 parts
+    #[allow(clippy::cast_precision_loss)]
+    fn score_name(
+        &self,
+        filename: &str,
+        paths: &[PathBuf],
+        query: &str,
+        scored_results: &mut Vec<(PathBuf, f64)>,
+    ) {
+        if self.config.match_relative_path {
+            for path in paths {
+                let score = self.calculate_fuzzy_score(&self.relative_path(path), query);
+                if score > 0.0 {
+                    scored_results.push((path.clone(), score));
+                }
+            }
+            return;
+        }
+        let (dir_queries, name_query) = split_path_query(query);
+        let score = self.calculate_fuzzy_score(filename, name_query);
+        if score <= 0.0 {
+            return;
+        }
+        if dir_queries.is_empty() {
+            for path in paths {
+                scored_results.push((path.clone(), score));
+            }
+            return;
+        }
+        for path in paths {
+            if let Some(dir_score) = self.directory_score(path, &dir_queries) {
+                scored_results.push((
+                    path.clone(),
+                    (score + dir_score) / (dir_queries.len() + 1) as f64,
+                ));
+            }
+        }
+    }
+
+    /// Sum of the fuzzy scores of `dir_queries` matched from right to left
+    /// against the directories holding `path`, nearest first, or `None` if
+    /// one of them matches no directory
+    #[cfg(feature = "fuzzy")]
+    fn directory_score(&self, path: &Path, dir_queries: &[&str]) -> Option<f64> {
+        let mut dirs = path
+            .parent()?
+            .components()
+            .rev()
+            .filter_map(|component| match component {
+                std::path::Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            });
+        let mut total = 0.0;
+        for dir_query in dir_queries.iter().rev() {
+            total += dirs
+                .by_ref()
+                .map(|dir| self.calculate_fuzzy_score(&dir, dir_query))
+                .find(|score| *score > 0.0)?;
+        }
+        Some(total)
+    }
+
+    #[cfg(feature = "fuzzy")]
+    fn calculate_fuzzy_score(&self, filename: &str, query: &str) -> f64 {
+        let folding = self.folding();
+        let filename_lower = folding.fold(filename);
+        let query_lower = folding.fold(query);
+
+        if let Some(scorer) = &self.scorer {
+            let score = scorer.score(&filename_lower, &query_lower).min(1.0);
+            // NaN scores are dropped as well
+            return if score >= self.config.fuzzy_min_score {
+                score
+            } else {
+                0.0
+            };
+        }
 
         // Exact match
         if filename_lower == query_lower {
@@ -278,69 +1118,55 @@ impl SearchEngine {
         }
 
         // Substring match
-        if filename_lower.contains(&query_lower) {
+        if filename_lower.contains(&*query_lower) {
             return 0.9
                 - (filename_lower.len() as f64 - query_lower.len() as f64)
                     / filename_lower.len() as f64
                     * 0.1;
         }
 
-        // Calculate multiple scoring methods and combine them
-        let levenshtein_score = self.levenshtein_score(&filename_lower, &query_lower);
+        // Skip candidates whose length alone rules out reaching the threshold
+        let min_score = self.config.fuzzy_min_score;
+        let weights = &self.config.fuzzy_scoring;
+        let total = weights.total();
+        let filename_len = filename_lower.chars().count();
+        let query_len = query_lower.chars().count();
+        if best_possible_score(filename_len, query_len, weights) < min_score {
+            return 0.0;
+        }
+
+        // Calculate multiple scoring methods and combine them. The cheap scorers
+        // run first so the edit distance can stop once it cannot reach the threshold.
         let subsequence_score = self.subsequence_score(&filename_lower, &query_lower);
         let ngram_score = self.ngram_score(&filename_lower, &query_lower);
+        let levenshtein_score = if weights.levenshtein > 0.0 {
+            let needed = (min_score * total
+                - subsequence_score * weights.subsequence
+                - ngram_score * weights.ngram)
+                / weights.levenshtein;
+            let Some(score) = levenshtein_score(&filename_lower, &query_lower, needed) else {
+                return 0.0;
+            };
+            score
+        } else {
+            0.0
+        };
 
         // Combine scores with weights
-        let combined_score =
-            (levenshtein_score * 0.4) + (subsequence_score * 0.4) + (ngram_score * 0.2);
+        let combined_score = (levenshtein_score * weights.levenshtein
+            + subsequence_score * weights.subsequence
+            + ngram_score * weights.ngram)
+            / total;
 
         // Only return meaningful scores
-        if combined_score < 0.3 {
+        if combined_score < min_score {
             0.0
         } else {
             combined_score
         }
     }
 
-    fn levenshtein_score(&self, s1: &str, s2: &str) -> f64 {
-        let len1 = s1.chars().count();
-        let len2 = s2.chars().count();
-
-        if len1 == 0 {
-            return if len2 == 0 { 1.0 } else { 0.0 };
-        }
-        if len2 == 0 {
-            return 0.0;
-        }
-
-        let chars1: Vec<char> = s1.chars().collect();
-        let chars2: Vec<char> = s2.chars().collect();
-
-        let mut prev_row: Vec<usize> = (0..=len2).collect();
-        let mut curr_row = vec![0; len2 + 1];
-
-        for i in 1..=len1 {
-            curr_row[0] = i;
-            for j in 1..=len2 {
-                let cost = if chars1[i - 1] == chars2[j - 1] { 0 } else { 1 };
-                curr_row[j] = std::cmp::min(
-                    std::cmp::min(curr_row[j - 1] + 1, prev_row[j] + 1),
-                    prev_row[j - 1] + cost,
-                );
-            }
-            std::mem::swap(&mut prev_row, &mut curr_row);
-        }
-
-        let distance = prev_row[len2];
-        let max_len = std::cmp::max(len1, len2);
-
-        if max_len == 0 {
-            1.0
-        } else {
-            1.0 - (distance as f64 / max_len as f64)
-        }
-    }
-
+    #[cfg(feature = "fuzzy")]
     fn subsequence_score(&self, filename: &str, query: &str) -> f64 {
         let filename_chars: Vec<char> = filename.chars().collect();
         let query_chars: Vec<char> = query.chars().collect();
@@ -376,6 +1202,7 @@ impl SearchEngine {
         }
     }
 
+    #[cfg(feature = "fuzzy")]
     fn ngram_score(&self, s1: &str, s2: &str) -> f64 {
         const N: usize = 2; // bigrams
 
@@ -400,6 +1227,7 @@ impl SearchEngine {
         common as f64 / total as f64
     }
 
+    #[cfg(feature = "fuzzy")]
     fn get_ngrams(&self, s: &str, n: usize) -> Vec<String> {
         let chars: Vec<char> = s.chars().collect();
         if chars.len() < n {
@@ -412,3 +1240,145 @@ impl SearchEngine {
             .collect()
     }
 }
+
+/// Whether `matcher` accepts the file name of `path` for `query`
+fn custom_matches(matcher: &dyn custom::PatternMatcher, query: &str, path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| matcher.matches(&name.to_string_lossy(), query))
+}
+
+/// Whether `needle` occurs in `haystack` with no letter or digit directly
+/// before or after it
+///
+/// Punctuation such as `.`, `_` and `-` separates words, so `log` is a word
+/// of `error.log` and `log_parser.rs` but not of `catalog.pdf`.
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    if needle.is_empty() {
+        return true;
+    }
+    haystack
+        .match_indices(needle)
+        .any(|(start, _)| is_word(haystack, start..start + needle.len()))
+}
+
+/// Whether `range` of `haystack` has no letter or digit directly before or
+/// after it
+fn is_word(haystack: &str, range: Range<usize>) -> bool {
+    let before = haystack[..range.start].chars().next_back();
+    let after = haystack[range.end..].chars().next();
+    !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+}
+
+/// Split a fuzzy query at its last path separator into the queries for the
+/// directories, with empty parts dropped, and the query for the file name
+///
+/// Queries without a separator have no directory queries.
+#[cfg(feature = "fuzzy")]
+fn split_path_query(query: &str) -> (Vec<&str>, &str) {
+    match query.rfind(std::path::is_separator) {
+        Some(at) => (
+            query[..at]
+                .split(std::path::is_separator)
+                .filter(|part| !part.is_empty())
+                .collect(),
+            &query[at + 1..],
+        ),
+        None => (Vec::new(), query),
+    }
+}
+
+/// Upper bound on the combined fuzzy score of a filename and query that are
+/// not substrings of each other, given only their lengths in characters and
+/// the weights of the measures
+///
+/// The edit distance is at least the length difference, the bigram score is
+/// at most 1, and the subsequence score is 0 unless the query fits in the
+/// filename, with its coverage limited by the consecutive-match bonus.
+#[cfg(feature = "fuzzy")]
+// Filename lengths are far below 2^52
+#[allow(clippy::cast_precision_loss)]
+fn best_possible_score(
+    filename_len: usize,
+    query_len: usize,
+    weights: &crate::config::FuzzyScoringConfig,
+) -> f64 {
+    let max_len = filename_len.max(query_len);
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let levenshtein = 1.0 - filename_len.abs_diff(query_len) as f64 / max_len as f64;
+    let subsequence = if query_len <= filename_len {
+        let q = query_len as f64;
+        let coverage = (q + 0.05 * q * (q + 1.0)) / filename_len as f64;
+        coverage * 0.4 + 0.6
+    } else {
+        0.0
+    };
+    (levenshtein * weights.levenshtein + subsequence * weights.subsequence + weights.ngram)
+        / weights.total()
+}
+
+/// Normalised edit similarity, or `None` if it is certainly below `min_score`
+// Distances and lengths are far below 2^52, and the bound is clamped to 0..=max_len
+#[cfg(feature = "fuzzy")]
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn levenshtein_score(s1: &str, s2: &str, min_score: f64) -> Option<f64> {
+    let chars1: Vec<char> = s1.chars().collect();
+    let chars2: Vec<char> = s2.chars().collect();
+    let max_len = std::cmp::max(chars1.len(), chars2.len());
+
+    if max_len == 0 {
+        return Some(1.0);
+    }
+
+    let max_distance = ((1.0 - min_score).clamp(0.0, 1.0) * max_len as f64 + 1e-9) as usize;
+    let distance = bounded_levenshtein(&chars1, &chars2, max_distance.min(max_len))?;
+    Some(1.0 - (distance as f64 / max_len as f64))
+}
+
+/// Levenshtein distance between `a` and `b`, or `None` if it exceeds `max`
+///
+/// Only the diagonal band of width `2 * max + 1` is computed, and the loop
+/// stops as soon as every cell of a row exceeds `max`.
+#[cfg(feature = "fuzzy")]
+fn bounded_levenshtein(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let beyond = max + 1;
+    let mut prev_row: Vec<usize> = (0..=b.len()).collect();
+    let mut curr_row = vec![beyond; b.len() + 1];
+
+    for i in 1..=a.len() {
+        let lo = i.saturating_sub(max).max(1);
+        let hi = (i + max).min(b.len());
+
+        curr_row[lo - 1] = if lo == 1 { i } else { beyond };
+        let mut row_min = curr_row[lo - 1];
+        for j in lo..=hi {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            curr_row[j] = (prev_row[j - 1] + cost)
+                .min(prev_row[j] + 1)
+                .min(curr_row[j - 1] + 1)
+                .min(beyond);
+            row_min = row_min.min(curr_row[j]);
+        }
+        if hi < b.len() {
+            curr_row[hi + 1] = beyond;
+        }
+
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+
+    let distance = prev_row[b.len()];
+    (distance <= max).then_some(distance)
+}
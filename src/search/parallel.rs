@@ -0,0 +1,59 @@
+//! Matching filenames on several threads
+//!
+//! A query is matched against every filename key of the index, which takes
+//! noticeable time once an index holds millions of entries. The keys of an
+//! in-memory [`FileIndex`] are stored contiguously, so they can be split into
+//! ranges matched on rayon's worker threads, each collecting its own matches
+//! before they are merged. Searches sort their results afterwards, so the
+//! order in which threads finish does not show.
+//!
+//! Small indexes are matched on the calling thread, where handing the work to
+//! a pool would cost more than it saves.
+
+use crate::indexer::FileIndex;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use std::path::PathBuf;
+
+/// Fewest filename keys matched as one piece of work
+const MIN_CHUNK: usize = 4096;
+
+/// Call `matches` with every filename key of `index` and its paths, on the
+/// threads of `pool` or of rayon's global pool if `None`, and merge what the
+/// calls push
+pub(crate) fn collect_matches<T, F>(
+    index: &FileIndex,
+    pool: Option<&ThreadPool>,
+    matches: F,
+) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&str, &[PathBuf], &mut Vec<T>) + Sync,
+{
+    if index.len() < 2 * MIN_CHUNK {
+        let mut results = Vec::new();
+        for (name, paths) in index {
+            matches(name, paths, &mut results);
+        }
+        return results;
+    }
+
+    let run = || {
+        (0..index.len())
+            .into_par_iter()
+            .with_min_len(MIN_CHUNK)
+            .fold(Vec::new, |mut results, position| {
+                let (name, paths) = index.entry(position);
+                matches(name, paths, &mut results);
+                results
+            })
+            .reduce(Vec::new, |mut all, mut more| {
+                all.append(&mut more);
+                all
+            })
+    };
+    match pool {
+        Some(pool) => pool.install(run),
+        None => run(),
+    }
+}
@@ -0,0 +1,110 @@
+//! Named searches kept in the configuration
+//!
+//! A [`Preset`] bundles a query with its search mode, the directory to
+//! search, filters on the results, an order and a limit, so a recurring
+//! search can be run by name with
+//! [`FileSearcher::run_preset`](crate::FileSearcher::run_preset). Presets are
+//! stored by name in [`Config::presets`](crate::config::Config::presets).
+
+use super::SearchMode;
+pub use super::SortOrder;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "config")]
+use serde::{Deserialize, Serialize};
+
+/// A saved search
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "config", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config", serde(default))]
+pub struct Preset {
+    /// Query to search for
+    pub query: String,
+    /// Search mode, or `None` to detect it from the query
+    pub mode: Option<SearchMode>,
+    /// Directory to search, or `None` for the current directory
+    pub root: Option<PathBuf>,
+    /// Only keep files with one of these extensions, compared without regard
+    /// to case (empty keeps every file)
+    pub extensions: Vec<String>,
+    /// Only keep files of at least this many bytes
+    pub min_size: Option<u64>,
+    /// Only keep files of at most this many bytes
+    pub max_size: Option<u64>,
+    /// Only keep files modified within this long before the search
+    pub modified_within: Option<Duration>,
+    /// Order of the results
+    pub sort: SortOrder,
+    /// Keep at most this many results, after sorting
+    pub limit: Option<usize>,
+}
+
+impl Preset {
+    /// Search for `query` with every other setting left at its default
+    #[must_use]
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Filter, sort and truncate the results of the preset's search
+    ///
+    /// Files whose metadata cannot be read fail the size and age filters.
+    #[must_use]
+    pub fn apply(&self, results: Vec<PathBuf>) -> Vec<PathBuf> {
+        let stat =
+            self.min_size.is_some() || self.max_size.is_some() || self.modified_within.is_some();
+        let now = SystemTime::now();
+        let mut kept: Vec<(PathBuf, Option<Metadata>)> = results
+            .into_iter()
+            .filter(|path| self.has_extension(path))
+            .map(|path| {
+                let metadata = if stat { fs::metadata(&path).ok() } else { None };
+                (path, metadata)
+            })
+            .filter(|(_, metadata)| self.keeps(metadata.as_ref(), now))
+            .collect();
+
+        self.sort.sort_by_path(&mut kept, |(path, _)| path);
+        if let Some(limit) = self.limit {
+            kept.truncate(limit);
+        }
+        kept.into_iter().map(|(path, _)| path).collect()
+    }
+
+    fn has_extension(&self, path: &Path) -> bool {
+        self.extensions.is_empty()
+            || path.extension().is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|wanted| extension.eq_ignore_ascii_case(wanted.trim_start_matches('.')))
+            })
+    }
+
+    fn keeps(&self, metadata: Option<&Metadata>, now: SystemTime) -> bool {
+        if self.min_size.is_none() && self.max_size.is_none() && self.modified_within.is_none() {
+            return true;
+        }
+        let Some(metadata) = metadata else {
+            return false;
+        };
+        let size = metadata.len();
+        if self.min_size.is_some_and(|min| size < min)
+            || self.max_size.is_some_and(|max| size > max)
+        {
+            return false;
+        }
+        match (self.modified_within, metadata.modified()) {
+            (None, _) => true,
+            // Modification times in the future count as just now
+            (Some(window), Ok(modified)) => now
+                .duration_since(modified)
+                .map_or(true, |age| age <= window),
+            (Some(_), Err(_)) => false,
+        }
+    }
+}
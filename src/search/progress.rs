@@ -0,0 +1,135 @@
+//! Progress reports for frontends
+//!
+//! Walking a big tree can take a while, and GUI or TUI frontends want to show
+//! how far along it is. A [`ProgressReporter`] registered on a
+//! [`FileSearcher`](crate::FileSearcher), or on a
+//! [`FileIndexer`](crate::indexer::FileIndexer) directly, receives the
+//! directories scanned and files indexed so far at most every
+//! [`REPORT_INTERVAL`] during a walk and once more when it is done. Searches
+//! then report again with the number of matches found. Reports are made on
+//! the thread doing the walk, so a reporter should return quickly.
+//!
+//! Any `Fn(&SearchProgress) + Send + Sync` closure is a reporter:
+//!
+//! ```rust
+//! use whatever_find::{FileSearcher, SearchProgress};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let searcher = FileSearcher::builder()
+//!     .progress_reporter(|progress: &SearchProgress| {
+//!         eprint!("\r{} files in {} directories", progress.files_indexed, progress.dirs_scanned);
+//!     })
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Shortest time between two reports during a walk
+pub const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How far an indexing walk or search has come
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchProgress {
+    /// Directories entered so far
+    pub dirs_scanned: usize,
+    /// Files found and indexed so far
+    pub files_indexed: usize,
+    /// Files matching the query so far, zero while a walk is still indexing
+    /// for a search that matches afterwards
+    pub matches: usize,
+}
+
+/// Receives progress reports while indexing and searching
+pub trait ProgressReporter: Send + Sync {
+    /// Called with the progress so far
+    fn report(&self, progress: &SearchProgress);
+}
+
+impl<F> ProgressReporter for F
+where
+    F: Fn(&SearchProgress) + Send + Sync,
+{
+    fn report(&self, progress: &SearchProgress) {
+        self(progress);
+    }
+}
+
+/// The reporter registered on a searcher, if any
+#[derive(Clone, Default)]
+pub(crate) struct Reporter(pub(crate) Option<Arc<dyn ProgressReporter>>);
+
+impl Reporter {
+    /// Report `matches` found among the files of a walk that counted
+    /// `progress`
+    pub(crate) fn matches(&self, progress: SearchProgress, matches: usize) {
+        if let Some(reporter) = &self.0 {
+            reporter.report(&SearchProgress {
+                matches,
+                ..progress
+            });
+        }
+    }
+}
+
+impl fmt::Debug for Reporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() {
+            "Reporter(set)"
+        } else {
+            "Reporter(none)"
+        })
+    }
+}
+
+/// Counts of a walk, passed on to a reporter at most every
+/// [`REPORT_INTERVAL`]
+pub(crate) struct ProgressCounter<'a> {
+    reporter: Option<&'a Arc<dyn ProgressReporter>>,
+    progress: SearchProgress,
+    last_report: Instant,
+}
+
+impl<'a> ProgressCounter<'a> {
+    /// Start counting a walk reported to `reporter`, if any
+    pub(crate) fn new(reporter: Option<&'a Arc<dyn ProgressReporter>>) -> Self {
+        Self {
+            reporter,
+            progress: SearchProgress::default(),
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Count a directory entered
+    pub(crate) fn dir(&mut self) {
+        self.progress.dirs_scanned += 1;
+        self.tick();
+    }
+
+    /// Count a file indexed
+    pub(crate) fn file(&mut self) {
+        self.progress.files_indexed += 1;
+        self.tick();
+    }
+
+    fn tick(&mut self) {
+        let Some(reporter) = self.reporter else {
+            return;
+        };
+        if self.last_report.elapsed() >= REPORT_INTERVAL {
+            reporter.report(&self.progress);
+            self.last_report = Instant::now();
+        }
+    }
+
+    /// Report the finished walk and return its counts
+    pub(crate) fn finish(self) -> SearchProgress {
+        if let Some(reporter) = self.reporter {
+            reporter.report(&self.progress);
+        }
+        self.progress
+    }
+}
@@ -0,0 +1,346 @@
+//! Structured queries with attribute filters
+//!
+//! A [`Query`] combines name patterns with filters on a file's extension,
+//! size and age, written as space-separated terms:
+//!
+//! ```text
+//! name:*.rs size:>1M mtime:<7d ext:rs,toml
+//! ```
+//!
+//! - `name:PATTERN`, or a bare `PATTERN`, matches file names, with the
+//!   search mode detected from the pattern as for
+//!   [`FileSearcher::search_auto`](crate::FileSearcher::search_auto), except
+//!   that wildcards are an error rather than text when built without the
+//!   `glob` feature
+//! - `path:TEXT` keeps files whose path contains `TEXT`, written with either
+//!   path separator on Windows
+//! - `ext:A,B` keeps files with one of the extensions, ignoring case
+//! - `size:>N`, `size:<N` or `size:N` compares the size in bytes, with an
+//!   optional `k`, `M`, `G` or `T` suffix for powers of 1024
+//! - `mtime:<AGE`, `mtime:>AGE` or `mtime:AGE` compares how long ago the
+//!   file was modified, in `s`, `m`, `h`, `d` or `w`, so `mtime:<7d` keeps
+//!   files modified within the last week
+//!
+//! Every term must hold for a file to match. Values containing spaces can be
+//! quoted, as in `name:"my notes"`. A term whose prefix before `:` is not one
+//! of the keys above is taken as a name pattern.
+//!
+//! Queries can also be built and combined in code, with [`Query::all`],
+//! [`Query::any`] and [`Query::not`]:
+//!
+//! ```rust
+//! use whatever_find::search::query::Query;
+//!
+//! // Rust files with "test" in their name, outside vendored code
+//! let query = Query::all([
+//!     Query::name("*.rs"),
+//!     Query::name("test"),
+//!     Query::not(Query::path("vendor")),
+//! ]);
+//! ```
+//!
+//! Queries are evaluated while walking, by
+//! [`FileSearcher::search_query`](crate::FileSearcher::search_query), and
+//! only read the metadata of files whose name and extension already match.
+
+use super::detect::Reason;
+use super::matcher::path_contains;
+use super::{FileMatcher, SearchEngine};
+use crate::{FileSearchError, Result, SearchMode};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// Comparison of a file attribute against a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound<T> {
+    /// Greater than the value
+    Over(T),
+    /// Less than the value
+    Under(T),
+    /// Equal to the value
+    Exactly(T),
+}
+
+impl<T: PartialOrd> Bound<T> {
+    /// Whether `value` lies within the bound
+    #[must_use]
+    pub fn matches(&self, value: &T) -> bool {
+        match self {
+            Self::Over(bound) => value > bound,
+            Self::Under(bound) => value < bound,
+            Self::Exactly(bound) => value == bound,
+        }
+    }
+}
+
+/// A parsed or combined structured query
+///
+/// A file matches when it satisfies every term and every nested query. The
+/// default query matches every file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    /// Patterns every file name must match
+    pub names: Vec<String>,
+    /// Text every file path must contain
+    pub paths: Vec<String>,
+    /// Extensions, without the leading dot, of which a file must have one
+    /// (empty keeps every file)
+    pub extensions: Vec<String>,
+    /// Bounds on the size in bytes
+    pub size: Vec<Bound<u64>>,
+    /// Bounds on the time since the last modification
+    pub age: Vec<Bound<Duration>>,
+    /// Queries that must all match as well
+    pub all: Vec<Query>,
+    /// Queries of which at least one must match, or `None` to require none
+    ///
+    /// `Some` of an empty list matches no file.
+    pub any: Option<Vec<Query>>,
+    /// Queries none of which may match
+    pub not: Vec<Query>,
+}
+
+impl Query {
+    /// Match file names against `pattern`, with its search mode detected
+    #[must_use]
+    pub fn name(pattern: impl Into<String>) -> Self {
+        Self {
+            names: vec![pattern.into()],
+            ..Self::default()
+        }
+    }
+
+    /// Match files whose path contains `text`
+    #[must_use]
+    pub fn path(text: impl Into<String>) -> Self {
+        Self {
+            paths: vec![text.into()],
+            ..Self::default()
+        }
+    }
+
+    /// Match files matching every one of `queries`
+    #[must_use]
+    pub fn all(queries: impl IntoIterator<Item = Query>) -> Self {
+        Self {
+            all: queries.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Match files matching at least one of `queries`
+    #[must_use]
+    pub fn any(queries: impl IntoIterator<Item = Query>) -> Self {
+        Self {
+            any: Some(queries.into_iter().collect()),
+            ..Self::default()
+        }
+    }
+
+    /// Match files not matching `query`
+    #[must_use]
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(query: Query) -> Self {
+        Self {
+            not: vec![query],
+            ..Self::default()
+        }
+    }
+
+    /// Parse `query` as described in the [module documentation](self)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FileSearchError::InvalidQuery`] for an unterminated quote or
+    /// an empty, malformed or out of range filter value
+    pub fn parse(query: &str) -> Result<Self> {
+        let invalid = |reason: String| FileSearchError::invalid_query(reason, query);
+        let mut parsed = Self::default();
+        for term in terms(query).map_err(|reason| invalid(reason.to_string()))? {
+            let (key, value) = term.split_once(':').unwrap_or(("", &term));
+            let known = matches!(key, "name" | "path" | "ext" | "size" | "mtime");
+            if known && value.is_empty() {
+                return Err(invalid(format!("'{key}:' needs a value")));
+            }
+            match key {
+                "name" => parsed.names.push(value.to_string()),
+                "path" => parsed.paths.push(value.to_string()),
+                "ext" => parsed.extensions.extend(
+                    value
+                        .split(',')
+                        .map(|ext| ext.trim_start_matches('.'))
+                        .filter(|ext| !ext.is_empty())
+                        .map(str::to_string),
+                ),
+                "size" => parsed.size.push(
+                    parse_bound(value, parse_size)
+                        .ok_or_else(|| invalid(format!("'{value}' is not a size such as >1M")))?,
+                ),
+                "mtime" => parsed.age.push(
+                    parse_bound(value, parse_age)
+                        .ok_or_else(|| invalid(format!("'{value}' is not an age such as <7d")))?,
+                ),
+                _ => parsed.names.push(term.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Compile the query into a test of one file at a time, given its index
+    /// key and path, with name patterns matched by `engine`
+    ///
+    /// A name pattern with wildcards fails with
+    /// [`FileSearchError::FeatureDisabled`] when built without the `glob`
+    /// feature, instead of silently matching the wildcards as text.
+    pub(crate) fn matcher<'a>(&'a self, engine: &'a SearchEngine) -> Result<FileMatcher<'a>> {
+        let compile = |queries: &'a [Query]| {
+            queries
+                .iter()
+                .map(|query| query.matcher(engine))
+                .collect::<Result<Vec<_>>>()
+        };
+        let names = self
+            .names
+            .iter()
+            .map(|name| {
+                let detection = engine.explain_detection(name);
+                if !cfg!(feature = "glob") && detection.reason == Reason::Disabled(SearchMode::Glob)
+                {
+                    return Err(FileSearchError::feature_disabled("glob"));
+                }
+                engine.file_matcher(name, detection.mode)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let all = compile(&self.all)?;
+        let any = self.any.as_deref().map(compile).transpose()?;
+        let not = compile(&self.not)?;
+        let now = SystemTime::now();
+        Ok(Box::new(move |key, path| {
+            names.iter().all(|matches| matches(key, path))
+                && self.paths.iter().all(|text| path_contains(path, text))
+                && self.has_extension(path)
+                && all.iter().all(|matches| matches(key, path))
+                && any
+                    .as_ref()
+                    .map_or(true, |any| any.iter().any(|matches| matches(key, path)))
+                && !not.iter().any(|matches| matches(key, path))
+                && self.matches_metadata(path, now)
+        }))
+    }
+
+    fn has_extension(&self, path: &Path) -> bool {
+        self.extensions.is_empty()
+            || path.extension().is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|wanted| extension.eq_ignore_ascii_case(wanted))
+            })
+    }
+
+    /// Whether the size and age of the file at `path` are within bounds,
+    /// reading its metadata only if there are bounds
+    ///
+    /// Files whose metadata cannot be read fail every bound. Modification
+    /// times in the future count as just now.
+    fn matches_metadata(&self, path: &Path, now: SystemTime) -> bool {
+        if self.size.is_empty() && self.age.is_empty() {
+            return true;
+        }
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+        if !self.size.iter().all(|bound| bound.matches(&metadata.len())) {
+            return false;
+        }
+        if self.age.is_empty() {
+            return true;
+        }
+        let Ok(modified) = metadata.modified() else {
+            return false;
+        };
+        let age = now.duration_since(modified).unwrap_or_default();
+        self.age.iter().all(|bound| bound.matches(&age))
+    }
+}
+
+impl FromStr for Query {
+    type Err = FileSearchError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Split `query` at whitespace outside double quotes, dropping the quotes
+fn terms(query: &str) -> std::result::Result<Vec<String>, &'static str> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+            }
+            c => term.push(c),
+        }
+    }
+    if quoted {
+        return Err("query has an unterminated '\"'");
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    Ok(terms)
+}
+
+/// Parse `>V`, `<V` or `V` with `parse` parsing the value `V`
+fn parse_bound<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Bound<T>> {
+    if let Some(value) = value.strip_prefix('>') {
+        parse(value).map(Bound::Over)
+    } else if let Some(value) = value.strip_prefix('<') {
+        parse(value).map(Bound::Under)
+    } else {
+        parse(value).map(Bound::Exactly)
+    }
+}
+
+/// `digits` followed by a unit suffix, split apart
+fn split_unit(value: &str) -> Option<(u64, &str)> {
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    Some((value[..end].parse().ok()?, &value[end..]))
+}
+
+/// Parse a size such as `512`, `10k` or `2MB`, in powers of 1024
+fn parse_size(value: &str) -> Option<u64> {
+    let (n, unit) = split_unit(value)?;
+    let unit = unit.strip_suffix(['b', 'B']).unwrap_or(unit);
+    let shift = match unit {
+        "" => 0,
+        "k" | "K" => 10,
+        "m" | "M" => 20,
+        "g" | "G" => 30,
+        "t" | "T" => 40,
+        _ => return None,
+    };
+    n.checked_mul(1 << shift)
+}
+
+/// Parse an age such as `30s`, `15m`, `12h`, `7d` or `2w`
+fn parse_age(value: &str) -> Option<Duration> {
+    let (n, unit) = split_unit(value)?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    n.checked_mul(seconds).map(Duration::from_secs)
+}
@@ -0,0 +1,136 @@
+//! Disk inventory reports
+//!
+//! A [`SizeReport`] counts files and bytes below a root, grouped by
+//! extension and by the top-level directory each file sits in. Files directly
+//! inside the root are grouped under `.`. Reports are written as an aligned
+//! text table or as CSV, and serialize to JSON with the `config` feature.
+
+use super::results::{SearchResults, SizeTotal};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+#[cfg(feature = "config")]
+use serde::Serialize;
+
+/// Counts and sizes of files below a root
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize))]
+pub struct SizeReport {
+    /// Directory the report covers
+    pub root: PathBuf,
+    /// All files together
+    pub total: SizeTotal,
+    /// Totals per lowercased extension, with `""` for files without one
+    pub by_extension: BTreeMap<String, SizeTotal>,
+    /// Totals per top-level directory under the root
+    pub by_directory: BTreeMap<String, SizeTotal>,
+}
+
+impl SizeReport {
+    /// Report on `results`, grouping directories relative to `root`
+    ///
+    /// Results outside `root` are grouped under their full parent directory.
+    #[must_use]
+    pub fn new(root: &Path, results: &SearchResults) -> Self {
+        let mut by_directory: BTreeMap<String, SizeTotal> = BTreeMap::new();
+        for (path, size) in results.iter() {
+            let total = by_directory.entry(top_directory(root, path)).or_default();
+            total.files += 1;
+            total.bytes += size;
+        }
+        Self {
+            root: root.to_path_buf(),
+            total: SizeTotal {
+                files: results.len(),
+                bytes: results.total_size(),
+            },
+            by_extension: results.size_by_extension(),
+            by_directory,
+        }
+    }
+
+    /// Write the report as aligned text tables, largest groups first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `out` fails
+    pub fn write_table<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "{}: {} files, {} bytes",
+            self.root.display(),
+            self.total.files,
+            self.total.bytes
+        )?;
+        for (title, groups) in [
+            ("Extension", &self.by_extension),
+            ("Directory", &self.by_directory),
+        ] {
+            let mut rows: Vec<_> = groups.iter().collect();
+            rows.sort_by_key(|(_, total)| std::cmp::Reverse(total.bytes));
+            writeln!(out)?;
+            writeln!(out, "{:>15}  {:>10}  {title}", "Bytes", "Files")?;
+            for (key, total) in rows {
+                let key = match key.as_str() {
+                    "" => "(none)",
+                    key => key,
+                };
+                writeln!(out, "{:>15}  {:>10}  {key}", total.bytes, total.files)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the report as CSV with the columns `group`, `key`, `files` and
+    /// `bytes`, where `group` is `total`, `extension` or `directory`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `out` fails
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "group,key,files,bytes")?;
+        writeln!(out, "total,,{},{}", self.total.files, self.total.bytes)?;
+        for (group, groups) in [
+            ("extension", &self.by_extension),
+            ("directory", &self.by_directory),
+        ] {
+            for (key, total) in groups {
+                writeln!(
+                    out,
+                    "{group},{},{},{}",
+                    csv_field(key),
+                    total.files,
+                    total.bytes
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Name of the directory directly under `root` that holds `path`
+fn top_directory(root: &Path, path: &Path) -> String {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_string_lossy()
+            .into_owned();
+    };
+    let mut components = relative
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir));
+    match (components.next(), components.next()) {
+        (Some(first), Some(_)) => first.as_os_str().to_string_lossy().into_owned(),
+        _ => ".".to_string(),
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,102 @@
+//! Search results with their file metadata
+//!
+//! [`SearchResult`] pairs a matched path with the size, modification time
+//! and type of the file, read once when the result is created, so callers
+//! showing hits do not have to stat every one of them again. Fuzzy results
+//! also carry their score, and results of a search the spans of the file
+//! name the query matched.
+
+use std::fs::Metadata;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// What kind of filesystem entry a result is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileKind {
+    /// A regular file
+    File,
+    /// A directory
+    Directory,
+    /// A symbolic link, which is not followed
+    Symlink,
+    /// Anything else, such as a socket or device, or an entry whose metadata
+    /// could not be read
+    Other,
+}
+
+impl FileKind {
+    fn of(metadata: &Metadata) -> Self {
+        let file_type = metadata.file_type();
+        if file_type.is_symlink() {
+            Self::Symlink
+        } else if file_type.is_dir() {
+            Self::Directory
+        } else if file_type.is_file() {
+            Self::File
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// A matched path together with its metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    /// Path of the match
+    pub path: PathBuf,
+    /// Size in bytes, zero for directories and unreadable entries
+    pub size: u64,
+    /// Last modification time, `None` where the platform or filesystem does
+    /// not record it or the entry could not be read
+    pub modified: Option<SystemTime>,
+    /// Kind of the entry itself, without following symlinks
+    pub kind: FileKind,
+    /// Fuzzy match score, only set for fuzzy searches
+    pub score: Option<f64>,
+    /// Byte ranges of the file name matched by the query, for highlighting
+    ///
+    /// See [`SearchEngine::match_spans`](crate::search::SearchEngine::match_spans)
+    /// for what each search mode reports.
+    pub spans: Vec<Range<usize>>,
+}
+
+impl SearchResult {
+    /// Read the metadata of the entry at `path`
+    #[must_use]
+    pub fn new(path: PathBuf) -> Self {
+        match std::fs::symlink_metadata(&path) {
+            Ok(metadata) => Self {
+                size: if metadata.is_dir() { 0 } else { metadata.len() },
+                modified: metadata.modified().ok(),
+                kind: FileKind::of(&metadata),
+                score: None,
+                spans: Vec::new(),
+                path,
+            },
+            Err(_) => Self {
+                path,
+                size: 0,
+                modified: None,
+                kind: FileKind::Other,
+                score: None,
+                spans: Vec::new(),
+            },
+        }
+    }
+
+    /// Read the metadata of the entry at `path`, a fuzzy match with `score`
+    #[must_use]
+    pub fn scored(path: PathBuf, score: f64) -> Self {
+        Self {
+            score: Some(score),
+            ..Self::new(path)
+        }
+    }
+}
+
+impl From<PathBuf> for SearchResult {
+    fn from(path: PathBuf) -> Self {
+        Self::new(path)
+    }
+}
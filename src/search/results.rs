@@ -0,0 +1,121 @@
+//! Result sets with their sizes
+//!
+//! [`SearchResults`] reads the size of every matched file once, so totals
+//! can be taken over the whole set or grouped by extension or directory.
+//! Only regular files count towards the totals: directories and files whose
+//! metadata cannot be read contribute zero bytes.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "config")]
+use serde::Serialize;
+
+/// Number of files and their combined size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(Serialize))]
+pub struct SizeTotal {
+    /// Number of files
+    pub files: usize,
+    /// Combined size in bytes
+    pub bytes: u64,
+}
+
+impl SizeTotal {
+    fn add(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Matched paths together with their sizes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchResults {
+    files: Vec<(PathBuf, u64)>,
+}
+
+impl SearchResults {
+    /// Read the size of every path in `paths`
+    #[must_use]
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        paths.into_iter().collect()
+    }
+
+    /// Number of results
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether there are no results
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Every result with its size in bytes, in the original order
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, u64)> {
+        self.files
+            .iter()
+            .map(|(path, size)| (path.as_path(), *size))
+    }
+
+    /// The result paths, dropping their sizes
+    #[must_use]
+    pub fn into_paths(self) -> Vec<PathBuf> {
+        self.files.into_iter().map(|(path, _)| path).collect()
+    }
+
+    /// Combined size of all results in bytes
+    #[must_use]
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|(_, size)| size).sum()
+    }
+
+    /// Totals per lowercased extension, with `""` for files without one
+    #[must_use]
+    pub fn size_by_extension(&self) -> BTreeMap<String, SizeTotal> {
+        let mut totals: BTreeMap<String, SizeTotal> = BTreeMap::new();
+        for (path, size) in &self.files {
+            let extension = path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            totals.entry(extension).or_default().add(*size);
+        }
+        totals
+    }
+
+    /// Totals per directory directly containing the results
+    #[must_use]
+    pub fn size_by_directory(&self) -> BTreeMap<PathBuf, SizeTotal> {
+        let mut totals: BTreeMap<PathBuf, SizeTotal> = BTreeMap::new();
+        for (path, size) in &self.files {
+            let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+            totals.entry(dir).or_default().add(*size);
+        }
+        totals
+    }
+}
+
+impl FromIterator<PathBuf> for SearchResults {
+    fn from_iter<I: IntoIterator<Item = PathBuf>>(paths: I) -> Self {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let size = std::fs::metadata(&path)
+                    .ok()
+                    .filter(std::fs::Metadata::is_file)
+                    .map_or(0, |metadata| metadata.len());
+                (path, size)
+            })
+            .collect();
+        Self { files }
+    }
+}
+
+impl From<Vec<PathBuf>> for SearchResults {
+    fn from(paths: Vec<PathBuf>) -> Self {
+        Self::new(paths)
+    }
+}
@@ -0,0 +1,77 @@
+//! Integrator-supplied fuzzy scoring
+//!
+//! Fuzzy searches rank names with a blend of edit distance, in-order
+//! character matching and shared bigrams, whose weights can be tuned with
+//! [`Config::fuzzy_scoring`](crate::Config::fuzzy_scoring). Applications that
+//! want a different algorithm altogether, such as fzf's, register a
+//! [`Scorer`] on a [`FileSearcher`](crate::FileSearcher), or on a
+//! [`SearchEngine`](super::SearchEngine) directly, and it scores every name
+//! in place of the built-in one.
+//!
+//! Names and queries are lowercased before scoring unless
+//! [`Config::case_sensitive`](crate::Config::case_sensitive) is set, and
+//! normalized first with
+//! [`Config::unicode_normalization`](crate::Config::unicode_normalization). Scores
+//! run from 0.0 for no match to 1.0 for a perfect one, and names scoring
+//! below [`Config::fuzzy_min_score`](crate::Config::fuzzy_min_score) are not
+//! reported. Ranking then applies the extension weights, recency and depth
+//! penalty as usual. Any `Fn(&str, &str) -> f64 + Send + Sync` closure taking
+//! the name and the query is a scorer:
+//!
+//! ```rust
+//! use whatever_find::FileSearcher;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // Prefer names starting with the query
+//! let searcher = FileSearcher::builder()
+//!     .scorer(|name: &str, query: &str| {
+//!         if name.starts_with(query) {
+//!             1.0
+//!         } else if name.contains(query) {
+//!             0.5
+//!         } else {
+//!             0.0
+//!         }
+//!     })
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A registered scorer is not limited to names sharing a bigram with the
+//! query, so the candidate index of
+//! [`Config::fuzzy_candidate_index`](crate::Config::fuzzy_candidate_index)
+//! is not used with it.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Scores how well a name matches a fuzzy query
+pub trait Scorer: Send + Sync {
+    /// How well the file or directory name `name` matches `query`, from 0.0
+    /// for not at all to 1.0 for a perfect match
+    fn score(&self, name: &str, query: &str) -> f64;
+}
+
+impl<F> Scorer for F
+where
+    F: Fn(&str, &str) -> f64 + Send + Sync,
+{
+    fn score(&self, name: &str, query: &str) -> f64 {
+        self(name, query)
+    }
+}
+
+/// The scorer registered on a searcher, if any
+#[derive(Clone, Default)]
+pub(crate) struct CustomScorer(pub(crate) Option<Arc<dyn Scorer>>);
+
+impl fmt::Debug for CustomScorer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() {
+            "CustomScorer(set)"
+        } else {
+            "CustomScorer(none)"
+        })
+    }
+}
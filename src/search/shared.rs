@@ -0,0 +1,141 @@
+//! One index shared by concurrent queries
+//!
+//! [`SharedSearcher`] is `Send + Sync`, so a single instance behind an `Arc`
+//! can serve queries from any number of threads. The index is held as an
+//! `Arc<FileIndex>` behind a `RwLock` that is only locked long enough to clone
+//! or replace that `Arc`: queries run against a snapshot without holding the
+//! lock, and a refresh builds the new index before swapping it in, so readers
+//! never wait for a walk and never see a partially built index.
+
+use crate::config::Config;
+use crate::indexer::{FileIndex, FileIndexer};
+use crate::search::compiled::CompiledQuery;
+use crate::search::{SearchEngine, SearchMode};
+use crate::{FileSearchError, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// A file index that many threads can query while another refreshes it
+pub struct SharedSearcher {
+    root: PathBuf,
+    engine: SearchEngine,
+    index: RwLock<Arc<FileIndex>>,
+}
+
+// Checked at compile time so the guarantee documented above cannot regress
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedSearcher>();
+};
+
+impl SharedSearcher {
+    /// Index `root` with `config`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` is not valid UTF-8 or cannot be walked
+    pub fn new<P: Into<PathBuf>>(root: P, config: Config) -> Result<Self> {
+        let root = root.into();
+        let index = build(&root, &config)?;
+        Ok(Self {
+            root,
+            engine: SearchEngine::new(config),
+            index: RwLock::new(Arc::new(index)),
+        })
+    }
+
+    /// Directory the index covers
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Configuration used for indexing and searching
+    #[must_use]
+    pub fn config(&self) -> &Config {
+        self.engine.config()
+    }
+
+    /// The current index
+    ///
+    /// The snapshot stays valid, and unchanged, across later refreshes.
+    #[must_use]
+    pub fn snapshot(&self) -> Arc<FileIndex> {
+        Arc::clone(&self.index.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Walk the root again and replace the index
+    ///
+    /// Queries keep using the previous index until the walk completes. When
+    /// several refreshes overlap, the last one to finish wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the root cannot be walked; the previous index is
+    /// kept in that case
+    pub fn refresh(&self) -> Result<()> {
+        let index = Arc::new(build(&self.root, self.engine.config())?);
+        *self.index.write().unwrap_or_else(PoisonError::into_inner) = index;
+        Ok(())
+    }
+
+    /// Search the current index, detecting the mode from `query`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is not a valid pattern
+    pub fn search_auto(&self, query: &str) -> Result<Vec<PathBuf>> {
+        self.engine.search_auto(self.snapshot().as_ref(), query)
+    }
+
+    /// Search the current index in `mode`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is not a valid pattern for `mode`, or if
+    /// `mode` was disabled at compile time
+    pub fn search(&self, query: &str, mode: SearchMode) -> Result<Vec<PathBuf>> {
+        self.engine.search(self.snapshot().as_ref(), query, mode)
+    }
+
+    /// Check and compile `query` for `mode` once, to be searched with
+    /// [`SharedSearcher::search_compiled`] as often as needed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is not a valid pattern for `mode`, or if
+    /// `mode` was disabled at compile time
+    pub fn compile(&self, query: &str, mode: SearchMode) -> Result<CompiledQuery> {
+        self.engine.compile(query, mode)
+    }
+
+    /// Search the current index for a query compiled by
+    /// [`SharedSearcher::compile`]
+    #[must_use]
+    pub fn search_compiled(&self, query: &CompiledQuery) -> Vec<PathBuf> {
+        self.engine.search_compiled(self.snapshot().as_ref(), query)
+    }
+
+    /// Fuzzy search the current index, best matches first
+    #[cfg(feature = "fuzzy")]
+    #[must_use]
+    pub fn search_fuzzy(&self, query: &str) -> Vec<(PathBuf, f64)> {
+        self.engine.search_fuzzy(self.snapshot().as_ref(), query)
+    }
+
+    /// The `k` best fuzzy matches in the current index scoring at least
+    /// `min_score`, best first
+    #[cfg(feature = "fuzzy")]
+    #[must_use]
+    pub fn search_fuzzy_top_k(&self, query: &str, k: usize, min_score: f64) -> Vec<(PathBuf, f64)> {
+        self.engine
+            .search_fuzzy_top_k(self.snapshot().as_ref(), query, k, min_score)
+    }
+}
+
+fn build(root: &Path, config: &Config) -> Result<FileIndex> {
+    let root_str = root
+        .to_str()
+        .ok_or_else(|| FileSearchError::invalid_path(root, "Contains invalid UTF-8"))?;
+    FileIndexer::new(config.clone()).build_index(root_str)
+}
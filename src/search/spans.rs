@@ -0,0 +1,91 @@
+//! Where a query matched within a file name
+//!
+//! Spans are byte ranges of the original file name, so a frontend can slice
+//! the name with them to highlight what matched. Case-insensitive and
+//! normalized matches are found in a folded copy of the name, and their
+//! offsets mapped back, as folding can change the length of non-ASCII
+//! characters.
+
+use super::fold::Folding;
+use std::ops::Range;
+
+/// A file name, folded as configured, with the original characters each byte
+/// came from
+struct Folded {
+    text: String,
+    origins: Vec<Range<usize>>,
+}
+
+impl Folded {
+    fn new(name: &str, folding: Folding) -> Self {
+        let mut text = String::with_capacity(name.len());
+        let mut origins = Vec::with_capacity(name.len());
+        for (origin, piece) in folding.pieces(name) {
+            text.push_str(&piece);
+            origins.resize(text.len(), origin);
+        }
+        Self { text, origins }
+    }
+
+    /// The range of the original name covering `range` of the folded text
+    fn original(&self, range: Range<usize>) -> Range<usize> {
+        self.origins[range.start].start..self.origins[range.end - 1].end
+    }
+}
+
+/// Every occurrence of `query`, already case-folded as configured, in `name`
+///
+/// With `whole_words`, only occurrences with no letter or digit directly
+/// before or after them count.
+pub(crate) fn substring(
+    name: &str,
+    query: &str,
+    folding: Folding,
+    whole_words: bool,
+) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let folded = Folded::new(name, folding);
+    folded
+        .text
+        .match_indices(query)
+        .map(|(start, _)| start..start + query.len())
+        .filter(|range| !whole_words || super::is_word(&folded.text, range.clone()))
+        .map(|range| folded.original(range))
+        .collect()
+}
+
+/// The characters of `name` matching those of `query` in order, taking the
+/// earliest match of each, with adjacent characters joined into one span
+///
+/// A query found whole in the name is reported as one span. Returns no spans
+/// unless every character of the query is matched.
+#[cfg(feature = "fuzzy")]
+pub(crate) fn subsequence(name: &str, query: &str, folding: Folding) -> Vec<Range<usize>> {
+    let query = folding.fold(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let folded = Folded::new(name, folding);
+    if let Some(start) = folded.text.find(&*query) {
+        return vec![folded.original(start..start + query.len())];
+    }
+
+    let mut wanted = query.chars().peekable();
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    for (start, c) in folded.text.char_indices() {
+        if wanted.next_if_eq(&c).is_none() {
+            continue;
+        }
+        let span = folded.original(start..start + c.len_utf8());
+        match spans.last_mut() {
+            Some(last) if last.end >= span.start => last.end = last.end.max(span.end),
+            _ => spans.push(span),
+        }
+    }
+    if wanted.peek().is_some() {
+        return Vec::new();
+    }
+    spans
+}
@@ -0,0 +1,65 @@
+//! The best fuzzy matches picked without sorting every match
+//!
+//! A bounded min-heap holds the best `k` matches seen so far, so picking them
+//! from `n` matches takes `O(n log k)` time instead of the `O(n log n)` of a
+//! full sort, and the rest are dropped as soon as they fall behind.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+
+/// A match with the rank it is ordered by, greater meaning better
+struct Ranked {
+    rank: f64,
+    path: PathBuf,
+    score: f64,
+}
+
+impl Ord for Ranked {
+    /// Higher ranks first, ties going to the smaller path as in
+    /// [`SearchEngine::rank_fuzzy`](super::SearchEngine::rank_fuzzy)
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank
+            .total_cmp(&other.rank)
+            .then_with(|| other.path.cmp(&self.path))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+/// The `k` best of `matches`, given as rank, path and score, best first
+pub(super) fn best(
+    matches: impl IntoIterator<Item = (f64, PathBuf, f64)>,
+    k: usize,
+) -> Vec<(PathBuf, f64)> {
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for (rank, path, score) in matches {
+        let ranked = Ranked { rank, path, score };
+        if heap.len() < k {
+            heap.push(Reverse(ranked));
+        } else if heap.peek().is_some_and(|Reverse(worst)| ranked > *worst) {
+            heap.pop();
+            heap.push(Reverse(ranked));
+        }
+    }
+    // Ascending order of `Reverse` is best first
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(ranked)| (ranked.path, ranked.score))
+        .collect()
+}
@@ -0,0 +1,129 @@
+//! Indexes built ahead of the first query
+//!
+//! Walking a large tree takes seconds, which an application would rather
+//! spend at startup than after the user typed a query. A warm-up walks one
+//! root on a background thread as soon as it is requested. Searches of that
+//! root wait for the walk to finish, if it has not already, and then use its
+//! index instead of walking again, while [`IndexState`] lets the application
+//! show "indexing…" in the meantime.
+//!
+//! The index is a snapshot of the tree at warm-up time; warming up again
+//! replaces it. A failed warm-up is not fatal, as searches then walk the root
+//! themselves and report the error.
+
+use crate::indexer::{FileIndex, FileIndexer};
+use crate::{FileSearchError, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+/// Progress of a warm-up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexState {
+    /// The root is still being walked
+    Indexing,
+    /// The index is ready for queries
+    Ready,
+    /// The walk failed, so searches walk the root themselves
+    Failed,
+}
+
+/// An index together with the indexer that built it, which holds the walk's
+/// statistics and fuzzy candidate index
+pub(crate) struct BuiltIndex {
+    pub(crate) indexer: FileIndexer,
+    pub(crate) index: FileIndex,
+}
+
+impl BuiltIndex {
+    /// Walk `root` with `indexer`
+    pub(crate) fn build(root: &Path, mut indexer: FileIndexer) -> Result<Self> {
+        let root = root
+            .to_str()
+            .ok_or_else(|| FileSearchError::invalid_path(root, "Contains invalid UTF-8"))?;
+        let index = indexer.build_index(root)?;
+        Ok(Self { indexer, index })
+    }
+}
+
+enum Slot {
+    Indexing,
+    Ready(Arc<BuiltIndex>),
+    Failed,
+}
+
+/// An index being built for one root on a background thread
+pub(crate) struct WarmIndex {
+    root: PathBuf,
+    slot: Mutex<Slot>,
+    done: Condvar,
+}
+
+impl WarmIndex {
+    /// Start walking `root` with `indexer` on a new thread
+    pub(crate) fn start(root: PathBuf, indexer: FileIndexer) -> Arc<Self> {
+        let warm = Arc::new(Self {
+            root,
+            slot: Mutex::new(Slot::Indexing),
+            done: Condvar::new(),
+        });
+        let worker = Arc::clone(&warm);
+        let spawned = std::thread::Builder::new()
+            .name("whatever-find-warm-up".to_string())
+            .spawn(move || {
+                let slot = match BuiltIndex::build(&worker.root, indexer) {
+                    Ok(built) => Slot::Ready(Arc::new(built)),
+                    Err(_) => Slot::Failed,
+                };
+                worker.finish(slot);
+            });
+        if spawned.is_err() {
+            warm.finish(Slot::Failed);
+        }
+        warm
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Slot> {
+        self.slot.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn finish(&self, slot: Slot) {
+        *self.lock() = slot;
+        self.done.notify_all();
+    }
+
+    /// How far the warm-up has come
+    pub(crate) fn state(&self) -> IndexState {
+        match *self.lock() {
+            Slot::Indexing => IndexState::Indexing,
+            Slot::Ready(_) => IndexState::Ready,
+            Slot::Failed => IndexState::Failed,
+        }
+    }
+
+    /// The index of `root`, waiting for the walk to finish first
+    ///
+    /// Returns `None` if the warm-up is for another root or failed.
+    pub(crate) fn wait(&self, root: &Path) -> Option<Arc<BuiltIndex>> {
+        if root != self.root {
+            return None;
+        }
+        let slot = self
+            .done
+            .wait_while(self.lock(), |slot| matches!(slot, Slot::Indexing))
+            .unwrap_or_else(PoisonError::into_inner);
+        match &*slot {
+            Slot::Ready(built) => Some(Arc::clone(built)),
+            Slot::Indexing | Slot::Failed => None,
+        }
+    }
+}
+
+impl fmt::Debug for WarmIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WarmIndex")
+            .field("root", &self.root)
+            .field("state", &self.state())
+            .finish_non_exhaustive()
+    }
+}